@@ -1,4 +1,6 @@
 connect-to = Verbinde zu { $address }...
+reconnect-countdown = Verbindung verloren, neuer Versuch in { $seconds } s ({ $attempt }/{ $retries })...
+reconnect-giving-up = Verbindung verloren, keine weiteren Versuche.
 
 title-connected = iCY TERM { $version } - Verbunden zu { $name } seit { $time }
 title-offline   = iCY TERM { $version } - Keine Verbindung
@@ -36,8 +38,23 @@ settings-scaling-linear = Linear
 
 settings-terminal-category = Terminal
 settings-terminal-console-beep-checkbox = Konsole Beep
+settings-terminal-resume-downloads-checkbox = Unvollständige Zmodem Downloads fortsetzen
+settings-terminal-resume-downloads-tooltip = Vorhandene Dateien im Download Verzeichnis werden fortgesetzt, wenn der Sender bestätigt, dass ihr Inhalt übereinstimmt.
 settings-terminal-open-settings-dir-button = Einstellungsverzeichnis öffnen
 
+settings-text-transfer-category = Textübertragung
+settings-text-transfer-line-delay = Zeilenverzögerung (ms):
+settings-text-transfer-char-delay = Zeichenverzögerung (ms):
+settings-text-transfer-line-ending = Zeilenende:
+settings-text-transfer-wait-for-prompt = Auf Prompt warten:
+settings-text-transfer-wait-for-echo-checkbox = Auf Echo jeder Zeile warten
+settings-text-transfer-capture-file-name = Aufnahmedatei:
+settings-text-transfer-idle-timeout = Leerlauf-Timeout (s):
+settings-text-transfer-end-pattern = Endmuster:
+settings-text-transfer-end-on-ctrl-z-checkbox = Bei Strg-Z beenden
+
+settings-proxy-category = Proxy
+
 settings-keybinds-category = Tastatur
 settings-keybinds-disconnect = Trennen:
 settings-keybinds-dialing-directory = Telefonbuch:
@@ -57,6 +74,47 @@ dialing_directory-name-placeholder = BBS Name
 dialing_directory-filter-placeholder = Namensfilter
 dialing_directory-address = Addresse:
 dialing_directory-protocol = Protokoll:
+dialing_directory-phone-number = Telefonnummer:
+dialing_directory-serial-device = Gerät:
+dialing_directory-serial-baud-rate = Baudrate:
+dialing_directory-serial-format = Daten/Parität/Stop:
+dialing_directory-serial-flow-control = Flusskontrolle:
+dialing_directory-ssh-key-file = Privater Schlüssel:
+dialing_directory-ssh-key-passphrase = Passphrase:
+dialing_directory-ssh-auth-order = Authentifizierung:
+dialing_directory-ssh-add-auth-method = Methode hinzufügen…
+dialing_directory-telnet-terminal-types = Terminaltypen
+dialing_directory-telnet-terminal-types-default = Abhängig vom Terminaltyp
+dialing_directory-telnet-environment = Umgebungsvariablen
+dialing_directory-telnet-charsets = Zeichensätze
+dialing_directory-telnet-add-charset = Zeichensatz hinzufügen…
+dialing_directory-rlogin-send-password = Passwort als Client-Benutzer senden
+dialing_directory-rlogin-send-password-hint = Door-Server (wie der von Synchronet) erwarten das Passwort im Client-Benutzer-Feld des rlogin-Handshakes. Andere rlogin-Server erhalten das Passwort im Klartext und protokollieren es eventuell.
+dialing_directory-tls-verify-certificate = Zertifikat prüfen
+dialing_directory-tls-fingerprint = Fester Fingerabdruck
+dialing_directory-tls-server-name = Servername (SNI)
+dialing_directory-command = Befehl
+dialing_directory-command-hint = Wird in einer Shell ausgeführt, die Verbindung läuft über stdin/stdout. %h und %p werden durch Host und Port ersetzt.
+dialing_directory-command-use-telnet = Telnet-Protokoll verwenden
+dialing_directory-local-program = Programm
+dialing_directory-local-working-directory = Arbeitsverzeichnis
+dialing_directory-reconnect = Bei Verbindungsabbruch neu verbinden
+dialing_directory-reconnect-retries = Versuche
+dialing_directory-reconnect-delay = Wartezeit
+dialing_directory-reconnect-delay-hint = Wartezeit vor dem ersten Versuch, sie verdoppelt sich mit jedem Versuch.
+dialing_directory-reconnects = Automatische Neuverbindungen
+dialing_directory-keepalive-interval = Keepalive
+dialing_directory-keepalive-interval-hint = Sekunden zwischen Keepalive-Nachrichten des Protokolls, solange nichts gesendet wird. 0 schaltet sie ab.
+dialing_directory-keepalive-telnet-command = Telnet-Keepalive
+dialing_directory-keepalive-telnet-ayt = Are You There
+dialing_directory-keepalive-anti-idle = Anti-Idle
+dialing_directory-keepalive-anti-idle-hint = Text, der nach der angegebenen Zeit ohne gesendete Daten verschickt wird. Unterstützt die Escapes des Auto-Logins wie \r, \e, \x1B oder \cC.
+dialing_directory-proxy-override = Eigene Proxy-Einstellungen
+dialing_directory-proxy-type = Proxy
+dialing_directory-proxy-none = Keiner
+dialing_directory-proxy-address = Proxy-Adresse
+dialing_directory-proxy-user = Proxy-Benutzer
+dialing_directory-proxy-password = Proxy-Passwort
 dialing_directory-user = User:
 dialing_directory-password = Passwort:
 dialing_directory-generate = Generieren
@@ -64,6 +122,24 @@ dialing_directory-screen_mode = Bildschirmmodus:
 dialing_directory-terminal_type = Terminaltyp:
 dialing_directory-music-option = Ansimusik:
 dialing_directory-autologin = Autologin String:
+dialing_directory-autologin-help =
+    !W / !Q wartet auf die Namens-/Passwortabfrage, !W"Text"10 wartet auf einen Text (max. 10s)
+    !N !F !L !P sendet Benutzername, Vorname, Nachname, Passwort
+    !D4 / !D250ms Pause, !E wartet bis die BBS ruhig ist, !I deaktiviert IEMSI
+    !?("ANSI?" y\r | !Q !P | 30 \r) führt den Zweig der ersten Abfrage (oder des Timeouts) aus
+    \r \n \e \cC \x1B senden Steuerzeichen, !! sendet ein '!'
+dialing_directory-login-script = Login-Skript:
+dialing_directory-script-macros = Skript-Makros:
+dialing_directory-triggers = Auslöser:
+dialing_directory-trigger-pattern = Empfangener Text
+dialing_directory-trigger-regex = Regex
+dialing_directory-trigger-send = Senden
+dialing_directory-trigger-beep = Piepton
+dialing_directory-trigger-download = Download
+dialing_directory-trigger-upload = Upload
+dialing_directory-trigger-capture = Aufnahme starten
+dialing_directory-trigger-highlight = Hervorheben
+dialing_directory-trigger-notify = Benachrichtigen
 dialing_directory-comment = Kommentar:
 dialing_directory-delete = Ausgewählte BBS löschen
 dialing_directory-add = Neue BBS
@@ -85,6 +161,7 @@ dialing_directory-custom-iemsi-login-data = Benutzerdefinierte IEMSI Login-Daten
 terminal-upload = Upload
 terminal-download = Download
 terminal-hangup = Auflegen
+terminal-scripts = Skripte
 terminal-autologin = Username/Passwort senden
 terminal-dialing_directory = Telefonbuch
 terminal-menu-copy = Kopieren
@@ -103,7 +180,8 @@ protocol-xmodem1k-description = XModem Variante
 protocol-xmodem1kG-description = Schnelle XModem Variante
 protocol-ymodem-description = Solide, aber ZModem ist besser
 protocol-ymodemg-description = Schnelle YModem Variante
-protocol-text-description = Text file hochladen
+protocol-kermit-description = Langsam, aber läuft fast überall
+protocol-text-description = Text senden oder aufzeichnen
 
 transfer-upload = { terminal-upload }
 transfer-download = { terminal-download }
@@ -119,8 +197,13 @@ menu-item-discuss=Forum
 menu-item-report-bug=Fehler melden
 menu-item-settings={ settings-heading }
 menu-item-capture-dialog=Aufnehmen…
+menu-item-view-file=Datei anzeigen…
+menu-item-run-script=Skript ausführen…
+menu-item-stop-script=Skript anhalten
+menu-item-script-console=Skript-Konsole
 
 toolbar-stop-capture=Aufnahmestopp
+toolbar-stop-recording=Mitschnitt beenden
 toolbar-show-iemsi=IEMSI anzeigen
 toolbar-hide-iemsi=IEMSI verstecken
 toolbar-stop-playing1 = Bitte AUFHÖREN
@@ -136,6 +219,32 @@ capture-dialog-capture-title=Aufnahme
 capture-dialog-capture-button=Start
 capture-dialog-open-folder-button=Verzeichnis öffnen…
 
+capture-dialog-recording-label=Sitzungsmitschnitt (mit Zeitablauf):
+capture-dialog-record-button=Mitschneiden
+capture-dialog-play-button=Mitschnitt abspielen…
+
+playback-pause=Pause
+playback-resume=Fortsetzen
+playback-stop=Wiedergabe beenden
+
+viewer-restart=Erneut anzeigen
+viewer-close=Datei schließen
+viewer-sauce-title=Titel:
+viewer-sauce-author=Autor:
+viewer-sauce-group=Gruppe:
+viewer-sauce-date=Datum:
+viewer-sauce-font=Zeichensatz:
+viewer-sauce-width=Breite:
+
+script-console-title=Skript-Konsole
+script-console-hint=Rhai-Anweisung, z.B. send_line("hallo")
+script-console-run=Ausführen
+script-console-stop=Anhalten
+script-console-clear=Leeren
+script-started={ $name } läuft
+script-stopped={ $name } angehalten
+script-finished={ $name } beendet
+
 show-iemsi-dialog-heading=IEMSI Server-Information
 show-iemsi-dialog-name=Name:
 show-iemsi-dialog-location=Ort:
@@ -144,4 +253,12 @@ show-iemsi-dialog-notice=Notiz:
 show-iemsi-dialog-capabilities=Server-Fähigkeiten:
 show-iemsi-dialog-id=Id:
 
-delete-entry-heading=Delete entry
\ No newline at end of file
+delete-entry-heading=Delete entry
+host-key-unknown-title=Unbekannter Host-Schlüssel
+host-key-unknown-question=Die Echtheit von '{ $host }' kann nicht festgestellt werden. Vertrauen Sie diesem Fingerabdruck?
+host-key-changed-title=Host-Schlüssel geändert
+host-key-changed-warning=Der Host-Schlüssel von '{ $host }' hat sich geändert! Jemand könnte Sie abhören. Die Verbindung wurde abgebrochen. Falls die Änderung erwartet ist, entfernen Sie den alten Schlüssel aus der known_hosts Datei im Einstellungsverzeichnis.
+host-key-accept-save-button=Akzeptieren und speichern
+host-key-accept-once-button=Einmal akzeptieren
+host-key-abort-button=Abbrechen
+host-key-close-button=Schließen
@@ -1,4 +1,6 @@
 connect-to = Connect to { $address }...
+reconnect-countdown = Connection lost, reconnect in { $seconds } s (attempt { $attempt }/{ $retries })...
+reconnect-giving-up = Connection lost, no more reconnect attempts.
 
 title-connected = iCY TERM { $version } - Connected { $time } to { $name }
 title-offline   = iCY TERM { $version } - Offline
@@ -35,8 +37,23 @@ settings-scaling-linear = Linear
 
 settings-terminal-category = Terminal
 settings-terminal-console-beep-checkbox = Console beep
+settings-terminal-resume-downloads-checkbox = Resume incomplete Zmodem downloads
+settings-terminal-resume-downloads-tooltip = Existing files in the download directory are continued if the sender confirms that their content matches.
 settings-terminal-open-settings-dir-button = Open settings directory
 
+settings-text-transfer-category = Text transfer
+settings-text-transfer-line-delay = Line delay (ms):
+settings-text-transfer-char-delay = Character delay (ms):
+settings-text-transfer-line-ending = Line ending:
+settings-text-transfer-wait-for-prompt = Wait for prompt:
+settings-text-transfer-wait-for-echo-checkbox = Wait for echo of each line
+settings-text-transfer-capture-file-name = Capture file:
+settings-text-transfer-idle-timeout = Idle timeout (s):
+settings-text-transfer-end-pattern = End pattern:
+settings-text-transfer-end-on-ctrl-z-checkbox = End on Ctrl-Z
+
+settings-proxy-category = Proxy
+
 settings-keybinds-category = Keybinds
 settings-keybinds-disconnect = Disconnect:
 settings-keybinds-dialing-directory = Dialing directory:
@@ -56,6 +73,47 @@ dialing_directory-name-placeholder = BBS name
 dialing_directory-filter-placeholder = Filter names
 dialing_directory-address = Address:
 dialing_directory-protocol = Protocol:
+dialing_directory-phone-number = Phone number:
+dialing_directory-serial-device = Device:
+dialing_directory-serial-baud-rate = Baud rate:
+dialing_directory-serial-format = Data/Parity/Stop:
+dialing_directory-serial-flow-control = Flow control:
+dialing_directory-ssh-key-file = Private key:
+dialing_directory-ssh-key-passphrase = Passphrase:
+dialing_directory-ssh-auth-order = Authentication:
+dialing_directory-ssh-add-auth-method = Add method…
+dialing_directory-telnet-terminal-types = Terminal types
+dialing_directory-telnet-terminal-types-default = Derived from the terminal type
+dialing_directory-telnet-environment = Environment
+dialing_directory-telnet-charsets = Charsets
+dialing_directory-telnet-add-charset = Add charset…
+dialing_directory-rlogin-send-password = Send password as client user
+dialing_directory-rlogin-send-password-hint = Door servers (like the one from Synchronet) expect the password in the client user field of the rlogin handshake. Other rlogin servers get the password in clear text and may log it.
+dialing_directory-tls-verify-certificate = Verify certificate
+dialing_directory-tls-fingerprint = Pinned fingerprint
+dialing_directory-tls-server-name = Server name (SNI)
+dialing_directory-command = Command
+dialing_directory-command-hint = Runs in a shell, the BBS is reached through its stdin/stdout. %h and %p are replaced by host and port.
+dialing_directory-command-use-telnet = Use the telnet protocol
+dialing_directory-local-program = Program
+dialing_directory-local-working-directory = Working directory
+dialing_directory-reconnect = Reconnect if the connection drops
+dialing_directory-reconnect-retries = Attempts
+dialing_directory-reconnect-delay = Delay
+dialing_directory-reconnect-delay-hint = Wait before the first attempt, it doubles with every attempt.
+dialing_directory-reconnects = Automatic reconnects
+dialing_directory-keepalive-interval = Keepalive
+dialing_directory-keepalive-interval-hint = Seconds between keepalive messages of the protocol while nothing is sent, 0 turns them off.
+dialing_directory-keepalive-telnet-command = Telnet keepalive
+dialing_directory-keepalive-telnet-ayt = Are You There
+dialing_directory-keepalive-anti-idle = Anti-idle
+dialing_directory-keepalive-anti-idle-hint = Text that is sent after the given time without sending anything. Supports the escapes of the auto login like \r, \e, \x1B or \cC.
+dialing_directory-proxy-override = Own proxy settings
+dialing_directory-proxy-type = Proxy
+dialing_directory-proxy-none = None
+dialing_directory-proxy-address = Proxy address
+dialing_directory-proxy-user = Proxy user
+dialing_directory-proxy-password = Proxy password
 dialing_directory-user = User:
 dialing_directory-password = Password:
 dialing_directory-generate = Generate
@@ -64,6 +122,24 @@ dialing_directory-terminal_type = Terminal type:
 dialing_directory-music-option = Ansi music:
 dialing_directory-baud-emulation = Baud emulation:
 dialing_directory-autologin = Autologin String:
+dialing_directory-autologin-help =
+    !W / !Q wait for the name / password prompt, !W"text"10 waits for a text (timeout 10s)
+    !N !F !L !P send user name, first name, last name, password
+    !D4 / !D250ms delay, !E wait until the BBS is quiet, !I disable IEMSI
+    !?("ANSI?" y\r | !Q !P | 30 \r) runs the branch of the first prompt (or timeout)
+    \r \n \e \cC \x1B send control characters, !! sends a '!'
+dialing_directory-login-script = Login script:
+dialing_directory-script-macros = Script macros:
+dialing_directory-triggers = Triggers:
+dialing_directory-trigger-pattern = Incoming text
+dialing_directory-trigger-regex = Regex
+dialing_directory-trigger-send = Send
+dialing_directory-trigger-beep = Beep
+dialing_directory-trigger-download = Download
+dialing_directory-trigger-upload = Upload
+dialing_directory-trigger-capture = Start capture
+dialing_directory-trigger-highlight = Highlight
+dialing_directory-trigger-notify = Notify
 dialing_directory-comment = Comment:
 dialing_directory-delete = Delete selected BBS
 dialing_directory-add = Add new BBS
@@ -86,6 +162,7 @@ dialing_directory-custom-iemsi-login-data = Custom IEMSI login data
 terminal-upload = Upload
 terminal-download = Download
 terminal-hangup = Hangup
+terminal-scripts = Scripts
 terminal-autologin = Send user name/password
 terminal-dialing_directory = Dialing directory
 terminal-menu = Menu
@@ -105,7 +182,8 @@ protocol-xmodem1k-description = Rarely used anymore
 protocol-xmodem1kG-description = Does that even exist?
 protocol-ymodem-description = Ok but Zmodem is better
 protocol-ymodemg-description = A fast Ymodem variant
-protocol-text-description = Upload a text file
+protocol-kermit-description = Slow but works almost everywhere
+protocol-text-description = Send or capture plain text
 
 transfer-upload = { terminal-upload }
 transfer-download = { terminal-download }
@@ -121,8 +199,13 @@ menu-item-discuss=Forum
 menu-item-report-bug=Report a bug
 menu-item-settings={ settings-heading }
 menu-item-capture-dialog=Capture…
+menu-item-view-file=View file…
+menu-item-run-script=Run script…
+menu-item-stop-script=Stop script
+menu-item-script-console=Script console
 
 toolbar-stop-capture=Stop capture
+toolbar-stop-recording=Stop recording
 toolbar-show-iemsi=Show IEMSI
 toolbar-hide-iemsi=Hide IEMSI
 toolbar-stop-playing1 = Stop PLZ
@@ -137,6 +220,32 @@ capture-dialog-capture-title=Capture
 capture-dialog-capture-button=Start
 capture-dialog-open-folder-button=Open folder…
 
+capture-dialog-recording-label=Session recording (with timing):
+capture-dialog-record-button=Record
+capture-dialog-play-button=Play recording…
+
+playback-pause=Pause
+playback-resume=Resume
+playback-stop=Stop playback
+
+viewer-restart=Show again
+viewer-close=Close file
+viewer-sauce-title=Title:
+viewer-sauce-author=Author:
+viewer-sauce-group=Group:
+viewer-sauce-date=Date:
+viewer-sauce-font=Font:
+viewer-sauce-width=Width:
+
+script-console-title=Script console
+script-console-hint=Rhai statement, e.g. send_line("hello")
+script-console-run=Run
+script-console-stop=Stop
+script-console-clear=Clear
+script-started=Running { $name }
+script-stopped={ $name } stopped
+script-finished={ $name } finished
+
 show-iemsi-dialog-heading=IEMSI Server information
 show-iemsi-dialog-name=Name:
 show-iemsi-dialog-location=Location:
@@ -147,4 +256,13 @@ show-iemsi-dialog-id=Id:
 
 delete-bbs-title=Delete BBS
 delete-bbs-question=Are you sure to you want to delete '{ $system }'?
-delete-bbs-delete-button=Delete  
\ No newline at end of file
+delete-bbs-delete-button=Delete  
+
+host-key-unknown-title=Unknown host key
+host-key-unknown-question=The authenticity of '{ $host }' can't be established. Do you trust this key fingerprint?
+host-key-changed-title=Host key changed
+host-key-changed-warning=The host key of '{ $host }' has changed! Someone could be eavesdropping on you. The connection was aborted. If the change is expected remove the old key from the known_hosts file in the settings directory.
+host-key-accept-save-button=Accept and save
+host-key-accept-once-button=Accept once
+host-key-abort-button=Abort
+host-key-close-button=Close
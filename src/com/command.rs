@@ -0,0 +1,337 @@
+use super::{connector, telnet::TelnetProtocol, Com, OpenConnectionData, TermComResult};
+use crate::addresses::{Charset, TelnetKeepAlive, Terminal};
+use icy_engine::Size;
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Mutex,
+    },
+    thread,
+};
+
+/// Port for %p if the address has none
+const DEFAULT_PORT: u16 = 23;
+
+/// Connection through the stdin/stdout of a program (`ssh -W`, `nc`, `socat`, …).
+/// The output is read by a thread because pipes can't be read without blocking.
+pub struct ComCommandImpl {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    /// In a mutex because `Com` needs to be `Sync`
+    rx: Mutex<Receiver<Vec<u8>>>,
+    /// Optional telnet layer on top of the byte stream
+    telnet: Option<TelnetProtocol>,
+    keepalive: TelnetKeepAlive,
+}
+
+impl ComCommandImpl {
+    pub fn connect(connection_data: &OpenConnectionData) -> TermComResult<Self> {
+        let command_line =
+            expand_command(&connection_data.command.command, &connection_data.address);
+        if command_line.trim().is_empty() {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::InvalidInput,
+                "No command set for the address",
+            )));
+        }
+
+        let mut child = shell_command(&command_line)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+
+        let Some(stdout) = child.stdout.take() else {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::BrokenPipe,
+                "Command has no output",
+            )));
+        };
+        let rx = spawn_reader(stdout);
+        // ssh & co. print their errors there
+        if let Some(stderr) = child.stderr.take() {
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    log::warn!("{line}");
+                }
+            });
+        }
+
+        let telnet = if connection_data.command.use_telnet {
+            Some(TelnetProtocol::from_connection_data(connection_data))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            child,
+            stdin,
+            rx: Mutex::new(rx),
+            telnet,
+            keepalive: connection_data.keepalive.telnet_command,
+        })
+    }
+
+    fn write(&mut self, data: &[u8]) -> TermComResult<()> {
+        let Some(stdin) = &mut self.stdin else {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::NotConnected,
+                "Command is closed",
+            )));
+        };
+        if let Err(e) = stdin.write_all(data).and_then(|()| stdin.flush()) {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::ConnectionAborted,
+                format!("Connection aborted: {e}"),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sends the pending negotiation replies of the telnet layer.
+    fn flush_telnet_output(&mut self) -> TermComResult<()> {
+        let output = match &mut self.telnet {
+            Some(telnet) => telnet.take_output(),
+            None => return Ok(()),
+        };
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.write(&output)
+    }
+
+    fn exit_error(&mut self) -> io::Error {
+        let status = match self.child.try_wait() {
+            Ok(Some(status)) => status.to_string(),
+            _ => "output closed".to_string(),
+        };
+        io::Error::new(
+            ErrorKind::ConnectionAborted,
+            format!("Connection aborted: command ended ({status})"),
+        )
+    }
+}
+
+/// Reads the output of a program in a thread, the channel disconnects when the output ends.
+pub(crate) fn spawn_reader<R: Read + Send + 'static>(mut reader: R) -> Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(size) => {
+                    if tx.send(buf[0..size].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(not(windows))]
+fn shell_command(command_line: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(command_line);
+    command
+}
+
+#[cfg(windows)]
+fn shell_command(command_line: &str) -> Command {
+    let mut command = Command::new("cmd");
+    command.arg("/C").arg(command_line);
+    command
+}
+
+/// Replaces %h and %p with host and port of the address, %% is a single %.
+fn expand_command(command: &str, address: &str) -> String {
+    let (host, port) = connector::split_host_port(address, DEFAULT_PORT);
+    let mut result = String::new();
+    let mut chars = command.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => result.push_str(&host),
+            Some('p') => result.push_str(&port.to_string()),
+            Some(ch) if ch != '%' => {
+                result.push('%');
+                result.push(ch);
+            }
+            _ => result.push('%'),
+        }
+    }
+    result
+}
+
+impl Com for ComCommandImpl {
+    fn get_name(&self) -> &'static str {
+        "Command"
+    }
+
+    fn default_port(&self) -> u16 {
+        0
+    }
+
+    fn set_terminal_type(&mut self, terminal: Terminal) {
+        if let Some(telnet) = &mut self.telnet {
+            telnet.set_terminal_type(terminal);
+        }
+    }
+
+    fn read_data(&mut self) -> TermComResult<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+        let rx = self.rx.get_mut().unwrap();
+        loop {
+            match rx.try_recv() {
+                Ok(buf) => data.extend(buf),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if data.is_empty() {
+                        return Err(Box::new(self.exit_error()));
+                    }
+                    break;
+                }
+            }
+        }
+        if data.is_empty() {
+            return Ok(None);
+        }
+        if let Some(telnet) = &mut self.telnet {
+            data = telnet.parse(&data);
+            self.flush_telnet_output()?;
+        }
+        Ok(Some(data))
+    }
+
+    fn send(&mut self, buf: &[u8]) -> TermComResult<usize> {
+        if self.telnet.is_some() {
+            self.write(&TelnetProtocol::encode(buf))?;
+        } else {
+            self.write(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn set_window_size(&mut self, window_size: Size<u16>) -> TermComResult<()> {
+        if let Some(telnet) = &mut self.telnet {
+            telnet.set_window_size(window_size);
+        }
+        self.flush_telnet_output()
+    }
+
+    fn take_charset(&mut self) -> Option<Charset> {
+        self.telnet.as_mut().and_then(TelnetProtocol::take_charset)
+    }
+
+    fn send_keepalive(&mut self) -> TermComResult<()> {
+        if self.telnet.is_some() {
+            self.write(&TelnetProtocol::keepalive(self.keepalive))?;
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> TermComResult<()> {
+        // closing stdin is enough for most programs, kill the rest
+        self.stdin = None;
+        if self.child.try_wait()?.is_none() {
+            self.child.kill()?;
+        }
+        self.child.wait()?;
+        Ok(())
+    }
+}
+
+impl Drop for ComCommandImpl {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.disconnect();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::addresses::{Address, Protocol};
+    use web_time::{Duration, Instant};
+
+    fn connection_data(command: &str, use_telnet: bool) -> OpenConnectionData {
+        let mut adr = Address::new("command".to_string());
+        adr.address = "bbs.example.com:2323".to_string();
+        adr.protocol = Protocol::Command;
+        adr.command.command = command.to_string();
+        adr.command.use_telnet = use_telnet;
+        OpenConnectionData::from(&adr, Duration::from_secs(5), Size::new(80, 25))
+    }
+
+    fn read_until(com: &mut ComCommandImpl, expected: &[u8]) -> Vec<u8> {
+        let start = Instant::now();
+        let mut received = Vec::new();
+        while !received.ends_with(expected) {
+            assert!(start.elapsed() < Duration::from_secs(5), "{received:?}");
+            if let Some(data) = com.read_data().unwrap() {
+                received.extend(data);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        received
+    }
+
+    #[test]
+    fn test_expand_command() {
+        assert_eq!(
+            "ssh -W bbs.example.com:2323 jump 100%",
+            expand_command("ssh -W %h:%p jump 100%%", "bbs.example.com:2323")
+        );
+        assert_eq!("nc bbs 23 %x", expand_command("nc %h %p %x", "bbs"));
+    }
+
+    #[test]
+    fn test_echo() {
+        let mut com = ComCommandImpl::connect(&connection_data("echo %h %p; cat", false)).unwrap();
+        read_until(&mut com, b"bbs.example.com 2323\n");
+        com.send(b"hello\xFF").unwrap();
+        read_until(&mut com, b"hello\xFF");
+        com.disconnect().unwrap();
+    }
+
+    #[test]
+    fn test_telnet_layer() {
+        let mut com = ComCommandImpl::connect(&connection_data(
+            r"printf 'A\377\377B\377\373\001'; cat",
+            true,
+        ))
+        .unwrap();
+        assert_eq!(b"A\xFFB".to_vec(), read_until(&mut com, b"B"));
+        // IAC is doubled and the DO ECHO reply is echoed back by cat
+        com.send(b"\xFF").unwrap();
+        read_until(&mut com, b"\xFF");
+    }
+
+    #[test]
+    fn test_command_exit() {
+        let mut com = ComCommandImpl::connect(&connection_data("exit 3", false)).unwrap();
+        let start = Instant::now();
+        let err = loop {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            match com.read_data() {
+                Ok(_) => thread::sleep(Duration::from_millis(10)),
+                Err(err) => break err,
+            }
+        };
+        assert!(err.to_string().contains("command ended"), "{err}");
+    }
+
+    #[test]
+    fn test_empty_command() {
+        assert!(ComCommandImpl::connect(&connection_data(" ", false)).is_err());
+    }
+}
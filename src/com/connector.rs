@@ -0,0 +1,445 @@
+//! Opens the TCP connections for all socket based connections - directly or through a proxy.
+
+use super::TermComResult;
+use crate::addresses::{ProxySettings, ProxyType};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    error::Error,
+    io::{self, ErrorKind, Read, Write},
+    net::{IpAddr, Ipv4Addr, TcpStream},
+    time::Duration,
+};
+
+/// Timeout for each step of the proxy handshake
+const PROXY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Limit for the HTTP CONNECT response header
+const MAX_HTTP_HEADER_SIZE: usize = 8 * 1024;
+
+mod socks4 {
+    pub const VERSION: u8 = 4;
+    pub const CONNECT: u8 = 1;
+    pub const GRANTED: u8 = 0x5A;
+}
+
+mod socks5 {
+    pub const VERSION: u8 = 5;
+    pub const CONNECT: u8 = 1;
+    pub const SUCCEEDED: u8 = 0;
+
+    pub const NO_AUTHENTICATION: u8 = 0;
+    pub const USER_PASSWORD: u8 = 2;
+    pub const NO_ACCEPTABLE_METHOD: u8 = 0xFF;
+    /// Version of the user/password sub negotiation, see <https://www.rfc-editor.org/rfc/rfc1929>.
+    pub const USER_PASSWORD_VERSION: u8 = 1;
+
+    pub const ATYP_IPV4: u8 = 1;
+    pub const ATYP_DOMAIN: u8 = 3;
+    pub const ATYP_IPV6: u8 = 4;
+}
+
+/// Connects to host:port, through the proxy if one is set.
+pub fn connect(host: &str, port: u16, proxy: &ProxySettings) -> TermComResult<TcpStream> {
+    if proxy.proxy_type == ProxyType::None {
+        return Ok(TcpStream::connect((host, port))?);
+    }
+
+    let (proxy_host, proxy_port) = split_host_port(&proxy.address, proxy.proxy_type.default_port());
+    let mut stream = TcpStream::connect((proxy_host.as_str(), proxy_port)).map_err(|err| {
+        proxy_error(&format!(
+            "can't connect to {proxy_host}:{proxy_port} ({err})"
+        ))
+    })?;
+    stream.set_read_timeout(Some(PROXY_TIMEOUT))?;
+    stream.set_write_timeout(Some(PROXY_TIMEOUT))?;
+
+    match proxy.proxy_type {
+        ProxyType::None => {}
+        ProxyType::Socks4a => socks4a_handshake(&mut stream, host, port, &proxy.user_name)?,
+        ProxyType::Socks5 => socks5_handshake(&mut stream, host, port, proxy)?,
+        ProxyType::Http => http_connect(&mut stream, host, port, proxy)?,
+    }
+
+    stream.set_read_timeout(None)?;
+    stream.set_write_timeout(None)?;
+    Ok(stream)
+}
+
+/// Splits "host:port" - the port is optional.
+pub fn split_host_port(address: &str, default_port: u16) -> (String, u16) {
+    if let Some((host, port)) = address.rsplit_once(':') {
+        // an ipv6 address without port
+        if !host.contains(':') || host.ends_with(']') {
+            if let Ok(port) = port.parse() {
+                return (
+                    host.trim_matches(|c| c == '[' || c == ']').to_string(),
+                    port,
+                );
+            }
+        }
+    }
+    (
+        address.trim_matches(|c| c == '[' || c == ']').to_string(),
+        default_port,
+    )
+}
+
+fn proxy_error(message: &str) -> Box<dyn Error + Send + Sync> {
+    io::Error::new(ErrorKind::ConnectionRefused, format!("Proxy: {message}")).into()
+}
+
+/// See <https://www.openssh.com/txt/socks4a.protocol>
+fn socks4a_handshake(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    user_name: &str,
+) -> TermComResult<()> {
+    let mut request = vec![socks4::VERSION, socks4::CONNECT];
+    request.extend_from_slice(&port.to_be_bytes());
+    if let Ok(ip) = host.parse::<Ipv4Addr>() {
+        request.extend_from_slice(&ip.octets());
+        request.extend_from_slice(user_name.as_bytes());
+        request.push(0);
+    } else {
+        // 0.0.0.x tells the proxy that the host name follows the user
+        request.extend_from_slice(&[0, 0, 0, 1]);
+        request.extend_from_slice(user_name.as_bytes());
+        request.push(0);
+        request.extend_from_slice(host.as_bytes());
+        request.push(0);
+    }
+    stream.write_all(&request)?;
+
+    let mut reply = [0; 8];
+    stream.read_exact(&mut reply)?;
+    if reply[1] != socks4::GRANTED {
+        return Err(proxy_error(&format!(
+            "SOCKS4 request rejected (0x{:02X})",
+            reply[1]
+        )));
+    }
+    Ok(())
+}
+
+/// See <https://www.rfc-editor.org/rfc/rfc1928>
+fn socks5_handshake(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    proxy: &ProxySettings,
+) -> TermComResult<()> {
+    let use_authentication = !proxy.user_name.is_empty();
+    if use_authentication {
+        stream.write_all(&[
+            socks5::VERSION,
+            2,
+            socks5::NO_AUTHENTICATION,
+            socks5::USER_PASSWORD,
+        ])?;
+    } else {
+        stream.write_all(&[socks5::VERSION, 1, socks5::NO_AUTHENTICATION])?;
+    }
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply)?;
+    if reply[0] != socks5::VERSION {
+        return Err(proxy_error("no SOCKS5 proxy"));
+    }
+    match reply[1] {
+        socks5::NO_AUTHENTICATION => {}
+        socks5::USER_PASSWORD if use_authentication => {
+            let mut request = vec![socks5::USER_PASSWORD_VERSION];
+            for field in [&proxy.user_name, &proxy.password] {
+                let len = u8::try_from(field.len())
+                    .map_err(|_| proxy_error("user name or password too long"))?;
+                request.push(len);
+                request.extend_from_slice(field.as_bytes());
+            }
+            stream.write_all(&request)?;
+
+            stream.read_exact(&mut reply)?;
+            if reply[1] != socks5::SUCCEEDED {
+                return Err(proxy_error("authentication failed"));
+            }
+        }
+        socks5::NO_ACCEPTABLE_METHOD => {
+            return Err(proxy_error("no acceptable authentication method"));
+        }
+        method => {
+            return Err(proxy_error(&format!(
+                "unsupported authentication method 0x{method:02X}"
+            )));
+        }
+    }
+
+    let mut request = vec![socks5::VERSION, socks5::CONNECT, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(socks5::ATYP_IPV4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(socks5::ATYP_IPV6);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            let len = u8::try_from(host.len()).map_err(|_| proxy_error("host name too long"))?;
+            request.push(socks5::ATYP_DOMAIN);
+            request.push(len);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[1] != socks5::SUCCEEDED {
+        return Err(proxy_error(&format!(
+            "SOCKS5 request rejected (0x{:02X})",
+            reply[1]
+        )));
+    }
+    // skip the bound address and port
+    let address_len = match reply[3] {
+        socks5::ATYP_IPV4 => 4,
+        socks5::ATYP_IPV6 => 16,
+        socks5::ATYP_DOMAIN => {
+            let mut len = [0; 1];
+            stream.read_exact(&mut len)?;
+            len[0] as usize
+        }
+        atyp => {
+            return Err(proxy_error(&format!("invalid address type 0x{atyp:02X}")));
+        }
+    };
+    let mut bound_address = vec![0; address_len + 2];
+    stream.read_exact(&mut bound_address)?;
+    Ok(())
+}
+
+/// See <https://www.rfc-editor.org/rfc/rfc9110#name-connect>
+fn http_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    proxy: &ProxySettings,
+) -> TermComResult<()> {
+    let authority = if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    };
+    let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+    if !proxy.user_name.is_empty() {
+        let credentials = STANDARD.encode(format!("{}:{}", proxy.user_name, proxy.password));
+        request.push_str("Proxy-Authorization: Basic ");
+        request.push_str(&credentials);
+        request.push_str("\r\n");
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    // read byte by byte - everything after the header belongs to the connection
+    let mut header = Vec::new();
+    let mut buf = [0; 1];
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() >= MAX_HTTP_HEADER_SIZE {
+            return Err(proxy_error("response header too long"));
+        }
+        stream.read_exact(&mut buf)?;
+        header.push(buf[0]);
+    }
+
+    let header = String::from_utf8_lossy(&header);
+    let status_line = header.lines().next().unwrap_or_default();
+    let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+    if !status.starts_with('2') {
+        return Err(proxy_error(status_line));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, thread};
+
+    /// A proxy that expects each request and sends the answer to it.
+    /// After a successful handshake it greets like the target would.
+    type ProxyScript = &'static [(&'static [u8], &'static [u8])];
+
+    fn start_proxy(
+        proxy_type: ProxyType,
+        script: ProxyScript,
+    ) -> (ProxySettings, thread::JoinHandle<bool>) {
+        let listener = TcpListener::bind("localhost:0").unwrap();
+        let proxy = ProxySettings {
+            proxy_type,
+            address: format!("localhost:{}", listener.local_addr().unwrap().port()),
+            ..Default::default()
+        };
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            for (request, answer) in script {
+                let mut received = vec![0; request.len()];
+                stream.read_exact(&mut received).unwrap();
+                assert_eq!(
+                    String::from_utf8_lossy(request),
+                    String::from_utf8_lossy(&received)
+                );
+                stream.write_all(answer).unwrap();
+            }
+            stream.write_all(b"Welcome").is_ok()
+        });
+        (proxy, handle)
+    }
+
+    fn read_welcome(stream: &mut TcpStream) {
+        let mut buf = [0; 7];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(b"Welcome", &buf);
+    }
+
+    #[test]
+    fn test_direct_connection() {
+        let listener = TcpListener::bind("localhost:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"Welcome").unwrap();
+        });
+        let mut stream = connect("localhost", port, &ProxySettings::default()).unwrap();
+        read_welcome(&mut stream);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_socks4a() {
+        let (mut proxy, server) = start_proxy(
+            ProxyType::Socks4a,
+            &[(
+                b"\x04\x01\x00\x17\x00\x00\x00\x01user\0bbs.example.com\0",
+                b"\0\x5A\0\0\0\0\0\0",
+            )],
+        );
+        proxy.user_name = "user".to_string();
+
+        let mut stream = connect("bbs.example.com", 23, &proxy).unwrap();
+        read_welcome(&mut stream);
+        assert!(server.join().unwrap());
+    }
+
+    #[test]
+    fn test_socks4a_rejected() {
+        let (proxy, _server) = start_proxy(
+            ProxyType::Socks4a,
+            &[(b"\x04\x01\x00\x17\x7F\x00\x00\x01\0", b"\0\x5B\0\0\0\0\0\0")],
+        );
+        let err = connect("127.0.0.1", 23, &proxy).unwrap_err();
+        assert!(err.to_string().contains("rejected"), "{err}");
+    }
+
+    #[test]
+    fn test_socks5() {
+        let (proxy, server) = start_proxy(
+            ProxyType::Socks5,
+            &[
+                (b"\x05\x01\x00", b"\x05\x00"),
+                (
+                    b"\x05\x01\x00\x03\x0Fbbs.example.com\x00\x17",
+                    b"\x05\x00\x00\x01\x7F\x00\x00\x01\x04\x38",
+                ),
+            ],
+        );
+        let mut stream = connect("bbs.example.com", 23, &proxy).unwrap();
+        read_welcome(&mut stream);
+        assert!(server.join().unwrap());
+    }
+
+    #[test]
+    fn test_socks5_authentication() {
+        let (mut proxy, server) = start_proxy(
+            ProxyType::Socks5,
+            &[
+                (b"\x05\x02\x00\x02", b"\x05\x02"),
+                (b"\x01\x04user\x06secret", b"\x01\x00"),
+                (
+                    b"\x05\x01\x00\x01\x0A\x00\x00\x01\x00\x17",
+                    b"\x05\x00\x00\x03\x05proxy\x04\x38",
+                ),
+            ],
+        );
+        proxy.user_name = "user".to_string();
+        proxy.password = "secret".to_string();
+
+        let mut stream = connect("10.0.0.1", 23, &proxy).unwrap();
+        read_welcome(&mut stream);
+        assert!(server.join().unwrap());
+    }
+
+    #[test]
+    fn test_socks5_authentication_failed() {
+        let (mut proxy, _server) = start_proxy(
+            ProxyType::Socks5,
+            &[
+                (b"\x05\x02\x00\x02", b"\x05\x02"),
+                (b"\x01\x04user\x05wrong", b"\x01\x01"),
+            ],
+        );
+        proxy.user_name = "user".to_string();
+        proxy.password = "wrong".to_string();
+
+        let err = connect("bbs.example.com", 23, &proxy).unwrap_err();
+        assert!(err.to_string().contains("authentication failed"), "{err}");
+    }
+
+    #[test]
+    fn test_http_connect() {
+        let (mut proxy, server) = start_proxy(
+            ProxyType::Http,
+            &[(
+                b"CONNECT bbs.example.com:23 HTTP/1.1\r\nHost: bbs.example.com:23\r\nProxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n\r\n",
+                b"HTTP/1.1 200 Connection established\r\nProxy-Agent: test\r\n\r\n",
+            )],
+        );
+        proxy.user_name = "user".to_string();
+        proxy.password = "secret".to_string();
+
+        // the data after the header must not get lost
+        let mut stream = connect("bbs.example.com", 23, &proxy).unwrap();
+        read_welcome(&mut stream);
+        assert!(server.join().unwrap());
+    }
+
+    #[test]
+    fn test_http_connect_denied() {
+        let (proxy, _server) = start_proxy(
+            ProxyType::Http,
+            &[(
+                b"CONNECT [::1]:23 HTTP/1.1\r\nHost: [::1]:23\r\n\r\n",
+                b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n",
+            )],
+        );
+        let err = connect("::1", 23, &proxy).unwrap_err();
+        assert!(err.to_string().contains("407"), "{err}");
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(
+            ("bbs.example.com".to_string(), 992),
+            split_host_port("bbs.example.com", 992)
+        );
+        assert_eq!(
+            ("bbs.example.com".to_string(), 2323),
+            split_host_port("bbs.example.com:2323", 992)
+        );
+        assert_eq!(
+            ("::1".to_string(), 2323),
+            split_host_port("[::1]:2323", 992)
+        );
+        assert_eq!(("::1".to_string(), 992), split_host_port("::1", 992));
+    }
+}
@@ -0,0 +1,229 @@
+use super::{command::spawn_reader, rlogin::terminal_name, Com, OpenConnectionData, TermComResult};
+use crate::addresses::Terminal;
+use icy_engine::Size;
+use portable_pty::{native_pty_system, Child, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use std::{
+    io::{self, ErrorKind, Write},
+    sync::{
+        mpsc::{Receiver, TryRecvError},
+        Mutex,
+    },
+};
+
+/// Runs a local program (BBS software, DOSBox with a door, lrzsz, …) in a pseudo terminal.
+/// The address is the command line, it's run by the shell.
+pub struct ComLocalImpl {
+    child: Box<dyn Child + Send + Sync>,
+    /// The pty and writer are in a mutex because `Com` needs to be `Sync`
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    rx: Mutex<Receiver<Vec<u8>>>,
+    window_size: Size<u16>,
+}
+
+impl ComLocalImpl {
+    pub fn connect(connection_data: &OpenConnectionData) -> TermComResult<Self> {
+        let command_line = connection_data.address.trim();
+        if command_line.is_empty() {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::InvalidInput,
+                "No program set for the address",
+            )));
+        }
+
+        let pair = native_pty_system().openpty(pty_size(connection_data.window_size))?;
+        let mut command = shell_command(command_line);
+        command.env("TERM", terminal_name(connection_data.terminal));
+        if !connection_data.local.working_directory.is_empty() {
+            command.cwd(&connection_data.local.working_directory);
+        }
+        let child = pair.slave.spawn_command(command)?;
+        // the reader only ends if the slave side is closed in the child
+        drop(pair.slave);
+
+        let rx = spawn_reader(pair.master.try_clone_reader()?);
+        let writer = pair.master.take_writer()?;
+        Ok(Self {
+            child,
+            master: Mutex::new(pair.master),
+            writer: Mutex::new(writer),
+            rx: Mutex::new(rx),
+            window_size: connection_data.window_size,
+        })
+    }
+
+    fn exit_error(&mut self) -> io::Error {
+        let status = match self.child.try_wait() {
+            Ok(Some(status)) => format!("exit code {}", status.exit_code()),
+            _ => "output closed".to_string(),
+        };
+        io::Error::new(
+            ErrorKind::ConnectionAborted,
+            format!("Connection aborted: program ended ({status})"),
+        )
+    }
+}
+
+fn pty_size(window_size: Size<u16>) -> PtySize {
+    PtySize {
+        rows: window_size.height,
+        cols: window_size.width,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+#[cfg(not(windows))]
+fn shell_command(command_line: &str) -> CommandBuilder {
+    let mut command = CommandBuilder::new("sh");
+    command.args(["-c", command_line]);
+    command
+}
+
+#[cfg(windows)]
+fn shell_command(command_line: &str) -> CommandBuilder {
+    let mut command = CommandBuilder::new("cmd");
+    command.args(["/C", command_line]);
+    command
+}
+
+impl Com for ComLocalImpl {
+    fn get_name(&self) -> &'static str {
+        "Local"
+    }
+
+    fn default_port(&self) -> u16 {
+        0
+    }
+
+    fn set_terminal_type(&mut self, _terminal: Terminal) {}
+
+    fn read_data(&mut self) -> TermComResult<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+        let rx = self.rx.get_mut().unwrap();
+        loop {
+            match rx.try_recv() {
+                Ok(buf) => data.extend(buf),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if data.is_empty() {
+                        return Err(Box::new(self.exit_error()));
+                    }
+                    break;
+                }
+            }
+        }
+        if data.is_empty() {
+            // the ConPTY output doesn't end with the program
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return Err(Box::new(self.exit_error()));
+            }
+            return Ok(None);
+        }
+        Ok(Some(data))
+    }
+
+    fn send(&mut self, buf: &[u8]) -> TermComResult<usize> {
+        let writer = self.writer.get_mut().unwrap();
+        if let Err(e) = writer.write_all(buf).and_then(|()| writer.flush()) {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::ConnectionAborted,
+                format!("Connection aborted: {e}"),
+            )));
+        }
+        Ok(buf.len())
+    }
+
+    fn set_window_size(&mut self, window_size: Size<u16>) -> TermComResult<()> {
+        if self.window_size == window_size {
+            return Ok(());
+        }
+        self.window_size = window_size;
+        self.master
+            .get_mut()
+            .unwrap()
+            .resize(pty_size(window_size))?;
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> TermComResult<()> {
+        if self.child.try_wait()?.is_none() {
+            self.child.kill()?;
+        }
+        self.child.wait()?;
+        Ok(())
+    }
+}
+
+impl Drop for ComLocalImpl {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.disconnect();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::addresses::{Address, Protocol};
+    use std::thread;
+    use web_time::{Duration, Instant};
+
+    fn connection_data(command_line: &str) -> OpenConnectionData {
+        let mut adr = Address::new("local".to_string());
+        adr.address = command_line.to_string();
+        adr.protocol = Protocol::Local;
+        OpenConnectionData::from(&adr, Duration::from_secs(5), Size::new(80, 25))
+    }
+
+    fn read_until(com: &mut ComLocalImpl, expected: &[u8]) -> Vec<u8> {
+        let start = Instant::now();
+        let mut received = Vec::new();
+        while !received.windows(expected.len()).any(|w| w == expected) {
+            assert!(start.elapsed() < Duration::from_secs(5), "{received:?}");
+            if let Some(data) = com.read_data().unwrap() {
+                received.extend(data);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        received
+    }
+
+    #[test]
+    fn test_window_size() {
+        let mut com = ComLocalImpl::connect(&connection_data(
+            "stty size; echo $TERM; read x; stty size; read x",
+        ))
+        .unwrap();
+        let received = read_until(&mut com, b"ansi");
+        assert!(received.windows(5).any(|w| w == b"25 80"), "{received:?}");
+
+        com.set_window_size(Size::new(132, 50)).unwrap();
+        com.send(b"\r").unwrap();
+        read_until(&mut com, b"50 132");
+        com.disconnect().unwrap();
+    }
+
+    #[test]
+    fn test_echo() {
+        let mut com = ComLocalImpl::connect(&connection_data("cat")).unwrap();
+        com.send(b"hello\r").unwrap();
+        read_until(&mut com, b"hello");
+        com.disconnect().unwrap();
+    }
+
+    #[test]
+    fn test_program_exit() {
+        let mut com = ComLocalImpl::connect(&connection_data("exit 3")).unwrap();
+        let start = Instant::now();
+        let err = loop {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            match com.read_data() {
+                Ok(_) => thread::sleep(Duration::from_millis(10)),
+                Err(err) => break err,
+            }
+        };
+        assert!(err.to_string().contains("program ended"), "{err}");
+    }
+}
@@ -11,12 +11,30 @@ pub use telnet::*;
 pub mod raw;
 pub use raw::*;
 
+pub mod connector;
+
 pub mod websocket;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rlogin;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod command;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod local;
+
 #[cfg(not(target_arch = "wasm32"))]
 pub mod ssh;
 
-use crate::{addresses::Terminal, ui::connection::OpenConnectionData};
+#[cfg(not(target_arch = "wasm32"))]
+pub mod serial;
+
+use crate::{
+    addresses::{Charset, Terminal},
+    ui::connection::OpenConnectionData,
+};
+use icy_engine::Size;
 pub type TermComResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 
 pub trait Com: Sync + Send {
@@ -27,6 +45,22 @@ pub trait Com: Sync + Send {
     fn read_data(&mut self) -> TermComResult<Option<Vec<u8>>>;
     fn set_terminal_type(&mut self, terminal: Terminal);
 
+    /// Tells the remote side about a new terminal size (width, height).
+    /// Connections without a terminal size just ignore it.
+    fn set_window_size(&mut self, _window_size: Size<u16>) -> TermComResult<()> {
+        Ok(())
+    }
+
+    /// Returns a charset that was negotiated with the remote side since the last call.
+    fn take_charset(&mut self) -> Option<Charset> {
+        None
+    }
+
+    /// Keeps an idle connection open, connections without a keepalive message do nothing.
+    fn send_keepalive(&mut self) -> TermComResult<()> {
+        Ok(())
+    }
+
     fn disconnect(&mut self) -> TermComResult<()>;
 }
 pub struct NullConnection {}
@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use super::{Com, OpenConnectionData, TermComResult};
+use super::{connector, Com, OpenConnectionData, TermComResult};
 use std::{
     io::{self, ErrorKind, Read, Write},
     net::TcpStream,
@@ -13,7 +13,18 @@ pub struct ComRawImpl {
 
 impl ComRawImpl {
     pub fn connect(connection_data: &OpenConnectionData) -> TermComResult<Self> {
-        let tcp_stream = TcpStream::connect(&connection_data.address)?;
+        // there's no port raw connections could default to
+        let (host, port) = connector::split_host_port(&connection_data.address, 0);
+        if port == 0 {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Raw connections need a port - use host:port instead of '{}'",
+                    connection_data.address
+                ),
+            )));
+        }
+        let tcp_stream = connector::connect(&host, port, &connection_data.proxy)?;
         tcp_stream.set_write_timeout(Some(Duration::from_millis(2000)))?;
         tcp_stream.set_read_timeout(Some(Duration::from_millis(2000)))?;
 
@@ -34,18 +45,18 @@ impl Com for ComRawImpl {
 
     fn read_data(&mut self) -> TermComResult<Option<Vec<u8>>> {
         let mut buf = [0; 1024 * 256];
-        if self.tcp_stream.peek(&mut buf)? == 0 {
-            return Ok(None);
-        }
-
         match self.tcp_stream.read(&mut buf) {
+            Ok(0) => Err(Box::new(io::Error::new(
+                ErrorKind::ConnectionAborted,
+                "Connection closed by remote",
+            ))),
             Ok(size) => {
                 self.tcp_stream.set_nonblocking(true)?;
                 Ok(Some(buf[0..size].to_vec()))
             }
             Err(ref e) => {
                 self.tcp_stream.set_nonblocking(true)?;
-                if e.kind() == io::ErrorKind::WouldBlock {
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) {
                     return Ok(None);
                 }
                 Err(Box::new(io::Error::new(
@@ -78,3 +89,23 @@ impl Com for ComRawImpl {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addresses::{Address, Protocol};
+    use icy_engine::Size;
+
+    #[test]
+    fn test_connect_without_port() {
+        let mut adr = Address::new("raw".to_string());
+        adr.address = "localhost".to_string();
+        adr.protocol = Protocol::Raw;
+        let data =
+            OpenConnectionData::from(&adr, web_time::Duration::from_secs(5), Size::new(80, 25));
+        let Err(err) = ComRawImpl::connect(&data) else {
+            panic!("connected without a port");
+        };
+        assert!(err.to_string().contains("host:port"));
+    }
+}
@@ -0,0 +1,319 @@
+use super::{connector, Com, OpenConnectionData, TermComResult};
+use crate::addresses::Terminal;
+use icy_engine::Size;
+use socket2::SockRef;
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    mem::MaybeUninit,
+    net::TcpStream,
+    time::Duration,
+};
+
+pub const DEFAULT_RLOGIN_PORT: u16 = 513;
+
+/// Terminal speed announced in the handshake, it's only informational for the server.
+const TERMINAL_SPEED: u32 = 38400;
+
+/// Control bytes the server sends as TCP urgent data (RFC 1282)
+mod control {
+    /// Discard all buffered output
+    pub const FLUSH_OUTPUT: u8 = 0x02;
+    /// The server wants to know the window size
+    pub const WINDOW_SIZE_REQUEST: u8 = 0x80;
+}
+
+const WINDOW_SIZE_MAGIC: [u8; 4] = [0xFF, 0xFF, b's', b's'];
+
+/// rlogin connection (RFC 1282).
+///
+/// The handshake sends the client user, the server user and the terminal type.
+/// Door servers (like the one from Synchronet) use the client user as password,
+/// with `RloginSettings::send_password` the address password is sent there.
+pub struct ComRloginImpl {
+    tcp_stream: TcpStream,
+    window_size: Size<u16>,
+    /// The server acknowledges the handshake with a single 0 byte.
+    got_ack: bool,
+    /// Window size changes are only sent after the server asked for them.
+    send_window_size: bool,
+}
+
+impl ComRloginImpl {
+    pub fn connect(connection_data: &OpenConnectionData) -> TermComResult<Self> {
+        let (host, port) =
+            connector::split_host_port(&connection_data.address, DEFAULT_RLOGIN_PORT);
+        let mut tcp_stream = connector::connect(&host, port, &connection_data.proxy)?;
+        tcp_stream.set_write_timeout(Some(Duration::from_millis(2000)))?;
+        tcp_stream.set_read_timeout(Some(Duration::from_millis(2000)))?;
+
+        let client_user =
+            if connection_data.rlogin.send_password && !connection_data.password.is_empty() {
+                &connection_data.password
+            } else {
+                &connection_data.user_name
+            };
+        tcp_stream.write_all(&handshake(
+            client_user,
+            &connection_data.user_name,
+            connection_data.terminal,
+        ))?;
+
+        Ok(Self {
+            tcp_stream,
+            window_size: connection_data.window_size,
+            got_ack: false,
+            send_window_size: false,
+        })
+    }
+
+    /// Reads a pending control byte, the urgent data isn't part of the normal data stream.
+    /// Reads stop at the urgent mark so the byte is still there at the next call.
+    fn read_control_byte(&self) -> Option<u8> {
+        let mut buf = [MaybeUninit::<u8>::uninit()];
+        // fails if there is no urgent data
+        match SockRef::from(&self.tcp_stream).recv_out_of_band(&mut buf) {
+            // SAFETY: recv initialized the first byte
+            Ok(1) => Some(unsafe { buf[0].assume_init() }),
+            _ => None,
+        }
+    }
+
+    fn handle_control_byte(&mut self, control: u8) -> TermComResult<()> {
+        if control & control::WINDOW_SIZE_REQUEST != 0 {
+            self.send_window_size = true;
+            self.tcp_stream
+                .write_all(&window_size_message(self.window_size))?;
+        }
+        if control & control::FLUSH_OUTPUT != 0 {
+            log::debug!("rlogin: server requested output flush");
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn terminal_name(terminal: Terminal) -> &'static str {
+    match terminal {
+        Terminal::Ansi => "ansi",
+        Terminal::Avatar => "avatar",
+        Terminal::Ascii => "dumb",
+        Terminal::PETscii => "petscii",
+        Terminal::ATAscii => "atascii",
+        Terminal::ViewData => "viewdata",
+    }
+}
+
+fn handshake(client_user: &str, server_user: &str, terminal: Terminal) -> Vec<u8> {
+    let mut result = vec![0];
+    for field in [
+        client_user,
+        server_user,
+        &format!("{}/{TERMINAL_SPEED}", terminal_name(terminal)),
+    ] {
+        result.extend_from_slice(field.as_bytes());
+        result.push(0);
+    }
+    result
+}
+
+fn window_size_message(window_size: Size<u16>) -> Vec<u8> {
+    let mut result = WINDOW_SIZE_MAGIC.to_vec();
+    // rows, columns, x pixels, y pixels
+    for value in [window_size.height, window_size.width, 0, 0] {
+        result.extend_from_slice(&value.to_be_bytes());
+    }
+    result
+}
+
+impl Com for ComRloginImpl {
+    fn get_name(&self) -> &'static str {
+        "Rlogin"
+    }
+
+    fn default_port(&self) -> u16 {
+        DEFAULT_RLOGIN_PORT
+    }
+
+    fn set_terminal_type(&mut self, _terminal: Terminal) {}
+
+    fn read_data(&mut self) -> TermComResult<Option<Vec<u8>>> {
+        // a read past the urgent mark discards the urgent data
+        if let Some(control) = self.read_control_byte() {
+            self.handle_control_byte(control)?;
+        }
+
+        let mut buf = [0; 1024 * 256];
+        let result = match self.tcp_stream.read(&mut buf) {
+            Ok(0) => {
+                return Err(Box::new(io::Error::new(
+                    ErrorKind::ConnectionAborted,
+                    "Connection closed by remote",
+                )));
+            }
+            Ok(mut size) => {
+                self.tcp_stream.set_nonblocking(true)?;
+                let mut data = &buf[0..size];
+                if !self.got_ack {
+                    self.got_ack = true;
+                    if data[0] == 0 {
+                        data = &data[1..];
+                        size -= 1;
+                    }
+                }
+                if size == 0 {
+                    None
+                } else {
+                    Some(data.to_vec())
+                }
+            }
+            Err(ref e) => {
+                self.tcp_stream.set_nonblocking(true)?;
+                if !matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) {
+                    return Err(Box::new(io::Error::new(
+                        ErrorKind::ConnectionAborted,
+                        format!("Connection aborted: {e}"),
+                    )));
+                }
+                None
+            }
+        };
+        Ok(result)
+    }
+
+    fn send(&mut self, buf: &[u8]) -> TermComResult<usize> {
+        let r = self.tcp_stream.write_all(buf);
+
+        match r {
+            Ok(()) => Ok(buf.len()),
+            Err(ref e) => {
+                if e.kind() == io::ErrorKind::WouldBlock {
+                    return self.send(buf);
+                }
+                Err(Box::new(io::Error::new(
+                    ErrorKind::ConnectionAborted,
+                    format!("Connection aborted: {e}"),
+                )))
+            }
+        }
+    }
+
+    fn set_window_size(&mut self, window_size: Size<u16>) -> TermComResult<()> {
+        if self.window_size == window_size {
+            return Ok(());
+        }
+        self.window_size = window_size;
+        if self.send_window_size {
+            self.tcp_stream
+                .write_all(&window_size_message(window_size))?;
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> TermComResult<()> {
+        self.tcp_stream.shutdown(std::net::Shutdown::Both)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addresses::{Address, Protocol};
+    use socket2::Socket;
+    use std::{
+        net::TcpListener,
+        sync::mpsc::{channel, Receiver, Sender},
+        thread,
+    };
+
+    fn connection_data(port: u16, send_password: bool) -> OpenConnectionData {
+        let mut adr = Address::new("rlogin".to_string());
+        adr.address = format!("localhost:{port}");
+        adr.protocol = Protocol::Rlogin;
+        adr.user_name = "sysop".to_string();
+        adr.password = "secret".to_string();
+        adr.rlogin.send_password = send_password;
+        adr.terminal_type = Terminal::PETscii;
+        OpenConnectionData::from(&adr, web_time::Duration::from_secs(5), Size::new(80, 25))
+    }
+
+    fn read_exact(stream: &mut TcpStream, len: usize) -> Vec<u8> {
+        let mut buf = vec![0; len];
+        stream.read_exact(&mut buf).unwrap();
+        buf
+    }
+
+    fn read_until(com: &mut ComRloginImpl, expected: &[u8]) {
+        let mut received = Vec::new();
+        while !received.ends_with(expected) {
+            if let Some(data) = com.read_data().unwrap() {
+                received.extend(data);
+            }
+        }
+    }
+
+    /// Runs the server side of the handshake, the server steps are driven by the test.
+    fn start_server(
+        handshake_len: usize,
+    ) -> (u16, Sender<()>, Receiver<Vec<u8>>, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("localhost:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (step_tx, step_rx) = channel();
+        let (data_tx, data_rx) = channel();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            data_tx
+                .send(read_exact(&mut stream, handshake_len))
+                .unwrap();
+            stream.write_all(b"\0Welcome").unwrap();
+
+            // ask for the window size
+            if step_rx.recv().is_err() {
+                return;
+            }
+            Socket::from(stream.try_clone().unwrap())
+                .send_out_of_band(&[control::WINDOW_SIZE_REQUEST])
+                .unwrap();
+            stream.write_all(b"Go").unwrap();
+            data_tx.send(Vec::new()).unwrap();
+            data_tx.send(read_exact(&mut stream, 12)).unwrap();
+
+            // window size change
+            data_tx.send(read_exact(&mut stream, 12)).unwrap();
+        });
+        (port, step_tx, data_rx, handle)
+    }
+
+    #[test]
+    fn test_handshake() {
+        let expected = b"\0sysop\0sysop\0petscii/38400\0";
+        let (port, step, data, server) = start_server(expected.len());
+        let mut com = ComRloginImpl::connect(&connection_data(port, false)).unwrap();
+        assert_eq!(513, com.default_port());
+        assert_eq!(expected.to_vec(), data.recv().unwrap());
+        // the handshake ack isn't part of the data
+        read_until(&mut com, b"Welcome");
+
+        // the window size is sent after the server requested it
+        com.set_window_size(Size::new(132, 50)).unwrap();
+        step.send(()).unwrap();
+        assert!(data.recv().unwrap().is_empty());
+        read_until(&mut com, b"Go");
+        let mut expected = WINDOW_SIZE_MAGIC.to_vec();
+        expected.extend_from_slice(&[0, 50, 0, 132, 0, 0, 0, 0]);
+        assert_eq!(expected, data.recv().unwrap());
+
+        com.set_window_size(Size::new(80, 25)).unwrap();
+        let mut expected = WINDOW_SIZE_MAGIC.to_vec();
+        expected.extend_from_slice(&[0, 25, 0, 80, 0, 0, 0, 0]);
+        assert_eq!(expected, data.recv().unwrap());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_password_is_client_user() {
+        let expected = b"\0secret\0sysop\0petscii/38400\0";
+        let (port, _step, data, _server) = start_server(expected.len());
+        let _com = ComRloginImpl::connect(&connection_data(port, true)).unwrap();
+        assert_eq!(expected.to_vec(), data.recv().unwrap());
+    }
+}
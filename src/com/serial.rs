@@ -0,0 +1,371 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use serialport::SerialPort;
+use web_time::Instant;
+
+use super::{Com, OpenConnectionData, TermComResult};
+use crate::addresses::{FlowControl, Parity, StopBits};
+
+/// Guard time around the "+++" escape sequence
+const ESCAPE_GUARD_TIME: Duration = Duration::from_millis(1100);
+
+pub struct ComSerialImpl {
+    // SerialPort is only Send, the mutex is just there to make Com happy.
+    port: Mutex<Box<dyn SerialPort>>,
+    /// Data that was received together with the CONNECT message
+    buffer: Vec<u8>,
+    dialed: bool,
+}
+
+impl ComSerialImpl {
+    pub fn connect(connection_data: &OpenConnectionData) -> TermComResult<Self> {
+        let settings = &connection_data.serial;
+        let data_bits = match settings.data_bits {
+            5 => serialport::DataBits::Five,
+            6 => serialport::DataBits::Six,
+            7 => serialport::DataBits::Seven,
+            _ => serialport::DataBits::Eight,
+        };
+        let parity = match settings.parity {
+            Parity::None => serialport::Parity::None,
+            Parity::Odd => serialport::Parity::Odd,
+            Parity::Even => serialport::Parity::Even,
+        };
+        let stop_bits = match settings.stop_bits {
+            StopBits::One => serialport::StopBits::One,
+            StopBits::Two => serialport::StopBits::Two,
+        };
+        let flow_control = match settings.flow_control {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Software => serialport::FlowControl::Software,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+        };
+
+        let mut port = serialport::new(&settings.device, settings.baud_rate)
+            .data_bits(data_bits)
+            .parity(parity)
+            .stop_bits(stop_bits)
+            .flow_control(flow_control)
+            .timeout(Duration::from_millis(10))
+            .open()?;
+
+        // not all devices support DTR (for example pseudo terminals)
+        if let Err(err) = port.write_data_terminal_ready(true) {
+            log::warn!("Can't raise DTR on {}: {err}", settings.device);
+        }
+
+        let number = connection_data.address.trim();
+        let dialed = !number.is_empty();
+        let buffer = if dialed {
+            dial(&mut port, number, connection_data.timeout)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            port: Mutex::new(port),
+            buffer,
+            dialed,
+        })
+    }
+
+    fn port(&mut self) -> &mut Box<dyn SerialPort> {
+        match self.port.get_mut() {
+            Ok(port) => port,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Sends the dial string to the modem and waits for the result.
+/// Returns the data the modem sent after the CONNECT message.
+pub fn dial<T: Read + Write + ?Sized>(
+    port: &mut T,
+    number: &str,
+    timeout: Duration,
+) -> TermComResult<Vec<u8>> {
+    // allow complete dial strings like "ATX3DT555-1234"
+    let dial_string = if number.to_ascii_uppercase().starts_with("AT") {
+        format!("{number}\r")
+    } else {
+        format!("ATDT{number}\r")
+    };
+    port.write_all(dial_string.as_bytes())?;
+    port.flush()?;
+
+    let start = Instant::now();
+    let mut line = Vec::new();
+    let mut buf = [0; 256];
+    while start.elapsed() < timeout {
+        let size = match port.read(&mut buf) {
+            Ok(size) => size,
+            Err(err) if matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => 0,
+            Err(err) => return Err(Box::new(err)),
+        };
+        if size == 0 {
+            thread::sleep(Duration::from_millis(10));
+            continue;
+        }
+        for (i, ch) in buf[..size].iter().enumerate() {
+            if *ch != b'\r' && *ch != b'\n' {
+                line.push(*ch);
+                continue;
+            }
+            match parse_modem_response(&line) {
+                Some(Ok(())) => {
+                    let mut rest = &buf[i + 1..size];
+                    if *ch == b'\r' && rest.first() == Some(&b'\n') {
+                        rest = &rest[1..];
+                    }
+                    return Ok(rest.to_vec());
+                }
+                Some(Err(msg)) => return Err(msg.into()),
+                None => line.clear(),
+            }
+        }
+    }
+    Err("Modem didn't answer.".into())
+}
+
+/// Sends the escape sequence with the guard times around it and hangs up.
+pub fn hang_up<T: Write + ?Sized>(port: &mut T, guard_time: Duration) -> io::Result<()> {
+    thread::sleep(guard_time);
+    port.write_all(b"+++")?;
+    port.flush()?;
+    thread::sleep(guard_time);
+    port.write_all(b"ATH0\r")?;
+    port.flush()
+}
+
+/// Checks a line of modem output for a result code.
+/// Returns `None` for lines that don't end the dialing (echo, OK, RING…)
+#[must_use]
+pub fn parse_modem_response(line: &[u8]) -> Option<Result<(), String>> {
+    let line = String::from_utf8_lossy(line).trim().to_ascii_uppercase();
+    if line.starts_with("CONNECT") {
+        return Some(Ok(()));
+    }
+    match line.as_str() {
+        "NO CARRIER" | "BUSY" | "NO DIALTONE" | "NO DIAL TONE" | "NO ANSWER" | "ERROR" => {
+            Some(Err(line))
+        }
+        _ => None,
+    }
+}
+
+impl Com for ComSerialImpl {
+    fn get_name(&self) -> &'static str {
+        "Serial"
+    }
+
+    fn default_port(&self) -> u16 {
+        0
+    }
+
+    fn set_terminal_type(&mut self, _terminal: crate::addresses::Terminal) {}
+
+    fn read_data(&mut self) -> TermComResult<Option<Vec<u8>>> {
+        if !self.buffer.is_empty() {
+            return Ok(Some(std::mem::take(&mut self.buffer)));
+        }
+        let mut buf = [0; 1024 * 8];
+        match self.port().read(&mut buf) {
+            Ok(0) => Ok(None),
+            Ok(size) => Ok(Some(buf[0..size].to_vec())),
+            Err(ref e) => {
+                if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) {
+                    return Ok(None);
+                }
+                Err(Box::new(io::Error::new(
+                    ErrorKind::ConnectionAborted,
+                    format!("Connection aborted: {e}"),
+                )))
+            }
+        }
+    }
+
+    fn send(&mut self, buf: &[u8]) -> TermComResult<usize> {
+        let port = self.port();
+        match port.write_all(buf).and_then(|()| port.flush()) {
+            Ok(()) => Ok(buf.len()),
+            Err(e) => Err(Box::new(io::Error::new(
+                ErrorKind::ConnectionAborted,
+                format!("Connection aborted: {e}"),
+            ))),
+        }
+    }
+
+    /// Drops DTR. If a number was dialed the modem gets "+++" and "ATH0" as well,
+    /// that takes about 2.2 s because of the guard times - a thread waits them out
+    /// so the connection thread isn't blocked.
+    fn disconnect(&mut self) -> TermComResult<()> {
+        let dialed = std::mem::take(&mut self.dialed);
+        let port = self.port();
+        if let Err(err) = port.write_data_terminal_ready(false) {
+            log::warn!("Can't drop DTR: {err}");
+        }
+        if dialed {
+            // modems may be configured to ignore DTR - hang up the hayes way.
+            let mut port = port.try_clone()?;
+            thread::spawn(move || {
+                if let Err(err) = hang_up(&mut port, ESCAPE_GUARD_TIME) {
+                    log::warn!("Can't hang up the modem: {err}");
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Read, Write},
+        time::Duration,
+    };
+
+    use super::{dial, hang_up, parse_modem_response};
+
+    /// Answers a dial string with a fixed modem response.
+    struct TestModem {
+        written: Vec<u8>,
+        response: Vec<u8>,
+    }
+
+    impl Read for TestModem {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.written.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "timeout"));
+            }
+            let size = buf.len().min(self.response.len());
+            buf[..size].copy_from_slice(&self.response[..size]);
+            self.response.drain(..size);
+            Ok(size)
+        }
+    }
+
+    impl Write for TestModem {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn test_modem(response: &[u8]) -> TestModem {
+        TestModem {
+            written: Vec::new(),
+            response: response.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_parse_modem_response() {
+        assert_eq!(Some(Ok(())), parse_modem_response(b"CONNECT"));
+        assert_eq!(Some(Ok(())), parse_modem_response(b"CONNECT 14400/ARQ"));
+        assert_eq!(
+            Some(Err("NO CARRIER".to_string())),
+            parse_modem_response(b"no carrier")
+        );
+        assert_eq!(Some(Err("BUSY".to_string())), parse_modem_response(b"BUSY"));
+        assert_eq!(None, parse_modem_response(b"ATDT5551234"));
+        assert_eq!(None, parse_modem_response(b"RING"));
+        assert_eq!(None, parse_modem_response(b""));
+    }
+
+    #[test]
+    fn test_dial() {
+        let mut modem = test_modem(b"ATDT5551234\r\r\nCONNECT 2400\r\nWelcome");
+        let rest = dial(&mut modem, "5551234", Duration::from_secs(1)).unwrap();
+        assert_eq!(b"ATDT5551234\r".to_vec(), modem.written);
+        assert_eq!(b"Welcome".to_vec(), rest);
+    }
+
+    #[test]
+    fn test_dial_custom_dial_string() {
+        let mut modem = test_modem(b"\r\nCONNECT\r\n");
+        dial(&mut modem, "ATX3DP555", Duration::from_secs(1)).unwrap();
+        assert_eq!(b"ATX3DP555\r".to_vec(), modem.written);
+    }
+
+    #[test]
+    fn test_dial_busy() {
+        let mut modem = test_modem(b"\r\nBUSY\r\n");
+        let err = dial(&mut modem, "5551234", Duration::from_secs(1)).unwrap_err();
+        assert_eq!("BUSY", err.to_string());
+    }
+
+    #[test]
+    fn test_hang_up() {
+        let mut modem = test_modem(b"");
+        hang_up(&mut modem, Duration::ZERO).unwrap();
+        assert_eq!(b"+++ATH0\r".to_vec(), modem.written);
+    }
+
+    #[test]
+    fn test_dial_timeout() {
+        let mut modem = test_modem(b"OK\r\n");
+        assert!(dial(&mut modem, "5551234", Duration::from_millis(50)).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pty_connection() {
+        use crate::{com::Com, ui::connection::OpenConnectionData, Address, Protocol};
+
+        let (mut master, slave) = serialport::TTYPort::pair().expect("can't create pty pair");
+        let device = slave.name().expect("pty has no name");
+        drop(slave);
+
+        let remote = std::thread::spawn(move || {
+            let mut line = Vec::new();
+            let mut buf = [0; 64];
+            while !line.ends_with(b"\r") {
+                if let Ok(size) = master.read(&mut buf) {
+                    line.extend_from_slice(&buf[..size]);
+                }
+            }
+            master.write_all(b"\r\nCONNECT 9600\r\nHello").unwrap();
+            master.flush().unwrap();
+            let mut data = Vec::new();
+            while data.len() < 5 {
+                if let Ok(size) = master.read(&mut buf) {
+                    data.extend_from_slice(&buf[..size]);
+                }
+            }
+            (line, data)
+        });
+
+        let mut address = Address::new("pty".to_string());
+        address.protocol = Protocol::Serial;
+        address.address = "42".to_string();
+        address.serial.device = device;
+        let connection_data = OpenConnectionData::from(
+            &address,
+            web_time::Duration::from_secs(5),
+            icy_engine::Size::new(80, 25),
+        );
+        let mut com = super::ComSerialImpl::connect(&connection_data).unwrap();
+
+        let mut received = Vec::new();
+        while received.len() < 5 {
+            if let Some(data) = com.read_data().unwrap() {
+                received.extend(data);
+            }
+        }
+        assert_eq!(b"Hello".to_vec(), received);
+        com.send(b"World").unwrap();
+
+        let (dial_string, data) = remote.join().unwrap();
+        assert_eq!(b"ATDT42\r".to_vec(), dial_string);
+        assert_eq!(b"World".to_vec(), data);
+    }
+}
@@ -1,10 +1,17 @@
 #![allow(dead_code)]
 
-use super::{Com, OpenConnectionData, TermComResult};
-use libssh_rs::{Channel, Session, SshOption};
+use super::{connector, Com, OpenConnectionData, TermComResult};
+use crate::{
+    addresses::{ProxyType, SshAuthMethod},
+    ui::connection::{HostKeyDecision, HostKeyInfo},
+};
+use libssh_rs::{
+    AuthMethods, AuthStatus, Channel, KnownHosts, PublicKeyHashType, Session, SshKey, SshOption,
+};
 use std::{
     io::ErrorKind,
     io::{Read, Write},
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 pub struct SSHComImpl {
@@ -15,26 +22,50 @@ pub struct SSHComImpl {
 const SUPPORTED_CIPHERS: &str = "aes128-ctr,aes192-ctr,aes256-ctr,aes128-gcm,aes128-gcm@openssh.com,aes256-gcm,aes256-gcm@openssh.com,aes256-cbc,aes192-cbc,aes128-cbc,blowfish-cbc,3des-cbc,arcfour256,arcfour128,cast128-cbc,arcfour";
 const SUPPORTED_KEY_EXCHANGES: &str = "ecdh-sha2-nistp256,ecdh-sha2-nistp384,ecdh-sha2-nistp521,diffie-hellman-group14-sha1,diffie-hellman-group1-sha1";
 
+/// Servers may ask several rounds of questions - don't loop forever on misconfigured ones.
+const MAX_INTERACTIVE_ROUNDS: usize = 8;
+
 impl SSHComImpl {
-    pub fn connect(connection_data: &OpenConnectionData) -> TermComResult<Self> {
+    /// `verify_host_key` is asked for keys that aren't in the known hosts file.
+    pub fn connect(
+        connection_data: &OpenConnectionData,
+        verify_host_key: &mut dyn FnMut(HostKeyInfo) -> HostKeyDecision,
+    ) -> TermComResult<Self> {
         let session = Session::new()?;
         let (host, port) = Self::parse_address(&connection_data.address)?;
 
+        if connection_data.proxy.proxy_type != ProxyType::None {
+            // libssh can't use the proxy itself - it takes over the tunneled socket.
+            let stream = connector::connect(&host, port, &connection_data.proxy)?;
+            #[cfg(unix)]
+            let socket = std::os::unix::io::IntoRawFd::into_raw_fd(stream);
+            #[cfg(windows)]
+            let socket = std::os::windows::io::IntoRawSocket::into_raw_socket(stream);
+            session.set_option(SshOption::Socket(socket))?;
+        }
         session.set_option(SshOption::Hostname(host))?;
         session.set_option(SshOption::Port(port))?;
         session.set_option(SshOption::KeyExchange(SUPPORTED_KEY_EXCHANGES.to_string()))?;
         session.set_option(SshOption::CiphersCS(SUPPORTED_CIPHERS.to_string()))?;
         session.set_option(SshOption::CiphersSC(SUPPORTED_CIPHERS.to_string()))?;
+        let known_hosts = known_hosts_file();
+        if let Some(file) = &known_hosts {
+            session.set_option(SshOption::KnownHosts(Some(
+                file.to_string_lossy().to_string(),
+            )))?;
+        }
 
         session.connect()?;
 
-        //  :TODO: SECURITY: verify_known_hosts() implemented here -- ie: user must accept & we save somewhere
-
-        session.userauth_password(
-            Some(connection_data.user_name.as_str()),
-            Some(connection_data.password.as_str()),
+        Self::verify_known_host(
+            &session,
+            connection_data,
+            known_hosts.as_ref(),
+            verify_host_key,
         )?;
 
+        Self::authenticate(&session, connection_data)?;
+
         let chan = session.new_channel()?;
         chan.open_session()?;
         let terminal_type = connection_data.terminal.to_string().to_lowercase();
@@ -52,6 +83,153 @@ impl SSHComImpl {
         })
     }
 
+    fn verify_known_host(
+        session: &Session,
+        connection_data: &OpenConnectionData,
+        known_hosts: Option<&PathBuf>,
+        verify_host_key: &mut dyn FnMut(HostKeyInfo) -> HostKeyDecision,
+    ) -> TermComResult<()> {
+        let state = session.is_known_server()?;
+        if let KnownHosts::Ok = state {
+            return Ok(());
+        }
+        let key = session.get_server_public_key()?;
+        let fingerprint = format!(
+            "SHA256:{}",
+            key.get_public_key_hash_hexa(PublicKeyHashType::Sha256)?
+        );
+        let changed = matches!(state, KnownHosts::Changed | KnownHosts::Other);
+        let info = HostKeyInfo {
+            host: connection_data.address.clone(),
+            fingerprint: fingerprint.clone(),
+            changed,
+        };
+        let decision = verify_host_key(info);
+        if changed {
+            log::error!(
+                "Host key of {} changed, got {fingerprint}",
+                connection_data.address
+            );
+            return Err(Box::new(std::io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "WARNING: THE HOST KEY OF {} HAS CHANGED! Someone could be eavesdropping on you. New key: {fingerprint}",
+                    connection_data.address
+                ),
+            )));
+        }
+
+        match decision {
+            HostKeyDecision::AcceptOnce => Ok(()),
+            HostKeyDecision::AcceptAndSave => {
+                if let Some(dir) = known_hosts.and_then(|file| file.parent()) {
+                    std::fs::create_dir_all(dir)?;
+                }
+                session.update_known_hosts_file()?;
+                Ok(())
+            }
+            HostKeyDecision::Abort => Err(Box::new(std::io::Error::new(
+                ErrorKind::PermissionDenied,
+                "Host key not accepted.",
+            ))),
+        }
+    }
+
+    /// Tries the authentication methods of the address in order until one succeeds.
+    /// A partial success (multi factor authentication) continues with the next methods.
+    fn authenticate(session: &Session, connection_data: &OpenConnectionData) -> TermComResult<()> {
+        let user_name = Some(connection_data.user_name.as_str());
+        // "none" is required to get the list of methods the server supports
+        if let AuthStatus::Success = session.userauth_none(user_name)? {
+            return Ok(());
+        }
+        let mut methods = session.userauth_list(user_name)?;
+
+        for method in &connection_data.ssh.auth_order {
+            let supported = match method {
+                SshAuthMethod::PublicKey | SshAuthMethod::Agent => {
+                    methods.contains(AuthMethods::PUBLIC_KEY)
+                }
+                SshAuthMethod::KeyboardInteractive => methods.contains(AuthMethods::INTERACTIVE),
+                SshAuthMethod::Password => methods.contains(AuthMethods::PASSWORD),
+            };
+            if !supported {
+                continue;
+            }
+            let result = match method {
+                SshAuthMethod::PublicKey => Self::auth_public_key(session, connection_data),
+                SshAuthMethod::Agent => session.userauth_agent(user_name).map_err(Into::into),
+                SshAuthMethod::KeyboardInteractive => {
+                    Self::auth_keyboard_interactive(session, connection_data)
+                }
+                SshAuthMethod::Password => session
+                    .userauth_password(user_name, Some(connection_data.password.as_str()))
+                    .map_err(Into::into),
+            };
+            match result {
+                Ok(AuthStatus::Success) => return Ok(()),
+                Ok(AuthStatus::Partial) => {
+                    methods = session.userauth_list(user_name)?;
+                }
+                Ok(status) => log::info!("ssh {method} authentication failed: {status:?}"),
+                // a missing agent or key file shouldn't stop trying the other methods
+                Err(err) => log::warn!("ssh {method} authentication failed: {err}"),
+            }
+        }
+        Err(Box::new(std::io::Error::new(
+            ErrorKind::PermissionDenied,
+            "SSH authentication failed.",
+        )))
+    }
+
+    fn auth_public_key(
+        session: &Session,
+        connection_data: &OpenConnectionData,
+    ) -> TermComResult<AuthStatus> {
+        let user_name = Some(connection_data.user_name.as_str());
+        let passphrase = if connection_data.ssh.key_passphrase.is_empty() {
+            None
+        } else {
+            Some(connection_data.ssh.key_passphrase.as_str())
+        };
+        if connection_data.ssh.key_file.is_empty() {
+            // default keys from ~/.ssh
+            return Ok(session.userauth_public_key_auto(user_name, passphrase)?);
+        }
+        let key_file = expand_home_dir(&connection_data.ssh.key_file);
+        let key = SshKey::from_privkey_file(&key_file, passphrase)?;
+        Ok(session.userauth_publickey(user_name, &key)?)
+    }
+
+    fn auth_keyboard_interactive(
+        session: &Session,
+        connection_data: &OpenConnectionData,
+    ) -> TermComResult<AuthStatus> {
+        let user_name = Some(connection_data.user_name.as_str());
+        let mut status = session.userauth_keyboard_interactive(user_name, None)?;
+        for _ in 0..MAX_INTERACTIVE_ROUNDS {
+            if !matches!(status, AuthStatus::Info) {
+                break;
+            }
+            let info = session.userauth_keyboard_interactive_info()?;
+            let answers: Vec<String> = info
+                .prompts
+                .iter()
+                .map(|prompt| {
+                    // visible prompts ask for the user name, hidden ones for the password
+                    if prompt.echo {
+                        connection_data.user_name.clone()
+                    } else {
+                        connection_data.password.clone()
+                    }
+                })
+                .collect();
+            session.userauth_keyboard_interactive_set_answers(&answers)?;
+            status = session.userauth_keyboard_interactive(user_name, None)?;
+        }
+        Ok(status)
+    }
+
     fn default_port() -> u16 {
         22
     }
@@ -74,6 +252,25 @@ impl SSHComImpl {
     }
 }
 
+/// known_hosts file in the icy_term config directory
+fn known_hosts_file() -> Option<PathBuf> {
+    directories::ProjectDirs::from("com", "GitHub", "icy_term")
+        .map(|proj_dirs| proj_dirs.config_dir().join("known_hosts"))
+}
+
+fn expand_home_dir(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(user_dirs) = directories::UserDirs::new() {
+            return user_dirs
+                .home_dir()
+                .join(rest)
+                .to_string_lossy()
+                .to_string();
+        }
+    }
+    path.to_string()
+}
+
 impl Com for SSHComImpl {
     fn get_name(&self) -> &'static str {
         "SSH"
@@ -123,6 +320,26 @@ impl Com for SSHComImpl {
         }
     }
 
+    fn set_window_size(&mut self, window_size: icy_engine::Size<u16>) -> TermComResult<()> {
+        match self.channel.lock() {
+            Ok(locked) => {
+                locked
+                    .change_pty_size(u32::from(window_size.width), u32::from(window_size.height))?;
+                Ok(())
+            }
+            Err(err) => Err(Box::new(std::io::Error::new(
+                ErrorKind::ConnectionAborted,
+                format!("Can't lock channel: {err}"),
+            ))),
+        }
+    }
+
+    fn send_keepalive(&mut self) -> TermComResult<()> {
+        // SSH_MSG_IGNORE, it's dropped by the server
+        self.session.send_ignore(&[])?;
+        Ok(())
+    }
+
     fn disconnect(&mut self) -> TermComResult<()> {
         self.session.disconnect();
         Ok(())
@@ -0,0 +1,161 @@
+use super::{connector, Com, TermComResult};
+use crate::addresses::{Protocol, TelnetKeepAlive};
+use icy_engine::Size;
+use std::io::{self, ErrorKind, Read, Write};
+use web_time::Duration;
+
+mod protocol;
+pub(crate) use protocol::TelnetProtocol;
+
+mod tls;
+use tls::{TelnetStream, DEFAULT_TLS_PORT};
+
+mod tests;
+
+const DEFAULT_PORT: u16 = 23;
+
+#[derive(Debug)]
+pub struct ComTelnetImpl {
+    tcp_stream: TelnetStream,
+    protocol: TelnetProtocol,
+    use_raw_transfer: bool,
+    is_tls: bool,
+    keepalive: TelnetKeepAlive,
+}
+
+#[allow(dead_code)]
+impl ComTelnetImpl {
+    pub fn connect(connection_data: &super::OpenConnectionData) -> TermComResult<Self> {
+        let is_tls = connection_data.protocol == Protocol::TelnetTls;
+        let default_port = if is_tls {
+            DEFAULT_TLS_PORT
+        } else {
+            DEFAULT_PORT
+        };
+        let (host, port) = connector::split_host_port(&connection_data.address, default_port);
+        let tcp_stream = connector::connect(&host, port, &connection_data.proxy)?;
+        tcp_stream.set_write_timeout(Some(Duration::from_millis(2000)))?;
+        tcp_stream.set_read_timeout(Some(Duration::from_millis(2000)))?;
+        let tcp_stream = if is_tls {
+            tls::connect_tls(tcp_stream, &host, &connection_data.tls)?
+        } else {
+            TelnetStream::Plain(tcp_stream)
+        };
+        Ok(Self {
+            tcp_stream,
+            protocol: TelnetProtocol::from_connection_data(connection_data),
+            use_raw_transfer: false,
+            is_tls,
+            keepalive: connection_data.keepalive.telnet_command,
+        })
+    }
+
+    /// Sends the pending negotiation replies to the server.
+    fn flush_protocol_output(&mut self) -> TermComResult<()> {
+        let output = self.protocol.take_output();
+        if !output.is_empty() {
+            self.tcp_stream.write_all(&output)?;
+        }
+        Ok(())
+    }
+
+    fn parse(&mut self, data: &[u8]) -> TermComResult<Option<Vec<u8>>> {
+        if self.use_raw_transfer {
+            return Ok(Some(data.to_vec()));
+        }
+        let buf = self.protocol.parse(data);
+        self.flush_protocol_output()?;
+        Ok(Some(buf))
+    }
+}
+
+impl Com for ComTelnetImpl {
+    fn get_name(&self) -> &'static str {
+        "Telnet"
+    }
+
+    fn default_port(&self) -> u16 {
+        if self.is_tls {
+            DEFAULT_TLS_PORT
+        } else {
+            DEFAULT_PORT
+        }
+    }
+
+    fn set_terminal_type(&mut self, terminal: crate::addresses::Terminal) {
+        self.protocol.set_terminal_type(terminal);
+    }
+
+    fn read_data(&mut self) -> TermComResult<Option<Vec<u8>>> {
+        let mut buf = [0; 1024 * 256];
+        match self.tcp_stream.read(&mut buf) {
+            Ok(0) => Err(Box::new(io::Error::new(
+                ErrorKind::ConnectionAborted,
+                "Connection closed by remote",
+            ))),
+            Ok(size) => {
+                self.tcp_stream.set_nonblocking(true)?;
+                self.parse(&buf[0..size])
+            }
+            Err(ref e) => {
+                self.tcp_stream.set_nonblocking(true)?;
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) {
+                    return Ok(None);
+                }
+                Err(Box::new(io::Error::new(
+                    ErrorKind::ConnectionAborted,
+                    format!("Connection aborted: {e}"),
+                )))
+            }
+        }
+    }
+
+    fn send(&mut self, buf: &[u8]) -> TermComResult<usize> {
+        let r = if self.use_raw_transfer {
+            self.tcp_stream.write_all(buf)
+        } else {
+            self.tcp_stream.write_all(&TelnetProtocol::encode(buf))
+        };
+
+        match r {
+            Ok(()) => Ok(buf.len()),
+            Err(ref e) => {
+                if e.kind() == io::ErrorKind::WouldBlock {
+                    return self.send(buf);
+                }
+                Err(Box::new(io::Error::new(
+                    ErrorKind::ConnectionAborted,
+                    format!("Connection aborted: {e}"),
+                )))
+            }
+        }
+    }
+
+    fn set_window_size(&mut self, window_size: Size<u16>) -> TermComResult<()> {
+        self.protocol.set_window_size(window_size);
+        if self.use_raw_transfer {
+            // don't mix negotiation into a raw transfer
+            return Ok(());
+        }
+        self.flush_protocol_output()
+    }
+
+    fn take_charset(&mut self) -> Option<crate::addresses::Charset> {
+        self.protocol.take_charset()
+    }
+
+    fn send_keepalive(&mut self) -> TermComResult<()> {
+        if self.use_raw_transfer {
+            return Ok(());
+        }
+        self.tcp_stream
+            .write_all(&TelnetProtocol::keepalive(self.keepalive))?;
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> TermComResult<()> {
+        self.tcp_stream.shutdown()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,955 @@
+use icy_engine::Size;
+
+use crate::{
+    addresses::{Charset, TelnetKeepAlive, Terminal},
+    ui::connection::OpenConnectionData,
+};
+
+/// Subnegotiations larger than that are cut - no option we support needs that much.
+const MAX_SUBNEGOTIATION_SIZE: usize = 4096;
+
+pub mod terminal_type {
+    pub const IS: u8 = 0x00;
+    pub const SEND: u8 = 0x01;
+    // pub const MAXLN: usize = 40;
+}
+
+/// <https://www.rfc-editor.org/rfc/rfc1572>
+pub mod new_environ {
+    pub const IS: u8 = 0x00;
+    pub const SEND: u8 = 0x01;
+    // pub const INFO: u8 = 0x02;
+
+    pub const VAR: u8 = 0x00;
+    pub const VALUE: u8 = 0x01;
+    pub const ESC: u8 = 0x02;
+    pub const USERVAR: u8 = 0x03;
+
+    /// Variables defined by the RFC, everything else is sent as USERVAR.
+    pub const WELL_KNOWN_VARS: [&str; 6] =
+        ["USER", "JOB", "ACCT", "PRINTER", "SYSTEMTYPE", "DISPLAY"];
+}
+
+/// <https://www.rfc-editor.org/rfc/rfc2066>
+pub mod charset {
+    pub const REQUEST: u8 = 0x01;
+    pub const ACCEPTED: u8 = 0x02;
+    pub const REJECTED: u8 = 0x03;
+    pub const TTABLE_IS: u8 = 0x04;
+    pub const TTABLE_REJECTED: u8 = 0x05;
+}
+
+pub mod telnet_cmd {
+    use crate::com::TermComResult;
+
+    /// End of subnegotiation parameters.
+    pub const SE: u8 = 0xF0;
+
+    /// No operation.
+    pub const Nop: u8 = 0xF1;
+
+    /// The data stream portion of a Synch.
+    /// This should always be accompanied
+    /// by a TCP Urgent notification.
+    pub const DataMark: u8 = 0xF2;
+
+    /// NVT character BRK
+    pub const Break: u8 = 0xF3;
+
+    /// The function Interrupt Process
+    pub const IP: u8 = 0xF4;
+
+    // The function Abort output
+    pub const AO: u8 = 0xF5;
+
+    // The function Are You There
+    pub const Ayt: u8 = 0xF6;
+
+    // The function Erase character
+    pub const EC: u8 = 0xF7;
+
+    // The function Erase line
+    pub const EL: u8 = 0xF8;
+
+    // The Go ahead signal.
+    pub const GA: u8 = 0xF9;
+
+    // Indicates that what follows is subnegotiation of the indicated option.
+    pub const SB: u8 = 0xFA;
+
+    ///  (option code)
+    /// Indicates the desire to begin performing, or confirmation that you are now performing, the indicated option.
+    pub const Will: u8 = 0xFB;
+
+    /// (option code)
+    /// Indicates the refusal to perform, or continue performing, the indicated option.
+    pub const Wont: u8 = 0xFC;
+
+    /// (option code)
+    /// Indicates the request that the other party perform, or confirmation that you are expecting
+    /// the other party to perform, the indicated option.
+    pub const DO: u8 = 0xFD;
+
+    /// (option code)
+    /// Indicates the demand that the other party stop performing,
+    /// or confirmation that you are no longer expecting the other party
+    /// to perform, the indicated option.
+    pub const Dont: u8 = 0xFE;
+
+    /// Data Byte 255.
+    pub const Iac: u8 = 0xFF;
+
+    pub fn make_cmd(byte: u8) -> [u8; 2] {
+        [Iac, byte]
+    }
+
+    pub fn make_cmd_with_option(byte: u8, option: u8) -> [u8; 3] {
+        [Iac, byte, option]
+    }
+
+    pub fn check(byte: u8) -> TermComResult<u8> {
+        match byte {
+            0xF0..=0xFF => Ok(byte),
+            _ => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unknown IAC: {byte}/x{byte:02X}"),
+            ))),
+        }
+    }
+
+    pub fn to_string(byte: u8) -> &'static str {
+        match byte {
+            SE => "SE",
+            Nop => "Nop",
+            DataMark => "DataMark",
+            Break => "Break",
+            IP => "IP",
+            AO => "AO",
+            Ayt => "Ayt",
+            EC => "EC",
+            EL => "EL",
+            GA => "GA",
+            SB => "SB",
+            Will => "Will",
+            Wont => "Wont",
+            DO => "DO",
+            Dont => "Dont",
+            Iac => "Iac",
+            _ => "unknown",
+        }
+    }
+}
+
+/**
+<http://www.iana.org/assignments/telnet-options/telnet-options.xhtml>
+*/
+pub mod telnet_option {
+    /// <https://www.rfc-editor.org/rfc/rfc856>
+    pub const TransmitBinary: u8 = 0x00;
+    /// <https://www.rfc-editor.org/rfc/rfc857>
+    pub const Echo: u8 = 0x01;
+    /// ???
+    pub const Reconnection: u8 = 0x02;
+    /// <https://www.rfc-editor.org/rfc/rfc858>
+    pub const SuppressGoAhead: u8 = 0x03;
+    /// <https://www.rfc-editor.org/rfc/rfc859>
+    pub const Status: u8 = 0x05;
+    /// <https://www.rfc-editor.org/rfc/rfc860>
+    pub const TimingMark: u8 = 0x06;
+    /// <https://www.rfc-editor.org/rfc/rfc726.html>
+    pub const RemoteControlledTransAndEcho: u8 = 0x07;
+    /// ???
+    pub const OutputLineWidth: u8 = 0x08;
+    /// ???
+    pub const OutputPageSize: u8 = 0x09;
+    ///<https://www.rfc-editor.org/rfc/RFC652>
+    pub const OutputCarriageReturnDisposition: u8 = 10;
+    ///<https://www.rfc-editor.org/rfc/RFC653>
+    pub const OutputHorizontalTabStops: u8 = 11;
+    ///<https://www.rfc-editor.org/rfc/RFC654>
+    pub const OutputHorizontalTabDisposition: u8 = 12;
+    ///<https://www.rfc-editor.org/rfc/RFC655>
+    pub const OutputFormfeedDisposition: u8 = 13;
+    ///<https://www.rfc-editor.org/rfc/RFC656>
+    pub const OutputVerticalTabstops: u8 = 14;
+    ///<https://www.rfc-editor.org/rfc/RFC657>
+    pub const OutputVerticalTabDisposition: u8 = 15;
+    ///<https://www.rfc-editor.org/rfc/RFC658>
+    pub const OutputLinefeedDisposition: u8 = 16;
+    ///<https://www.rfc-editor.org/rfc/RFC698>
+    pub const ExtendedASCII: u8 = 17;
+    ///<https://www.rfc-editor.org/rfc/RFC727>
+    pub const Logout: u8 = 18;
+    ///<https://www.rfc-editor.org/rfc/RFC735>
+    pub const ByteMacro: u8 = 19;
+    ///<https://www.rfc-editor.org/rfc/RFC1043][RFC732>
+    pub const DataEntryTerminal: u8 = 20;
+    ///<https://www.rfc-editor.org/rfc/RFC736][RFC734>
+    pub const SupDup: u8 = 21;
+    ///<https://www.rfc-editor.org/rfc/RFC749>
+    pub const SupDupOutput: u8 = 22;
+    ///<https://www.rfc-editor.org/rfc/RFC779>
+    pub const SendLocation: u8 = 23;
+    /// <https://www.rfc-editor.org/rfc/rfc1091>
+    pub const TerminalType: u8 = 24;
+    /// <https://www.rfc-editor.org/rfc/rfc885>
+    pub const EndOfRecord: u8 = 25;
+    /// <https://www.rfc-editor.org/rfc/rfc1073>
+    pub const NegotiateAboutWindowSize: u8 = 31;
+    /// <https://www.rfc-editor.org/rfc/rfc1079>
+    pub const TerminalSpeed: u8 = 32;
+    /// <https://www.rfc-editor.org/rfc/rfc1372>
+    pub const ToggleFlowControl: u8 = 33;
+    /// <https://www.rfc-editor.org/rfc/rfc1184>
+    pub const LineMode: u8 = 34;
+    /// <https://www.rfc-editor.org/rfc/rfc1096>
+    pub const XDisplayLocation: u8 = 35;
+    /// <https://www.rfc-editor.org/rfc/rfc1408>
+    pub const EnvironmentOption: u8 = 36;
+    /// <https://www.rfc-editor.org/rfc/rfc2941>
+    pub const Authentication: u8 = 37;
+    /// <https://www.rfc-editor.org/rfc/rfc2946>
+    pub const Encrypt: u8 = 38;
+    /// <https://www.rfc-editor.org/rfc/rfc1572>
+    pub const NewEnviron: u8 = 39;
+    ///<https://www.rfc-editor.org/rfc/RFC2355>
+    pub const TN3270E: u8 = 40;
+    ///<https://www.rfc-editor.org/rfc/Rob_Earhart>
+    pub const XAuth: u8 = 41;
+    ///<https://www.rfc-editor.org/rfc/RFC2066>
+    pub const CharSet: u8 = 42;
+    ///<https://www.rfc-editor.org/rfc/Robert_Barnes>
+    pub const TelnetRemoteSerialPortRSP: u8 = 43;
+    ///<https://www.rfc-editor.org/rfc/RFC2217>
+    pub const ComPortControlOption: u8 = 44;
+    ///<https://www.rfc-editor.org/rfc/Wirt_Atmar>
+    pub const TelnetSuppressLocalEcho: u8 = 45;
+    ///<https://www.rfc-editor.org/rfc/Michael_Boe>
+    pub const TelnetStartTLS: u8 = 46;
+    ///<https://www.rfc-editor.org/rfc/RFC2840>
+    pub const Kermit: u8 = 47;
+    ///<https://www.rfc-editor.org/rfc/David_Croft>
+    pub const SendURL: u8 = 48;
+    ///<https://www.rfc-editor.org/rfc/Jeffrey_Altman>
+    pub const ForwardX: u8 = 49;
+    // 50-137 	Unassigned
+    pub const TelOptPragmaLogon: u8 = 138;
+    ///<https://www.rfc-editor.org/rfc/Steve_McGregory>
+    pub const TelOptSSPILogon: u8 = 139;
+    ///<https://www.rfc-editor.org/rfc/Steve_McGregory>
+    pub const TelOptPragmaHeartbeat: u8 = 140;
+    ///<https://www.rfc-editor.org/rfc/Steve_McGregory>
+    // 141-254 	Unassigned
+    /// <https://www.rfc-editor.org/rfc/rfc861>
+    pub const ExtendedOptionsList: u8 = 0xFF;
+
+    pub fn to_string(byte: u8) -> &'static str {
+        match byte {
+            TransmitBinary => "TransmitBinary",
+            Echo => "Echo",
+            Reconnection => "Reconnection",
+            SuppressGoAhead => "SuppressGoAhead",
+            Status => "Status",
+            TimingMark => "TimingMark",
+            RemoteControlledTransAndEcho => "RemoteControlledTransAndEcho",
+            OutputLineWidth => "OutputLineWidth",
+            OutputPageSize => "OutputPageSize",
+            OutputCarriageReturnDisposition => "OutputCarriageReturnDisposition",
+            OutputHorizontalTabStops => "OutputHorizontalTabStops",
+            OutputHorizontalTabDisposition => "OutputHorizontalTabDisposition",
+            OutputFormfeedDisposition => "OutputFormfeedDisposition",
+            OutputVerticalTabstops => "OutputVerticalTabstops",
+            OutputVerticalTabDisposition => "OutputVerticalTabDisposition",
+            OutputLinefeedDisposition => "OutputLinefeedDisposition",
+            ExtendedASCII => "ExtendedASCII",
+            Logout => "Logout",
+            ByteMacro => "ByteMacro",
+            DataEntryTerminal => "DataEntryTerminal",
+            SupDup => "SupDup",
+            SupDupOutput => "SupDupOutput",
+            SendLocation => "SendLocation",
+            TerminalType => "TerminalType",
+            EndOfRecord => "EndOfRecord",
+            NegotiateAboutWindowSize => "NegotiateAboutWindowSize",
+            TerminalSpeed => "TerminalSpeed",
+            ToggleFlowControl => "ToggleFlowControl",
+            LineMode => "LineMode",
+            XDisplayLocation => "XDisplayLocation",
+            EnvironmentOption => "EnvironmentOption",
+            Authentication => "Authentication",
+            Encrypt => "Encrypt",
+            NewEnviron => "NewEnviron",
+            TN3270E => "TN3270E",
+            XAuth => "XAuth",
+            CharSet => "CharSet",
+            TelnetRemoteSerialPortRSP => "TelnetRemoteSerialPortRSP",
+            ComPortControlOption => "ComPortControlOption",
+            TelnetSuppressLocalEcho => "TelnetSuppressLocalEcho",
+            TelnetStartTLS => "TelnetStartTLS",
+            Kermit => "Kermit",
+            SendURL => "SendURL",
+            ForwardX => "ForwardX",
+            TelOptPragmaLogon => "TelOptPragmaLogon",
+            TelOptSSPILogon => "TelOptSSPILogon",
+            TelOptPragmaHeartbeat => "TelOptPragmaHeartbeat",
+            ExtendedOptionsList => "ExtendedOptionsList",
+            _ => "Unknown",
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParserState {
+    Data,
+    Iac,
+    Will,
+    Wont,
+    Do,
+    Dont,
+    /// IAC SB - next byte is the option
+    SubNegotiation,
+    SubNegotiationData,
+    SubNegotiationIac,
+}
+
+/// Option state of the Q method <https://www.rfc-editor.org/rfc/rfc1143>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum QState {
+    #[default]
+    No,
+    Yes,
+    WantNo,
+    WantYes,
+}
+
+/// The queue bit of the Q method - set if the opposite was requested while negotiating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Queue {
+    #[default]
+    Empty,
+    Opposite,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct OptionState {
+    state: QState,
+    queue: Queue,
+}
+
+/// Which side performs an option: `Us` is negotiated with WILL/WONT, `Him` with DO/DONT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Us,
+    Him,
+}
+
+impl Side {
+    fn enable_cmd(self) -> u8 {
+        match self {
+            Side::Us => telnet_cmd::Will,
+            Side::Him => telnet_cmd::DO,
+        }
+    }
+
+    fn disable_cmd(self) -> u8 {
+        match self {
+            Side::Us => telnet_cmd::Wont,
+            Side::Him => telnet_cmd::Dont,
+        }
+    }
+}
+
+/// The telnet protocol without the transport. Data from the server goes through `parse`,
+/// everything that needs to be sent back to the server is collected and can be fetched
+/// with `take_output`.
+#[derive(Debug)]
+pub struct TelnetProtocol {
+    state: ParserState,
+    us: [OptionState; 256],
+    him: [OptionState; 256],
+
+    sb_option: u8,
+    sb_data: Vec<u8>,
+
+    window_size: Size<u16>,
+    terminal: Terminal,
+    terminal_types: Vec<String>,
+    /// Variables sent with NEW-ENVIRON as (type, name, value)
+    environment: Vec<(u8, String, String)>,
+    /// Preferred charsets for CHARSET
+    charsets: Vec<Charset>,
+    /// Charset the server agreed to, not yet picked up by `take_charset`
+    negotiated_charset: Option<Charset>,
+    /// Position in `terminal_types` for the next TTYPE request
+    terminal_type_index: usize,
+
+    output: Vec<u8>,
+}
+
+impl TelnetProtocol {
+    pub fn new(window_size: Size<u16>, terminal: Terminal) -> Self {
+        Self {
+            state: ParserState::Data,
+            us: [OptionState::default(); 256],
+            him: [OptionState::default(); 256],
+            sb_option: 0,
+            sb_data: Vec::new(),
+            window_size,
+            terminal,
+            terminal_types: Vec::new(),
+            environment: Vec::new(),
+            charsets: Vec::new(),
+            negotiated_charset: None,
+            terminal_type_index: 0,
+            output: Vec::new(),
+        }
+    }
+
+    /// Sets up the negotiation with the telnet settings of the address.
+    pub fn from_connection_data(connection_data: &OpenConnectionData) -> Self {
+        let mut protocol = Self::new(connection_data.window_size, connection_data.terminal);
+        protocol.set_terminal_types(&connection_data.telnet.terminal_types);
+        protocol.set_environment(
+            &connection_data.user_name,
+            &connection_data.telnet.environment,
+        );
+        protocol.set_charsets(&connection_data.telnet.charsets);
+        protocol
+    }
+
+    pub fn set_terminal_type(&mut self, terminal: Terminal) {
+        self.terminal = terminal;
+    }
+
+    /// Names that are answered in sequence to TTYPE requests, the terminal type is used if empty.
+    pub fn set_terminal_types(&mut self, terminal_types: &[String]) {
+        self.terminal_types = terminal_types
+            .iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        self.terminal_type_index = 0;
+    }
+
+    /// Sets the NEW-ENVIRON variables, the user name is sent as USER.
+    /// NEW-ENVIRON is refused if there is nothing to send.
+    pub fn set_environment(&mut self, user_name: &str, variables: &[(String, String)]) {
+        self.environment.clear();
+        if !user_name.is_empty() {
+            self.environment
+                .push((new_environ::VAR, "USER".to_string(), user_name.to_string()));
+        }
+        for (name, value) in variables {
+            let name = name.trim();
+            if name.is_empty() || name == "USER" && !user_name.is_empty() {
+                continue;
+            }
+            let var_type = if new_environ::WELL_KNOWN_VARS.contains(&name) {
+                new_environ::VAR
+            } else {
+                new_environ::USERVAR
+            };
+            self.environment
+                .push((var_type, name.to_string(), value.clone()));
+        }
+    }
+
+    /// Sets the preferred charsets, CHARSET is refused if the list is empty.
+    pub fn set_charsets(&mut self, charsets: &[Charset]) {
+        self.charsets = charsets.to_vec();
+    }
+
+    /// The charset that was agreed on since the last call.
+    pub fn take_charset(&mut self) -> Option<Charset> {
+        self.negotiated_charset.take()
+    }
+
+    /// Sends a NAWS update if the server asked for the window size.
+    pub fn set_window_size(&mut self, window_size: Size<u16>) {
+        self.window_size = window_size;
+        if self.is_enabled(Side::Us, telnet_option::NegotiateAboutWindowSize) {
+            self.send_window_size();
+        }
+    }
+
+    pub fn is_enabled(&self, side: Side, option: u8) -> bool {
+        self.option_state(side, option).state == QState::Yes
+    }
+
+    /// Everything that needs to be sent to the server.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// The command that keeps an idle connection open.
+    pub fn keepalive(command: TelnetKeepAlive) -> [u8; 2] {
+        match command {
+            TelnetKeepAlive::Nop => telnet_cmd::make_cmd(telnet_cmd::Nop),
+            TelnetKeepAlive::AreYouThere => telnet_cmd::make_cmd(telnet_cmd::Ayt),
+        }
+    }
+
+    /// Escapes IAC in data that is sent to the server.
+    pub fn encode(data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(data.len());
+        for b in data {
+            if *b == telnet_cmd::Iac {
+                result.push(telnet_cmd::Iac);
+            }
+            result.push(*b);
+        }
+        result
+    }
+
+    /// Parses data from the server and returns the payload without telnet commands.
+    pub fn parse(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(data.len());
+        for b in data {
+            self.parse_byte(*b, &mut result);
+        }
+        result
+    }
+
+    fn parse_byte(&mut self, b: u8, result: &mut Vec<u8>) {
+        match self.state {
+            ParserState::Data => {
+                if b == telnet_cmd::Iac {
+                    self.state = ParserState::Iac;
+                } else {
+                    result.push(b);
+                }
+            }
+            ParserState::Iac => {
+                self.state = ParserState::Data;
+                match telnet_cmd::check(b) {
+                    Ok(telnet_cmd::Iac) => result.push(telnet_cmd::Iac),
+                    Ok(telnet_cmd::Will) => self.state = ParserState::Will,
+                    Ok(telnet_cmd::Wont) => self.state = ParserState::Wont,
+                    Ok(telnet_cmd::DO) => self.state = ParserState::Do,
+                    Ok(telnet_cmd::Dont) => self.state = ParserState::Dont,
+                    Ok(telnet_cmd::SB) => self.state = ParserState::SubNegotiation,
+                    Ok(telnet_cmd::Ayt) => {
+                        self.output
+                            .extend_from_slice(&telnet_cmd::make_cmd(telnet_cmd::Nop));
+                    }
+                    Ok(telnet_cmd::SE | telnet_cmd::Nop | telnet_cmd::GA) => {}
+                    Ok(cmd) => {
+                        log::warn!("unsupported IAC: {}", telnet_cmd::to_string(cmd));
+                    }
+                    Err(err) => {
+                        log::error!("error parsing IAC: {err}");
+                    }
+                }
+            }
+            ParserState::Will => {
+                self.state = ParserState::Data;
+                self.receive_enable(Side::Him, b);
+            }
+            ParserState::Wont => {
+                self.state = ParserState::Data;
+                self.receive_disable(Side::Him, b);
+            }
+            ParserState::Do => {
+                self.state = ParserState::Data;
+                self.receive_enable(Side::Us, b);
+            }
+            ParserState::Dont => {
+                self.state = ParserState::Data;
+                self.receive_disable(Side::Us, b);
+            }
+            ParserState::SubNegotiation => {
+                self.sb_option = b;
+                self.sb_data.clear();
+                self.state = ParserState::SubNegotiationData;
+            }
+            ParserState::SubNegotiationData => {
+                if b == telnet_cmd::Iac {
+                    self.state = ParserState::SubNegotiationIac;
+                } else if self.sb_data.len() < MAX_SUBNEGOTIATION_SIZE {
+                    self.sb_data.push(b);
+                }
+            }
+            ParserState::SubNegotiationIac => match b {
+                telnet_cmd::Iac => {
+                    if self.sb_data.len() < MAX_SUBNEGOTIATION_SIZE {
+                        self.sb_data.push(telnet_cmd::Iac);
+                    }
+                    self.state = ParserState::SubNegotiationData;
+                }
+                telnet_cmd::SE => {
+                    self.state = ParserState::Data;
+                    self.handle_subnegotiation();
+                }
+                _ => {
+                    // missing SE - end the subnegotiation and handle the command
+                    log::warn!(
+                        "subnegotiation of {} not terminated with SE",
+                        telnet_option::to_string(self.sb_option)
+                    );
+                    self.handle_subnegotiation();
+                    self.state = ParserState::Iac;
+                    self.parse_byte(b, result);
+                }
+            },
+        }
+    }
+
+    /// Options we agree to enable when asked.
+    fn accepts(&self, side: Side, option: u8) -> bool {
+        match side {
+            Side::Us => match option {
+                telnet_option::TransmitBinary
+                | telnet_option::SuppressGoAhead
+                | telnet_option::TerminalType
+                | telnet_option::NegotiateAboutWindowSize => true,
+                telnet_option::NewEnviron => !self.environment.is_empty(),
+                telnet_option::CharSet => !self.charsets.is_empty(),
+                _ => false,
+            },
+            Side::Him => match option {
+                telnet_option::TransmitBinary
+                | telnet_option::Echo
+                | telnet_option::SuppressGoAhead => true,
+                telnet_option::CharSet => !self.charsets.is_empty(),
+                _ => false,
+            },
+        }
+    }
+
+    fn option_state(&self, side: Side, option: u8) -> OptionState {
+        match side {
+            Side::Us => self.us[option as usize],
+            Side::Him => self.him[option as usize],
+        }
+    }
+
+    fn set_option_state(&mut self, side: Side, option: u8, state: OptionState) {
+        match side {
+            Side::Us => self.us[option as usize] = state,
+            Side::Him => self.him[option as usize] = state,
+        }
+    }
+
+    fn send_cmd(&mut self, cmd: u8, option: u8) {
+        self.output
+            .extend_from_slice(&telnet_cmd::make_cmd_with_option(cmd, option));
+    }
+
+    /// Handles WILL (for him) or DO (for us).
+    fn receive_enable(&mut self, side: Side, option: u8) {
+        let mut st = self.option_state(side, option);
+        let mut reply = None;
+        let mut enabled = false;
+        match (st.state, st.queue) {
+            (QState::No, _) => {
+                if self.accepts(side, option) {
+                    st.state = QState::Yes;
+                    reply = Some(side.enable_cmd());
+                    enabled = true;
+                } else {
+                    log::info!(
+                        "refuse {:?} option {}",
+                        side,
+                        telnet_option::to_string(option)
+                    );
+                    reply = Some(side.disable_cmd());
+                }
+            }
+            // already enabled - answering again would start a negotiation loop
+            (QState::Yes, _) => {}
+            (QState::WantNo, Queue::Empty) => {
+                log::warn!(
+                    "disable request of {} answered with enable",
+                    telnet_option::to_string(option)
+                );
+                st.state = QState::No;
+            }
+            (QState::WantNo, Queue::Opposite) => {
+                log::warn!(
+                    "disable request of {} answered with enable",
+                    telnet_option::to_string(option)
+                );
+                st.state = QState::Yes;
+                st.queue = Queue::Empty;
+                enabled = true;
+            }
+            (QState::WantYes, Queue::Empty) => {
+                st.state = QState::Yes;
+                enabled = true;
+            }
+            (QState::WantYes, Queue::Opposite) => {
+                st.state = QState::WantNo;
+                st.queue = Queue::Empty;
+                reply = Some(side.disable_cmd());
+            }
+        }
+        self.set_option_state(side, option, st);
+        if let Some(cmd) = reply {
+            self.send_cmd(cmd, option);
+        }
+        if enabled {
+            self.option_enabled(side, option);
+        }
+    }
+
+    /// Handles WONT (for him) or DONT (for us).
+    fn receive_disable(&mut self, side: Side, option: u8) {
+        let mut st = self.option_state(side, option);
+        let mut reply = None;
+        match (st.state, st.queue) {
+            (QState::No, _) => {}
+            (QState::Yes, _) => {
+                st.state = QState::No;
+                reply = Some(side.disable_cmd());
+            }
+            (QState::WantNo, Queue::Empty) => st.state = QState::No,
+            (QState::WantNo, Queue::Opposite) => {
+                st.state = QState::WantYes;
+                st.queue = Queue::Empty;
+                reply = Some(side.enable_cmd());
+            }
+            (QState::WantYes, _) => {
+                st.state = QState::No;
+                st.queue = Queue::Empty;
+            }
+        }
+        self.set_option_state(side, option, st);
+        if let Some(cmd) = reply {
+            self.send_cmd(cmd, option);
+        }
+    }
+
+    /// Asks the server to enable or disable an option.
+    #[allow(dead_code)]
+    pub fn request(&mut self, side: Side, option: u8, enable: bool) {
+        let mut st = self.option_state(side, option);
+        let mut send = None;
+        match (enable, st.state, st.queue) {
+            (true, QState::No, _) => {
+                st.state = QState::WantYes;
+                send = Some(side.enable_cmd());
+            }
+            (false, QState::Yes, _) => {
+                st.state = QState::WantNo;
+                send = Some(side.disable_cmd());
+            }
+            (true, QState::WantNo, Queue::Empty) | (false, QState::WantYes, Queue::Empty) => {
+                st.queue = Queue::Opposite;
+            }
+            (true, QState::WantYes, Queue::Opposite) | (false, QState::WantNo, Queue::Opposite) => {
+                st.queue = Queue::Empty;
+            }
+            // already in the requested state or negotiating it
+            _ => {}
+        }
+        self.set_option_state(side, option, st);
+        if let Some(cmd) = send {
+            self.send_cmd(cmd, option);
+        }
+    }
+
+    fn option_enabled(&mut self, side: Side, option: u8) {
+        if side != Side::Us {
+            return;
+        }
+        match option {
+            telnet_option::NegotiateAboutWindowSize => self.send_window_size(),
+            telnet_option::TerminalType => self.terminal_type_index = 0,
+            telnet_option::CharSet => self.send_charset_request(),
+            _ => {}
+        }
+    }
+
+    fn handle_subnegotiation(&mut self) {
+        let data = std::mem::take(&mut self.sb_data);
+        match self.sb_option {
+            telnet_option::TerminalType => self.handle_terminal_type(&data),
+            telnet_option::NewEnviron => self.handle_new_environ(&data),
+            telnet_option::CharSet => self.handle_charset(&data),
+            option => {
+                log::info!(
+                    "unsupported subnegotiation {}: {data:?}",
+                    telnet_option::to_string(option)
+                );
+            }
+        }
+    }
+
+    fn handle_terminal_type(&mut self, data: &[u8]) {
+        if data.first() != Some(&terminal_type::SEND) {
+            return;
+        }
+        let mut payload = vec![terminal_type::IS];
+        payload.extend_from_slice(&self.next_terminal_type());
+        self.send_subnegotiation(telnet_option::TerminalType, &payload);
+    }
+
+    /// The last name of the list is repeated once to signal the end of the list,
+    /// after that the list starts over (RFC 1091).
+    fn next_terminal_type(&mut self) -> Vec<u8> {
+        if self.terminal_types.is_empty() {
+            return match self.terminal {
+                Terminal::Ansi => b"ANSI".to_vec(),
+                Terminal::PETscii => b"PETSCII".to_vec(),
+                Terminal::ATAscii => b"ATASCII".to_vec(),
+                Terminal::ViewData => b"VIEWDATA".to_vec(),
+                Terminal::Ascii => b"RAW".to_vec(),
+                Terminal::Avatar => b"AVATAR".to_vec(),
+            };
+        }
+        let len = self.terminal_types.len();
+        let name = self.terminal_types[self.terminal_type_index.min(len - 1)].clone();
+        self.terminal_type_index = (self.terminal_type_index + 1) % (len + 1);
+        name.into_bytes()
+    }
+
+    fn handle_new_environ(&mut self, data: &[u8]) {
+        if data.first() != Some(&new_environ::SEND)
+            || !self.is_enabled(Side::Us, telnet_option::NewEnviron)
+        {
+            return;
+        }
+        let requested = parse_environ_list(&data[1..]);
+        let mut payload = vec![new_environ::IS];
+        for (var_type, name, value) in &self.environment {
+            // an empty list or a type without names requests all variables of that kind
+            let is_requested = requested.is_empty()
+                || requested
+                    .iter()
+                    .any(|(t, n)| t == var_type && (n.is_empty() || n == name.as_bytes()));
+            if is_requested {
+                payload.push(*var_type);
+                escape_environ(name.as_bytes(), &mut payload);
+                payload.push(new_environ::VALUE);
+                escape_environ(value.as_bytes(), &mut payload);
+            }
+        }
+        // variables we don't know are answered without value
+        for (var_type, name) in &requested {
+            if !name.is_empty()
+                && !self
+                    .environment
+                    .iter()
+                    .any(|(t, n, _)| t == var_type && n.as_bytes() == name.as_slice())
+            {
+                payload.push(*var_type);
+                escape_environ(name, &mut payload);
+            }
+        }
+        self.send_subnegotiation(telnet_option::NewEnviron, &payload);
+    }
+
+    fn send_charset_request(&mut self) {
+        let mut payload = vec![charset::REQUEST];
+        for charset in &self.charsets {
+            payload.push(b';');
+            payload.extend_from_slice(charset.name().as_bytes());
+        }
+        self.send_subnegotiation(telnet_option::CharSet, &payload);
+    }
+
+    fn handle_charset(&mut self, data: &[u8]) {
+        if !self.is_enabled(Side::Us, telnet_option::CharSet)
+            && !self.is_enabled(Side::Him, telnet_option::CharSet)
+        {
+            return;
+        }
+        match data.first() {
+            Some(&charset::REQUEST) => {
+                let mut list = &data[1..];
+                if let Some(rest) = list.strip_prefix(b"[TTABLE]") {
+                    // skip the version, we don't support translation tables
+                    list = rest.get(1..).unwrap_or_default();
+                }
+                let Some((separator, list)) = list.split_first() else {
+                    self.send_subnegotiation(telnet_option::CharSet, &[charset::REJECTED]);
+                    return;
+                };
+                let offered: Vec<&[u8]> = list.split(|b| b == separator).collect();
+                let accepted = self.charsets.iter().find_map(|preferred| {
+                    offered
+                        .iter()
+                        .find(|name| {
+                            Charset::from_name(&String::from_utf8_lossy(name)) == Some(*preferred)
+                        })
+                        .map(|name| (*preferred, *name))
+                });
+                if let Some((accepted, name)) = accepted {
+                    let payload = [&[charset::ACCEPTED][..], name].concat();
+                    self.send_subnegotiation(telnet_option::CharSet, &payload);
+                    self.negotiated_charset = Some(accepted);
+                    return;
+                }
+                log::info!(
+                    "no preferred charset offered: {}",
+                    String::from_utf8_lossy(list)
+                );
+                self.send_subnegotiation(telnet_option::CharSet, &[charset::REJECTED]);
+            }
+            Some(&charset::ACCEPTED) => {
+                let name = String::from_utf8_lossy(&data[1..]);
+                match Charset::from_name(&name) {
+                    Some(accepted) => self.negotiated_charset = Some(accepted),
+                    None => log::warn!("server accepted unknown charset {name}"),
+                }
+            }
+            Some(&charset::REJECTED) => {
+                log::info!("server rejected the charsets");
+            }
+            Some(&charset::TTABLE_IS) => {
+                self.send_subnegotiation(telnet_option::CharSet, &[charset::TTABLE_REJECTED]);
+            }
+            _ => {}
+        }
+    }
+
+    fn send_window_size(&mut self) {
+        let mut payload = Vec::new();
+        payload.extend(self.window_size.width.to_be_bytes());
+        payload.extend(self.window_size.height.to_be_bytes());
+        self.send_subnegotiation(telnet_option::NegotiateAboutWindowSize, &payload);
+    }
+
+    fn send_subnegotiation(&mut self, option: u8, payload: &[u8]) {
+        self.output
+            .extend_from_slice(&[telnet_cmd::Iac, telnet_cmd::SB, option]);
+        self.output.extend(Self::encode(payload));
+        self.output
+            .extend_from_slice(&[telnet_cmd::Iac, telnet_cmd::SE]);
+    }
+}
+
+/// Parses the variable list of a NEW-ENVIRON SEND into (type, name) pairs.
+fn parse_environ_list(data: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let mut result: Vec<(u8, Vec<u8>)> = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            new_environ::VAR | new_environ::USERVAR => result.push((data[i], Vec::new())),
+            new_environ::ESC if i + 1 < data.len() => {
+                i += 1;
+                if let Some((_, name)) = result.last_mut() {
+                    name.push(data[i]);
+                }
+            }
+            b => {
+                if let Some((_, name)) = result.last_mut() {
+                    name.push(b);
+                }
+            }
+        }
+        i += 1;
+    }
+    result
+}
+
+fn escape_environ(data: &[u8], payload: &mut Vec<u8>) {
+    for b in data {
+        if matches!(
+            *b,
+            new_environ::VAR | new_environ::VALUE | new_environ::ESC | new_environ::USERVAR
+        ) {
+            payload.push(new_environ::ESC);
+        }
+        payload.push(*b);
+    }
+}
@@ -0,0 +1,656 @@
+#[cfg(test)]
+mod telnet_test {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use icy_engine::Size;
+
+    use super::super::{
+        protocol::{
+            charset, new_environ, telnet_cmd, telnet_option, terminal_type, Side, TelnetProtocol,
+        },
+        ComTelnetImpl,
+    };
+    use crate::{
+        addresses::{Charset, Protocol, TelnetKeepAlive, Terminal, TlsSettings},
+        com::Com,
+        ui::connection::OpenConnectionData,
+        Address,
+    };
+
+    const IAC: u8 = telnet_cmd::Iac;
+    const WILL: u8 = telnet_cmd::Will;
+    const WONT: u8 = telnet_cmd::Wont;
+    const DO: u8 = telnet_cmd::DO;
+    const DONT: u8 = telnet_cmd::Dont;
+    const SB: u8 = telnet_cmd::SB;
+    const SE: u8 = telnet_cmd::SE;
+
+    const BINARY: u8 = telnet_option::TransmitBinary;
+    const ECHO: u8 = telnet_option::Echo;
+    const SGA: u8 = telnet_option::SuppressGoAhead;
+    const TTYPE: u8 = telnet_option::TerminalType;
+    const TSPEED: u8 = telnet_option::TerminalSpeed;
+    const SEND_LOCATION: u8 = telnet_option::SendLocation;
+    const NAWS: u8 = telnet_option::NegotiateAboutWindowSize;
+    const NEW_ENVIRON: u8 = telnet_option::NewEnviron;
+    const CHARSET: u8 = telnet_option::CharSet;
+
+    fn naws(width: &[u8], height: &[u8]) -> Vec<u8> {
+        let mut result = vec![IAC, SB, NAWS];
+        result.extend_from_slice(width);
+        result.extend_from_slice(height);
+        result.extend_from_slice(&[IAC, SE]);
+        result
+    }
+
+    fn ttype_is(name: &[u8]) -> Vec<u8> {
+        [&[IAC, SB, TTYPE, terminal_type::IS], name, &[IAC, SE]].concat()
+    }
+
+    fn protocol() -> TelnetProtocol {
+        TelnetProtocol::new(Size::new(80, 25), Terminal::Ansi)
+    }
+
+    /// Feeds the server data and returns the data & the replies.
+    fn replay(protocol: &mut TelnetProtocol, server_data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let data = protocol.parse(server_data);
+        (data, protocol.take_output())
+    }
+
+    // Hand written connect sequences with the options BBS servers commonly request.
+
+    #[test]
+    fn test_negotiation_with_terminal_detection() {
+        let mut protocol = protocol();
+        let server_data = [
+            &[IAC, WILL, ECHO, IAC, WILL, SGA][..],
+            b"\r\nWelcome\r\n",
+            &[IAC, DO, TTYPE, IAC, DO, TSPEED, IAC, DO, SEND_LOCATION],
+            &[IAC, DO, NAWS, IAC, DO, NEW_ENVIRON],
+            &[IAC, SB, TTYPE, terminal_type::SEND, IAC, SE],
+            b"Detecting terminal type...",
+        ]
+        .concat();
+        let (data, replies) = replay(&mut protocol, &server_data);
+        assert_eq!(b"\r\nWelcome\r\nDetecting terminal type...".to_vec(), data);
+        let expected = [
+            &[IAC, DO, ECHO, IAC, DO, SGA][..],
+            &[
+                IAC,
+                WILL,
+                TTYPE,
+                IAC,
+                WONT,
+                TSPEED,
+                IAC,
+                WONT,
+                SEND_LOCATION,
+            ],
+            &[IAC, WILL, NAWS],
+            &naws(&[0, 80], &[0, 25]),
+            &[IAC, WONT, NEW_ENVIRON],
+            &ttype_is(b"ANSI"),
+        ]
+        .concat();
+        assert_eq!(expected, replies);
+
+        // re-sent options need no reply if they are already enabled
+        let (_, replies) = replay(&mut protocol, &[IAC, WILL, ECHO, IAC, DO, NAWS]);
+        assert!(replies.is_empty());
+        assert!(protocol.is_enabled(Side::Him, ECHO));
+        assert!(protocol.is_enabled(Side::Us, NAWS));
+        assert!(!protocol.is_enabled(Side::Us, NEW_ENVIRON));
+    }
+
+    #[test]
+    fn test_binary_negotiation_with_utf8_data() {
+        let mut protocol = protocol();
+        let server_data = [
+            &[IAC, WILL, ECHO, IAC, WILL, SGA, IAC, DO, SGA][..],
+            &[IAC, DO, BINARY, IAC, WILL, BINARY],
+            &[IAC, DO, TTYPE, IAC, DO, NAWS, IAC, DO, NEW_ENVIRON],
+            b"Welcome ",
+            &[0xC2, 0xBD],
+            &[IAC, SB, TTYPE, terminal_type::SEND, IAC, SE],
+        ]
+        .concat();
+        let (data, replies) = replay(&mut protocol, &server_data);
+        assert_eq!([&b"Welcome "[..], &[0xC2, 0xBD]].concat(), data);
+        let expected = [
+            &[IAC, DO, ECHO, IAC, DO, SGA, IAC, WILL, SGA][..],
+            &[IAC, WILL, BINARY, IAC, DO, BINARY],
+            &[IAC, WILL, TTYPE, IAC, WILL, NAWS],
+            &naws(&[0, 80], &[0, 25]),
+            &[IAC, WONT, NEW_ENVIRON],
+            &ttype_is(b"ANSI"),
+        ]
+        .concat();
+        assert_eq!(expected, replies);
+
+        // refused options aren't answered twice
+        let (_, replies) = replay(&mut protocol, &[IAC, DONT, NEW_ENVIRON]);
+        assert!(replies.is_empty());
+    }
+
+    #[test]
+    fn test_binary_negotiation_with_escaped_iac() {
+        let mut protocol = protocol();
+        protocol.set_terminal_type(Terminal::Avatar);
+        let server_data = [
+            &[IAC, WILL, ECHO, IAC, WILL, SGA][..],
+            &[IAC, DO, BINARY, IAC, WILL, BINARY],
+            &[IAC, DO, TTYPE, IAC, DO, NAWS],
+            &[IAC, SB, TTYPE, terminal_type::SEND, IAC, SE],
+            b"Welcome\r\n",
+            // binary data contains escaped IACs
+            &[IAC, IAC, 0x41],
+        ]
+        .concat();
+        let (data, replies) = replay(&mut protocol, &server_data);
+        assert_eq!(b"Welcome\r\n\xFFA".to_vec(), data);
+        let expected = [
+            &[IAC, DO, ECHO, IAC, DO, SGA][..],
+            &[IAC, WILL, BINARY, IAC, DO, BINARY],
+            &[IAC, WILL, TTYPE, IAC, WILL, NAWS],
+            &naws(&[0, 80], &[0, 25]),
+            &ttype_is(b"AVATAR"),
+        ]
+        .concat();
+        assert_eq!(expected, replies);
+
+        // echo off for a password prompt and on again
+        let (_, replies) = replay(&mut protocol, &[IAC, WONT, ECHO]);
+        assert_eq!(vec![IAC, DONT, ECHO], replies);
+        let (_, replies) = replay(&mut protocol, &[IAC, WILL, ECHO]);
+        assert_eq!(vec![IAC, DO, ECHO], replies);
+    }
+
+    #[test]
+    fn test_negotiation_split_across_reads() {
+        let mut protocol = protocol();
+        let (data, replies) = replay(&mut protocol, &[b'a', IAC]);
+        assert_eq!(b"a".to_vec(), data);
+        assert!(replies.is_empty());
+        let (data, replies) = replay(&mut protocol, &[DO, NAWS, b'b', IAC, SB, TTYPE]);
+        assert_eq!(b"b".to_vec(), data);
+        assert_eq!(
+            [&[IAC, WILL, NAWS][..], &naws(&[0, 80], &[0, 25])].concat(),
+            replies
+        );
+        let (data, replies) = replay(&mut protocol, &[terminal_type::SEND, IAC, SE, b'c']);
+        assert_eq!(b"c".to_vec(), data);
+        assert_eq!(ttype_is(b"ANSI"), replies);
+    }
+
+    #[test]
+    fn test_request_option() {
+        let mut protocol = protocol();
+        protocol.request(Side::Him, SGA, true);
+        assert_eq!(vec![IAC, DO, SGA], protocol.take_output());
+
+        // the answer to our own request isn't answered again
+        let (_, replies) = replay(&mut protocol, &[IAC, WILL, SGA]);
+        assert!(replies.is_empty());
+        assert!(protocol.is_enabled(Side::Him, SGA));
+
+        // requesting an enabled option does nothing
+        protocol.request(Side::Him, SGA, true);
+        assert!(protocol.take_output().is_empty());
+
+        // the server refuses our request
+        protocol.request(Side::Us, BINARY, true);
+        assert_eq!(vec![IAC, WILL, BINARY], protocol.take_output());
+        let (_, replies) = replay(&mut protocol, &[IAC, DONT, BINARY]);
+        assert!(replies.is_empty());
+        assert!(!protocol.is_enabled(Side::Us, BINARY));
+
+        // disable requested while the enable is pending
+        protocol.request(Side::Him, ECHO, true);
+        protocol.request(Side::Him, ECHO, false);
+        assert_eq!(vec![IAC, DO, ECHO], protocol.take_output());
+        let (_, replies) = replay(&mut protocol, &[IAC, WILL, ECHO]);
+        assert_eq!(vec![IAC, DONT, ECHO], replies);
+        let (_, replies) = replay(&mut protocol, &[IAC, WONT, ECHO]);
+        assert!(replies.is_empty());
+        assert!(!protocol.is_enabled(Side::Him, ECHO));
+    }
+
+    #[test]
+    fn test_subnegotiation_buffering() {
+        let mut protocol = protocol();
+
+        // unknown subnegotiations with escaped IACs are skipped
+        let server_data = [
+            &[IAC, SB, NEW_ENVIRON, 1, 0, b'U', IAC, IAC, b'S'][..],
+            &[IAC, SE],
+            b"ok",
+        ]
+        .concat();
+        let (data, replies) = replay(&mut protocol, &server_data);
+        assert_eq!(b"ok".to_vec(), data);
+        assert!(replies.is_empty());
+
+        // missing SE ends the subnegotiation
+        let server_data = [
+            &[IAC, SB, TTYPE, terminal_type::SEND][..],
+            &[IAC, DO, TTYPE],
+            b"x",
+        ]
+        .concat();
+        let (data, replies) = replay(&mut protocol, &server_data);
+        assert_eq!(b"x".to_vec(), data);
+        assert_eq!(
+            [ttype_is(b"ANSI"), vec![IAC, WILL, TTYPE]].concat(),
+            replies
+        );
+    }
+
+    #[test]
+    fn test_terminal_type_cycling() {
+        let mut protocol = protocol();
+        protocol.set_terminal_types(&[
+            "xterm-256color".to_string(),
+            "ansi".to_string(),
+            "vt100".to_string(),
+        ]);
+        let (_, replies) = replay(&mut protocol, &[IAC, DO, TTYPE]);
+        assert_eq!(vec![IAC, WILL, TTYPE], replies);
+
+        let send = [IAC, SB, TTYPE, terminal_type::SEND, IAC, SE];
+        let mut names = Vec::new();
+        for _ in 0..6 {
+            let (_, replies) = replay(&mut protocol, &send);
+            names.push(replies);
+        }
+        let expected: Vec<Vec<u8>> = [
+            &b"xterm-256color"[..],
+            b"ansi",
+            b"vt100",
+            b"vt100",
+            b"xterm-256color",
+            b"ansi",
+        ]
+        .iter()
+        .map(|name| ttype_is(name))
+        .collect();
+        assert_eq!(expected, names);
+
+        // a new negotiation starts with the first name
+        replay(&mut protocol, &[IAC, DONT, TTYPE, IAC, DO, TTYPE]);
+        let (_, replies) = replay(&mut protocol, &send);
+        assert_eq!(ttype_is(b"xterm-256color"), replies);
+    }
+
+    fn environ_var(var_type: u8, name: &[u8], value: Option<&[u8]>) -> Vec<u8> {
+        let mut result = vec![var_type];
+        result.extend_from_slice(name);
+        if let Some(value) = value {
+            result.push(new_environ::VALUE);
+            result.extend_from_slice(value);
+        }
+        result
+    }
+
+    #[test]
+    fn test_new_environ() {
+        let mut protocol = protocol();
+        protocol.set_environment(
+            "sysop",
+            &[
+                ("TERM".to_string(), "ansi-bbs".to_string()),
+                ("SYSTEMTYPE".to_string(), "WIN32".to_string()),
+                ("ROWS".to_string(), "25".to_string()),
+            ],
+        );
+        let (_, replies) = replay(&mut protocol, &[IAC, DO, NEW_ENVIRON]);
+        assert_eq!(vec![IAC, WILL, NEW_ENVIRON], replies);
+
+        // the server asks for USER and all user variables
+        let (_, replies) = replay(
+            &mut protocol,
+            &[
+                &[IAC, SB, NEW_ENVIRON, new_environ::SEND, new_environ::VAR][..],
+                b"USER",
+                &[new_environ::USERVAR, IAC, SE],
+            ]
+            .concat(),
+        );
+        let expected = [
+            &[IAC, SB, NEW_ENVIRON, new_environ::IS][..],
+            &environ_var(new_environ::VAR, b"USER", Some(b"sysop")),
+            &environ_var(new_environ::USERVAR, b"TERM", Some(b"ansi-bbs")),
+            &environ_var(new_environ::USERVAR, b"ROWS", Some(b"25")),
+            &[IAC, SE],
+        ]
+        .concat();
+        assert_eq!(expected, replies);
+
+        // an empty list requests everything, unknown variables are sent without value
+        let (_, replies) = replay(
+            &mut protocol,
+            &[IAC, SB, NEW_ENVIRON, new_environ::SEND, IAC, SE],
+        );
+        let expected = [
+            &[IAC, SB, NEW_ENVIRON, new_environ::IS][..],
+            &environ_var(new_environ::VAR, b"USER", Some(b"sysop")),
+            &environ_var(new_environ::USERVAR, b"TERM", Some(b"ansi-bbs")),
+            &environ_var(new_environ::VAR, b"SYSTEMTYPE", Some(b"WIN32")),
+            &environ_var(new_environ::USERVAR, b"ROWS", Some(b"25")),
+            &[IAC, SE],
+        ]
+        .concat();
+        assert_eq!(expected, replies);
+
+        let (_, replies) = replay(
+            &mut protocol,
+            &[
+                &[IAC, SB, NEW_ENVIRON, new_environ::SEND, new_environ::VAR][..],
+                b"ACCT",
+                &[IAC, SE],
+            ]
+            .concat(),
+        );
+        let expected = [
+            &[IAC, SB, NEW_ENVIRON, new_environ::IS][..],
+            &environ_var(new_environ::VAR, b"ACCT", None),
+            &[IAC, SE],
+        ]
+        .concat();
+        assert_eq!(expected, replies);
+    }
+
+    #[test]
+    fn test_new_environ_escaping() {
+        let mut protocol = protocol();
+        protocol.set_environment("", &[("KEY".to_string(), "a\x01\x02b".to_string())]);
+        replay(&mut protocol, &[IAC, DO, NEW_ENVIRON]);
+        let (_, replies) = replay(
+            &mut protocol,
+            &[IAC, SB, NEW_ENVIRON, new_environ::SEND, IAC, SE],
+        );
+        let expected = [
+            &[IAC, SB, NEW_ENVIRON, new_environ::IS, new_environ::USERVAR][..],
+            b"KEY",
+            &[
+                new_environ::VALUE,
+                b'a',
+                new_environ::ESC,
+                1,
+                new_environ::ESC,
+                2,
+                b'b',
+            ],
+            &[IAC, SE],
+        ]
+        .concat();
+        assert_eq!(expected, replies);
+    }
+
+    #[test]
+    fn test_new_environ_refused_without_variables() {
+        let mut protocol = protocol();
+        protocol.set_environment("", &[]);
+        let (_, replies) = replay(&mut protocol, &[IAC, DO, NEW_ENVIRON]);
+        assert_eq!(vec![IAC, WONT, NEW_ENVIRON], replies);
+        let (_, replies) = replay(
+            &mut protocol,
+            &[IAC, SB, NEW_ENVIRON, new_environ::SEND, IAC, SE],
+        );
+        assert!(replies.is_empty());
+    }
+
+    fn charset_sb(command: u8, data: &[u8]) -> Vec<u8> {
+        [&[IAC, SB, CHARSET, command][..], data, &[IAC, SE]].concat()
+    }
+
+    #[test]
+    fn test_charset_server_request() {
+        let mut protocol = protocol();
+        protocol.set_charsets(&[Charset::Utf8, Charset::Cp437]);
+        let (_, replies) = replay(&mut protocol, &[IAC, WILL, CHARSET]);
+        assert_eq!(vec![IAC, DO, CHARSET], replies);
+
+        let (_, replies) = replay(
+            &mut protocol,
+            &charset_sb(charset::REQUEST, b" CP437 utf-8"),
+        );
+        assert_eq!(charset_sb(charset::ACCEPTED, b"utf-8"), replies);
+        assert_eq!(Some(Charset::Utf8), protocol.take_charset());
+        assert_eq!(None, protocol.take_charset());
+
+        // translation tables aren't supported, the charset list is still used
+        let (_, replies) = replay(
+            &mut protocol,
+            &charset_sb(charset::REQUEST, b"[TTABLE]\x01;ISO-8859-1;IBM437"),
+        );
+        assert_eq!(charset_sb(charset::ACCEPTED, b"IBM437"), replies);
+        assert_eq!(Some(Charset::Cp437), protocol.take_charset());
+
+        let (_, replies) = replay(
+            &mut protocol,
+            &charset_sb(charset::REQUEST, b";KOI8-R;ISO-8859-1"),
+        );
+        assert_eq!(charset_sb(charset::REJECTED, b""), replies);
+        assert_eq!(None, protocol.take_charset());
+    }
+
+    #[test]
+    fn test_charset_client_request() {
+        let mut protocol = protocol();
+        protocol.set_charsets(&[Charset::Cp437, Charset::Utf8]);
+        let (_, replies) = replay(&mut protocol, &[IAC, DO, CHARSET]);
+        assert_eq!(
+            [
+                vec![IAC, WILL, CHARSET],
+                charset_sb(charset::REQUEST, b";IBM437;UTF-8")
+            ]
+            .concat(),
+            replies
+        );
+        let (_, replies) = replay(&mut protocol, &charset_sb(charset::ACCEPTED, b"UTF-8"));
+        assert!(replies.is_empty());
+        assert_eq!(Some(Charset::Utf8), protocol.take_charset());
+
+        let (_, replies) = replay(&mut protocol, &charset_sb(charset::TTABLE_IS, b"\x01"));
+        assert_eq!(charset_sb(charset::TTABLE_REJECTED, b""), replies);
+    }
+
+    #[test]
+    fn test_charset_refused_without_preferences() {
+        let mut protocol = protocol();
+        let (_, replies) = replay(&mut protocol, &[IAC, WILL, CHARSET, IAC, DO, CHARSET]);
+        assert_eq!(vec![IAC, DONT, CHARSET, IAC, WONT, CHARSET], replies);
+        let (_, replies) = replay(&mut protocol, &charset_sb(charset::REQUEST, b";UTF-8"));
+        assert!(replies.is_empty());
+        assert_eq!(None, protocol.take_charset());
+    }
+
+    #[test]
+    fn test_are_you_there() {
+        let mut protocol = protocol();
+        let (data, replies) = replay(&mut protocol, &[IAC, telnet_cmd::Ayt, b'a']);
+        assert_eq!(b"a".to_vec(), data);
+        assert_eq!(vec![IAC, telnet_cmd::Nop], replies);
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            vec![b'a', IAC, IAC, b'b'],
+            TelnetProtocol::encode(&[b'a', IAC, b'b'])
+        );
+    }
+
+    #[test]
+    fn test_keepalive() {
+        assert_eq!(
+            [IAC, telnet_cmd::Nop],
+            TelnetProtocol::keepalive(TelnetKeepAlive::Nop)
+        );
+        assert_eq!(
+            [IAC, telnet_cmd::Ayt],
+            TelnetProtocol::keepalive(TelnetKeepAlive::AreYouThere)
+        );
+    }
+
+    #[test]
+    fn test_naws_window_size_change() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut address = Address::new("test".to_string());
+        address.address = listener.local_addr().unwrap().to_string();
+        let connection_data = OpenConnectionData::from(
+            &address,
+            web_time::Duration::from_secs(5),
+            Size::new(80, 25),
+        );
+        let mut com = ComTelnetImpl::connect(&connection_data).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+
+        // window size changes before DO NAWS aren't sent
+        com.set_window_size(Size::new(132, 37)).unwrap();
+
+        server.write_all(&[IAC, DO, NAWS]).unwrap();
+        while com.read_data().unwrap().is_none() {}
+
+        com.set_window_size(Size::new(255, 52)).unwrap();
+
+        let expected = [
+            vec![IAC, WILL, NAWS],
+            naws(&[0, 132], &[0, 37]),
+            naws(&[0, 255, 255], &[0, 52]),
+        ]
+        .concat();
+        let mut received = vec![0; expected.len()];
+        server.read_exact(&mut received).unwrap();
+        assert_eq!(expected, received);
+    }
+
+    const TEST_CERT: &[u8] = include_bytes!("test_cert.der");
+    const TEST_KEY: &[u8] = include_bytes!("test_key.der");
+    const TEST_CERT_FINGERPRINT: &str = "56:06:F2:27:F2:10:06:F0:0B:5C:0F:D5:62:CE:61:58:ED:79:FC:5B:AD:EE:C8:78:BA:1A:67:E4:2C:54:87:E9";
+
+    /// SNI and received data - `None` if the handshake failed
+    type TlsServerResult = Option<(Option<String>, Vec<u8>)>;
+
+    /// Starts a TLS telnet server with a self signed certificate for "localhost".
+    /// It requests NAWS, greets the client and returns the SNI and everything it received.
+    fn start_tls_server() -> (String, std::thread::JoinHandle<TlsServerResult>) {
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![rustls::Certificate(TEST_CERT.to_vec())],
+                rustls::PrivateKey(TEST_KEY.to_vec()),
+            )
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (tcp_stream, _) = listener.accept().unwrap();
+            let conn = rustls::ServerConnection::new(std::sync::Arc::new(config)).unwrap();
+            let mut stream = rustls::StreamOwned::new(conn, tcp_stream);
+            // fails if the client rejects the certificate
+            stream.write_all(&[IAC, DO, NAWS]).ok()?;
+            stream.write_all(b"Welcome").ok()?;
+            stream.flush().ok()?;
+            let sni = stream.conn.server_name().map(ToString::to_string);
+
+            let expected = [
+                vec![IAC, WILL, NAWS],
+                naws(&[0, 80], &[0, 25]),
+                b"hi".to_vec(),
+            ]
+            .concat();
+            let mut received = vec![0; expected.len()];
+            stream.read_exact(&mut received).ok()?;
+            Some((sni, received))
+        });
+        (format!("localhost:{port}"), handle)
+    }
+
+    fn tls_connection_data(address: String, tls: TlsSettings) -> OpenConnectionData {
+        let mut adr = Address::new("tls".to_string());
+        adr.address = address;
+        adr.protocol = Protocol::TelnetTls;
+        adr.tls = tls;
+        OpenConnectionData::from(&adr, web_time::Duration::from_secs(5), Size::new(80, 25))
+    }
+
+    fn read_until(com: &mut ComTelnetImpl, expected: &[u8]) {
+        let mut received = Vec::new();
+        while !received.ends_with(expected) {
+            if let Some(data) = com.read_data().unwrap() {
+                received.extend(data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tls_pinned_certificate() {
+        let (address, server) = start_tls_server();
+        let tls = TlsSettings {
+            fingerprint: format!(
+                "sha256:{}",
+                TEST_CERT_FINGERPRINT.replace(':', "").to_lowercase()
+            ),
+            ..Default::default()
+        };
+        let mut com = ComTelnetImpl::connect(&tls_connection_data(address, tls)).unwrap();
+        assert_eq!(992, com.default_port());
+        read_until(&mut com, b"Welcome");
+        com.send(b"hi").unwrap();
+
+        let (sni, received) = server.join().unwrap().unwrap();
+        assert_eq!(Some("localhost".to_string()), sni);
+        assert_eq!(
+            [
+                vec![IAC, WILL, NAWS],
+                naws(&[0, 80], &[0, 25]),
+                b"hi".to_vec()
+            ]
+            .concat(),
+            received
+        );
+    }
+
+    #[test]
+    fn test_tls_self_signed_certificate_is_rejected() {
+        let (address, server) = start_tls_server();
+        let err = ComTelnetImpl::connect(&tls_connection_data(address, TlsSettings::default()))
+            .unwrap_err();
+        // the user needs the fingerprint to pin the certificate
+        assert!(err.to_string().contains(TEST_CERT_FINGERPRINT), "{err}");
+        assert!(server.join().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_tls_wrong_fingerprint() {
+        let (address, server) = start_tls_server();
+        let tls = TlsSettings {
+            verify_certificate: false,
+            fingerprint: TEST_CERT_FINGERPRINT.replace("56:06", "00:00"),
+            ..Default::default()
+        };
+        assert!(ComTelnetImpl::connect(&tls_connection_data(address, tls)).is_err());
+        assert!(server.join().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_tls_without_verification_and_custom_sni() {
+        let (address, server) = start_tls_server();
+        let tls = TlsSettings {
+            verify_certificate: false,
+            server_name: "bbs.example.com".to_string(),
+            ..Default::default()
+        };
+        let mut com = ComTelnetImpl::connect(&tls_connection_data(address, tls)).unwrap();
+        read_until(&mut com, b"Welcome");
+        com.send(b"hi").unwrap();
+
+        let (sni, _) = server.join().unwrap().unwrap();
+        assert_eq!(Some("bbs.example.com".to_string()), sni);
+    }
+}
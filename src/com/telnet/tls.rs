@@ -0,0 +1,195 @@
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+    Certificate, ClientConfig, ClientConnection, OwnedTrustAnchor, RootCertStore, ServerName,
+    StreamOwned,
+};
+
+use crate::{addresses::TlsSettings, com::TermComResult};
+
+/// Default port for telnet over TLS (telnets)
+pub const DEFAULT_TLS_PORT: u16 = 992;
+
+/// The transport below the telnet protocol.
+#[derive(Debug)]
+pub enum TelnetStream {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl TelnetStream {
+    fn tcp_stream(&self) -> &TcpStream {
+        match self {
+            TelnetStream::Plain(stream) => stream,
+            TelnetStream::Tls(stream) => &stream.sock,
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.tcp_stream().set_nonblocking(nonblocking)
+    }
+
+    pub fn shutdown(&mut self) -> io::Result<()> {
+        if let TelnetStream::Tls(stream) = self {
+            stream.conn.send_close_notify();
+            // the server may already be gone - the socket is closed anyways
+            let _ = stream.conn.complete_io(&mut stream.sock);
+        }
+        self.tcp_stream().shutdown(Shutdown::Both)
+    }
+}
+
+impl Read for TelnetStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            TelnetStream::Plain(stream) => stream.read(buf),
+            TelnetStream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for TelnetStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TelnetStream::Plain(stream) => stream.write(buf),
+            TelnetStream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TelnetStream::Plain(stream) => stream.flush(),
+            TelnetStream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Wraps the tcp stream in TLS and does the handshake.
+/// `host` is used for SNI and the certificate check unless the settings override it.
+pub fn connect_tls(
+    mut tcp_stream: TcpStream,
+    host: &str,
+    settings: &TlsSettings,
+) -> TermComResult<TelnetStream> {
+    let server_name = if settings.server_name.trim().is_empty() {
+        host
+    } else {
+        settings.server_name.trim()
+    };
+    let server_name = ServerName::try_from(server_name)
+        .map_err(|err| format!("Invalid server name '{server_name}': {err}"))?;
+
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(CertificateVerifier::new(settings)))
+        .with_no_client_auth();
+    let mut conn = ClientConnection::new(Arc::new(config), server_name)?;
+    while conn.is_handshaking() {
+        conn.complete_io(&mut tcp_stream)?;
+    }
+    Ok(TelnetStream::Tls(Box::new(StreamOwned::new(
+        conn, tcp_stream,
+    ))))
+}
+
+/// SHA-256 fingerprint of a certificate in the usual "AB:CD:…" notation.
+pub fn certificate_fingerprint(certificate: &Certificate) -> String {
+    ring::digest::digest(&ring::digest::SHA256, &certificate.0)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+/// Accepts different fingerprint notations ("SHA256:", colons, lower case).
+fn normalize_fingerprint(fingerprint: &str) -> String {
+    let fingerprint = fingerprint.trim();
+    let fingerprint = fingerprint
+        .strip_prefix("SHA256:")
+        .or_else(|| fingerprint.strip_prefix("sha256:"))
+        .unwrap_or(fingerprint);
+    fingerprint
+        .chars()
+        .filter(char::is_ascii_hexdigit)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+struct CertificateVerifier {
+    fingerprint: Option<String>,
+    webpki: Option<WebPkiVerifier>,
+}
+
+impl CertificateVerifier {
+    fn new(settings: &TlsSettings) -> Self {
+        let fingerprint = normalize_fingerprint(&settings.fingerprint);
+        let webpki = if settings.verify_certificate {
+            let mut root_store = RootCertStore::empty();
+            root_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    ta.subject,
+                    ta.spki,
+                    ta.name_constraints,
+                )
+            }));
+            Some(WebPkiVerifier::new(root_store, None))
+        } else {
+            None
+        };
+        Self {
+            fingerprint: if fingerprint.is_empty() {
+                None
+            } else {
+                Some(fingerprint)
+            },
+            webpki,
+        }
+    }
+}
+
+impl ServerCertVerifier for CertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint = certificate_fingerprint(end_entity);
+
+        // a pinned certificate is accepted even if it's self signed or expired
+        if let Some(pinned) = &self.fingerprint {
+            if *pinned == normalize_fingerprint(&fingerprint) {
+                return Ok(ServerCertVerified::assertion());
+            }
+            return Err(rustls::Error::General(format!(
+                "certificate fingerprint {fingerprint} doesn't match the pinned fingerprint"
+            )));
+        }
+
+        match &self.webpki {
+            Some(webpki) => webpki
+                .verify_server_cert(
+                    end_entity,
+                    intermediates,
+                    server_name,
+                    scts,
+                    ocsp_response,
+                    now,
+                )
+                .map_err(|err| {
+                    rustls::Error::General(format!("{err} (certificate fingerprint {fingerprint})"))
+                }),
+            None => Ok(ServerCertVerified::assertion()),
+        }
+    }
+}
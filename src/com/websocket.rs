@@ -1,7 +1,7 @@
 use crate::addresses;
 use std::sync::Arc;
 
-use super::{Com, OpenConnectionData, TermComResult};
+use super::{connector, Com, OpenConnectionData, TermComResult};
 
 use http::Uri;
 use rustls::{OwnedTrustAnchor, RootCertStore};
@@ -33,7 +33,6 @@ impl WebSocketComImpl {
         let is_secure = connection_data.protocol == addresses::Protocol::WebSocket(true);
 
         // build an ws:// or wss:// address
-        //  :TODO: default port if not supplied in address
         let url = format!(
             "{}://{}",
             Self::schema_prefix(is_secure),
@@ -61,7 +60,16 @@ impl WebSocketComImpl {
 
         let config = Arc::new(config);
 
-        let stream = TcpStream::connect(connection_data.address.clone())?;
+        let host = req
+            .uri()
+            .host()
+            .unwrap_or_default()
+            .trim_matches(|c| c == '[' || c == ']');
+        let port = req
+            .uri()
+            .port_u16()
+            .unwrap_or(if is_secure { 443 } else { 80 });
+        let stream = connector::connect(host, port, &connection_data.proxy)?;
         let connector: tungstenite::Connector = tungstenite::Connector::Rustls(config);
         let (mut socket, _) =
             tungstenite::client_tls_with_config(req, stream, None, Some(connector))?;
@@ -116,6 +124,11 @@ impl Com for WebSocketComImpl {
         Ok(buf.len())
     }
 
+    fn send_keepalive(&mut self) -> TermComResult<()> {
+        self.socket.send(Message::Ping(Vec::new()))?;
+        Ok(())
+    }
+
     fn disconnect(&mut self) -> TermComResult<()> {
         Ok(self.socket.close(None)?)
     }
@@ -1,3 +1,5 @@
+use crate::features::{parse_escaped_text, LoginParseError};
+use crate::protocol::TransferType;
 use crate::ui::screen_modes::ScreenMode;
 use crate::TerminalResult;
 use chrono::{Duration, Utc};
@@ -55,15 +57,24 @@ impl Display for Terminal {
 pub enum Protocol {
     #[default]
     Telnet,
+    /// Telnet over TLS
+    TelnetTls,
     Raw,
+    Rlogin,
     Ssh,
     WebSocket(bool), // true=secure
+    Serial,
+    /// stdin/stdout of an external program
+    Command,
+    /// Local program in a pseudo terminal, the address is the command line
+    Local,
 }
 
 impl Display for Protocol {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Ssh => write!(f, "SSH"),
+            Self::TelnetTls => write!(f, "Telnet (TLS)"),
             Self::WebSocket(is_secure) => match is_secure {
                 true => write!(f, "Secure WebSocket"),
                 false => write!(f, "WebSocket"),
@@ -75,17 +86,445 @@ impl Display for Protocol {
 
 impl Protocol {
     #[cfg(not(target_arch = "wasm32"))]
-    pub const ALL: [Protocol; 5] = [
+    pub const ALL: [Protocol; 10] = [
         Protocol::Telnet,
+        Protocol::TelnetTls,
         Protocol::Raw,
+        Protocol::Rlogin,
         Protocol::Ssh,
         Protocol::WebSocket(true),
         Protocol::WebSocket(false),
+        Protocol::Serial,
+        Protocol::Command,
+        Protocol::Local,
     ];
     #[cfg(target_arch = "wasm32")]
     pub const ALL: [Protocol; 3] = [Protocol::Telnet, Protocol::Raw, Protocol::WebSocket(true)];
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+impl Parity {
+    pub const ALL: [Parity; 3] = [Parity::None, Parity::Odd, Parity::Even];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StopBits {
+    #[default]
+    One,
+    Two,
+}
+
+impl StopBits {
+    pub const ALL: [StopBits; 2] = [StopBits::One, StopBits::Two];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
+impl FlowControl {
+    pub const ALL: [FlowControl; 3] = [
+        FlowControl::None,
+        FlowControl::Software,
+        FlowControl::Hardware,
+    ];
+}
+
+impl Display for FlowControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlowControl::None => write!(f, "None"),
+            FlowControl::Software => write!(f, "XON/XOFF"),
+            FlowControl::Hardware => write!(f, "RTS/CTS"),
+        }
+    }
+}
+
+/// Settings for serial connections. The address of a serial connection is the
+/// phone number that is dialed with the modem, it's empty for direct connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialSettings {
+    pub device: String,
+    pub baud_rate: u32,
+    pub data_bits: u8,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+}
+
+impl SerialSettings {
+    pub const BAUD_RATES: [u32; 11] = [
+        300, 1200, 2400, 4800, 9600, 14400, 19200, 38400, 57600, 115_200, 230_400,
+    ];
+}
+
+impl Default for SerialSettings {
+    fn default() -> Self {
+        Self {
+            #[cfg(windows)]
+            device: "COM1".to_string(),
+            #[cfg(not(windows))]
+            device: "/dev/ttyS0".to_string(),
+            baud_rate: 9600,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::Hardware,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SshAuthMethod {
+    /// The private key file of the address or the default keys from ~/.ssh
+    PublicKey,
+    Agent,
+    KeyboardInteractive,
+    Password,
+}
+
+impl SshAuthMethod {
+    pub const ALL: [SshAuthMethod; 4] = [
+        SshAuthMethod::PublicKey,
+        SshAuthMethod::Agent,
+        SshAuthMethod::KeyboardInteractive,
+        SshAuthMethod::Password,
+    ];
+}
+
+impl Display for SshAuthMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SshAuthMethod::PublicKey => write!(f, "Public key"),
+            SshAuthMethod::Agent => write!(f, "SSH agent"),
+            SshAuthMethod::KeyboardInteractive => write!(f, "Keyboard interactive"),
+            SshAuthMethod::Password => write!(f, "Password"),
+        }
+    }
+}
+
+/// Settings for ssh connections, the authentication methods are tried in `auth_order`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshSettings {
+    pub key_file: String,
+    pub key_passphrase: String,
+    pub auth_order: Vec<SshAuthMethod>,
+}
+
+impl Default for SshSettings {
+    fn default() -> Self {
+        Self {
+            key_file: String::new(),
+            key_passphrase: String::new(),
+            auth_order: vec![
+                SshAuthMethod::PublicKey,
+                SshAuthMethod::Agent,
+                SshAuthMethod::Password,
+                SshAuthMethod::KeyboardInteractive,
+            ],
+        }
+    }
+}
+
+/// Character sets that can be negotiated with telnet CHARSET, see <https://www.rfc-editor.org/rfc/rfc2066>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    #[default]
+    Cp437,
+    Utf8,
+    Latin1,
+}
+
+impl Charset {
+    pub const ALL: [Charset; 3] = [Charset::Utf8, Charset::Cp437, Charset::Latin1];
+
+    /// The IANA name that is used in the negotiation
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Cp437 => "IBM437",
+            Charset::Utf8 => "UTF-8",
+            Charset::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_uppercase().as_str() {
+            "IBM437" | "IBM-437" | "CP437" | "CP-437" | "437" | "CSPC8CODEPAGE437" => {
+                Some(Charset::Cp437)
+            }
+            "UTF-8" | "UTF8" => Some(Charset::Utf8),
+            "ISO-8859-1" | "ISO_8859-1" | "ISO8859-1" | "LATIN1" | "LATIN-1" | "L1" => {
+                Some(Charset::Latin1)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for Charset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TelnetSettings {
+    /// Names answered to TTYPE requests in this order, see <https://www.rfc-editor.org/rfc/rfc1091>.
+    /// If empty the name is derived from the terminal type.
+    pub terminal_types: Vec<String>,
+    /// Variables sent with NEW-ENVIRON in addition to USER, see <https://www.rfc-editor.org/rfc/rfc1572>.
+    pub environment: Vec<(String, String)>,
+    /// Preferred character sets for CHARSET, nothing is negotiated if empty.
+    pub charsets: Vec<Charset>,
+}
+
+/// Connection through the stdin/stdout of a program, like the `ProxyCommand` of ssh
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandSettings {
+    /// Run by the shell - %h and %p are replaced by the host and port of the address.
+    pub command: String,
+    /// Run the telnet protocol on top of the byte stream
+    pub use_telnet: bool,
+}
+
+/// Settings for programs that are run in a pseudo terminal
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalSettings {
+    /// The program is started there, the current directory is used if empty.
+    pub working_directory: String,
+}
+
+/// Rhai scripts of the address, see `features::scripting`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptSettings {
+    /// Path of the script that runs once the connection is established
+    pub login_script: String,
+    /// Paths of the scripts offered in the script menu
+    pub macros: Vec<String>,
+}
+
+/// What a trigger does when its pattern arrives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerAction {
+    /// Text with the escapes of the auto login string, like `\r`
+    Send(String),
+    Beep,
+    Download(TransferType),
+    Upload(TransferType),
+    StartCapture,
+    /// Selects the matched text on the caret row
+    Highlight,
+    /// Beeps and asks the desktop to flag the window
+    Notify,
+}
+
+impl TriggerAction {
+    /// Name in the address book
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            TriggerAction::Send(_) => "send",
+            TriggerAction::Beep => "beep",
+            TriggerAction::Download(_) => "download",
+            TriggerAction::Upload(_) => "upload",
+            TriggerAction::StartCapture => "capture",
+            TriggerAction::Highlight => "highlight",
+            TriggerAction::Notify => "notify",
+        }
+    }
+}
+
+/// Runs the action when the pattern arrives, see `features::triggers`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trigger {
+    /// Literal text (ignoring the case) or a regular expression that matches within a line
+    pub pattern: String,
+    pub is_regex: bool,
+    pub action: TriggerAction,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            is_regex: false,
+            action: TriggerAction::Send(String::new()),
+        }
+    }
+}
+
+/// Longest wait between two reconnect attempts
+const MAX_RECONNECT_DELAY: u64 = 5 * 60;
+
+/// Redial after the remote side dropped the connection (not after a hangup).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectSettings {
+    pub enabled: bool,
+    /// Attempts before giving up
+    pub retries: u32,
+    /// Seconds before the first attempt, the delay doubles with every attempt.
+    pub delay: u32,
+}
+
+impl Default for ReconnectSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retries: 5,
+            delay: 5,
+        }
+    }
+}
+
+impl ReconnectSettings {
+    /// Time to wait before the attempt, attempts start at 0.
+    #[must_use]
+    pub fn delay_for(&self, attempt: u32) -> std::time::Duration {
+        let secs = u64::from(self.delay)
+            .saturating_mul(1 << attempt.min(16))
+            .min(MAX_RECONNECT_DELAY);
+        std::time::Duration::from_secs(secs)
+    }
+}
+
+/// Command that is sent as telnet keepalive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TelnetKeepAlive {
+    #[default]
+    Nop,
+    /// Are You There - most servers answer with a text.
+    AreYouThere,
+}
+
+/// Keeps idle sessions open, nothing is sent during file transfers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeepAliveSettings {
+    /// Seconds between keepalives of the protocol (telnet command, SSH ignore message,
+    /// WebSocket ping) while nothing is sent, 0 turns them off.
+    pub interval: u32,
+    pub telnet_command: TelnetKeepAlive,
+    /// Sent after `anti_idle_minutes` without sending anything, off if empty.
+    /// Supports the escapes of the auto login, see `features::parse_escaped_text`.
+    pub anti_idle_string: String,
+    pub anti_idle_minutes: u32,
+}
+
+impl Default for KeepAliveSettings {
+    fn default() -> Self {
+        Self {
+            interval: 0,
+            telnet_command: TelnetKeepAlive::default(),
+            anti_idle_string: String::new(),
+            anti_idle_minutes: 5,
+        }
+    }
+}
+
+impl KeepAliveSettings {
+    /// The anti-idle string with resolved escapes
+    ///
+    /// # Errors
+    ///
+    /// Fails if the string contains an invalid escape sequence.
+    pub fn anti_idle_data(&self) -> Result<Vec<u8>, LoginParseError> {
+        parse_escaped_text(&self.anti_idle_string)
+    }
+}
+
+/// Settings of rlogin connections
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RloginSettings {
+    /// Sends the password instead of the user name as client user in the handshake.
+    /// Door servers (like the one from Synchronet) expect the password there.
+    pub send_password: bool,
+}
+
+/// Certificate checks for TLS connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsSettings {
+    /// Check the certificate chain and the server name against the web PKI roots.
+    pub verify_certificate: bool,
+    /// SHA-256 fingerprint of the server certificate - if set only this certificate is accepted.
+    /// That's the way to connect to boards with self signed certificates.
+    pub fingerprint: String,
+    /// Name for SNI and the certificate check, the host of the address is used if empty.
+    pub server_name: String,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        Self {
+            verify_certificate: true,
+            fingerprint: String::new(),
+            server_name: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProxyType {
+    /// Direct connection
+    #[default]
+    None,
+    Socks4a,
+    Socks5,
+    Http,
+}
+
+impl ProxyType {
+    pub const ALL: [ProxyType; 4] = [
+        ProxyType::None,
+        ProxyType::Socks4a,
+        ProxyType::Socks5,
+        ProxyType::Http,
+    ];
+
+    #[must_use]
+    pub fn default_port(self) -> u16 {
+        match self {
+            ProxyType::Http => 8080,
+            _ => 1080,
+        }
+    }
+}
+
+impl Display for ProxyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Socks4a => write!(f, "SOCKS4a"),
+            Self::Socks5 => write!(f, "SOCKS5"),
+            Self::Http => write!(f, "HTTP CONNECT"),
+            Self::None => write!(f, "None"),
+        }
+    }
+}
+
+/// Proxy for all socket based connections
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    pub proxy_type: ProxyType,
+    /// host:port of the proxy
+    pub address: String,
+    /// `SOCKS4a` only uses the user name.
+    pub user_name: String,
+    pub password: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct AddressBook {
     pub write_lock: bool,
@@ -146,6 +585,8 @@ pub struct Address {
     pub overall_duration: chrono::Duration,
 
     pub number_of_calls: usize,
+    /// Automatic redials after a dropped connection
+    pub number_of_reconnects: usize,
     pub last_call: Option<chrono::DateTime<Utc>>,
     pub last_call_duration: chrono::Duration,
     pub uploaded_bytes: usize,
@@ -154,6 +595,20 @@ pub struct Address {
     pub override_iemsi_settings: bool,
     pub iemsi_user: String,
     pub iemsi_password: String,
+
+    pub serial: SerialSettings,
+    pub ssh: SshSettings,
+    pub telnet: TelnetSettings,
+    pub rlogin: RloginSettings,
+    pub tls: TlsSettings,
+    pub command: CommandSettings,
+    pub local: LocalSettings,
+    pub reconnect: ReconnectSettings,
+    pub keepalive: KeepAliveSettings,
+    pub scripts: ScriptSettings,
+    pub triggers: Vec<Trigger>,
+    /// Own proxy settings, the global proxy settings are used if `None`.
+    pub proxy: Option<ProxySettings>,
 }
 
 const TEMPLATE: &str = r#"
@@ -240,6 +695,7 @@ impl Address {
             updated: time,
             overall_duration: Duration::zero(),
             number_of_calls: 0,
+            number_of_reconnects: 0,
             last_call: None,
             last_call_duration: Duration::zero(),
             uploaded_bytes: 0,
@@ -248,6 +704,18 @@ impl Address {
             override_iemsi_settings: false,
             iemsi_user: String::new(),
             iemsi_password: String::new(),
+            serial: SerialSettings::default(),
+            ssh: SshSettings::default(),
+            telnet: TelnetSettings::default(),
+            rlogin: RloginSettings::default(),
+            tls: TlsSettings::default(),
+            command: CommandSettings::default(),
+            local: LocalSettings::default(),
+            reconnect: ReconnectSettings::default(),
+            keepalive: KeepAliveSettings::default(),
+            scripts: ScriptSettings::default(),
+            triggers: Vec::new(),
+            proxy: None,
         }
     }
 
@@ -456,7 +924,7 @@ fn start_watch_thread() {
     }
 }
 
-fn parse_address(value: &Value) -> Address {
+pub(crate) fn parse_address(value: &Value) -> Address {
     let mut result = Address::new(String::new());
     if let Value::Table(table) = value {
         if let Some(Value::String(value)) = table.get("system_name") {
@@ -486,6 +954,11 @@ fn parse_address(value: &Value) -> Address {
                 result.number_of_calls = *value as usize;
             }
         }
+        if let Some(Value::Integer(value)) = table.get("number_of_reconnects") {
+            if *value >= 0 {
+                result.number_of_reconnects = *value as usize;
+            }
+        }
 
         if let Some(Value::String(value)) = table.get("last_call") {
             result.last_call = Some(chrono::DateTime::parse_from_rfc3339(value).unwrap().into());
@@ -498,10 +971,15 @@ fn parse_address(value: &Value) -> Address {
         if let Some(Value::String(value)) = table.get("protocol") {
             match value.to_lowercase().as_str() {
                 "telnet" => result.protocol = Protocol::Telnet,
+                "telnettls" => result.protocol = Protocol::TelnetTls,
                 "ssh" => result.protocol = Protocol::Ssh,
                 "raw" => result.protocol = Protocol::Raw,
+                "rlogin" => result.protocol = Protocol::Rlogin,
                 "websocket(true)" => result.protocol = Protocol::WebSocket(true),
                 "websocket(false)" => result.protocol = Protocol::WebSocket(false),
+                "serial" => result.protocol = Protocol::Serial,
+                "command" => result.protocol = Protocol::Command,
+                "local" => result.protocol = Protocol::Local,
                 _ => {}
             }
         }
@@ -560,11 +1038,313 @@ fn parse_address(value: &Value) -> Address {
                 result.iemsi_password = value.clone();
             }
         }
+        if let Some(Value::Table(map)) = table.get("SERIAL") {
+            parse_serial_settings(&mut result.serial, map);
+        }
+        if let Some(Value::Table(map)) = table.get("SSH") {
+            parse_ssh_settings(&mut result.ssh, map);
+        }
+        if let Some(Value::Table(map)) = table.get("TELNET") {
+            parse_telnet_settings(&mut result.telnet, map);
+        }
+        if let Some(Value::Table(map)) = table.get("RLOGIN") {
+            if let Some(Value::Boolean(value)) = map.get("send_password") {
+                result.rlogin.send_password = *value;
+            }
+        }
+        if let Some(Value::Table(map)) = table.get("TLS") {
+            parse_tls_settings(&mut result.tls, map);
+        }
+        if let Some(Value::Table(map)) = table.get("COMMAND") {
+            parse_command_settings(&mut result.command, map);
+        }
+        if let Some(Value::Table(map)) = table.get("KEEPALIVE") {
+            parse_keepalive_settings(&mut result.keepalive, map);
+        }
+        if let Some(Value::Table(map)) = table.get("RECONNECT") {
+            parse_reconnect_settings(&mut result.reconnect, map);
+        }
+        if let Some(Value::Table(map)) = table.get("SCRIPTS") {
+            parse_script_settings(&mut result.scripts, map);
+        }
+        if let Some(Value::Array(values)) = table.get("TRIGGERS") {
+            result.triggers = values
+                .iter()
+                .filter_map(|value| match value {
+                    Value::Table(map) => parse_trigger(map),
+                    _ => None,
+                })
+                .collect();
+        }
+        if let Some(Value::Table(map)) = table.get("LOCAL") {
+            if let Some(Value::String(value)) = map.get("working_directory") {
+                result.local.working_directory = value.clone();
+            }
+        }
+        if let Some(Value::Table(map)) = table.get("PROXY") {
+            let mut proxy = ProxySettings::default();
+            parse_proxy_settings(&mut proxy, map);
+            result.proxy = Some(proxy);
+        }
     }
 
     result
 }
 
+fn parse_serial_settings(serial: &mut SerialSettings, map: &toml::map::Map<String, Value>) {
+    if let Some(Value::String(value)) = map.get("device") {
+        serial.device = value.clone();
+    }
+    if let Some(Value::Integer(value)) = map.get("baud_rate") {
+        if *value > 0 {
+            serial.baud_rate = *value as u32;
+        }
+    }
+    if let Some(Value::Integer(value)) = map.get("data_bits") {
+        if (5..=8).contains(value) {
+            serial.data_bits = *value as u8;
+        }
+    }
+    if let Some(Value::String(value)) = map.get("parity") {
+        match value.to_lowercase().as_str() {
+            "none" => serial.parity = Parity::None,
+            "odd" => serial.parity = Parity::Odd,
+            "even" => serial.parity = Parity::Even,
+            _ => {}
+        }
+    }
+    if let Some(Value::String(value)) = map.get("stop_bits") {
+        match value.to_lowercase().as_str() {
+            "one" => serial.stop_bits = StopBits::One,
+            "two" => serial.stop_bits = StopBits::Two,
+            _ => {}
+        }
+    }
+    if let Some(Value::String(value)) = map.get("flow_control") {
+        match value.to_lowercase().as_str() {
+            "none" => serial.flow_control = FlowControl::None,
+            "software" => serial.flow_control = FlowControl::Software,
+            "hardware" => serial.flow_control = FlowControl::Hardware,
+            _ => {}
+        }
+    }
+}
+
+fn parse_ssh_settings(ssh: &mut SshSettings, map: &toml::map::Map<String, Value>) {
+    if let Some(Value::String(value)) = map.get("key_file") {
+        ssh.key_file = value.clone();
+    }
+    if let Some(Value::String(value)) = map.get("key_passphrase") {
+        ssh.key_passphrase = value.clone();
+    }
+    if let Some(Value::Array(values)) = map.get("auth_order") {
+        ssh.auth_order.clear();
+        for value in values {
+            if let Value::String(value) = value {
+                let method = match value.to_lowercase().as_str() {
+                    "publickey" => SshAuthMethod::PublicKey,
+                    "agent" => SshAuthMethod::Agent,
+                    "keyboardinteractive" => SshAuthMethod::KeyboardInteractive,
+                    "password" => SshAuthMethod::Password,
+                    _ => continue,
+                };
+                if !ssh.auth_order.contains(&method) {
+                    ssh.auth_order.push(method);
+                }
+            }
+        }
+    }
+}
+
+fn parse_telnet_settings(telnet: &mut TelnetSettings, map: &toml::map::Map<String, Value>) {
+    if let Some(Value::Array(values)) = map.get("terminal_types") {
+        telnet.terminal_types = values
+            .iter()
+            .filter_map(|value| match value {
+                Value::String(name) if !name.trim().is_empty() => Some(name.trim().to_string()),
+                _ => None,
+            })
+            .collect();
+    }
+    if let Some(Value::Table(values)) = map.get("environment") {
+        telnet.environment = values
+            .iter()
+            .filter_map(|(name, value)| match value {
+                Value::String(value) => Some((name.clone(), value.clone())),
+                Value::Integer(value) => Some((name.clone(), value.to_string())),
+                _ => None,
+            })
+            .collect();
+    }
+    if let Some(Value::Array(values)) = map.get("charsets") {
+        telnet.charsets.clear();
+        for value in values {
+            if let Value::String(name) = value {
+                if let Some(charset) = Charset::from_name(name) {
+                    if !telnet.charsets.contains(&charset) {
+                        telnet.charsets.push(charset);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn parse_script_settings(scripts: &mut ScriptSettings, map: &toml::map::Map<String, Value>) {
+    if let Some(Value::String(value)) = map.get("login_script") {
+        scripts.login_script = value.clone();
+    }
+    if let Some(Value::Array(values)) = map.get("macros") {
+        scripts.macros = values
+            .iter()
+            .filter_map(|value| match value {
+                Value::String(path) if !path.trim().is_empty() => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+    }
+}
+
+fn parse_trigger(map: &toml::map::Map<String, Value>) -> Option<Trigger> {
+    let Some(Value::String(pattern)) = map.get("pattern") else {
+        return None;
+    };
+    if pattern.is_empty() {
+        return None;
+    }
+    let text = match map.get("text") {
+        Some(Value::String(text)) => text.clone(),
+        _ => String::new(),
+    };
+    let protocol = match map.get("protocol") {
+        Some(Value::String(name)) => TransferType::from_name(name).unwrap_or_default(),
+        _ => TransferType::default(),
+    };
+    let action = match map.get("action") {
+        Some(Value::String(action)) => match action.to_lowercase().as_str() {
+            "send" => TriggerAction::Send(text),
+            "beep" => TriggerAction::Beep,
+            "download" => TriggerAction::Download(protocol),
+            "upload" => TriggerAction::Upload(protocol),
+            "capture" => TriggerAction::StartCapture,
+            "highlight" => TriggerAction::Highlight,
+            "notify" => TriggerAction::Notify,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(Trigger {
+        pattern: pattern.clone(),
+        is_regex: matches!(map.get("regex"), Some(Value::Boolean(true))),
+        action,
+    })
+}
+
+fn parse_tls_settings(tls: &mut TlsSettings, map: &toml::map::Map<String, Value>) {
+    if let Some(Value::Boolean(value)) = map.get("verify_certificate") {
+        tls.verify_certificate = *value;
+    }
+    if let Some(Value::String(value)) = map.get("fingerprint") {
+        tls.fingerprint = value.clone();
+    }
+    if let Some(Value::String(value)) = map.get("server_name") {
+        tls.server_name = value.clone();
+    }
+}
+
+fn parse_command_settings(command: &mut CommandSettings, map: &toml::map::Map<String, Value>) {
+    if let Some(Value::String(value)) = map.get("command") {
+        command.command = value.clone();
+    }
+    if let Some(Value::Boolean(value)) = map.get("use_telnet") {
+        command.use_telnet = *value;
+    }
+}
+
+fn parse_keepalive_settings(
+    keepalive: &mut KeepAliveSettings,
+    map: &toml::map::Map<String, Value>,
+) {
+    if let Some(Value::Integer(value)) = map.get("interval") {
+        if let Ok(value) = u32::try_from(*value) {
+            keepalive.interval = value;
+        }
+    }
+    if let Some(Value::String(value)) = map.get("telnet_command") {
+        match value.to_lowercase().as_str() {
+            "nop" => keepalive.telnet_command = TelnetKeepAlive::Nop,
+            "ayt" => keepalive.telnet_command = TelnetKeepAlive::AreYouThere,
+            _ => {}
+        }
+    }
+    if let Some(Value::String(value)) = map.get("anti_idle_string") {
+        keepalive.anti_idle_string = value.clone();
+    }
+    if let Some(Value::Integer(value)) = map.get("anti_idle_minutes") {
+        if let Ok(value) = u32::try_from(*value) {
+            keepalive.anti_idle_minutes = value;
+        }
+    }
+}
+
+fn parse_reconnect_settings(
+    reconnect: &mut ReconnectSettings,
+    map: &toml::map::Map<String, Value>,
+) {
+    if let Some(Value::Boolean(value)) = map.get("enabled") {
+        reconnect.enabled = *value;
+    }
+    if let Some(Value::Integer(value)) = map.get("retries") {
+        if let Ok(value) = u32::try_from(*value) {
+            reconnect.retries = value;
+        }
+    }
+    if let Some(Value::Integer(value)) = map.get("delay") {
+        if let Ok(value) = u32::try_from(*value) {
+            reconnect.delay = value;
+        }
+    }
+}
+
+pub(crate) fn parse_proxy_settings(proxy: &mut ProxySettings, map: &toml::map::Map<String, Value>) {
+    if let Some(Value::String(value)) = map.get("type") {
+        match value.to_lowercase().as_str() {
+            "none" => proxy.proxy_type = ProxyType::None,
+            "socks4a" => proxy.proxy_type = ProxyType::Socks4a,
+            "socks5" => proxy.proxy_type = ProxyType::Socks5,
+            "http" => proxy.proxy_type = ProxyType::Http,
+            _ => {}
+        }
+    }
+    if let Some(Value::String(value)) = map.get("address") {
+        proxy.address = value.clone();
+    }
+    if let Some(Value::String(value)) = map.get("user_name") {
+        proxy.user_name = value.clone();
+    }
+    if let Some(Value::String(value)) = map.get("password") {
+        proxy.password = value.clone();
+    }
+}
+
+/// Writes the fields of a proxy table, the caller writes the table header.
+pub(crate) fn write_proxy_settings(
+    file: &mut impl Write,
+    proxy: &ProxySettings,
+) -> TerminalResult<()> {
+    file.write_all(format!("type = \"{:?}\"\n", proxy.proxy_type).as_bytes())?;
+    if !proxy.address.is_empty() {
+        file.write_all(format!("address = \"{}\"\n", escape(&proxy.address)).as_bytes())?;
+    }
+    if !proxy.user_name.is_empty() {
+        file.write_all(format!("user_name = \"{}\"\n", escape(&proxy.user_name)).as_bytes())?;
+    }
+    if !proxy.password.is_empty() {
+        file.write_all(format!("password = \"{}\"\n", escape(&proxy.password)).as_bytes())?;
+    }
+    Ok(())
+}
+
 fn escape(value: &str) -> String {
     value
         .replace('\\', "\\\\")
@@ -574,7 +1354,7 @@ fn escape(value: &str) -> String {
         .replace('\r', "\\r")
 }
 
-fn store_address(file: &mut File, addr: &Address) -> TerminalResult<()> {
+fn store_address(file: &mut impl Write, addr: &Address) -> TerminalResult<()> {
     file.write_all(b"\n[[addresses]]\n")?;
     file.write_all(format!("system_name = \"{}\"\n", escape(&addr.system_name)).as_bytes())?;
     if addr.is_favored {
@@ -613,6 +1393,11 @@ fn store_address(file: &mut File, addr: &Address) -> TerminalResult<()> {
         file.write_all(format!("comment = \"{}\"\n", escape(&addr.comment)).as_bytes())?;
     }
     file.write_all(format!("number_of_calls = {}\n", addr.number_of_calls).as_bytes())?;
+    if addr.number_of_reconnects > 0 {
+        file.write_all(
+            format!("number_of_reconnects = {}\n", addr.number_of_reconnects).as_bytes(),
+        )?;
+    }
 
     if let Some(last_call) = addr.last_call {
         file.write_all(format!("last_call = \"{}\"\n", last_call.to_rfc3339()).as_bytes())?;
@@ -640,6 +1425,206 @@ fn store_address(file: &mut File, addr: &Address) -> TerminalResult<()> {
         }
     }
 
+    if addr.protocol == Protocol::Serial || addr.serial != SerialSettings::default() {
+        file.write_all("[addresses.SERIAL]\n".to_string().as_bytes())?;
+        file.write_all(format!("device = \"{}\"\n", escape(&addr.serial.device)).as_bytes())?;
+        file.write_all(format!("baud_rate = {}\n", addr.serial.baud_rate).as_bytes())?;
+        file.write_all(format!("data_bits = {}\n", addr.serial.data_bits).as_bytes())?;
+        file.write_all(format!("parity = \"{:?}\"\n", addr.serial.parity).as_bytes())?;
+        file.write_all(format!("stop_bits = \"{:?}\"\n", addr.serial.stop_bits).as_bytes())?;
+        file.write_all(format!("flow_control = \"{:?}\"\n", addr.serial.flow_control).as_bytes())?;
+    }
+
+    if addr.ssh != SshSettings::default() {
+        file.write_all("[addresses.SSH]\n".to_string().as_bytes())?;
+        if !addr.ssh.key_file.is_empty() {
+            file.write_all(format!("key_file = \"{}\"\n", escape(&addr.ssh.key_file)).as_bytes())?;
+        }
+        if !addr.ssh.key_passphrase.is_empty() {
+            file.write_all(
+                format!(
+                    "key_passphrase = \"{}\"\n",
+                    escape(&addr.ssh.key_passphrase)
+                )
+                .as_bytes(),
+            )?;
+        }
+        let auth_order: Vec<String> = addr
+            .ssh
+            .auth_order
+            .iter()
+            .map(|method| format!("\"{method:?}\""))
+            .collect();
+        file.write_all(format!("auth_order = [{}]\n", auth_order.join(", ")).as_bytes())?;
+    }
+
+    if addr.telnet != TelnetSettings::default() {
+        file.write_all("[addresses.TELNET]\n".to_string().as_bytes())?;
+        let terminal_types: Vec<String> = addr
+            .telnet
+            .terminal_types
+            .iter()
+            .map(|name| format!("\"{}\"", escape(name)))
+            .collect();
+        if !terminal_types.is_empty() {
+            file.write_all(
+                format!("terminal_types = [{}]\n", terminal_types.join(", ")).as_bytes(),
+            )?;
+        }
+        let environment: Vec<String> = addr
+            .telnet
+            .environment
+            .iter()
+            .filter(|(name, _)| !name.trim().is_empty())
+            .map(|(name, value)| format!("\"{}\" = \"{}\"", escape(name.trim()), escape(value)))
+            .collect();
+        if !environment.is_empty() {
+            file.write_all(format!("environment = {{ {} }}\n", environment.join(", ")).as_bytes())?;
+        }
+        if !addr.telnet.charsets.is_empty() {
+            let charsets: Vec<String> = addr
+                .telnet
+                .charsets
+                .iter()
+                .map(|charset| format!("\"{charset}\""))
+                .collect();
+            file.write_all(format!("charsets = [{}]\n", charsets.join(", ")).as_bytes())?;
+        }
+    }
+
+    if addr.rlogin != RloginSettings::default() {
+        file.write_all(b"[addresses.RLOGIN]\n")?;
+        file.write_all(format!("send_password = {}\n", addr.rlogin.send_password).as_bytes())?;
+    }
+
+    if addr.tls != TlsSettings::default() {
+        file.write_all("[addresses.TLS]\n".to_string().as_bytes())?;
+        file.write_all(
+            format!("verify_certificate = {}\n", addr.tls.verify_certificate).as_bytes(),
+        )?;
+        if !addr.tls.fingerprint.is_empty() {
+            file.write_all(
+                format!("fingerprint = \"{}\"\n", escape(&addr.tls.fingerprint)).as_bytes(),
+            )?;
+        }
+        if !addr.tls.server_name.is_empty() {
+            file.write_all(
+                format!("server_name = \"{}\"\n", escape(&addr.tls.server_name)).as_bytes(),
+            )?;
+        }
+    }
+
+    if addr.command != CommandSettings::default() {
+        file.write_all(b"[addresses.COMMAND]\n")?;
+        file.write_all(format!("command = \"{}\"\n", escape(&addr.command.command)).as_bytes())?;
+        if addr.command.use_telnet {
+            file.write_all(b"use_telnet = true\n")?;
+        }
+    }
+
+    if addr.keepalive != KeepAliveSettings::default() {
+        let keepalive = &addr.keepalive;
+        file.write_all(b"[addresses.KEEPALIVE]\n")?;
+        file.write_all(format!("interval = {}\n", keepalive.interval).as_bytes())?;
+        let telnet_command = match keepalive.telnet_command {
+            TelnetKeepAlive::Nop => "nop",
+            TelnetKeepAlive::AreYouThere => "ayt",
+        };
+        file.write_all(format!("telnet_command = \"{telnet_command}\"\n").as_bytes())?;
+        file.write_all(
+            format!(
+                "anti_idle_string = \"{}\"\n",
+                escape(&keepalive.anti_idle_string)
+            )
+            .as_bytes(),
+        )?;
+        file.write_all(
+            format!("anti_idle_minutes = {}\n", keepalive.anti_idle_minutes).as_bytes(),
+        )?;
+    }
+
+    if addr.reconnect != ReconnectSettings::default() {
+        file.write_all(b"[addresses.RECONNECT]\n")?;
+        file.write_all(format!("enabled = {}\n", addr.reconnect.enabled).as_bytes())?;
+        file.write_all(format!("retries = {}\n", addr.reconnect.retries).as_bytes())?;
+        file.write_all(format!("delay = {}\n", addr.reconnect.delay).as_bytes())?;
+    }
+
+    if addr.scripts != ScriptSettings::default() {
+        file.write_all(b"[addresses.SCRIPTS]\n")?;
+        if !addr.scripts.login_script.is_empty() {
+            file.write_all(
+                format!(
+                    "login_script = \"{}\"\n",
+                    escape(&addr.scripts.login_script)
+                )
+                .as_bytes(),
+            )?;
+        }
+        let macros: Vec<String> = addr
+            .scripts
+            .macros
+            .iter()
+            .filter(|path| !path.trim().is_empty())
+            .map(|path| format!("\"{}\"", escape(path)))
+            .collect();
+        if !macros.is_empty() {
+            file.write_all(format!("macros = [{}]\n", macros.join(", ")).as_bytes())?;
+        }
+    }
+
+    if addr.local != LocalSettings::default() {
+        file.write_all(b"[addresses.LOCAL]\n")?;
+        file.write_all(
+            format!(
+                "working_directory = \"{}\"\n",
+                escape(&addr.local.working_directory)
+            )
+            .as_bytes(),
+        )?;
+    }
+
+    if let Some(proxy) = &addr.proxy {
+        file.write_all(b"[addresses.PROXY]\n")?;
+        write_proxy_settings(file, proxy)?;
+    }
+
+    for trigger in addr
+        .triggers
+        .iter()
+        .filter(|trigger| !trigger.pattern.is_empty())
+    {
+        file.write_all(b"[[addresses.TRIGGERS]]\n")?;
+        file.write_all(format!("pattern = \"{}\"\n", escape(&trigger.pattern)).as_bytes())?;
+        if trigger.is_regex {
+            file.write_all(b"regex = true\n")?;
+        }
+        file.write_all(format!("action = \"{}\"\n", trigger.action.name()).as_bytes())?;
+        match &trigger.action {
+            TriggerAction::Send(text) => {
+                file.write_all(format!("text = \"{}\"\n", escape(text)).as_bytes())?;
+            }
+            TriggerAction::Download(protocol) | TriggerAction::Upload(protocol) => {
+                file.write_all(format!("protocol = \"{}\"\n", protocol.name()).as_bytes())?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the settings needed to replay a session as `[address]` table.
+/// Credentials and connection settings are left out, recordings get passed around.
+pub(crate) fn write_session_settings(out: &mut impl Write, addr: &Address) -> TerminalResult<()> {
+    out.write_all(b"[address]\n")?;
+    out.write_all(format!("system_name = \"{}\"\n", escape(&addr.system_name)).as_bytes())?;
+    out.write_all(format!("address = \"{}\"\n", escape(&addr.address)).as_bytes())?;
+    out.write_all(format!("protocol = \"{:?}\"\n", addr.protocol).as_bytes())?;
+    out.write_all(format!("terminal_type = \"{:?}\"\n", addr.terminal_type).as_bytes())?;
+    out.write_all(format!("ansi_music = \"{:?}\"\n", addr.ansi_music).as_bytes())?;
+    out.write_all(format!("baud_emulation = \"{}\"\n", addr.baud_emulation).as_bytes())?;
+    out.write_all(format!("screen_mode = \"{:?}\"\n", addr.screen_mode).as_bytes())?;
     Ok(())
 }
 
@@ -713,13 +1698,539 @@ mod tests {
     #![allow(clippy::field_reassign_with_default)]
     use super::*;
 
-    #[test]
-    fn test_load_default_template() {
+    fn load(toml: &str) -> AddressBook {
         let mut res = AddressBook {
             write_lock: false,
             created_backup: false,
             addresses: Vec::new(),
         };
-        res.load_string(TEMPLATE).unwrap();
+        res.load_string(toml).unwrap();
+        res
+    }
+
+    /// Writes the address like the phone book does and reads it back.
+    fn store_and_load(adr: &Address) -> Address {
+        let mut data = format!("version = \"{}\"\n", AddressBook::VERSION).into_bytes();
+        store_address(&mut data, adr).unwrap();
+        load(&String::from_utf8(data).unwrap()).addresses.remove(0)
+    }
+
+    #[test]
+    fn test_load_default_template() {
+        load(TEMPLATE);
+    }
+
+    #[test]
+    fn test_store_serial_settings() {
+        let mut adr = Address::new("Null modem".to_string());
+        adr.protocol = Protocol::Serial;
+        adr.serial = SerialSettings {
+            device: "/dev/ttyUSB0".to_string(),
+            baud_rate: 2400,
+            data_bits: 7,
+            parity: Parity::Even,
+            stop_bits: StopBits::Two,
+            flow_control: FlowControl::Hardware,
+        };
+        let loaded = store_and_load(&adr);
+        assert_eq!(Protocol::Serial, loaded.protocol);
+        assert_eq!(adr.serial, loaded.serial);
+    }
+
+    #[test]
+    fn test_parse_serial_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Null modem"
+address = "5551234"
+protocol = "Serial"
+[addresses.SERIAL]
+device = "/dev/ttyUSB0"
+baud_rate = 2400
+data_bits = 7
+parity = "Even"
+stop_bits = "Two"
+flow_control = "Software"
+"#,
+        );
+        let adr = &res.addresses[0];
+        assert_eq!(Protocol::Serial, adr.protocol);
+        assert_eq!("/dev/ttyUSB0", adr.serial.device);
+        assert_eq!(2400, adr.serial.baud_rate);
+        assert_eq!(7, adr.serial.data_bits);
+        assert_eq!(Parity::Even, adr.serial.parity);
+        assert_eq!(StopBits::Two, adr.serial.stop_bits);
+        assert_eq!(FlowControl::Software, adr.serial.flow_control);
+    }
+
+    #[test]
+    fn test_parse_ssh_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Internal board"
+address = "bbs.local:2222"
+protocol = "Ssh"
+[addresses.SSH]
+key_file = "/home/sysop/.ssh/id_ed25519"
+key_passphrase = "secret"
+auth_order = ["Agent", "PublicKey", "Unknown", "Agent", "KeyboardInteractive"]
+"#,
+        );
+        let adr = &res.addresses[0];
+        assert_eq!("/home/sysop/.ssh/id_ed25519", adr.ssh.key_file);
+        assert_eq!("secret", adr.ssh.key_passphrase);
+        assert_eq!(
+            vec![
+                SshAuthMethod::Agent,
+                SshAuthMethod::PublicKey,
+                SshAuthMethod::KeyboardInteractive
+            ],
+            adr.ssh.auth_order
+        );
+    }
+
+    #[test]
+    fn test_store_ssh_settings() {
+        let mut adr = Address::new("SSH".to_string());
+        adr.protocol = Protocol::Ssh;
+        adr.ssh = SshSettings {
+            key_file: "~/.ssh/id_ed25519".to_string(),
+            key_passphrase: "pass \"phrase\"".to_string(),
+            auth_order: vec![SshAuthMethod::Password, SshAuthMethod::KeyboardInteractive],
+        };
+        assert_eq!(adr.ssh, store_and_load(&adr).ssh);
+    }
+
+    #[test]
+    fn test_parse_telnet_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Synchronet"
+address = "vert.synchro.net"
+[addresses.TELNET]
+terminal_types = ["xterm-256color", " ", "ANSI", 42]
+environment = { TERM = "ansi", COLUMNS = 80, SYSTEMTYPE = "LINUX", INVALID = false }
+charsets = ["utf8", "KOI8-R", "CP437", "IBM437"]
+"#,
+        );
+        assert_eq!(
+            vec!["xterm-256color".to_string(), "ANSI".to_string()],
+            res.addresses[0].telnet.terminal_types
+        );
+        let mut environment = res.addresses[0].telnet.environment.clone();
+        environment.sort();
+        assert_eq!(
+            vec![
+                ("COLUMNS".to_string(), "80".to_string()),
+                ("SYSTEMTYPE".to_string(), "LINUX".to_string()),
+                ("TERM".to_string(), "ansi".to_string()),
+            ],
+            environment
+        );
+        assert_eq!(
+            vec![Charset::Utf8, Charset::Cp437],
+            res.addresses[0].telnet.charsets
+        );
+    }
+
+    #[test]
+    fn test_store_telnet_settings() {
+        let mut adr = Address::new("Telnet".to_string());
+        adr.telnet.terminal_types = vec!["xterm".to_string(), "ansi".to_string()];
+        adr.telnet.environment = vec![
+            ("LANG".to_string(), "de_DE".to_string()),
+            ("TZ".to_string(), "UTC".to_string()),
+        ];
+        adr.telnet.charsets = vec![Charset::Utf8, Charset::Latin1];
+        assert_eq!(adr.telnet, store_and_load(&adr).telnet);
+    }
+
+    #[test]
+    fn test_parse_tls_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Secure board"
+address = "bbs.example.com:992"
+protocol = "TelnetTls"
+[addresses.TLS]
+verify_certificate = false
+fingerprint = "56:06:F2:27"
+server_name = "bbs.example.com"
+"#,
+        );
+        let adr = &res.addresses[0];
+        assert_eq!(Protocol::TelnetTls, adr.protocol);
+        assert!(!adr.tls.verify_certificate);
+        assert_eq!("56:06:F2:27", adr.tls.fingerprint);
+        assert_eq!("bbs.example.com", adr.tls.server_name);
+    }
+
+    #[test]
+    fn test_store_tls_settings() {
+        let mut adr = Address::new("TLS".to_string());
+        adr.protocol = Protocol::TelnetTls;
+        adr.tls = TlsSettings {
+            verify_certificate: false,
+            fingerprint: "56:06:F2:27".to_string(),
+            server_name: "bbs.example.com".to_string(),
+        };
+        assert_eq!(adr.tls, store_and_load(&adr).tls);
+    }
+
+    #[test]
+    fn test_parse_rlogin_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Door server"
+address = "bbs.example.com"
+protocol = "rlogin"
+[addresses.RLOGIN]
+send_password = true
+
+[[addresses]]
+system_name = "Plain rlogin"
+address = "bbs.example.com"
+protocol = "rlogin"
+"#,
+        );
+        assert!(res.addresses[0].rlogin.send_password);
+        assert!(!res.addresses[1].rlogin.send_password);
+    }
+
+    #[test]
+    fn test_store_rlogin_settings() {
+        let mut adr = Address::new("Rlogin".to_string());
+        adr.protocol = Protocol::Rlogin;
+        adr.rlogin.send_password = true;
+        assert_eq!(adr.rlogin, store_and_load(&adr).rlogin);
+    }
+
+    #[test]
+    fn test_parse_proxy_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Behind a proxy"
+address = "bbs.example.com:23"
+[addresses.PROXY]
+type = "Socks5"
+address = "proxy.example.com:1080"
+user_name = "user"
+password = "secret"
+
+[[addresses]]
+system_name = "Global proxy"
+address = "bbs.example.com:23"
+"#,
+        );
+        assert_eq!(
+            Some(ProxySettings {
+                proxy_type: ProxyType::Socks5,
+                address: "proxy.example.com:1080".to_string(),
+                user_name: "user".to_string(),
+                password: "secret".to_string(),
+            }),
+            res.addresses[0].proxy
+        );
+        assert_eq!(None, res.addresses[1].proxy);
+    }
+
+    #[test]
+    fn test_store_proxy_settings() {
+        let mut adr = Address::new("Proxy".to_string());
+        adr.proxy = Some(ProxySettings {
+            proxy_type: ProxyType::Socks5,
+            address: "localhost:1080".to_string(),
+            user_name: "user".to_string(),
+            password: "secret".to_string(),
+        });
+        assert_eq!(adr.proxy, store_and_load(&adr).proxy);
+    }
+
+    #[test]
+    fn test_parse_command_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Behind a bastion"
+address = "bbs.internal:23"
+protocol = "Command"
+[addresses.COMMAND]
+command = "ssh -W %h:%p jump"
+use_telnet = true
+"#,
+        );
+        let adr = &res.addresses[0];
+        assert_eq!(Protocol::Command, adr.protocol);
+        assert_eq!("ssh -W %h:%p jump", adr.command.command);
+        assert!(adr.command.use_telnet);
+    }
+
+    #[test]
+    fn test_store_command_settings() {
+        let mut adr = Address::new("Command".to_string());
+        adr.protocol = Protocol::Command;
+        adr.command = CommandSettings {
+            command: "ssh -W %h:%p \"jump host\"".to_string(),
+            use_telnet: true,
+        };
+        assert_eq!(adr.command, store_and_load(&adr).command);
+    }
+
+    #[test]
+    fn test_parse_local_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Local door"
+address = "dosbox -conf door.conf"
+protocol = "Local"
+[addresses.LOCAL]
+working_directory = "/home/sysop/doors"
+"#,
+        );
+        let adr = &res.addresses[0];
+        assert_eq!(Protocol::Local, adr.protocol);
+        assert_eq!("dosbox -conf door.conf", adr.address);
+        assert_eq!("/home/sysop/doors", adr.local.working_directory);
+    }
+
+    #[test]
+    fn test_store_local_settings() {
+        let mut adr = Address::new("Local".to_string());
+        adr.protocol = Protocol::Local;
+        adr.local.working_directory = "C:\\BBS".to_string();
+        assert_eq!(adr.local, store_and_load(&adr).local);
+    }
+
+    #[test]
+    fn test_parse_reconnect_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Flaky board"
+address = "bbs.example.com:23"
+number_of_calls = 3
+number_of_reconnects = 7
+[addresses.RECONNECT]
+enabled = true
+retries = 3
+delay = 10
+"#,
+        );
+        let adr = &res.addresses[0];
+        assert_eq!(7, adr.number_of_reconnects);
+        assert!(adr.reconnect.enabled);
+        assert_eq!(3, adr.reconnect.retries);
+        assert_eq!(10, adr.reconnect.delay);
+    }
+
+    #[test]
+    fn test_store_reconnect_settings() {
+        let mut adr = Address::new("Reconnect".to_string());
+        adr.reconnect = ReconnectSettings {
+            enabled: true,
+            retries: 3,
+            delay: 30,
+        };
+        assert_eq!(adr.reconnect, store_and_load(&adr).reconnect);
+    }
+
+    #[test]
+    fn test_parse_script_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Scripted"
+address = "bbs.example.com:23"
+[addresses.SCRIPTS]
+login_script = "/home/sysop/login.rhai"
+macros = ["/home/sysop/mail.rhai", "", "/home/sysop/files.rhai"]
+"#,
+        );
+        let scripts = &res.addresses[0].scripts;
+        assert_eq!("/home/sysop/login.rhai", scripts.login_script);
+        assert_eq!(
+            vec!["/home/sysop/mail.rhai", "/home/sysop/files.rhai"],
+            scripts.macros
+        );
+    }
+
+    #[test]
+    fn test_store_script_settings() {
+        let mut adr = Address::new("Scripts".to_string());
+        adr.scripts = ScriptSettings {
+            login_script: "login.rhai".to_string(),
+            macros: vec!["a.rhai".to_string(), "b.rhai".to_string()],
+        };
+        assert_eq!(adr.scripts, store_and_load(&adr).scripts);
+    }
+
+    #[test]
+    fn test_parse_triggers() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Triggered"
+address = "bbs.example.com:23"
+[[addresses.TRIGGERS]]
+pattern = "Press any key"
+action = "send"
+text = "\\r"
+[[addresses.TRIGGERS]]
+pattern = "Sysop is paging"
+action = "beep"
+[[addresses.TRIGGERS]]
+pattern = "^Ready to send .* YMODEM"
+regex = true
+action = "download"
+protocol = "Ymodem-G"
+[[addresses.TRIGGERS]]
+pattern = "New mail"
+action = "highlight"
+[[addresses.TRIGGERS]]
+pattern = "unknown action"
+action = "explode"
+"#,
+        );
+        assert_eq!(
+            vec![
+                Trigger {
+                    pattern: "Press any key".to_string(),
+                    is_regex: false,
+                    action: TriggerAction::Send("\\r".to_string()),
+                },
+                Trigger {
+                    pattern: "Sysop is paging".to_string(),
+                    is_regex: false,
+                    action: TriggerAction::Beep,
+                },
+                Trigger {
+                    pattern: "^Ready to send .* YMODEM".to_string(),
+                    is_regex: true,
+                    action: TriggerAction::Download(TransferType::YModemG),
+                },
+                Trigger {
+                    pattern: "New mail".to_string(),
+                    is_regex: false,
+                    action: TriggerAction::Highlight,
+                },
+            ],
+            res.addresses[0].triggers
+        );
+    }
+
+    #[test]
+    fn test_store_triggers() {
+        let mut adr = Address::new("Triggers".to_string());
+        adr.triggers = vec![
+            Trigger {
+                pattern: "Press any key".to_string(),
+                is_regex: false,
+                action: TriggerAction::Send(r"\r".to_string()),
+            },
+            Trigger {
+                pattern: r"^Sending \d+ files".to_string(),
+                is_regex: true,
+                action: TriggerAction::Download(TransferType::ZModem),
+            },
+            Trigger {
+                pattern: "New mail".to_string(),
+                is_regex: false,
+                action: TriggerAction::Highlight,
+            },
+            Trigger {
+                pattern: "is paging you".to_string(),
+                is_regex: false,
+                action: TriggerAction::Notify,
+            },
+        ];
+        assert_eq!(adr.triggers, store_and_load(&adr).triggers);
+    }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let reconnect = ReconnectSettings::default();
+        assert_eq!(5, reconnect.delay_for(0).as_secs());
+        assert_eq!(10, reconnect.delay_for(1).as_secs());
+        assert_eq!(40, reconnect.delay_for(3).as_secs());
+        assert_eq!(MAX_RECONNECT_DELAY, reconnect.delay_for(10).as_secs());
+        assert_eq!(MAX_RECONNECT_DELAY, reconnect.delay_for(u32::MAX).as_secs());
+    }
+
+    #[test]
+    fn test_parse_keepalive_settings() {
+        let res = load(
+            r#"
+version = "1.1.0"
+
+[[addresses]]
+system_name = "Strict idle timer"
+address = "bbs.example.com:23"
+[addresses.KEEPALIVE]
+interval = 60
+telnet_command = "ayt"
+anti_idle_string = " \\b"
+anti_idle_minutes = 4
+"#,
+        );
+        let keepalive = &res.addresses[0].keepalive;
+        assert_eq!(60, keepalive.interval);
+        assert_eq!(TelnetKeepAlive::AreYouThere, keepalive.telnet_command);
+        assert_eq!(4, keepalive.anti_idle_minutes);
+        assert_eq!(b" \x08".to_vec(), keepalive.anti_idle_data().unwrap());
+    }
+
+    #[test]
+    fn test_store_keepalive_settings() {
+        let mut adr = Address::new("Keepalive".to_string());
+        adr.keepalive = KeepAliveSettings {
+            interval: 60,
+            telnet_command: TelnetKeepAlive::AreYouThere,
+            anti_idle_string: r"\r\e".to_string(),
+            anti_idle_minutes: 10,
+        };
+        assert_eq!(adr.keepalive, store_and_load(&adr).keepalive);
+    }
+
+    #[test]
+    fn test_anti_idle_escapes() {
+        let mut keepalive = KeepAliveSettings {
+            anti_idle_string: r"a\r\n\e\\\x41\cCä".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            "a\r\n\x1B\\A\x03ä".as_bytes().to_vec(),
+            keepalive.anti_idle_data().unwrap()
+        );
+        keepalive.anti_idle_string = r"a\x".to_string();
+        assert_eq!(1, keepalive.anti_idle_data().unwrap_err().position);
     }
 }
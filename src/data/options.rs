@@ -10,7 +10,10 @@ use i18n_embed_fl::fl;
 use icy_engine_egui::MonitorSettings;
 use toml::Value;
 
-use crate::TerminalResult;
+use crate::{
+    addresses::{parse_proxy_settings, write_proxy_settings},
+    ProxySettings, TerminalResult,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Scaling {
@@ -276,16 +279,81 @@ impl Default for IEMSISettings {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Cr,
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 3] = [LineEnding::Cr, LineEnding::Lf, LineEnding::CrLf];
+
+    #[must_use]
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::Cr => b"\r",
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextTransferSettings {
+    /// Delay after each line in ms
+    pub line_delay: u32,
+    /// Delay between the characters of a line in ms
+    pub char_delay: u32,
+    pub wait_for_echo: bool,
+    /// If not empty the next line is sent after the remote printed this prompt.
+    pub wait_for_prompt: String,
+    pub line_ending: LineEnding,
+
+    /// File name used for text downloads
+    pub capture_file_name: String,
+    /// Text download ends on Ctrl-Z (SUB)
+    pub end_on_ctrl_z: bool,
+    /// Text download ends after that many seconds without data, 0 = no timeout
+    pub idle_timeout: u32,
+    /// If not empty the text download ends after this pattern was received.
+    pub end_pattern: String,
+}
+
+impl Default for TextTransferSettings {
+    fn default() -> Self {
+        Self {
+            line_delay: 50,
+            char_delay: 0,
+            wait_for_echo: false,
+            wait_for_prompt: String::default(),
+            line_ending: LineEnding::default(),
+            capture_file_name: "capture.txt".to_string(),
+            end_on_ctrl_z: true,
+            idle_timeout: 10,
+            end_pattern: String::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Options {
     pub scaling: Scaling,
     pub connect_timeout: Duration,
     pub capture_filename: String,
+    /// Sessions are recorded with timing to that file
+    pub recording_filename: String,
     pub console_beep: bool,
+    /// Continue incomplete ZMODEM downloads if the existing data matches
+    pub resume_downloads: bool,
 
     pub monitor_settings: MonitorSettings,
     pub bind: KeyBindings,
     pub iemsi: IEMSISettings,
+    pub text_transfer: TextTransferSettings,
+    /// Used by all addresses without own proxy settings.
+    pub proxy: ProxySettings,
 }
 
 impl Default for Options {
@@ -295,8 +363,12 @@ impl Default for Options {
             connect_timeout: Duration::default(),
             monitor_settings: MonitorSettings::default(),
             capture_filename: String::default(),
+            recording_filename: String::default(),
             iemsi: IEMSISettings::default(),
+            text_transfer: TextTransferSettings::default(),
+            proxy: ProxySettings::default(),
             console_beep: true,
+            resume_downloads: false,
             bind: KeyBindings::default(),
         }
     }
@@ -390,12 +462,24 @@ impl Options {
                 file.write_all(format!("console_beep = {}\n", self.console_beep).as_bytes())?;
             }
 
+            if self.resume_downloads != Options::default().resume_downloads {
+                file.write_all(
+                    format!("resume_downloads = {}\n", self.resume_downloads).as_bytes(),
+                )?;
+            }
+
             if !self.capture_filename.is_empty() {
                 file.write_all(
                     format!("capture_filename = \"{}\"\n", self.capture_filename).as_bytes(),
                 )?;
             }
 
+            if !self.recording_filename.is_empty() {
+                file.write_all(
+                    format!("recording_filename = \"{}\"\n", self.recording_filename).as_bytes(),
+                )?;
+            }
+
             file.write_all("[IEMSI]\n".to_string().as_bytes())?;
 
             if !self.iemsi.autologin {
@@ -423,6 +507,11 @@ impl Options {
                 file.write_all(format!("autologin = {}\n", self.iemsi.autologin).as_bytes())?;
             }
 
+            write_text_transfer_settings(&mut file, &self.text_transfer)?;
+            if self.proxy != ProxySettings::default() {
+                file.write_all(b"[PROXY]\n")?;
+                write_proxy_settings(&mut file, &self.proxy)?;
+            }
             write_keybindings(&mut file, &self.bind)?;
 
             file.flush()?;
@@ -526,6 +615,16 @@ fn parse_value(options: &mut Options, value: &Value) {
                             parse_iemsi_settings(options, iemsi_settings);
                         }
                     }
+                    "TEXT_TRANSFER" => {
+                        if let Value::Table(text_transfer_settings) = v {
+                            parse_text_transfer_settings(options, text_transfer_settings);
+                        }
+                    }
+                    "PROXY" => {
+                        if let Value::Table(proxy_settings) = v {
+                            parse_proxy_settings(&mut options.proxy, proxy_settings);
+                        }
+                    }
                     "KEYBINDINGS" => {
                         if let Value::Table(keybind_settings) = v {
                             parse_keybinding_settings(options, keybind_settings);
@@ -537,11 +636,21 @@ fn parse_value(options: &mut Options, value: &Value) {
                             options.console_beep = *b;
                         }
                     }
+                    "resume_downloads" => {
+                        if let Value::Boolean(b) = v {
+                            options.resume_downloads = *b;
+                        }
+                    }
                     "capture_filename" => {
                         if let Value::String(b) = v {
                             options.capture_filename = b.clone();
                         }
                     }
+                    "recording_filename" => {
+                        if let Value::String(b) = v {
+                            options.recording_filename = b.clone();
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -588,6 +697,118 @@ fn parse_iemsi_settings(options: &mut Options, iemsi_settings: &toml::map::Map<S
     }
 }
 
+fn write_text_transfer_settings(
+    file: &mut File,
+    settings: &TextTransferSettings,
+) -> TerminalResult<()> {
+    let default = TextTransferSettings::default();
+    file.write_all("[TEXT_TRANSFER]\n".to_string().as_bytes())?;
+    if settings.line_delay != default.line_delay {
+        file.write_all(format!("line_delay = {}\n", settings.line_delay).as_bytes())?;
+    }
+    if settings.char_delay != default.char_delay {
+        file.write_all(format!("char_delay = {}\n", settings.char_delay).as_bytes())?;
+    }
+    if settings.wait_for_echo {
+        file.write_all(format!("wait_for_echo = {}\n", settings.wait_for_echo).as_bytes())?;
+    }
+    if !settings.wait_for_prompt.is_empty() {
+        file.write_all(
+            format!(
+                "wait_for_prompt = \"{}\"\n",
+                settings.wait_for_prompt.escape_default()
+            )
+            .as_bytes(),
+        )?;
+    }
+    if settings.line_ending != default.line_ending {
+        file.write_all(format!("line_ending = \"{:?}\"\n", settings.line_ending).as_bytes())?;
+    }
+    if settings.capture_file_name != default.capture_file_name {
+        file.write_all(
+            format!(
+                "capture_file_name = \"{}\"\n",
+                settings.capture_file_name.escape_default()
+            )
+            .as_bytes(),
+        )?;
+    }
+    if settings.end_on_ctrl_z != default.end_on_ctrl_z {
+        file.write_all(format!("end_on_ctrl_z = {}\n", settings.end_on_ctrl_z).as_bytes())?;
+    }
+    if settings.idle_timeout != default.idle_timeout {
+        file.write_all(format!("idle_timeout = {}\n", settings.idle_timeout).as_bytes())?;
+    }
+    if !settings.end_pattern.is_empty() {
+        file.write_all(
+            format!(
+                "end_pattern = \"{}\"\n",
+                settings.end_pattern.escape_default()
+            )
+            .as_bytes(),
+        )?;
+    }
+    Ok(())
+}
+
+fn parse_text_transfer_settings(options: &mut Options, settings: &toml::map::Map<String, Value>) {
+    for (k, v) in settings {
+        match k.as_str() {
+            "line_delay" => {
+                if let Value::Integer(delay) = v {
+                    options.text_transfer.line_delay = *delay as u32;
+                }
+            }
+            "char_delay" => {
+                if let Value::Integer(delay) = v {
+                    options.text_transfer.char_delay = *delay as u32;
+                }
+            }
+            "wait_for_echo" => {
+                if let Value::Boolean(b) = v {
+                    options.text_transfer.wait_for_echo = *b;
+                }
+            }
+            "wait_for_prompt" => {
+                if let Value::String(str) = v {
+                    options.text_transfer.wait_for_prompt = str.clone();
+                }
+            }
+            "line_ending" => {
+                if let Value::String(str) = v {
+                    match str.as_str() {
+                        "Cr" => options.text_transfer.line_ending = LineEnding::Cr,
+                        "Lf" => options.text_transfer.line_ending = LineEnding::Lf,
+                        "CrLf" => options.text_transfer.line_ending = LineEnding::CrLf,
+                        _ => {}
+                    }
+                }
+            }
+            "capture_file_name" => {
+                if let Value::String(str) = v {
+                    options.text_transfer.capture_file_name = str.clone();
+                }
+            }
+            "end_on_ctrl_z" => {
+                if let Value::Boolean(b) = v {
+                    options.text_transfer.end_on_ctrl_z = *b;
+                }
+            }
+            "idle_timeout" => {
+                if let Value::Integer(timeout) = v {
+                    options.text_transfer.idle_timeout = *timeout as u32;
+                }
+            }
+            "end_pattern" => {
+                if let Value::String(str) = v {
+                    options.text_transfer.end_pattern = str.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::field_reassign_with_default)]
@@ -617,4 +838,38 @@ mod tests {
         opt.reset_keybindings();
         assert_eq!(Options::default().bind, opt.bind);
     }
+
+    #[test]
+    fn test_parse_text_transfer_settings() {
+        let opt = Options::from_str(
+            "[TEXT_TRANSFER]\nline_delay = 200\nchar_delay = 5\nwait_for_echo = true\nwait_for_prompt = \"> \"\nline_ending = \"CrLf\"\n",
+        );
+        assert_eq!(200, opt.text_transfer.line_delay);
+        assert_eq!(5, opt.text_transfer.char_delay);
+        assert!(opt.text_transfer.wait_for_echo);
+        assert_eq!("> ", opt.text_transfer.wait_for_prompt);
+        assert_eq!(LineEnding::CrLf, opt.text_transfer.line_ending);
+    }
+
+    #[test]
+    fn test_parse_text_capture_settings() {
+        let opt = Options::from_str(
+            "[TEXT_TRANSFER]\ncapture_file_name = \"msgs.txt\"\nend_on_ctrl_z = false\nidle_timeout = 0\nend_pattern = \"<<EOF>>\"\n",
+        );
+        assert_eq!("msgs.txt", opt.text_transfer.capture_file_name);
+        assert!(!opt.text_transfer.end_on_ctrl_z);
+        assert_eq!(0, opt.text_transfer.idle_timeout);
+        assert_eq!("<<EOF>>", opt.text_transfer.end_pattern);
+    }
+
+    #[test]
+    fn test_parse_proxy_settings() {
+        let opt = Options::from_str(
+            "[PROXY]\ntype = \"Http\"\naddress = \"proxy.example.com:3128\"\nuser_name = \"user\"\n",
+        );
+        assert_eq!(crate::ProxyType::Http, opt.proxy.proxy_type);
+        assert_eq!("proxy.example.com:3128", opt.proxy.address);
+        assert_eq!("user", opt.proxy.user_name);
+        assert!(opt.proxy.password.is_empty());
+    }
 }
@@ -1,15 +1,360 @@
 use web_time::Instant;
 
-use crate::{
-    ui::connection::Connection, util::PatternRecognizer, Address, Options, TerminalResult,
-};
-use std::{
-    io::{self, ErrorKind},
-    time::Duration,
-};
+use crate::{ui::connection::Connection, Address, Options, TerminalResult};
+use std::{collections::VecDeque, fmt, time::Duration};
 
 use super::iemsi_com::IEmsi;
 
+/// Received text the prompts are searched in, older text gets dropped.
+const MAX_RECEIVED: usize = 4096;
+/// `!E` waits until nothing was received for this long.
+const QUIET_TIME: Duration = Duration::from_millis(500);
+
+const NAME_PROMPTS: [&[u8]; 2] = [b"NAME", b"LOGIN:"];
+const PASSWORD_PROMPTS: [&[u8]; 2] = [b"PASSWORD", b"PASSWD"];
+
+/// A command of the auto login expression.
+///
+/// | Syntax              | Command                                                  |
+/// |---------------------|----------------------------------------------------------|
+/// | `!D4`, `!D250ms`    | Delay in seconds or milliseconds                         |
+/// | `!E`                | Wait until the remote side stopped sending               |
+/// | `!W`                | Wait for a name prompt (`NAME`, `LOGIN:`)                |
+/// | `!Q`                | Wait for a password prompt (`PASSWORD`, `PASSWD`)        |
+/// | `!W"text"`, `!W"text"10` | Wait for the text, at most 10 seconds               |
+/// | `!?("A" … \| !Q … \| 30 …)` | Run the branch of the prompt that comes first, a number is a timeout |
+/// | `!N`, `!F`, `!L`    | Send user name, first name, last name + CR               |
+/// | `!P`                | Send password + CR                                       |
+/// | `!I`                | Disable IEMSI for this session                           |
+/// | `!!`                | Send `!`                                                 |
+/// | `\r \n \t \e \a \b \0 \xHH \cX` | Send control characters (`\cC` = Ctrl+C)      |
+///
+/// Everything else is sent as it is, prompts match ignoring the case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginCommand {
+    Send(Vec<u8>),
+    UserName,
+    FirstName,
+    LastName,
+    Password,
+    DisableIemsi,
+    Delay(Duration),
+    WaitForQuiet,
+    /// Waits for the first prompt of the branches and continues with its commands.
+    Wait(Vec<LoginBranch>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginPrompt {
+    Name,
+    Password,
+    Text(Vec<u8>),
+    /// Taken if none of the other prompts arrived in time
+    Timeout(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginBranch {
+    pub prompt: LoginPrompt,
+    pub commands: Vec<LoginCommand>,
+}
+
+impl LoginBranch {
+    fn new(prompt: LoginPrompt) -> Self {
+        Self {
+            prompt,
+            commands: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginParseError {
+    /// Character position in the expression
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LoginParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "auto login: {} at character {}",
+            self.message,
+            self.position + 1
+        )
+    }
+}
+
+impl std::error::Error for LoginParseError {}
+
+/// Parses the auto login string of an address.
+///
+/// # Errors
+///
+/// Fails with the position of the first syntax error.
+pub fn parse_login_expression(expr: &str) -> Result<Vec<LoginCommand>, LoginParseError> {
+    let mut parser = LoginParser {
+        chars: expr.chars().collect(),
+        pos: 0,
+    };
+    parser.parse_commands(false)
+}
+
+/// Text with the escapes of the auto login expression, `!` has no special meaning.
+///
+/// # Errors
+///
+/// Fails if the text contains an invalid escape sequence.
+pub fn parse_escaped_text(text: &str) -> Result<Vec<u8>, LoginParseError> {
+    let mut parser = LoginParser {
+        chars: text.chars().collect(),
+        pos: 0,
+    };
+    let mut result = Vec::new();
+    while let Some(ch) = parser.next() {
+        if ch == '\\' {
+            result.push(parser.parse_escape()?);
+        } else {
+            let mut buf = [0; 4];
+            result.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+    Ok(result)
+}
+
+struct LoginParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl LoginParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek();
+        if ch.is_some() {
+            self.pos += 1;
+        }
+        ch
+    }
+
+    fn error<T>(position: usize, message: impl Into<String>) -> Result<T, LoginParseError> {
+        Err(LoginParseError {
+            position,
+            message: message.into(),
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Branches end at `|` or `)`, whitespace around the prompt and at the end of a branch is ignored.
+    fn parse_commands(&mut self, in_branch: bool) -> Result<Vec<LoginCommand>, LoginParseError> {
+        let mut commands = Vec::new();
+        let mut text = Vec::new();
+        // whitespace at the end of text, escaped whitespace is kept
+        let mut trailing_whitespace = 0;
+        while let Some(ch) = self.peek() {
+            if in_branch && (ch == '|' || ch == ')') {
+                break;
+            }
+            self.pos += 1;
+            match ch {
+                '\\' => {
+                    text.push(self.parse_escape()?);
+                    trailing_whitespace = 0;
+                }
+                '!' if self.peek() == Some('!') => {
+                    self.pos += 1;
+                    text.push(b'!');
+                    trailing_whitespace = 0;
+                }
+                '!' => {
+                    if !text.is_empty() {
+                        commands.push(LoginCommand::Send(std::mem::take(&mut text)));
+                    }
+                    commands.push(self.parse_command()?);
+                    trailing_whitespace = 0;
+                }
+                ch => {
+                    let mut buf = [0; 4];
+                    text.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                    if ch.is_whitespace() {
+                        trailing_whitespace += ch.len_utf8();
+                    } else {
+                        trailing_whitespace = 0;
+                    }
+                }
+            }
+        }
+        if in_branch {
+            text.truncate(text.len() - trailing_whitespace);
+        }
+        if !text.is_empty() {
+            commands.push(LoginCommand::Send(text));
+        }
+        Ok(commands)
+    }
+
+    /// Parses the command after a `!`.
+    fn parse_command(&mut self) -> Result<LoginCommand, LoginParseError> {
+        let start = self.pos - 1;
+        let Some(ch) = self.next() else {
+            return Self::error(start, "missing command after '!'");
+        };
+        let command = match ch {
+            'D' => match self.parse_duration()? {
+                Some(duration) => LoginCommand::Delay(duration),
+                None => return Self::error(self.pos, "missing delay after '!D'"),
+            },
+            'E' => LoginCommand::WaitForQuiet,
+            'N' => LoginCommand::UserName,
+            'F' => LoginCommand::FirstName,
+            'L' => LoginCommand::LastName,
+            'P' => LoginCommand::Password,
+            'I' => LoginCommand::DisableIemsi,
+            'Q' => LoginCommand::Wait(vec![LoginBranch::new(LoginPrompt::Password)]),
+            'W' if self.peek() == Some('"') => {
+                let mut branches = vec![LoginBranch::new(LoginPrompt::Text(self.parse_text()?))];
+                if let Some(timeout) = self.parse_duration()? {
+                    branches.push(LoginBranch::new(LoginPrompt::Timeout(timeout)));
+                }
+                LoginCommand::Wait(branches)
+            }
+            'W' => LoginCommand::Wait(vec![LoginBranch::new(LoginPrompt::Name)]),
+            '?' => LoginCommand::Wait(self.parse_branches(start)?),
+            ch => return Self::error(start, format!("unknown command '!{ch}'")),
+        };
+        Ok(command)
+    }
+
+    fn parse_branches(&mut self, start: usize) -> Result<Vec<LoginBranch>, LoginParseError> {
+        if self.next() != Some('(') {
+            return Self::error(self.pos.saturating_sub(1), "expected '(' after '!?'");
+        }
+        let mut branches = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let prompt = self.parse_prompt()?;
+            self.skip_whitespace();
+            branches.push(LoginBranch {
+                prompt,
+                commands: self.parse_commands(true)?,
+            });
+            match self.next() {
+                Some('|') => {}
+                Some(')') => return Ok(branches),
+                _ => return Self::error(start, "missing ')' for '!?('"),
+            }
+        }
+    }
+
+    fn parse_prompt(&mut self) -> Result<LoginPrompt, LoginParseError> {
+        let start = self.pos;
+        match self.peek() {
+            Some('"') => Ok(LoginPrompt::Text(self.parse_text()?)),
+            Some('!') => {
+                self.pos += 1;
+                match self.next() {
+                    Some('W') => Ok(LoginPrompt::Name),
+                    Some('Q') => Ok(LoginPrompt::Password),
+                    _ => Self::error(start, "only '!W' and '!Q' can be used as prompt"),
+                }
+            }
+            Some(ch) if ch.is_ascii_digit() => match self.parse_duration()? {
+                Some(timeout) => Ok(LoginPrompt::Timeout(timeout)),
+                None => Self::error(start, "invalid timeout"),
+            },
+            _ => Self::error(
+                start,
+                "expected a prompt: \"text\", '!W', '!Q' or a timeout",
+            ),
+        }
+    }
+
+    /// Parses a quoted text, the quote is the current character.
+    fn parse_text(&mut self) -> Result<Vec<u8>, LoginParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut text = Vec::new();
+        loop {
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => text.push(self.parse_escape()?),
+                Some(ch) => {
+                    let mut buf = [0; 4];
+                    text.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+                None => return Self::error(start, "missing closing '\"'"),
+            }
+        }
+        if text.is_empty() {
+            return Self::error(start, "empty prompt text");
+        }
+        Ok(text)
+    }
+
+    /// Parses seconds or milliseconds with a `ms` suffix, `None` if there is no number.
+    fn parse_duration(&mut self) -> Result<Option<Duration>, LoginParseError> {
+        let start = self.pos;
+        let mut value: u64 = 0;
+        while let Some(digit) = self.peek().and_then(|ch| ch.to_digit(10)) {
+            self.pos += 1;
+            let Some(v) = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add(u64::from(digit)))
+            else {
+                return Self::error(start, "number is too large");
+            };
+            value = v;
+        }
+        if self.pos == start {
+            return Ok(None);
+        }
+        if self.peek() == Some('m') && self.chars.get(self.pos + 1) == Some(&'s') {
+            self.pos += 2;
+            return Ok(Some(Duration::from_millis(value)));
+        }
+        Ok(Some(Duration::from_secs(value)))
+    }
+
+    /// Parses the escape after a `\`.
+    fn parse_escape(&mut self) -> Result<u8, LoginParseError> {
+        let start = self.pos - 1;
+        let byte = match self.next() {
+            Some('r') => b'\r',
+            Some('n') => b'\n',
+            Some('t') => b'\t',
+            Some('e') => 0x1B,
+            Some('a') => 0x07,
+            Some('b') => 0x08,
+            Some('0') => 0x00,
+            Some('x') => {
+                let hex: String = self.chars.iter().skip(self.pos).take(2).collect();
+                if hex.len() != 2 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                    return Self::error(start, "'\\x' needs two hex digits");
+                }
+                self.pos += 2;
+                u8::from_str_radix(&hex, 16).unwrap_or_default()
+            }
+            Some('c') => match self.next() {
+                Some(ch @ ('@'..='_' | 'a'..='z')) => ch.to_ascii_uppercase() as u8 & 0x1F,
+                _ => return Self::error(start, "'\\c' needs a letter"),
+            },
+            Some(ch @ ('\\' | '!' | '"' | '|' | '(' | ')')) => ch as u8,
+            Some(ch) => return Self::error(start, format!("invalid escape sequence '\\{ch}'")),
+            None => return Self::error(start, "missing escape character after '\\'"),
+        };
+        Ok(byte)
+    }
+}
+
 pub struct AutoLogin {
     pub logged_in: bool,
     pub disabled: bool,
@@ -18,15 +363,20 @@ pub struct AutoLogin {
     first_char_recv: Option<Instant>,
     continue_time: Instant,
 
-    login_expr: Vec<u8>,
-    cur_expr_idx: usize,
-    got_name: bool,
-    name_recognizer: PatternRecognizer,
-    login_recognizer: PatternRecognizer,
+    commands: VecDeque<LoginCommand>,
+    parse_error: Option<LoginParseError>,
+    /// Start of the current wait for the timeout branches
+    wait_start: Option<Instant>,
+    /// Received text in upper case, the prompts are searched in it
+    received: Vec<u8>,
 }
 
 impl AutoLogin {
     pub fn new(login_expr: &str) -> Self {
+        let (commands, parse_error) = match parse_login_expression(login_expr) {
+            Ok(commands) => (commands.into(), None),
+            Err(err) => (VecDeque::new(), Some(err)),
+        };
         Self {
             logged_in: false,
             disabled: false,
@@ -34,83 +384,102 @@ impl AutoLogin {
             first_char_recv: None,
             last_char_recv: Instant::now(),
             continue_time: Instant::now(),
-            login_expr: login_expr.as_bytes().to_vec(),
-            cur_expr_idx: 0,
-            got_name: false,
-            name_recognizer: PatternRecognizer::from(b"NAME", true),
-            login_recognizer: PatternRecognizer::from(b"LOGIN:", true),
-        }
-    }
-
-    pub fn run_command(&mut self, con: &mut Connection, adr: &Address) -> TerminalResult<bool> {
-        let ch = *self.login_expr.get(self.cur_expr_idx + 1).unwrap();
-        match ch {
-            b'D' => {
-                // Delay for x seconds. !D4= Delay for 4 seconds
-                let ch = self.login_expr[self.cur_expr_idx + 2];
-                self.continue_time =
-                    self.last_char_recv + Duration::from_secs(u64::from(ch - b'0'));
-                self.cur_expr_idx += 3;
-            }
-            b'E' => {
-                // wait until data came in
-                match self.first_char_recv {
-                    Some(_) => {
-                        if Instant::now()
-                            .duration_since(self.last_char_recv)
-                            .as_millis()
-                            < 500
-                        {
-                            return Ok(true);
-                        }
+            commands,
+            parse_error,
+            wait_start: None,
+            received: Vec::new(),
+        }
+    }
+
+    /// Runs the commands until one needs to wait, returns the data to send.
+    fn run_commands(&mut self, adr: &Address, now: Instant) -> Vec<u8> {
+        let mut output = Vec::new();
+        while now >= self.continue_time {
+            let Some(command) = self.commands.pop_front() else {
+                break;
+            };
+            match command {
+                LoginCommand::Send(data) => output.extend(data),
+                LoginCommand::UserName => {
+                    output.extend(adr.user_name.as_bytes());
+                    output.push(b'\r');
+                }
+                LoginCommand::FirstName => {
+                    let first_name = adr.user_name.split_whitespace().next().unwrap_or_default();
+                    output.extend(first_name.as_bytes());
+                    output.push(b'\r');
+                }
+                LoginCommand::LastName => {
+                    let last_name = adr.user_name.split_whitespace().last().unwrap_or_default();
+                    output.extend(last_name.as_bytes());
+                    output.push(b'\r');
+                }
+                LoginCommand::Password => {
+                    output.extend(adr.password.as_bytes());
+                    output.push(b'\r');
+                    self.logged_in = true;
+                }
+                LoginCommand::DisableIemsi => self.iemsi.aborted = true,
+                LoginCommand::Delay(duration) => self.continue_time = now + duration,
+                LoginCommand::WaitForQuiet => {
+                    let quiet = self.first_char_recv.is_some()
+                        && now.saturating_duration_since(self.last_char_recv) >= QUIET_TIME;
+                    if !quiet {
+                        self.commands.push_front(LoginCommand::WaitForQuiet);
+                        break;
                     }
-                    _ => return Ok(true),
                 }
-                self.cur_expr_idx += 2;
-                return Ok(true);
-            }
-            b'W' => {
-                // Wait for one of the name questions defined arrives
-                if self.got_name {
-                    self.cur_expr_idx += 2;
+                LoginCommand::Wait(mut branches) => {
+                    let start = *self.wait_start.get_or_insert(now);
+                    let Some(i) = self.find_branch(&branches, now.saturating_duration_since(start))
+                    else {
+                        self.commands.push_front(LoginCommand::Wait(branches));
+                        break;
+                    };
+                    self.wait_start = None;
+                    for command in branches.swap_remove(i).commands.into_iter().rev() {
+                        self.commands.push_front(command);
+                    }
                 }
-                return Ok(false);
-            }
-            b'N' => {
-                // Send full user name of active user
-                self.cur_expr_idx += 2;
-                con.send((adr.user_name.clone() + "\r").as_bytes().to_vec())?;
-            }
-            b'F' => {
-                // Send first name of active user
-                self.cur_expr_idx += 2;
-                con.send((adr.user_name.clone() + "first\r").as_bytes().to_vec())?;
-                // TODO
-            }
-            b'L' => {
-                // Send last name of active user
-                self.cur_expr_idx += 2;
-                con.send((adr.user_name.clone() + "last\r").as_bytes().to_vec())?;
-                // TODO
             }
-            b'P' => {
-                // Send password from active user
-                self.cur_expr_idx += 2;
-                con.send((adr.password.clone() + "\r").as_bytes().to_vec())?;
-                self.logged_in = true;
-            }
-            b'I' => {
-                // Disable IEMSI in this session
-                self.cur_expr_idx += 2;
-                self.iemsi.aborted = true;
-            }
-            ch => {
-                con.send(vec![ch])?;
-                self.cur_expr_idx += 1;
+        }
+        output
+    }
+
+    /// The prompt that arrived first wins, the received text up to it is consumed.
+    fn find_branch(&mut self, branches: &[LoginBranch], waited: Duration) -> Option<usize> {
+        let mut found: Option<(usize, usize, usize)> = None;
+        for (i, branch) in branches.iter().enumerate() {
+            let hit = match &branch.prompt {
+                LoginPrompt::Name => find_any(&self.received, &NAME_PROMPTS),
+                LoginPrompt::Password => find_any(&self.received, &PASSWORD_PROMPTS),
+                LoginPrompt::Text(text) => find(&self.received, &text.to_ascii_uppercase()),
+                LoginPrompt::Timeout(_) => None,
+            };
+            match (hit, found) {
+                (Some((pos, _)), Some((_, found_pos, _))) if found_pos <= pos => {}
+                (Some((pos, end)), _) => found = Some((i, pos, end)),
+                (None, _) => {}
             }
         }
+        if let Some((i, _, end)) = found {
+            self.received.drain(..end);
+            return Some(i);
+        }
+        branches.iter().position(
+            |branch| matches!(branch.prompt, LoginPrompt::Timeout(timeout) if waited >= timeout),
+        )
+    }
 
-        Ok(true)
+    fn push_received(&mut self, ch: u8) {
+        if self.first_char_recv.is_none() && ch.is_ascii_alphabetic() {
+            self.first_char_recv = Some(Instant::now());
+        }
+        self.last_char_recv = Instant::now();
+        self.received.push(ch.to_ascii_uppercase());
+        if self.received.len() > MAX_RECEIVED {
+            self.received.drain(..self.received.len() - MAX_RECEIVED);
+        }
     }
 
     pub fn try_login(
@@ -120,7 +489,8 @@ impl AutoLogin {
         ch: u8,
         options: &Options,
     ) -> TerminalResult<()> {
-        if self.logged_in || self.disabled {
+        // waits after the password still need to see the received text
+        if self.logged_in && self.commands.is_empty() || self.disabled {
             return Ok(());
         }
         if adr.user_name.is_empty() || adr.password.is_empty() {
@@ -128,78 +498,264 @@ impl AutoLogin {
             return Ok(());
         }
 
-        if self.first_char_recv.is_none() && (ch.is_ascii_uppercase() || ch.is_ascii_lowercase()) {
-            self.first_char_recv = Some(Instant::now());
+        self.push_received(ch);
+        if !self.logged_in {
+            self.logged_in = self.iemsi.try_login(connection, adr, ch, options)?;
         }
-
-        self.last_char_recv = Instant::now();
-        self.got_name |= self.name_recognizer.push_ch(ch) | self.login_recognizer.push_ch(ch);
-
-        self.logged_in |= self.iemsi.try_login(connection, adr, ch, options)?;
         Ok(())
     }
 
     pub fn run_autologin(&mut self, con: &mut Connection, adr: &Address) -> TerminalResult<()> {
-        if self.logged_in && self.cur_expr_idx >= self.login_expr.len() || self.disabled {
+        if self.logged_in && self.commands.is_empty() || self.disabled {
             return Ok(());
         }
         if adr.user_name.is_empty() || adr.password.is_empty() {
             self.logged_in = true;
             return Ok(());
         }
+        if let Some(err) = self.parse_error.take() {
+            self.disabled = true;
+            return Err(Box::new(err));
+        }
 
-        if adr.auto_login.is_empty() {
-            return Ok(());
+        let data = self.run_commands(adr, Instant::now());
+        if !data.is_empty() {
+            con.send(data)?;
         }
+        Ok(())
+    }
+}
 
-        if Instant::now() < self.continue_time {
-            return Ok(());
+/// Position and end of the first match
+fn find(haystack: &[u8], needle: &[u8]) -> Option<(usize, usize)> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| (pos, pos + needle.len()))
+}
+
+fn find_any(haystack: &[u8], needles: &[&[u8]]) -> Option<(usize, usize)> {
+    needles
+        .iter()
+        .filter_map(|needle| find(haystack, needle))
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address() -> Address {
+        let mut adr = Address::new("test".to_string());
+        adr.user_name = "John Doe".to_string();
+        adr.password = "secret".to_string();
+        adr
+    }
+
+    fn receive(login: &mut AutoLogin, text: &[u8]) {
+        for ch in text {
+            login.push_received(*ch);
         }
-        if self.cur_expr_idx < self.login_expr.len() {
-            match self.login_expr.get(self.cur_expr_idx).unwrap() {
-                b'!' => {
-                    self.run_command(con, adr)?;
-                }
-                b'\\' => {
-                    while self.cur_expr_idx < self.login_expr.len()
-                        && self.login_expr[self.cur_expr_idx] == b'\\'
-                    {
-                        self.cur_expr_idx += 1; // escape
-                        let ch = self.login_expr.get(self.cur_expr_idx).unwrap();
-                        match ch {
-                            b'e' => {
-                                con.send(vec![0x1B])?;
-                            }
-                            b'n' => {
-                                con.send(vec![b'\n'])?;
-                            }
-                            b'r' => {
-                                con.send(vec![b'\r'])?;
-                            }
-                            b't' => {
-                                con.send(vec![b'\t'])?;
-                            }
-                            ch => {
-                                self.cur_expr_idx += 1; // escape
-                                return Err(Box::new(io::Error::new(
-                                    ErrorKind::InvalidData,
-                                    format!(
-                                        "invalid escape sequence in autologin string: {:?}",
-                                        char::from_u32(u32::from(*ch))
-                                    ),
-                                )));
-                            }
-                        }
-                        self.cur_expr_idx += 1; // escape
-                    }
-                    self.last_char_recv = Instant::now();
-                }
-                ch => {
-                    con.send(vec![*ch])?;
-                    self.cur_expr_idx += 1;
-                }
+    }
+
+    #[test]
+    fn test_wait_after_password() {
+        let adr = address();
+        let options = Options::default();
+        let (tx, _out) = std::sync::mpsc::channel();
+        let (_in, rx) = std::sync::mpsc::channel();
+        let mut con = Connection::new(rx, tx);
+        let mut login = AutoLogin::new(r#"!W!N!Q!P!W"Press any key"\r"#);
+        let now = Instant::now();
+        let mut try_login = |login: &mut AutoLogin, text: &[u8]| {
+            for ch in text {
+                login.try_login(&mut con, &adr, *ch, &options).unwrap();
             }
-        }
-        Ok(())
+        };
+
+        try_login(&mut login, b"Name: ");
+        assert_eq!(b"John Doe\r".to_vec(), login.run_commands(&adr, now));
+        try_login(&mut login, b"Password: ");
+        assert_eq!(b"secret\r".to_vec(), login.run_commands(&adr, now));
+        assert!(login.logged_in);
+        assert!(login.run_commands(&adr, now).is_empty());
+        try_login(&mut login, b"Press any key");
+        assert_eq!(b"\r".to_vec(), login.run_commands(&adr, now));
+        assert!(login.commands.is_empty());
+    }
+
+    #[test]
+    fn test_parse_legacy_expression() {
+        assert_eq!(
+            vec![
+                LoginCommand::Wait(vec![LoginBranch::new(LoginPrompt::Name)]),
+                LoginCommand::UserName,
+                LoginCommand::Delay(Duration::from_secs(4)),
+                LoginCommand::Password,
+                LoginCommand::Send(b"\x1B\r".to_vec()),
+            ],
+            parse_login_expression(r"!W!N!D4!P\e\r").unwrap()
+        );
+        assert!(parse_login_expression("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_delays_and_control_chars() {
+        assert_eq!(
+            vec![
+                LoginCommand::Delay(Duration::from_secs(12)),
+                LoginCommand::Delay(Duration::from_millis(250)),
+                LoginCommand::Send(b"y!\x03\x1A\x7F \"".to_vec()),
+            ],
+            parse_login_expression(r#"!D12!D250msy!!\cC\cz\x7F\x20\""#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_waits() {
+        assert_eq!(
+            vec![
+                LoginCommand::Wait(vec![
+                    LoginBranch::new(LoginPrompt::Text(b"Press <Enter>".to_vec())),
+                    LoginBranch::new(LoginPrompt::Timeout(Duration::from_secs(10))),
+                ]),
+                LoginCommand::Send(b"\r".to_vec()),
+                LoginCommand::Wait(vec![LoginBranch::new(LoginPrompt::Password)]),
+            ],
+            parse_login_expression(r#"!W"Press <Enter>"10\r!Q"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_branches() {
+        assert_eq!(
+            vec![LoginCommand::Wait(vec![
+                LoginBranch {
+                    prompt: LoginPrompt::Text(b"ANSI?".to_vec()),
+                    commands: vec![LoginCommand::Send(b"y\r".to_vec())],
+                },
+                LoginBranch {
+                    prompt: LoginPrompt::Name,
+                    commands: vec![LoginCommand::UserName, LoginCommand::Send(b"a b".to_vec())],
+                },
+                LoginBranch {
+                    prompt: LoginPrompt::Timeout(Duration::from_millis(500)),
+                    commands: Vec::new(),
+                },
+            ])],
+            parse_login_expression(r#"!?( "ANSI?" y\r | !W !Na b | 500ms )"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |expr| parse_login_expression(expr).unwrap_err();
+        assert_eq!(3, error("abc!X").position);
+        assert_eq!(2, error("!D").position);
+        assert_eq!(0, error("!").position);
+        assert_eq!(1, error(r"a\q").position);
+        assert_eq!(0, error(r"\x4").position);
+        assert_eq!(2, error(r#"!W"abc"#).position);
+        assert_eq!(2, error(r#"!W"""#).position);
+        assert_eq!(2, error("!?x").position);
+        assert_eq!(0, error(r#"!?("a" b"#).position);
+        assert_eq!(3, error("!?(b)").position);
+        assert_eq!(
+            "auto login: unknown command '!X' at character 4",
+            error("abc!X").to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_escaped_text() {
+        assert_eq!(
+            b"y!\r\x03".to_vec(),
+            parse_escaped_text(r"y!\r\cC").unwrap()
+        );
+        assert_eq!(1, parse_escaped_text(r"a\q").unwrap_err().position);
+    }
+
+    #[test]
+    fn test_name_and_password_prompts() {
+        let adr = address();
+        let mut login = AutoLogin::new("!W!N!Q!P");
+        let now = Instant::now();
+        assert!(login.run_commands(&adr, now).is_empty());
+        receive(&mut login, b"What's your name? ");
+        assert_eq!(b"John Doe\r".to_vec(), login.run_commands(&adr, now));
+        assert!(!login.logged_in);
+        receive(&mut login, b"Password: ");
+        assert_eq!(b"secret\r".to_vec(), login.run_commands(&adr, now));
+        assert!(login.logged_in);
+    }
+
+    #[test]
+    fn test_first_and_last_name() {
+        let adr = address();
+        let mut login = AutoLogin::new("!F!L");
+        assert_eq!(
+            b"John\rDoe\r".to_vec(),
+            login.run_commands(&adr, Instant::now())
+        );
+    }
+
+    #[test]
+    fn test_delay() {
+        let adr = address();
+        let mut login = AutoLogin::new("a!D250msb");
+        let now = Instant::now();
+        assert_eq!(b"a".to_vec(), login.run_commands(&adr, now));
+        assert!(login
+            .run_commands(&adr, now + Duration::from_millis(100))
+            .is_empty());
+        assert_eq!(
+            b"b".to_vec(),
+            login.run_commands(&adr, now + Duration::from_millis(250))
+        );
+    }
+
+    #[test]
+    fn test_wait_timeout() {
+        let adr = address();
+        let mut login = AutoLogin::new(r#"!W"continue"2x"#);
+        let now = Instant::now();
+        assert!(login.run_commands(&adr, now).is_empty());
+        assert!(login
+            .run_commands(&adr, now + Duration::from_secs(1))
+            .is_empty());
+        assert_eq!(
+            b"x".to_vec(),
+            login.run_commands(&adr, now + Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn test_branches() {
+        let adr = address();
+        let expr = r#"!?("ansi" y\r!?("more" \r|!Q!P) | "new user" !I | 5 !W!N)"#;
+
+        let mut login = AutoLogin::new(expr);
+        let now = Instant::now();
+        assert!(login.run_commands(&adr, now).is_empty());
+        // the prompt that came first wins
+        receive(&mut login, b"New user? ANSI? ");
+        assert!(login.run_commands(&adr, now).is_empty());
+        assert!(login.iemsi.aborted);
+
+        let mut login = AutoLogin::new(expr);
+        let now = Instant::now();
+        receive(&mut login, b"Use ANSI? ");
+        assert_eq!(b"y\r".to_vec(), login.run_commands(&adr, now));
+        receive(&mut login, b"Password:");
+        assert_eq!(b"secret\r".to_vec(), login.run_commands(&adr, now));
+
+        let mut login = AutoLogin::new(expr);
+        let now = Instant::now();
+        assert!(login.run_commands(&adr, now).is_empty());
+        receive(&mut login, b"Login: ");
+        assert_eq!(
+            b"John Doe\r".to_vec(),
+            login.run_commands(&adr, now + Duration::from_secs(5))
+        );
     }
 }
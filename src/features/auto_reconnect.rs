@@ -0,0 +1,146 @@
+use web_time::{Duration, Instant};
+
+use crate::addresses::ReconnectSettings;
+
+/// Connections that lasted that long start a new series of attempts when they drop.
+const STABLE_CONNECTION: Duration = Duration::from_secs(60);
+
+/// Schedules redials after the remote side dropped the connection.
+/// A hangup or a new call cancels them.
+#[derive(Default)]
+pub struct AutoReconnect {
+    /// Attempts of the current series, 0 if there is none.
+    attempt: u32,
+    next_try: Option<Instant>,
+    last_countdown: Option<u64>,
+}
+
+impl AutoReconnect {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// A reconnect series is running - failed connects schedule the next attempt.
+    pub fn is_active(&self) -> bool {
+        self.attempt > 0
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.next_try.is_some()
+    }
+
+    /// The current attempt, starting at 1.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Schedules the next attempt, returns false if reconnecting is disabled or all attempts are used.
+    pub fn connection_lost(
+        &mut self,
+        settings: &ReconnectSettings,
+        connected_for: Duration,
+    ) -> bool {
+        if connected_for >= STABLE_CONNECTION {
+            self.attempt = 0;
+        }
+        if !settings.enabled || self.attempt >= settings.retries {
+            self.reset();
+            return false;
+        }
+        self.next_try = Some(Instant::now() + settings.delay_for(self.attempt));
+        self.last_countdown = None;
+        self.attempt += 1;
+        true
+    }
+
+    /// Returns the seconds until the next attempt whenever they change.
+    pub fn countdown(&mut self, now: Instant) -> Option<u64> {
+        let next_try = self.next_try?;
+        let millis = next_try.saturating_duration_since(now).as_millis();
+        let secs = u64::try_from(millis.div_ceil(1000)).unwrap_or(u64::MAX);
+        if self.last_countdown == Some(secs) {
+            return None;
+        }
+        self.last_countdown = Some(secs);
+        Some(secs)
+    }
+
+    /// Returns true once the time for the scheduled attempt has come.
+    pub fn should_reconnect(&mut self, now: Instant) -> bool {
+        match self.next_try {
+            Some(next_try) if now >= next_try => {
+                self.next_try = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> ReconnectSettings {
+        ReconnectSettings {
+            enabled: true,
+            retries: 2,
+            delay: 5,
+        }
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut reconnect = AutoReconnect::default();
+        assert!(!reconnect.connection_lost(&ReconnectSettings::default(), Duration::ZERO));
+        assert!(!reconnect.is_pending());
+        assert!(!reconnect.is_active());
+    }
+
+    #[test]
+    fn test_retries() {
+        let settings = settings();
+        let mut reconnect = AutoReconnect::default();
+        assert!(reconnect.connection_lost(&settings, Duration::from_secs(10)));
+        assert_eq!(1, reconnect.attempt());
+        let now = Instant::now();
+        assert!(!reconnect.should_reconnect(now));
+        assert!(reconnect.should_reconnect(now + Duration::from_secs(5)));
+        assert!(!reconnect.is_pending());
+
+        // the attempt failed
+        assert!(reconnect.connection_lost(&settings, Duration::ZERO));
+        assert_eq!(2, reconnect.attempt());
+        let now = Instant::now();
+        assert!(!reconnect.should_reconnect(now + Duration::from_secs(9)));
+        assert!(reconnect.should_reconnect(now + Duration::from_secs(11)));
+
+        assert!(!reconnect.connection_lost(&settings, Duration::ZERO));
+        assert!(!reconnect.is_active());
+    }
+
+    #[test]
+    fn test_stable_connection_starts_new_series() {
+        let settings = settings();
+        let mut reconnect = AutoReconnect::default();
+        reconnect.connection_lost(&settings, Duration::ZERO);
+        reconnect.connection_lost(&settings, Duration::ZERO);
+        assert_eq!(2, reconnect.attempt());
+        assert!(reconnect.connection_lost(&settings, STABLE_CONNECTION));
+        assert_eq!(1, reconnect.attempt());
+    }
+
+    #[test]
+    fn test_countdown() {
+        let mut reconnect = AutoReconnect::default();
+        reconnect.connection_lost(&settings(), Duration::ZERO);
+        let now = Instant::now();
+        assert_eq!(Some(5), reconnect.countdown(now));
+        assert_eq!(None, reconnect.countdown(now));
+        assert_eq!(
+            Some(4),
+            reconnect.countdown(now + Duration::from_millis(1500))
+        );
+        assert_eq!(Some(0), reconnect.countdown(now + Duration::from_secs(6)));
+    }
+}
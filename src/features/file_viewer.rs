@@ -0,0 +1,131 @@
+use icy_engine::ansi::BaudEmulation;
+use web_time::Instant;
+
+use crate::{addresses::Terminal, ui::ScreenMode, util::SauceRecord, TerminalResult};
+use std::path::{Path, PathBuf};
+
+const BITS_PER_BYTE: u32 = 8;
+
+/// Shows a local file (.ans, .seq, …) with the chosen parser,
+/// with baud emulation animations play out like online.
+pub struct FileViewer {
+    pub path: PathBuf,
+    /// Content without SAUCE record
+    data: Vec<u8>,
+    pub sauce: Option<SauceRecord>,
+    pub terminal: Terminal,
+    pub screen_mode: ScreenMode,
+    pub baud_emulation: BaudEmulation,
+    position: usize,
+    last_update: Option<Instant>,
+}
+
+impl FileViewer {
+    pub fn load(path: &Path) -> TerminalResult<Self> {
+        Ok(Self::new(path, &std::fs::read(path)?))
+    }
+
+    /// Guesses terminal & screen mode from the extension and the SAUCE record.
+    pub fn new(path: &Path, data: &[u8]) -> Self {
+        let (content, sauce) = SauceRecord::extract(data);
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let (terminal, mut screen_mode) = match extension.as_str() {
+            "seq" | "pet" => (Terminal::PETscii, ScreenMode::Vic),
+            "ata" | "atr" | "atascii" => (Terminal::ATAscii, ScreenMode::Antic),
+            "vdt" | "vtx" | "viewdata" => (Terminal::ViewData, ScreenMode::Videotex),
+            "avt" => (Terminal::Avatar, ScreenMode::Vga(80, 25)),
+            "asc" | "txt" | "nfo" | "diz" => (Terminal::Ascii, ScreenMode::Vga(80, 25)),
+            _ => (Terminal::Ansi, ScreenMode::Vga(80, 25)),
+        };
+        if matches!(screen_mode, ScreenMode::Vga(_, _)) {
+            if let Some(width) = sauce.as_ref().and_then(SauceRecord::width) {
+                if width > 80 {
+                    screen_mode = ScreenMode::Vga(132, 37);
+                }
+            }
+        }
+
+        Self {
+            path: path.to_path_buf(),
+            data: content.to_vec(),
+            sauce,
+            terminal,
+            screen_mode,
+            baud_emulation: BaudEmulation::Off,
+            position: 0,
+            last_update: None,
+        }
+    }
+
+    /// Shows the file from the beginning, needs to be called after changing the settings.
+    pub fn restart(&mut self) {
+        self.position = 0;
+        self.last_update = None;
+    }
+
+    /// Returns the data that is due - everything at once without baud emulation.
+    pub fn update(&mut self, now: Instant) -> &[u8] {
+        let bytes_per_sec = self.baud_emulation.get_baud_rate() / BITS_PER_BYTE;
+        let end = if bytes_per_sec == 0 {
+            self.data.len()
+        } else {
+            let Some(last_update) = self.last_update else {
+                self.last_update = Some(now);
+                return &[];
+            };
+            let elapsed_ms = now.duration_since(last_update).as_millis();
+            let bytes = u128::from(bytes_per_sec) * elapsed_ms / 1000;
+            if bytes == 0 {
+                return &[];
+            }
+            self.last_update = Some(now);
+            self.position
+                .saturating_add(usize::try_from(bytes).unwrap_or(usize::MAX))
+                .min(self.data.len())
+        };
+        let start = self.position;
+        self.position = end;
+        &self.data[start..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web_time::Duration;
+
+    #[test]
+    fn test_guess_terminal() {
+        let viewer = FileViewer::new(Path::new("demo.SEQ"), b"");
+        assert_eq!(Terminal::PETscii, viewer.terminal);
+        assert_eq!(ScreenMode::Vic, viewer.screen_mode);
+
+        let viewer = FileViewer::new(Path::new("demo.ans"), b"");
+        assert_eq!(Terminal::Ansi, viewer.terminal);
+        assert_eq!(ScreenMode::Vga(80, 25), viewer.screen_mode);
+    }
+
+    #[test]
+    fn test_without_baud_emulation() {
+        let mut viewer = FileViewer::new(Path::new("demo.ans"), b"Hello");
+        assert_eq!(b"Hello", viewer.update(Instant::now()));
+        assert!(viewer.update(Instant::now()).is_empty());
+        viewer.restart();
+        assert_eq!(b"Hello", viewer.update(Instant::now()));
+    }
+
+    #[test]
+    fn test_baud_emulation() {
+        let mut viewer = FileViewer::new(Path::new("demo.ans"), &[b'x'; 1000]);
+        viewer.baud_emulation = BaudEmulation::Rate(2400);
+        let now = Instant::now();
+        assert!(viewer.update(now).is_empty());
+        assert_eq!(30, viewer.update(now + Duration::from_millis(100)).len());
+        assert_eq!(300, viewer.update(now + Duration::from_millis(1100)).len());
+        assert_eq!(670, viewer.update(now + Duration::from_secs(10)).len());
+    }
+}
@@ -4,5 +4,25 @@ pub use auto_file_transfer::*;
 mod auto_login;
 pub use auto_login::*;
 
+mod auto_reconnect;
+pub use auto_reconnect::*;
+
+mod file_viewer;
+pub use file_viewer::*;
+
 mod iemsi_com;
 pub use iemsi_com::*;
+
+mod modem;
+pub use modem::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod scripting;
+#[cfg(not(target_arch = "wasm32"))]
+pub use scripting::*;
+
+mod session_recording;
+pub use session_recording::*;
+
+mod triggers;
+pub use triggers::*;
@@ -0,0 +1,682 @@
+use web_time::{Duration, Instant};
+
+use crate::AddressBook;
+
+const S_REGISTER_COUNT: usize = 40;
+const DEFAULT_S_REGISTERS: [u8; 13] = [0, 0, b'+', b'\r', b'\n', 8, 2, 50, 2, 6, 14, 95, 50];
+
+/// escape character, default '+'
+const S_ESCAPE_CHAR: usize = 2;
+/// carriage return character
+const S_CR_CHAR: usize = 3;
+/// line feed character
+const S_LF_CHAR: usize = 4;
+/// backspace character
+const S_BS_CHAR: usize = 5;
+/// escape guard time in 1/50 seconds
+const S_GUARD_TIME: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultCode {
+    Ok,
+    Connect,
+    Ring,
+    NoCarrier,
+    Error,
+    NoDialtone,
+    Busy,
+    NoAnswer,
+}
+
+impl ResultCode {
+    pub fn code(self) -> u8 {
+        match self {
+            ResultCode::Ok => 0,
+            ResultCode::Connect => 1,
+            ResultCode::Ring => 2,
+            ResultCode::NoCarrier => 3,
+            ResultCode::Error => 4,
+            ResultCode::NoDialtone => 6,
+            ResultCode::Busy => 7,
+            ResultCode::NoAnswer => 8,
+        }
+    }
+
+    pub fn text(self) -> &'static str {
+        match self {
+            ResultCode::Ok => "OK",
+            ResultCode::Connect => "CONNECT",
+            ResultCode::Ring => "RING",
+            ResultCode::NoCarrier => "NO CARRIER",
+            ResultCode::Error => "ERROR",
+            ResultCode::NoDialtone => "NO DIALTONE",
+            ResultCode::Busy => "BUSY",
+            ResultCode::NoAnswer => "NO ANSWER",
+        }
+    }
+
+    /// Maps a connection error to the result code a modem would have given.
+    pub fn from_connection_error(err: &str) -> Self {
+        let err = err.to_ascii_lowercase();
+        if err.contains("refused") {
+            ResultCode::Busy
+        } else if err.contains("timed out") || err.contains("timeout") {
+            ResultCode::NoAnswer
+        } else {
+            ResultCode::NoCarrier
+        }
+    }
+}
+
+/// What the main window needs to do after a command line got executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModemAction {
+    /// Call an entry of the address book
+    Dial(usize),
+    /// Call an address that isn't in the address book
+    DialAddress(String),
+    Hangup,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModemState {
+    Command,
+    Dialing,
+    Online,
+    /// Online but escaped with "+++"
+    OnlineCommand,
+}
+
+/// Emulates a hayes compatible modem for the time no connection is open.
+/// Dialing is done by the main window with the `ModemAction` returned by `input`.
+pub struct HayesModem {
+    state: ModemState,
+    carrier: bool,
+    s_registers: [u8; S_REGISTER_COUNT],
+    echo: bool,
+    quiet: bool,
+    verbose: bool,
+
+    line: Vec<u8>,
+    last_command: Vec<u8>,
+    output: Vec<u8>,
+
+    escape_count: usize,
+    last_send: Instant,
+}
+
+impl Default for HayesModem {
+    fn default() -> Self {
+        let mut res = Self {
+            state: ModemState::Command,
+            carrier: false,
+            s_registers: [0; S_REGISTER_COUNT],
+            echo: true,
+            quiet: false,
+            verbose: true,
+            line: Vec::new(),
+            last_command: Vec::new(),
+            output: Vec::new(),
+            escape_count: 0,
+            last_send: Instant::now(),
+        };
+        res.reset();
+        res
+    }
+}
+
+impl HayesModem {
+    /// True if the keyboard input should go to the modem instead of the connection.
+    pub fn is_command_mode(&self) -> bool {
+        !matches!(self.state, ModemState::Online)
+    }
+
+    pub fn is_dialing(&self) -> bool {
+        matches!(self.state, ModemState::Dialing)
+    }
+
+    pub fn is_online(&self) -> bool {
+        matches!(self.state, ModemState::Online | ModemState::OnlineCommand)
+    }
+
+    /// Returns everything the modem wants to print since the last call.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    fn reset(&mut self) {
+        self.s_registers = [0; S_REGISTER_COUNT];
+        self.s_registers[..DEFAULT_S_REGISTERS.len()].copy_from_slice(&DEFAULT_S_REGISTERS);
+        self.echo = true;
+        self.quiet = false;
+        self.verbose = true;
+    }
+
+    fn guard_time(&self) -> Duration {
+        Duration::from_millis(u64::from(self.s_registers[S_GUARD_TIME]) * 20)
+    }
+
+    /// Handles a key typed in command mode.
+    pub fn input(&mut self, ch: u8, address_book: &AddressBook) -> Option<ModemAction> {
+        match self.state {
+            ModemState::Online => return None,
+            ModemState::Dialing => {
+                // any key aborts dialing
+                self.state = ModemState::Command;
+                self.result(ResultCode::NoCarrier);
+                return Some(ModemAction::Hangup);
+            }
+            ModemState::Command | ModemState::OnlineCommand => {}
+        }
+
+        if ch == self.s_registers[S_BS_CHAR] || ch == 0x7F {
+            if self.line.pop().is_some() && self.echo {
+                let bs = self.s_registers[S_BS_CHAR];
+                self.output.extend_from_slice(&[bs, b' ', bs]);
+            }
+            return None;
+        }
+
+        if ch == self.s_registers[S_CR_CHAR] {
+            let line = std::mem::take(&mut self.line);
+            let line = line.trim_ascii();
+            if line.len() < 2 || !line[..2].eq_ignore_ascii_case(b"AT") {
+                // modems silently ignore everything that isn't a command
+                if self.echo {
+                    self.new_line();
+                }
+                return None;
+            }
+            self.last_command = line.to_vec();
+            return self.execute(&line[2..], address_book);
+        }
+
+        if ch < b' ' {
+            return None;
+        }
+        if self.echo {
+            self.output.push(ch);
+        }
+        self.line.push(ch);
+
+        if self.line.eq_ignore_ascii_case(b"A/") {
+            self.line.clear();
+            let command = self.last_command.clone();
+            if command.len() < 2 {
+                self.result(ResultCode::Error);
+                return None;
+            }
+            return self.execute(&command[2..], address_book);
+        }
+        None
+    }
+
+    /// Needs to be called for everything that is sent while online for detecting the escape sequence.
+    pub fn data_sent(&mut self, data: &[u8]) {
+        if self.state != ModemState::Online {
+            return;
+        }
+        let now = Instant::now();
+        let escape_char = self.s_registers[S_ESCAPE_CHAR];
+        for ch in data {
+            let guard_time_passed = now.duration_since(self.last_send) >= self.guard_time();
+            if *ch == escape_char
+                && escape_char < 128
+                && self.escape_count < 3
+                && (self.escape_count > 0 || guard_time_passed)
+            {
+                self.escape_count += 1;
+            } else {
+                self.escape_count = 0;
+            }
+            self.last_send = now;
+        }
+    }
+
+    /// Completes the escape sequence after the guard time passed.
+    pub fn poll(&mut self) {
+        if self.state == ModemState::Online
+            && self.escape_count == 3
+            && self.last_send.elapsed() >= self.guard_time()
+        {
+            self.escape_count = 0;
+            self.state = ModemState::OnlineCommand;
+            self.result(ResultCode::Ok);
+        }
+    }
+
+    /// Tells the modem the current state of the connection.
+    pub fn set_carrier(&mut self, carrier: bool) {
+        if carrier == self.carrier {
+            return;
+        }
+        self.carrier = carrier;
+        self.escape_count = 0;
+        self.last_send = Instant::now();
+        if carrier {
+            match self.state {
+                ModemState::Dialing => {
+                    self.state = ModemState::Online;
+                    self.result(ResultCode::Connect);
+                }
+                // connection was opened by the dialing directory
+                ModemState::Command => self.state = ModemState::Online,
+                ModemState::Online | ModemState::OnlineCommand => {}
+            }
+        } else if self.is_online() {
+            self.state = ModemState::Command;
+            self.result(ResultCode::NoCarrier);
+        }
+    }
+
+    /// Opening the connection requested by a dial command failed.
+    pub fn connection_failed(&mut self, err: &str) {
+        if self.state == ModemState::Dialing {
+            self.state = ModemState::Command;
+            self.result(ResultCode::from_connection_error(err));
+        }
+    }
+
+    fn execute(&mut self, commands: &[u8], address_book: &AddressBook) -> Option<ModemAction> {
+        let mut action = None;
+        if let Some(result) = self.run_commands(commands, address_book, &mut action) {
+            self.result(result);
+        }
+        action
+    }
+
+    fn run_commands(
+        &mut self,
+        commands: &[u8],
+        address_book: &AddressBook,
+        action: &mut Option<ModemAction>,
+    ) -> Option<ResultCode> {
+        let mut i = 0;
+        while i < commands.len() {
+            let cmd = commands[i].to_ascii_uppercase();
+            i += 1;
+            match cmd {
+                b' ' => {}
+                b'D' => return self.dial(&commands[i..], address_book, action),
+                b'H' => {
+                    parse_number(commands, &mut i);
+                    if self.is_online() {
+                        self.state = ModemState::Command;
+                        *action = Some(ModemAction::Hangup);
+                    }
+                }
+                b'Z' => {
+                    parse_number(commands, &mut i);
+                    self.reset();
+                    if self.is_online() {
+                        self.state = ModemState::Command;
+                        *action = Some(ModemAction::Hangup);
+                    }
+                }
+                b'&' => {
+                    let Some(cmd) = commands.get(i).map(u8::to_ascii_uppercase) else {
+                        return Some(ResultCode::Error);
+                    };
+                    i += 1;
+                    parse_number(commands, &mut i);
+                    match cmd {
+                        b'F' => self.reset(),
+                        // accepted but without effect
+                        b'C' | b'D' | b'K' | b'S' | b'V' | b'W' | b'Y' => {}
+                        _ => return Some(ResultCode::Error),
+                    }
+                }
+                b'I' => {
+                    parse_number(commands, &mut i);
+                    self.print_line(&format!("iCY TERM {}", crate::VERSION));
+                }
+                b'E' | b'Q' | b'V' => {
+                    let value = match parse_number(commands, &mut i).unwrap_or(0) {
+                        0 => false,
+                        1 => true,
+                        _ => return Some(ResultCode::Error),
+                    };
+                    match cmd {
+                        b'E' => self.echo = value,
+                        b'Q' => self.quiet = value,
+                        _ => self.verbose = value,
+                    }
+                }
+                b'S' => {
+                    let register = parse_number(commands, &mut i).unwrap_or(0) as usize;
+                    if register >= S_REGISTER_COUNT {
+                        return Some(ResultCode::Error);
+                    }
+                    match commands.get(i) {
+                        Some(b'=') => {
+                            i += 1;
+                            let Ok(value) =
+                                u8::try_from(parse_number(commands, &mut i).unwrap_or(0))
+                            else {
+                                return Some(ResultCode::Error);
+                            };
+                            self.s_registers[register] = value;
+                        }
+                        Some(b'?') => {
+                            i += 1;
+                            self.print_line(&format!("{:03}", self.s_registers[register]));
+                        }
+                        _ => return Some(ResultCode::Error),
+                    }
+                }
+                b'O' => {
+                    parse_number(commands, &mut i);
+                    if self.state != ModemState::OnlineCommand {
+                        return Some(ResultCode::NoCarrier);
+                    }
+                    self.state = ModemState::Online;
+                    self.last_send = Instant::now();
+                    return Some(ResultCode::Connect);
+                }
+                // there are no incoming calls
+                b'A' => return Some(ResultCode::NoCarrier),
+                // speaker, dialing & protocol settings - accepted but without effect
+                b'B' | b'C' | b'L' | b'M' | b'N' | b'P' | b'T' | b'W' | b'X' => {
+                    parse_number(commands, &mut i);
+                }
+                _ => return Some(ResultCode::Error),
+            }
+        }
+        Some(ResultCode::Ok)
+    }
+
+    fn dial(
+        &mut self,
+        dial_string: &[u8],
+        address_book: &AddressBook,
+        action: &mut Option<ModemAction>,
+    ) -> Option<ResultCode> {
+        if self.is_online() {
+            return Some(ResultCode::Error);
+        }
+        let dial_string = String::from_utf8_lossy(dial_string);
+        let mut dial_string = dial_string.trim();
+
+        let dial_action = match dial_string.chars().next().map(|ch| ch.to_ascii_uppercase()) {
+            Some('S') => {
+                let name = dial_string[1..].trim_start_matches('=').trim();
+                let index = if let Ok(index) = name.parse::<usize>() {
+                    // entry 0 is the quick connect address
+                    (1..address_book.addresses.len())
+                        .contains(&index)
+                        .then_some(index)
+                } else {
+                    address_book
+                        .addresses
+                        .iter()
+                        .skip(1)
+                        .position(|adr| adr.system_name.eq_ignore_ascii_case(name))
+                        .map(|i| i + 1)
+                };
+                let Some(index) = index else {
+                    return Some(ResultCode::Error);
+                };
+                ModemAction::Dial(index)
+            }
+            Some('T' | 'P') => {
+                dial_string = dial_string[1..].trim();
+                ModemAction::DialAddress(dial_string.trim_end_matches(';').to_string())
+            }
+            _ => ModemAction::DialAddress(dial_string.trim_end_matches(';').to_string()),
+        };
+
+        if let ModemAction::DialAddress(address) = &dial_action {
+            if address.is_empty() {
+                return Some(ResultCode::Error);
+            }
+        }
+        self.state = ModemState::Dialing;
+        *action = Some(dial_action);
+        None
+    }
+
+    fn new_line(&mut self) {
+        self.output.push(self.s_registers[S_CR_CHAR]);
+        self.output.push(self.s_registers[S_LF_CHAR]);
+    }
+
+    fn print_line(&mut self, line: &str) {
+        self.new_line();
+        self.output.extend_from_slice(line.as_bytes());
+        self.new_line();
+    }
+
+    fn result(&mut self, result: ResultCode) {
+        if self.quiet {
+            return;
+        }
+        if self.verbose {
+            self.print_line(result.text());
+        } else {
+            self.output
+                .extend_from_slice(result.code().to_string().as_bytes());
+            self.output.push(self.s_registers[S_CR_CHAR]);
+        }
+    }
+}
+
+fn parse_number(commands: &[u8], i: &mut usize) -> Option<u32> {
+    let start = *i;
+    let mut result = 0u32;
+    while *i < commands.len() && commands[*i].is_ascii_digit() {
+        result = result
+            .saturating_mul(10)
+            .saturating_add(u32::from(commands[*i] - b'0'));
+        *i += 1;
+    }
+    (*i > start).then_some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HayesModem, ModemAction, ResultCode};
+    use crate::{Address, AddressBook};
+
+    fn address_book() -> AddressBook {
+        let mut book = AddressBook::new();
+        let mut adr = Address::new("Crazy Paradise BBS".to_string());
+        adr.address = "cpbbs.de:2323".to_string();
+        book.addresses.push(adr);
+        book.addresses.push(Address::new("Amis XE".to_string()));
+        book
+    }
+
+    fn type_line(modem: &mut HayesModem, line: &str) -> (String, Option<ModemAction>) {
+        let book = address_book();
+        let mut action = None;
+        for ch in line.bytes() {
+            if let Some(a) = modem.input(ch, &book) {
+                action = Some(a);
+            }
+        }
+        if let Some(a) = modem.input(b'\r', &book) {
+            action = Some(a);
+        }
+        (
+            String::from_utf8_lossy(&modem.take_output()).to_string(),
+            action,
+        )
+    }
+
+    #[test]
+    fn test_simple_commands() {
+        let mut modem = HayesModem::default();
+        assert_eq!(
+            ("ATZ\r\nOK\r\n".to_string(), None),
+            type_line(&mut modem, "ATZ")
+        );
+        assert_eq!(
+            ("at e0".to_string() + "\r\nOK\r\n", None),
+            type_line(&mut modem, "at e0")
+        );
+        assert_eq!(
+            ("\r\nOK\r\n".to_string(), None),
+            type_line(&mut modem, "ATM1L2X4")
+        );
+        assert_eq!(
+            ("\r\nERROR\r\n".to_string(), None),
+            type_line(&mut modem, "ATY")
+        );
+        assert_eq!((String::new(), None), type_line(&mut modem, "hello"));
+        let (output, _) = type_line(&mut modem, "ATI");
+        assert!(output.contains(crate::VERSION));
+    }
+
+    #[test]
+    fn test_result_codes() {
+        let mut modem = HayesModem::default();
+        type_line(&mut modem, "ATE0V0");
+        assert_eq!(("0\r".to_string(), None), type_line(&mut modem, "AT"));
+        assert_eq!(("4\r".to_string(), None), type_line(&mut modem, "ATS99=1"));
+        type_line(&mut modem, "ATQ1");
+        assert_eq!((String::new(), None), type_line(&mut modem, "ATV1"));
+    }
+
+    #[test]
+    fn test_s_registers() {
+        let mut modem = HayesModem::default();
+        type_line(&mut modem, "ATE0");
+        assert_eq!(
+            ("\r\n043\r\n\r\nOK\r\n".to_string(), None),
+            type_line(&mut modem, "ATS2?")
+        );
+        type_line(&mut modem, "ATS7=30S2=42");
+        assert_eq!(
+            ("\r\n030\r\n\r\nOK\r\n".to_string(), None),
+            type_line(&mut modem, "ATS7?")
+        );
+        assert_eq!(42, modem.s_registers[2]);
+        assert_eq!(
+            ("\r\nERROR\r\n".to_string(), None),
+            type_line(&mut modem, "ATS7=256")
+        );
+        type_line(&mut modem, "AT&F");
+        assert_eq!(50, modem.s_registers[7]);
+    }
+
+    #[test]
+    fn test_backspace() {
+        let mut modem = HayesModem::default();
+        let (output, action) = type_line(&mut modem, "ATX\x08Z");
+        assert_eq!("ATX\x08 \x08Z\r\nOK\r\n", output);
+        assert_eq!(None, action);
+    }
+
+    #[test]
+    fn test_dial() {
+        let mut modem = HayesModem::default();
+        let (output, action) = type_line(&mut modem, "ATDT bbs.example.com:23");
+        assert_eq!("ATDT bbs.example.com:23", output);
+        assert_eq!(
+            Some(ModemAction::DialAddress("bbs.example.com:23".to_string())),
+            action
+        );
+        assert!(modem.is_dialing());
+
+        modem.set_carrier(true);
+        assert_eq!(b"\r\nCONNECT\r\n".to_vec(), modem.take_output());
+        assert!(!modem.is_command_mode());
+
+        modem.set_carrier(false);
+        assert_eq!(b"\r\nNO CARRIER\r\n".to_vec(), modem.take_output());
+        assert!(modem.is_command_mode());
+    }
+
+    #[test]
+    fn test_dial_stored() {
+        let mut modem = HayesModem::default();
+        type_line(&mut modem, "ATE0");
+        assert_eq!(
+            (String::new(), Some(ModemAction::Dial(2))),
+            type_line(&mut modem, "ATDS2")
+        );
+        modem.connection_failed("Connection refused");
+        assert_eq!(b"\r\nBUSY\r\n".to_vec(), modem.take_output());
+
+        assert_eq!(
+            (String::new(), Some(ModemAction::Dial(1))),
+            type_line(&mut modem, "ATDS=crazy paradise bbs")
+        );
+        modem.connection_failed("connection timed out");
+        assert_eq!(b"\r\nNO ANSWER\r\n".to_vec(), modem.take_output());
+
+        assert_eq!(
+            ("\r\nERROR\r\n".to_string(), None),
+            type_line(&mut modem, "ATDS0")
+        );
+        assert_eq!(
+            ("\r\nERROR\r\n".to_string(), None),
+            type_line(&mut modem, "ATDSfoo")
+        );
+    }
+
+    #[test]
+    fn test_abort_dialing() {
+        let mut modem = HayesModem::default();
+        type_line(&mut modem, "ATE0");
+        type_line(&mut modem, "ATDP5551234");
+        let (output, action) = type_line(&mut modem, "");
+        assert_eq!("\r\nNO CARRIER\r\n", output);
+        assert_eq!(Some(ModemAction::Hangup), action);
+    }
+
+    #[test]
+    fn test_escape_and_hangup() {
+        let mut modem = HayesModem::default();
+        type_line(&mut modem, "ATE0S12=0");
+        type_line(&mut modem, "ATDS1");
+        modem.set_carrier(true);
+        modem.take_output();
+
+        modem.data_sent(b"++a");
+        modem.poll();
+        assert!(!modem.is_command_mode());
+
+        modem.data_sent(b"+++");
+        modem.poll();
+        assert!(modem.is_command_mode());
+        assert_eq!(b"\r\nOK\r\n".to_vec(), modem.take_output());
+
+        assert_eq!(
+            ("\r\nCONNECT\r\n".to_string(), None),
+            type_line(&mut modem, "ATO")
+        );
+        modem.data_sent(b"+++");
+        modem.poll();
+        modem.take_output();
+
+        assert_eq!(
+            ("\r\nOK\r\n".to_string(), Some(ModemAction::Hangup)),
+            type_line(&mut modem, "ATH0")
+        );
+        // the hangup was requested - no NO CARRIER message
+        modem.set_carrier(false);
+        assert!(modem.take_output().is_empty());
+        assert_eq!(
+            ("\r\nNO CARRIER\r\n".to_string(), None),
+            type_line(&mut modem, "ATO")
+        );
+    }
+
+    #[test]
+    fn test_repeat_last_command() {
+        let mut modem = HayesModem::default();
+        type_line(&mut modem, "ATE0");
+        let book = address_book();
+        let mut action = None;
+        for ch in b"A/" {
+            action = modem.input(*ch, &book);
+        }
+        assert_eq!(None, action);
+        assert_eq!(b"\r\nOK\r\n".to_vec(), modem.take_output());
+        assert_eq!(
+            ResultCode::Busy,
+            ResultCode::from_connection_error("refused")
+        );
+    }
+}
@@ -0,0 +1,726 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::PathBuf,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+};
+
+use regex::Regex;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Position, INT};
+use web_time::{Duration, Instant};
+
+use crate::protocol::TransferType;
+
+/// Received text `wait_for` looks at, older text gets dropped.
+const MAX_RECEIVED: usize = 64 * 1024;
+/// Blocking calls check in this interval if the script got stopped.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// What a running script asks the main window to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptRequest {
+    Send(String),
+    /// Answered with [`ScriptEvent::Screen`]
+    ReadScreen,
+    /// Answered with [`ScriptEvent::TransferFinished`], uploads carry the files to send.
+    Transfer {
+        protocol: TransferType,
+        download: bool,
+        files: Vec<PathBuf>,
+    },
+    Hangup,
+    /// Output of `print` and `debug`
+    Print(String),
+    /// The script ended - with the error message if it failed.
+    Finished(Option<String>),
+}
+
+/// What the main window tells a running script.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptEvent {
+    /// Text received from the connection, decoded like the terminal shows it
+    Data(String),
+    Connected(bool),
+    Screen(ScreenSnapshot),
+    TransferFinished,
+}
+
+/// The visible screen at the time the script asked for it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScreenSnapshot {
+    pub width: INT,
+    /// Visible lines without trailing spaces
+    pub lines: Vec<String>,
+    pub cursor_x: INT,
+    pub cursor_y: INT,
+}
+
+impl ScreenSnapshot {
+    fn line(&self, y: INT) -> String {
+        usize::try_from(y)
+            .ok()
+            .and_then(|y| self.lines.get(y))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn char_at(&self, x: INT, y: INT) -> String {
+        usize::try_from(x)
+            .ok()
+            .and_then(|x| self.line(y).chars().nth(x))
+            .map_or_else(|| " ".to_string(), |ch| ch.to_string())
+    }
+
+    fn height(&self) -> INT {
+        INT::try_from(self.lines.len()).unwrap_or(INT::MAX)
+    }
+}
+
+/// Runs a [Rhai](https://rhai.rs/book/) script in its own thread,
+/// the main window passes the session data in and handles the requests coming out.
+pub struct ScriptRunner {
+    pub name: String,
+    /// Set while a transfer the script started runs
+    pub waiting_for_transfer: bool,
+    connected: bool,
+    events: Sender<ScriptEvent>,
+    requests: Receiver<ScriptRequest>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ScriptRunner {
+    pub fn start(name: String, source: String, connected: bool) -> Self {
+        let (event_tx, event_rx) = mpsc::channel();
+        let (request_tx, request_rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let host = ScriptHost {
+            requests: request_tx.clone(),
+            events: event_rx,
+            stop: stop.clone(),
+            received: String::new(),
+            connected,
+            screen: None,
+            transfer_finished: false,
+            start: Instant::now(),
+            timers: HashMap::new(),
+        };
+        thread::spawn(move || {
+            let result = run_script(host, &source);
+            request_tx.send(ScriptRequest::Finished(result.err())).ok();
+        });
+
+        Self {
+            name,
+            waiting_for_transfer: false,
+            connected,
+            events: event_tx,
+            requests: request_rx,
+            stop,
+        }
+    }
+
+    pub fn send_event(&self, event: ScriptEvent) {
+        // the script may have ended already
+        self.events.send(event).ok();
+    }
+
+    /// Tells the script about connection changes.
+    pub fn set_connected(&mut self, connected: bool) {
+        if self.connected != connected {
+            self.connected = connected;
+            self.send_event(ScriptEvent::Connected(connected));
+        }
+    }
+
+    pub fn try_request(&self) -> Option<ScriptRequest> {
+        self.requests.try_recv().ok()
+    }
+
+    /// The script gets terminated at the next statement or blocking call.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ScriptRunner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// The script side of the session, lives in the script thread.
+struct ScriptHost {
+    requests: Sender<ScriptRequest>,
+    events: Receiver<ScriptEvent>,
+    stop: Arc<AtomicBool>,
+    /// Received text that wasn't matched yet
+    received: String,
+    connected: bool,
+    screen: Option<ScreenSnapshot>,
+    transfer_finished: bool,
+    start: Instant,
+    /// Deadlines of the timers set with `set_timer`
+    timers: HashMap<String, Instant>,
+}
+
+impl ScriptHost {
+    fn request(&self, request: ScriptRequest) -> ScriptResult<()> {
+        self.requests.send(request).map_err(|_| terminated().into())
+    }
+
+    /// Handles the next event, returns false if the deadline passed before one arrived.
+    fn next_event(&mut self, deadline: Option<Instant>) -> ScriptResult<bool> {
+        loop {
+            if self.stop.load(Ordering::Relaxed) {
+                return Err(terminated().into());
+            }
+            let timeout = deadline.map_or(POLL_INTERVAL, |deadline| {
+                deadline
+                    .saturating_duration_since(Instant::now())
+                    .min(POLL_INTERVAL)
+            });
+            match self.events.recv_timeout(timeout) {
+                Ok(event) => {
+                    self.handle_event(event);
+                    return Ok(true);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        return Ok(false);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(terminated().into()),
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: ScriptEvent) {
+        match event {
+            ScriptEvent::Data(data) => {
+                self.received.push_str(&data);
+                if self.received.len() > MAX_RECEIVED {
+                    let mut cut = self.received.len() - MAX_RECEIVED;
+                    while !self.received.is_char_boundary(cut) {
+                        cut += 1;
+                    }
+                    self.received.drain(..cut);
+                }
+            }
+            ScriptEvent::Connected(connected) => self.connected = connected,
+            ScriptEvent::Screen(screen) => self.screen = Some(screen),
+            ScriptEvent::TransferFinished => self.transfer_finished = true,
+        }
+    }
+
+    /// Waits until the text was received, the text up to the match is consumed.
+    /// Gives up after the timeout or when the connection is gone.
+    fn wait_for(&mut self, text: &str, timeout: Option<Duration>) -> ScriptResult<bool> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(pos) = self.received.find(text) {
+                self.received.drain(..pos + text.len());
+                return Ok(true);
+            }
+            if !self.connected || !self.next_event(deadline)? {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Like `wait_for` - returns the matched text, an empty string if nothing matched.
+    fn wait_for_regex(&mut self, regex: &Regex, timeout: Option<Duration>) -> ScriptResult<String> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(m) = regex.find(&self.received) {
+                let result = m.as_str().to_string();
+                self.received.drain(..m.end());
+                return Ok(result);
+            }
+            if !self.connected || !self.next_event(deadline)? {
+                return Ok(String::new());
+            }
+        }
+    }
+
+    /// Waits for the first of the texts, returns its index or -1 on timeout.
+    /// The text up to the earliest match is consumed.
+    fn wait_for_any(&mut self, texts: &[String], timeout: Option<Duration>) -> ScriptResult<INT> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let first = texts
+                .iter()
+                .enumerate()
+                .filter_map(|(i, text)| self.received.find(text.as_str()).map(|pos| (pos, i)))
+                .min();
+            if let Some((pos, i)) = first {
+                self.received.drain(..pos + texts[i].len());
+                return Ok(INT::try_from(i).unwrap_or(INT::MAX));
+            }
+            if !self.connected || !self.next_event(deadline)? {
+                return Ok(-1);
+            }
+        }
+    }
+
+    fn sleep(&mut self, duration: Duration) -> ScriptResult<()> {
+        let deadline = Instant::now() + duration;
+        while self.next_event(Some(deadline))? {}
+        Ok(())
+    }
+
+    fn screen(&mut self) -> ScriptResult<ScreenSnapshot> {
+        self.screen = None;
+        self.request(ScriptRequest::ReadScreen)?;
+        loop {
+            if let Some(screen) = self.screen.take() {
+                return Ok(screen);
+            }
+            self.next_event(None)?;
+        }
+    }
+
+    /// Blocks until the transfer is over.
+    fn transfer(
+        &mut self,
+        protocol: &str,
+        download: bool,
+        files: Vec<PathBuf>,
+    ) -> ScriptResult<()> {
+        let Some(protocol) = TransferType::from_name(protocol) else {
+            return Err(format!("unknown transfer protocol '{protocol}'").into());
+        };
+        self.transfer_finished = false;
+        self.request(ScriptRequest::Transfer {
+            protocol,
+            download,
+            files,
+        })?;
+        while !self.transfer_finished {
+            self.next_event(None)?;
+        }
+        Ok(())
+    }
+}
+
+fn terminated() -> EvalAltResult {
+    EvalAltResult::ErrorTerminated(Dynamic::UNIT, Position::NONE)
+}
+
+fn millis(value: INT) -> Duration {
+    Duration::from_millis(u64::try_from(value).unwrap_or_default())
+}
+
+/// The texts of a script array, other values are an error.
+fn string_array(array: Array) -> ScriptResult<Vec<String>> {
+    array
+        .into_iter()
+        .map(|value| {
+            value
+                .into_string()
+                .map_err(|type_name| format!("expected a string, got {type_name}").into())
+        })
+        .collect()
+}
+
+fn compile_regex(pattern: &str) -> ScriptResult<Regex> {
+    Regex::new(pattern).map_err(|err| format!("invalid regex: {err}").into())
+}
+
+fn run_script(host: ScriptHost, source: &str) -> Result<(), String> {
+    let engine = create_engine(host);
+    match engine.run(source) {
+        // stopped by the user
+        Err(err) if matches!(*err, EvalAltResult::ErrorTerminated(..)) => Ok(()),
+        Err(err) => Err(err.to_string()),
+        Ok(()) => Ok(()),
+    }
+}
+
+fn create_engine(host: ScriptHost) -> Engine {
+    let mut engine = Engine::new();
+
+    let stop = host.stop.clone();
+    engine.on_progress(move |_| stop.load(Ordering::Relaxed).then_some(Dynamic::UNIT));
+    let requests = host.requests.clone();
+    engine.on_print(move |text| {
+        requests.send(ScriptRequest::Print(text.to_string())).ok();
+    });
+    let requests = host.requests.clone();
+    engine.on_debug(move |text, _source, pos| {
+        let text = if pos.is_none() {
+            text.to_string()
+        } else {
+            format!("{pos}: {text}")
+        };
+        requests.send(ScriptRequest::Print(text)).ok();
+    });
+
+    let host = Rc::new(RefCell::new(host));
+
+    let h = host.clone();
+    engine.register_fn("send", move |text: &str| -> ScriptResult<()> {
+        h.borrow().request(ScriptRequest::Send(text.to_string()))
+    });
+    let h = host.clone();
+    engine.register_fn("send_line", move |text: &str| -> ScriptResult<()> {
+        h.borrow().request(ScriptRequest::Send(format!("{text}\r")))
+    });
+    let h = host.clone();
+    engine.register_fn("wait_for", move |text: &str| -> ScriptResult<bool> {
+        h.borrow_mut().wait_for(text, None)
+    });
+    let h = host.clone();
+    engine.register_fn(
+        "wait_for",
+        move |text: &str, timeout: INT| -> ScriptResult<bool> {
+            h.borrow_mut().wait_for(text, Some(millis(timeout)))
+        },
+    );
+    let h = host.clone();
+    engine.register_fn(
+        "wait_for_regex",
+        move |pattern: &str| -> ScriptResult<String> {
+            h.borrow_mut()
+                .wait_for_regex(&compile_regex(pattern)?, None)
+        },
+    );
+    let h = host.clone();
+    engine.register_fn(
+        "wait_for_regex",
+        move |pattern: &str, timeout: INT| -> ScriptResult<String> {
+            h.borrow_mut()
+                .wait_for_regex(&compile_regex(pattern)?, Some(millis(timeout)))
+        },
+    );
+    let h = host.clone();
+    engine.register_fn("wait_for_any", move |texts: Array| -> ScriptResult<INT> {
+        h.borrow_mut().wait_for_any(&string_array(texts)?, None)
+    });
+    let h = host.clone();
+    engine.register_fn(
+        "wait_for_any",
+        move |texts: Array, timeout: INT| -> ScriptResult<INT> {
+            h.borrow_mut()
+                .wait_for_any(&string_array(texts)?, Some(millis(timeout)))
+        },
+    );
+    let h = host.clone();
+    engine.register_fn("sleep", move |ms: INT| -> ScriptResult<()> {
+        h.borrow_mut().sleep(millis(ms))
+    });
+    let h = host.clone();
+    engine.register_fn("millis", move || -> INT {
+        INT::try_from(h.borrow().start.elapsed().as_millis()).unwrap_or(INT::MAX)
+    });
+    let h = host.clone();
+    engine.register_fn("set_timer", move |name: &str, ms: INT| {
+        h.borrow_mut()
+            .timers
+            .insert(name.to_string(), Instant::now() + millis(ms));
+    });
+    let h = host.clone();
+    engine.register_fn("timer_expired", move |name: &str| -> bool {
+        h.borrow()
+            .timers
+            .get(name)
+            .is_some_and(|deadline| Instant::now() >= *deadline)
+    });
+    let h = host.clone();
+    engine.register_fn("is_connected", move || -> bool { h.borrow().connected });
+
+    let h = host.clone();
+    engine.register_fn("screen_width", move || -> ScriptResult<INT> {
+        Ok(h.borrow_mut().screen()?.width)
+    });
+    let h = host.clone();
+    engine.register_fn("screen_height", move || -> ScriptResult<INT> {
+        Ok(h.borrow_mut().screen()?.height())
+    });
+    let h = host.clone();
+    engine.register_fn("cursor_x", move || -> ScriptResult<INT> {
+        Ok(h.borrow_mut().screen()?.cursor_x)
+    });
+    let h = host.clone();
+    engine.register_fn("cursor_y", move || -> ScriptResult<INT> {
+        Ok(h.borrow_mut().screen()?.cursor_y)
+    });
+    let h = host.clone();
+    engine.register_fn("screen_line", move |y: INT| -> ScriptResult<String> {
+        Ok(h.borrow_mut().screen()?.line(y))
+    });
+    let h = host.clone();
+    engine.register_fn(
+        "screen_char",
+        move |x: INT, y: INT| -> ScriptResult<String> {
+            Ok(h.borrow_mut().screen()?.char_at(x, y))
+        },
+    );
+    let h = host.clone();
+    engine.register_fn("screen_text", move || -> ScriptResult<String> {
+        Ok(h.borrow_mut().screen()?.lines.join("\n"))
+    });
+
+    let h = host.clone();
+    engine.register_fn("download", move |protocol: &str| -> ScriptResult<()> {
+        h.borrow_mut().transfer(protocol, true, Vec::new())
+    });
+    let h = host.clone();
+    engine.register_fn(
+        "upload",
+        move |protocol: &str, file: &str| -> ScriptResult<()> {
+            h.borrow_mut()
+                .transfer(protocol, false, vec![PathBuf::from(file)])
+        },
+    );
+    engine.register_fn("hangup", move || -> ScriptResult<()> {
+        let mut host = host.borrow_mut();
+        host.connected = false;
+        host.request(ScriptRequest::Hangup)
+    });
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host() -> (ScriptHost, Sender<ScriptEvent>, Receiver<ScriptRequest>) {
+        let (event_tx, event_rx) = mpsc::channel();
+        let (request_tx, request_rx) = mpsc::channel();
+        let host = ScriptHost {
+            requests: request_tx,
+            events: event_rx,
+            stop: Arc::new(AtomicBool::new(false)),
+            received: String::new(),
+            connected: true,
+            screen: None,
+            transfer_finished: false,
+            start: Instant::now(),
+            timers: HashMap::new(),
+        };
+        (host, event_tx, request_rx)
+    }
+
+    /// Collects the requests until the script finished.
+    fn requests(runner: &ScriptRunner) -> Vec<ScriptRequest> {
+        let mut result = Vec::new();
+        loop {
+            let request = runner
+                .requests
+                .recv_timeout(Duration::from_secs(10))
+                .unwrap();
+            let finished = matches!(request, ScriptRequest::Finished(_));
+            result.push(request);
+            if finished {
+                return result;
+            }
+        }
+    }
+
+    #[test]
+    fn test_wait_for() {
+        let (mut host, events, _requests) = host();
+        events
+            .send(ScriptEvent::Data("Login: ".to_string()))
+            .unwrap();
+        assert!(host.wait_for("Login:", None).unwrap());
+        assert_eq!(" ", host.received);
+        assert!(!host
+            .wait_for("Password:", Some(Duration::from_millis(10)))
+            .unwrap());
+
+        events.send(ScriptEvent::Connected(false)).unwrap();
+        assert!(!host.wait_for("Password:", None).unwrap());
+    }
+
+    #[test]
+    fn test_wait_for_non_ascii() {
+        let (mut host, events, _requests) = host();
+        events
+            .send(ScriptEvent::Data("Größe ─ Menü: ".to_string()))
+            .unwrap();
+        assert!(host.wait_for("─ Menü:", None).unwrap());
+        assert_eq!(" ", host.received);
+    }
+
+    #[test]
+    fn test_wait_for_regex() {
+        let (mut host, events, _requests) = host();
+        events
+            .send(ScriptEvent::Data("You have 12 new mails".to_string()))
+            .unwrap();
+        let regex = compile_regex(r"\d+ new").unwrap();
+        assert_eq!("12 new", host.wait_for_regex(&regex, None).unwrap());
+        assert_eq!(
+            "",
+            host.wait_for_regex(&regex, Some(Duration::from_millis(10)))
+                .unwrap()
+        );
+        assert!(compile_regex("(").is_err());
+    }
+
+    #[test]
+    fn test_wait_for_any() {
+        let (mut host, events, _requests) = host();
+        events
+            .send(ScriptEvent::Data("More? [Y/n] Menu: ".to_string()))
+            .unwrap();
+        let texts = ["Menu:".to_string(), "More?".to_string()];
+        assert_eq!(1, host.wait_for_any(&texts, None).unwrap());
+        assert_eq!(0, host.wait_for_any(&texts, None).unwrap());
+        assert_eq!(
+            -1,
+            host.wait_for_any(&texts, Some(Duration::from_millis(10)))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_received_text_is_limited() {
+        let (mut host, _events, _requests) = host();
+        host.handle_event(ScriptEvent::Data("Ä".repeat(MAX_RECEIVED)));
+        host.handle_event(ScriptEvent::Data("end".to_string()));
+        assert!(host.received.len() <= MAX_RECEIVED + 1);
+        assert!(host.received.ends_with("end"));
+    }
+
+    #[test]
+    fn test_run_script() {
+        let runner = ScriptRunner::start(
+            "test".to_string(),
+            r#"
+                send("hello");
+                if wait_for("Name?", 5000) {
+                    send_line("sysop");
+                }
+                print("done");
+            "#
+            .to_string(),
+            true,
+        );
+        runner.send_event(ScriptEvent::Data("What's your Name? ".to_string()));
+        assert_eq!(
+            vec![
+                ScriptRequest::Send("hello".to_string()),
+                ScriptRequest::Send("sysop\r".to_string()),
+                ScriptRequest::Print("done".to_string()),
+                ScriptRequest::Finished(None),
+            ],
+            requests(&runner)
+        );
+    }
+
+    #[test]
+    fn test_timers() {
+        let runner = ScriptRunner::start(
+            "test".to_string(),
+            r#"
+                set_timer("now", 0);
+                set_timer("later", 60000);
+                send(`${timer_expired("now")} ${timer_expired("later")} ${timer_expired("unset")}`);
+                send(`${wait_for_any(["a", "b"], 10)}`);
+                wait_for_any([1]);
+            "#
+            .to_string(),
+            true,
+        );
+        let requests = requests(&runner);
+        assert_eq!(
+            ScriptRequest::Send("true false false".to_string()),
+            requests[0]
+        );
+        assert_eq!(ScriptRequest::Send("-1".to_string()), requests[1]);
+        assert!(matches!(requests[2], ScriptRequest::Finished(Some(_))));
+    }
+
+    #[test]
+    fn test_read_screen() {
+        let runner = ScriptRunner::start(
+            "test".to_string(),
+            "send(screen_line(1) + screen_char(1, 0) + cursor_y());".to_string(),
+            true,
+        );
+        assert_eq!(Some(ScriptRequest::ReadScreen), runner.requests.recv().ok());
+        let screen = ScreenSnapshot {
+            width: 80,
+            lines: vec!["Main menu".to_string(), "Select:".to_string()],
+            cursor_x: 8,
+            cursor_y: 1,
+        };
+        for _ in 0..3 {
+            runner.send_event(ScriptEvent::Screen(screen.clone()));
+        }
+        let requests = requests(&runner);
+        assert_eq!(
+            ScriptRequest::Send("Select:a1".to_string()),
+            requests[requests.len() - 2]
+        );
+    }
+
+    #[test]
+    fn test_transfer() {
+        let runner = ScriptRunner::start(
+            "test".to_string(),
+            r#"download("zmodem"); send("done");"#.to_string(),
+            true,
+        );
+        assert_eq!(
+            Some(ScriptRequest::Transfer {
+                protocol: TransferType::ZModem,
+                download: true,
+                files: Vec::new()
+            }),
+            runner.requests.recv().ok()
+        );
+        runner.send_event(ScriptEvent::TransferFinished);
+        assert_eq!(
+            ScriptRequest::Send("done".to_string()),
+            requests(&runner)[0]
+        );
+        assert_eq!(
+            Some(TransferType::XModem1k),
+            TransferType::from_name("XModem-1k")
+        );
+
+        let runner = ScriptRunner::start(
+            "test".to_string(),
+            r#"download("carrier pigeon");"#.to_string(),
+            true,
+        );
+        assert!(matches!(
+            requests(&runner)[0],
+            ScriptRequest::Finished(Some(_))
+        ));
+    }
+
+    #[test]
+    fn test_script_errors() {
+        let runner = ScriptRunner::start("test".to_string(), "send(".to_string(), true);
+        assert!(matches!(
+            requests(&runner)[0],
+            ScriptRequest::Finished(Some(_))
+        ));
+    }
+
+    #[test]
+    fn test_stop_script() {
+        let runner = ScriptRunner::start("test".to_string(), "loop {}".to_string(), true);
+        runner.stop();
+        assert_eq!(vec![ScriptRequest::Finished(None)], requests(&runner));
+
+        let runner =
+            ScriptRunner::start("test".to_string(), "wait_for(\"never\");".to_string(), true);
+        runner.stop();
+        assert_eq!(vec![ScriptRequest::Finished(None)], requests(&runner));
+    }
+}
@@ -0,0 +1,456 @@
+use chrono::Utc;
+use web_time::{Duration, Instant};
+
+use crate::{
+    addresses::{parse_address, write_session_settings},
+    Address, TerminalResult,
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, ErrorKind, Write},
+    path::Path,
+};
+use toml::Value;
+
+/// Recordings start with the magic followed by the size of the toml header.
+const MAGIC: &[u8; 8] = b"ICYREC1\n";
+/// Extension of recorded sessions
+pub const RECORDING_EXTENSION: &str = "icyrec";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Received from the remote side
+    Inbound,
+    /// Sent by the user
+    Outbound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedChunk {
+    /// Time since the recording started
+    pub time: Duration,
+    pub direction: Direction,
+    pub data: Vec<u8>,
+}
+
+/// A recorded session with the settings of the address it was recorded from.
+pub struct Recording {
+    /// Only the settings needed for playback are recorded
+    pub address: Address,
+    pub started: Option<chrono::DateTime<Utc>>,
+    pub chunks: Vec<RecordedChunk>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> TerminalResult<Self> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Parses a recording, a truncated last chunk (crash during recording) is skipped.
+    pub fn parse(data: &[u8]) -> TerminalResult<Self> {
+        if !data.starts_with(MAGIC) {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::InvalidData,
+                "Not an icy_term session recording",
+            )));
+        }
+        let mut reader = ChunkReader {
+            data,
+            pos: MAGIC.len(),
+        };
+        let Some(header) = reader
+            .read_u32()
+            .and_then(|len| reader.read_bytes(len as usize))
+        else {
+            return Err(Box::new(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Recording header is truncated",
+            )));
+        };
+        let header = String::from_utf8_lossy(header).parse::<Value>()?;
+
+        let mut result = Recording {
+            address: Address::new(String::new()),
+            started: None,
+            chunks: Vec::new(),
+        };
+        if let Some(address) = header.get("address") {
+            result.address = parse_address(address);
+        }
+        if let Some(Value::String(value)) = header.get("started") {
+            if let Ok(started) = chrono::DateTime::parse_from_rfc3339(value) {
+                result.started = Some(started.into());
+            }
+        }
+
+        while let Some(chunk) = reader.read_chunk() {
+            result.chunks.push(chunk);
+        }
+        Ok(result)
+    }
+
+    /// Time of the last chunk
+    pub fn duration(&self) -> Duration {
+        self.chunks
+            .last()
+            .map_or(Duration::ZERO, |chunk| chunk.time)
+    }
+}
+
+struct ChunkReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ChunkReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        let bytes = self.read_bytes(4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    fn read_chunk(&mut self) -> Option<RecordedChunk> {
+        let time = Duration::from_millis(u64::from(self.read_u32()?));
+        let direction = match self.read_bytes(1)?[0] {
+            0 => Direction::Inbound,
+            1 => Direction::Outbound,
+            _ => return None,
+        };
+        let len = self.read_u32()? as usize;
+        let data = self.read_bytes(len)?.to_vec();
+        Some(RecordedChunk {
+            time,
+            direction,
+            data,
+        })
+    }
+}
+
+/// Replaces the password of the address in the outbound data with '*'.
+struct PasswordMask {
+    password: Vec<u8>,
+    /// Sent bytes that may be the start of the password, they are held back until it's clear.
+    pending: Vec<u8>,
+}
+
+impl PasswordMask {
+    fn new(password: &str) -> Self {
+        Self {
+            password: password.as_bytes().to_vec(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn mask(&mut self, data: &[u8]) -> Vec<u8> {
+        if self.password.is_empty() {
+            return data.to_vec();
+        }
+        let mut result = Vec::new();
+        for &b in data {
+            self.pending.push(b);
+            while !self.password.starts_with(&self.pending) {
+                result.push(self.pending.remove(0));
+            }
+            if self.pending.len() == self.password.len() {
+                result.resize(result.len() + self.pending.len(), b'*');
+                self.pending.clear();
+            }
+        }
+        result
+    }
+}
+
+/// Writes the session data with timestamps, every chunk is flushed so crashes don't lose the recording.
+pub struct SessionRecorder {
+    out: BufWriter<File>,
+    start: Instant,
+    mask: PasswordMask,
+}
+
+impl SessionRecorder {
+    pub fn start(path: &Path, address: &Address) -> TerminalResult<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        Self::write_header(&mut out, address)?;
+        out.flush()?;
+        Ok(Self {
+            out,
+            start: Instant::now(),
+            mask: PasswordMask::new(&address.password),
+        })
+    }
+
+    fn write_header(out: &mut impl Write, address: &Address) -> TerminalResult<()> {
+        let mut header = Vec::new();
+        header.write_all(format!("started = \"{}\"\n", Utc::now().to_rfc3339()).as_bytes())?;
+        write_session_settings(&mut header, address)?;
+        out.write_all(MAGIC)?;
+        out.write_all(&u32::try_from(header.len())?.to_le_bytes())?;
+        out.write_all(&header)?;
+        Ok(())
+    }
+
+    /// The password of the address doesn't get recorded, even if it's typed in.
+    pub fn record(&mut self, direction: Direction, data: &[u8]) -> TerminalResult<()> {
+        let masked;
+        let data = if direction == Direction::Outbound {
+            masked = self.mask.mask(data);
+            &masked
+        } else {
+            data
+        };
+        if data.is_empty() {
+            return Ok(());
+        }
+        let millis = u32::try_from(self.start.elapsed().as_millis()).unwrap_or(u32::MAX);
+        write_chunk(&mut self.out, millis, direction, data)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+fn write_chunk(
+    out: &mut impl Write,
+    millis: u32,
+    direction: Direction,
+    data: &[u8],
+) -> TerminalResult<()> {
+    out.write_all(&millis.to_le_bytes())?;
+    out.write_all(&[match direction {
+        Direction::Inbound => 0,
+        Direction::Outbound => 1,
+    }])?;
+    out.write_all(&u32::try_from(data.len())?.to_le_bytes())?;
+    out.write_all(data)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackSpeed {
+    /// Timing as recorded
+    Real,
+    /// Recorded timing multiplied by the factor
+    Scaled(f32),
+    /// Everything at once
+    Instant,
+}
+
+impl PlaybackSpeed {
+    pub const ALL: [PlaybackSpeed; 6] = [
+        PlaybackSpeed::Real,
+        PlaybackSpeed::Scaled(0.5),
+        PlaybackSpeed::Scaled(2.0),
+        PlaybackSpeed::Scaled(4.0),
+        PlaybackSpeed::Scaled(8.0),
+        PlaybackSpeed::Instant,
+    ];
+}
+
+impl std::fmt::Display for PlaybackSpeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlaybackSpeed::Real => write!(f, "1x"),
+            PlaybackSpeed::Scaled(factor) => write!(f, "{factor}x"),
+            PlaybackSpeed::Instant => write!(f, "∞"),
+        }
+    }
+}
+
+/// Plays a recording back, only the inbound data goes to the screen.
+pub struct SessionPlayer {
+    pub recording: Recording,
+    pub speed: PlaybackSpeed,
+    paused: bool,
+    /// Position in recording time
+    position: Duration,
+    next_chunk: usize,
+    last_update: Option<Instant>,
+}
+
+impl SessionPlayer {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            speed: PlaybackSpeed::Real,
+            paused: false,
+            position: Duration::ZERO,
+            next_chunk: 0,
+            last_update: None,
+        }
+    }
+
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.last_update = None;
+    }
+
+    /// Moves to the position, returns true if the playback needs to start over
+    /// - the screen needs to be reset and the data up to the position gets replayed instantly.
+    pub fn seek(&mut self, position: Duration) -> bool {
+        let position = position.min(self.recording.duration());
+        let restart = position < self.position;
+        if restart {
+            self.next_chunk = 0;
+        }
+        self.position = position;
+        restart
+    }
+
+    /// Advances the playback and returns the inbound data that is due.
+    pub fn update(&mut self, now: Instant) -> Vec<u8> {
+        if !self.paused {
+            let elapsed = self
+                .last_update
+                .map_or(Duration::ZERO, |last| now.duration_since(last));
+            self.position = match self.speed {
+                PlaybackSpeed::Real => self.position + elapsed,
+                PlaybackSpeed::Scaled(factor) => self.position + elapsed.mul_f32(factor),
+                PlaybackSpeed::Instant => self.recording.duration(),
+            }
+            .min(self.recording.duration());
+            self.last_update = Some(now);
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = self.recording.chunks.get(self.next_chunk) {
+            if chunk.time > self.position {
+                break;
+            }
+            if chunk.direction == Direction::Inbound {
+                data.extend_from_slice(&chunk.data);
+            }
+            self.next_chunk += 1;
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addresses::Terminal;
+    use crate::ui::ScreenMode;
+
+    fn recording_data() -> Vec<u8> {
+        let mut address = Address::new("test".to_string());
+        address.password = "secret".to_string();
+        address.terminal_type = Terminal::PETscii;
+        address.screen_mode = ScreenMode::Vic;
+
+        let mut data = Vec::new();
+        SessionRecorder::write_header(&mut data, &address).unwrap();
+        write_chunk(&mut data, 0, Direction::Inbound, b"Hello").unwrap();
+        write_chunk(&mut data, 500, Direction::Outbound, b"x").unwrap();
+        write_chunk(&mut data, 1000, Direction::Inbound, b"World").unwrap();
+        data
+    }
+
+    #[test]
+    fn test_parse_recording() {
+        let recording = Recording::parse(&recording_data()).unwrap();
+        assert_eq!(Terminal::PETscii, recording.address.terminal_type);
+        assert_eq!(ScreenMode::Vic, recording.address.screen_mode);
+        assert!(recording.address.password.is_empty());
+        assert!(recording.started.is_some());
+        assert_eq!(3, recording.chunks.len());
+        assert_eq!(Direction::Outbound, recording.chunks[1].direction);
+        assert_eq!(Duration::from_secs(1), recording.duration());
+    }
+
+    #[test]
+    fn test_password_is_not_recorded() {
+        let path = std::env::temp_dir().join("icy_term_test_password_is_not_recorded.icyrec");
+        let mut address = Address::new("test".to_string());
+        address.password = "secret".to_string();
+
+        let mut recorder = SessionRecorder::start(&path, &address).unwrap();
+        recorder.record(Direction::Outbound, b"John Doe\r").unwrap();
+        recorder.record(Direction::Outbound, b"secret").unwrap();
+        recorder.record(Direction::Inbound, b"Password: ").unwrap();
+        // typed in, one chunk for every key
+        for ch in b"sesecret\r" {
+            recorder.record(Direction::Outbound, &[*ch]).unwrap();
+        }
+        drop(recorder);
+
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!data.windows(6).any(|window| window == b"secret"));
+        let outbound: Vec<u8> = Recording::parse(&data)
+            .unwrap()
+            .chunks
+            .into_iter()
+            .filter(|chunk| chunk.direction == Direction::Outbound)
+            .flat_map(|chunk| chunk.data)
+            .collect();
+        assert_eq!(b"John Doe\r******se******\r".to_vec(), outbound);
+    }
+
+    #[test]
+    fn test_truncated_recording() {
+        let mut data = recording_data();
+        data.truncate(data.len() - 2);
+        let recording = Recording::parse(&data).unwrap();
+        assert_eq!(2, recording.chunks.len());
+
+        assert!(Recording::parse(b"ICYREC1\n\xFF\xFF").is_err());
+        assert!(Recording::parse(b"capture").is_err());
+    }
+
+    #[test]
+    fn test_playback_timing() {
+        let mut player = SessionPlayer::new(Recording::parse(&recording_data()).unwrap());
+        let now = Instant::now();
+        assert_eq!(b"Hello".to_vec(), player.update(now));
+        assert!(player.update(now + Duration::from_millis(900)).is_empty());
+        assert_eq!(
+            b"World".to_vec(),
+            player.update(now + Duration::from_millis(1000))
+        );
+        assert!(player.update(now + Duration::from_secs(5)).is_empty());
+    }
+
+    #[test]
+    fn test_playback_speed() {
+        let mut player = SessionPlayer::new(Recording::parse(&recording_data()).unwrap());
+        player.speed = PlaybackSpeed::Scaled(4.0);
+        let now = Instant::now();
+        player.update(now);
+        assert_eq!(
+            b"World".to_vec(),
+            player.update(now + Duration::from_millis(250))
+        );
+
+        let mut player = SessionPlayer::new(Recording::parse(&recording_data()).unwrap());
+        player.speed = PlaybackSpeed::Instant;
+        assert_eq!(b"HelloWorld".to_vec(), player.update(Instant::now()));
+    }
+
+    #[test]
+    fn test_pause_and_seek() {
+        let mut player = SessionPlayer::new(Recording::parse(&recording_data()).unwrap());
+        player.set_paused(true);
+        let now = Instant::now();
+        assert_eq!(b"Hello".to_vec(), player.update(now));
+        assert!(player.update(now + Duration::from_secs(5)).is_empty());
+
+        assert!(!player.seek(Duration::from_secs(10)));
+        assert_eq!(Duration::from_secs(1), player.position());
+        assert_eq!(b"World".to_vec(), player.update(now));
+
+        assert!(player.seek(Duration::from_millis(200)));
+        assert_eq!(b"Hello".to_vec(), player.update(now));
+    }
+}
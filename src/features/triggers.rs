@@ -0,0 +1,205 @@
+use regex::bytes::Regex;
+
+use crate::{
+    addresses::{Trigger, TriggerAction},
+    util::PatternRecognizer,
+};
+
+use super::parse_escaped_text;
+
+/// Longest line the regular expressions see, older text gets dropped.
+const MAX_LINE: usize = 1024;
+
+enum Matcher {
+    Literal(PatternRecognizer, usize),
+    /// Matches against the current line
+    Regex(Regex),
+}
+
+struct ActiveTrigger {
+    matcher: Matcher,
+    action: TriggerAction,
+    /// The text of a send action with resolved escapes
+    data: Vec<u8>,
+}
+
+/// A trigger that fired
+pub struct TriggerMatch {
+    pub action: TriggerAction,
+    /// Data of a send action
+    pub data: Vec<u8>,
+    /// Length of the matched text in bytes, it ends with the last received byte.
+    pub len: usize,
+}
+
+/// Runs the triggers of an address against the incoming data.
+#[derive(Default)]
+pub struct Triggers {
+    active: Vec<ActiveTrigger>,
+    line: Vec<u8>,
+    /// A line fires a regular expression only once
+    line_matched: bool,
+}
+
+impl Triggers {
+    /// Invalid triggers are logged and left out.
+    pub fn new(triggers: &[Trigger]) -> Self {
+        let active = triggers
+            .iter()
+            .filter(|trigger| !trigger.pattern.is_empty())
+            .filter_map(|trigger| match compile_trigger(trigger) {
+                Ok(trigger) => Some(trigger),
+                Err(err) => {
+                    log::error!("trigger '{}': {err}", trigger.pattern);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            active,
+            line: Vec::new(),
+            line_matched: false,
+        }
+    }
+
+    /// Returns the first trigger that matches with this byte.
+    pub fn push_ch(&mut self, ch: u8) -> Option<TriggerMatch> {
+        if self.active.is_empty() {
+            return None;
+        }
+        let new_line = ch == b'\r' || ch == b'\n';
+        if new_line {
+            self.line.clear();
+            self.line_matched = false;
+        } else {
+            self.line.push(ch);
+            if self.line.len() > MAX_LINE {
+                self.line.drain(..self.line.len() - MAX_LINE);
+            }
+        }
+
+        let mut result = None;
+        for trigger in &mut self.active {
+            // all recognizers need to see every byte
+            let len = match &mut trigger.matcher {
+                Matcher::Literal(recognizer, len) => recognizer.push_ch(ch).then_some(*len),
+                Matcher::Regex(regex) if !new_line && !self.line_matched => regex
+                    .find_iter(&self.line)
+                    .last()
+                    .filter(|m| m.end() == self.line.len())
+                    .map(|m| m.len()),
+                Matcher::Regex(_) => None,
+            };
+            if let (Some(len), None) = (len, &result) {
+                self.line_matched |= matches!(trigger.matcher, Matcher::Regex(_));
+                result = Some(TriggerMatch {
+                    action: trigger.action.clone(),
+                    data: trigger.data.clone(),
+                    len,
+                });
+            }
+        }
+        result
+    }
+}
+
+fn compile_trigger(trigger: &Trigger) -> Result<ActiveTrigger, String> {
+    let matcher = if trigger.is_regex {
+        Matcher::Regex(Regex::new(&trigger.pattern).map_err(|err| err.to_string())?)
+    } else {
+        Matcher::Literal(
+            PatternRecognizer::from(trigger.pattern.as_bytes(), true),
+            trigger.pattern.len(),
+        )
+    };
+    let data = match &trigger.action {
+        TriggerAction::Send(text) => parse_escaped_text(text).map_err(|err| err.to_string())?,
+        _ => Vec::new(),
+    };
+    Ok(ActiveTrigger {
+        matcher,
+        action: trigger.action.clone(),
+        data,
+    })
+}
+
+/// Checks the regular expression and the text of a trigger.
+pub fn check_trigger(trigger: &Trigger) -> Result<(), String> {
+    compile_trigger(trigger).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::TransferType;
+
+    fn trigger(pattern: &str, is_regex: bool, action: TriggerAction) -> Trigger {
+        Trigger {
+            pattern: pattern.to_string(),
+            is_regex,
+            action,
+        }
+    }
+
+    fn push(triggers: &mut Triggers, data: &[u8]) -> Vec<(TriggerAction, Vec<u8>, usize)> {
+        data.iter()
+            .filter_map(|ch| triggers.push_ch(*ch))
+            .map(|m| (m.action, m.data, m.len))
+            .collect()
+    }
+
+    #[test]
+    fn test_literal_trigger() {
+        let mut triggers = Triggers::new(&[trigger(
+            "Press any key",
+            false,
+            TriggerAction::Send(r"\r".to_string()),
+        )]);
+        assert!(push(&mut triggers, b"Press a key").is_empty());
+        assert_eq!(
+            vec![(TriggerAction::Send(r"\r".to_string()), b"\r".to_vec(), 13)],
+            push(&mut triggers, b"\r\nPRESS ANY KEY")
+        );
+    }
+
+    #[test]
+    fn test_regex_trigger() {
+        let action = TriggerAction::Download(TransferType::YModem);
+        let mut triggers = Triggers::new(&[trigger(r"^Sending \d+ files", true, action.clone())]);
+        assert!(push(&mut triggers, b"Not Sending 2 files\r\n").is_empty());
+        assert_eq!(
+            vec![(action.clone(), Vec::new(), 16)],
+            push(&mut triggers, b"Sending 12 files")
+        );
+        // fires once per line
+        assert!(push(&mut triggers, b"Sending 3 files").is_empty());
+        assert_eq!(
+            vec![(action, Vec::new(), 15)],
+            push(&mut triggers, b"\nSending 3 files")
+        );
+    }
+
+    #[test]
+    fn test_first_trigger_wins() {
+        let mut triggers = Triggers::new(&[
+            trigger("paging", false, TriggerAction::Beep),
+            trigger("is paging", false, TriggerAction::Notify),
+        ]);
+        assert_eq!(
+            vec![(TriggerAction::Beep, Vec::new(), 6)],
+            push(&mut triggers, b"Sysop is paging you")
+        );
+    }
+
+    #[test]
+    fn test_invalid_triggers() {
+        let invalid_regex = trigger("(", true, TriggerAction::Beep);
+        let invalid_text = trigger("a", false, TriggerAction::Send(r"\q".to_string()));
+        assert!(check_trigger(&invalid_regex).is_err());
+        assert!(check_trigger(&invalid_text).is_err());
+        assert!(check_trigger(&trigger("(a)", true, TriggerAction::Beep)).is_ok());
+        assert!(Triggers::new(&[invalid_regex, invalid_text])
+            .active
+            .is_empty());
+    }
+}
@@ -1,9 +1,32 @@
-use std::{collections::HashMap, fs::File, io::Write, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use icy_engine::get_crc32;
 
 use crate::TerminalResult;
 
+/// Reduces a file name sent by the remote side to its last path component.
+/// Returns `None` for names that can't be stored in the download directory.
+pub fn sanitize_file_name(file_name: &str) -> Option<&str> {
+    let name = file_name.rsplit(['/', '\\']).next()?;
+    match name {
+        "" | "." | ".." => None,
+        name => Some(name),
+    }
+}
+
 pub trait FileStorageHandler {
     fn open_file(&mut self, file_name: &str, total_size: usize);
+    /// Looks for an incomplete file from a previous transfer if resuming is enabled.
+    /// Returns the length and the CRC32 of the existing data.
+    fn find_partial_file(&self, file_name: &str, total_size: usize) -> Option<(usize, u32)>;
+    /// Opens an incomplete file from a previous transfer for appending.
+    /// Returns the length of the existing data or `None` if there is nothing to resume.
+    fn open_partial_file(&mut self, file_name: &str, total_size: usize) -> Option<usize>;
     fn append(&mut self, data: &[u8]);
     fn close(&mut self);
 
@@ -18,6 +41,7 @@ pub struct TestStorageHandler {
     cur_file_name: Option<String>,
     cur_file_size: usize,
     pub file: HashMap<String, Vec<u8>>,
+    pub resume_partial_files: bool,
 }
 
 impl TestStorageHandler {
@@ -26,6 +50,7 @@ impl TestStorageHandler {
             cur_file_name: None,
             cur_file_size: 0,
             file: HashMap::new(),
+            resume_partial_files: false,
         }
     }
 }
@@ -38,6 +63,27 @@ impl FileStorageHandler for TestStorageHandler {
         self.file.insert(fn_string, Vec::new());
     }
 
+    fn find_partial_file(&self, file_name: &str, total_size: usize) -> Option<(usize, u32)> {
+        if !self.resume_partial_files {
+            return None;
+        }
+        let data = self.file.get(file_name)?;
+        if data.is_empty() || data.len() >= total_size {
+            return None;
+        }
+        Some((data.len(), get_crc32(data)))
+    }
+
+    fn open_partial_file(&mut self, file_name: &str, total_size: usize) -> Option<usize> {
+        let len = self.file.get(file_name)?.len();
+        if len == 0 || len >= total_size {
+            return None;
+        }
+        self.cur_file_name = Some(file_name.to_string());
+        self.cur_file_size = total_size;
+        Some(len)
+    }
+
     fn current_file_name(&self) -> Option<String> {
         self.cur_file_name.clone()
     }
@@ -79,6 +125,7 @@ pub struct DiskStorageHandler {
     current_file_length: usize,
     output_path: PathBuf,
     file: Option<File>,
+    pub resume_partial_files: bool,
 }
 
 impl DiskStorageHandler {
@@ -97,21 +144,28 @@ impl DiskStorageHandler {
             cur_total_file_size: 0,
             current_file_length: 0,
             file: None,
+            resume_partial_files: false,
         })
     }
+
+    fn partial_file_path(&self, file_name: &str, total_size: usize) -> Option<(PathBuf, usize)> {
+        let path = self.output_path.join(sanitize_file_name(file_name)?);
+        let len = usize::try_from(std::fs::metadata(&path).ok()?.len()).ok()?;
+        if len == 0 || len >= total_size {
+            return None;
+        }
+        Some((path, len))
+    }
 }
 
 impl FileStorageHandler for DiskStorageHandler {
     fn open_file(&mut self, file_name: &str, total_size: usize) {
-        let fn_string = file_name.to_string();
-        self.cur_file_name = Some(fn_string.clone());
+        self.cur_file_name = Some(file_name.to_string());
         self.cur_total_file_size = total_size;
 
-        let f = if file_name.is_empty() {
-            "new_file".to_string()
-        } else {
-            fn_string
-        };
+        let f = sanitize_file_name(file_name)
+            .unwrap_or("new_file")
+            .to_string();
 
         let mut file_name: PathBuf = self.output_path.join(f.clone());
         let mut i = 1;
@@ -124,6 +178,37 @@ impl FileStorageHandler for DiskStorageHandler {
         self.current_file_length = 0;
     }
 
+    fn find_partial_file(&self, file_name: &str, total_size: usize) -> Option<(usize, u32)> {
+        if !self.resume_partial_files {
+            return None;
+        }
+        let (path, _) = self.partial_file_path(file_name, total_size)?;
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(err) => {
+                log::error!("Error reading {}: {err}", path.display());
+                return None;
+            }
+        };
+        Some((data.len(), get_crc32(&data)))
+    }
+
+    fn open_partial_file(&mut self, file_name: &str, total_size: usize) -> Option<usize> {
+        let (path, len) = self.partial_file_path(file_name, total_size)?;
+        let file = match OpenOptions::new().append(true).open(&path) {
+            Ok(file) => file,
+            Err(err) => {
+                log::error!("Error opening {}: {err}", path.display());
+                return None;
+            }
+        };
+        self.cur_file_name = Some(file_name.to_string());
+        self.cur_total_file_size = total_size;
+        self.current_file_length = len;
+        self.file = Some(file);
+        Some(len)
+    }
+
     fn current_file_name(&self) -> Option<String> {
         self.cur_file_name.clone()
     }
@@ -152,3 +237,86 @@ impl FileStorageHandler for DiskStorageHandler {
         self.cur_total_file_size
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{sanitize_file_name, DiskStorageHandler, FileStorageHandler};
+
+    fn create_handler(dir_name: &str) -> DiskStorageHandler {
+        let output_path = std::env::temp_dir().join(dir_name);
+        let _ = std::fs::remove_dir_all(&output_path);
+        std::fs::create_dir_all(&output_path).unwrap();
+        DiskStorageHandler {
+            cur_file_name: None,
+            cur_total_file_size: 0,
+            current_file_length: 0,
+            output_path,
+            file: None,
+            resume_partial_files: true,
+        }
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(Some("foo.bar"), sanitize_file_name("foo.bar"));
+        assert_eq!(Some("passwd"), sanitize_file_name("../../etc/passwd"));
+        assert_eq!(Some("foo.bar"), sanitize_file_name("/tmp/foo.bar"));
+        assert_eq!(Some("foo.bar"), sanitize_file_name("..\\windows\\foo.bar"));
+        assert_eq!(None, sanitize_file_name(""));
+        assert_eq!(None, sanitize_file_name("."));
+        assert_eq!(None, sanitize_file_name(".."));
+        assert_eq!(None, sanitize_file_name("foo/"));
+        assert_eq!(None, sanitize_file_name("foo/.."));
+    }
+
+    #[test]
+    fn test_open_file_stays_in_output_path() {
+        let mut handler = create_handler("icy_term_test_open_file_stays_in_output_path");
+        handler.open_file("../escaped.bar", 4);
+        handler.append(b"1234");
+        handler.close();
+        assert!(handler.output_path.join("escaped.bar").exists());
+        assert!(!handler.output_path.join("../escaped.bar").exists());
+
+        std::fs::write(handler.output_path.join("foo.bar"), b"12").unwrap();
+        assert_eq!(None, handler.find_partial_file("..", 4));
+        assert_eq!(
+            Some(2),
+            handler
+                .find_partial_file("../foo.bar", 4)
+                .map(|(len, _)| len)
+        );
+        assert_eq!(Some(2), handler.open_partial_file("sub/foo.bar", 4));
+        handler.close();
+        std::fs::remove_dir_all(&handler.output_path).unwrap();
+    }
+
+    #[test]
+    fn test_resume_partial_file() {
+        let mut handler = create_handler("icy_term_test_resume_partial_file");
+        std::fs::write(handler.output_path.join("foo.bar"), b"1234").unwrap();
+
+        assert_eq!(Some(4), handler.open_partial_file("foo.bar", 8));
+        assert_eq!(4, handler.current_file_length());
+        handler.append(b"5678");
+        handler.close();
+
+        let data = std::fs::read(handler.output_path.join("foo.bar")).unwrap();
+        assert_eq!(b"12345678".to_vec(), data);
+        std::fs::remove_dir_all(&handler.output_path).unwrap();
+    }
+
+    #[test]
+    fn test_no_resume_for_complete_files() {
+        let mut handler = create_handler("icy_term_test_no_resume_for_complete_files");
+        std::fs::write(handler.output_path.join("foo.bar"), b"1234").unwrap();
+
+        assert_eq!(None, handler.open_partial_file("foo.bar", 4));
+        assert_eq!(None, handler.open_partial_file("bar.foo", 4));
+        assert_eq!(None, handler.find_partial_file("foo.bar", 4));
+
+        handler.resume_partial_files = false;
+        assert_eq!(None, handler.find_partial_file("foo.bar", 8));
+        std::fs::remove_dir_all(&handler.output_path).unwrap();
+    }
+}
@@ -0,0 +1,59 @@
+#![allow(dead_code)]
+
+//
+// Constants taken from:
+//
+//   Kermit Protocol Manual, Sixth Edition (Frank da Cruz, 1986)
+//   Long packet, sliding window and attribute packet extensions
+//
+// See https://www.kermitproject.org/kproto.pdf
+
+pub const SOH: u8 = 0x01; // MARK - start of every packet
+pub const CR: u8 = b'\r'; // default end of line character
+
+/* Packet types */
+pub const PACKET_SEND_INIT: u8 = b'S';
+pub const PACKET_ACK: u8 = b'Y';
+pub const PACKET_NAK: u8 = b'N';
+pub const PACKET_FILE_HEADER: u8 = b'F';
+pub const PACKET_ATTRIBUTES: u8 = b'A';
+pub const PACKET_DATA: u8 = b'D';
+pub const PACKET_EOF: u8 = b'Z';
+pub const PACKET_BREAK: u8 = b'B';
+pub const PACKET_ERROR: u8 = b'E';
+pub const PACKET_TEXT_HEADER: u8 = b'X';
+
+/* CAPAS bit mask */
+pub const CAPAS_CONTINUATION: u8 = 0x01;
+pub const CAPAS_LONG_PACKETS: u8 = 0x02;
+pub const CAPAS_SLIDING_WINDOWS: u8 = 0x04;
+pub const CAPAS_ATTRIBUTES: u8 = 0x08;
+
+/* Attributes */
+pub const ATTR_FILE_LENGTH: u8 = b'1'; // exact length in bytes
+pub const ATTR_FILE_LENGTH_K: u8 = b'!'; // approximate length in k bytes
+
+/* Data field of ACKs/EOFs */
+pub const ACCEPT_ATTRIBUTES: u8 = b'Y';
+pub const REFUSE_ATTRIBUTES: u8 = b'N';
+pub const INTERRUPT_FILE: u8 = b'X';
+pub const INTERRUPT_BATCH: u8 = b'Z';
+pub const DISCARD_FILE: u8 = b'D';
+
+pub const DEFAULT_QCTL: u8 = b'#';
+pub const DEFAULT_REPT: u8 = b'~';
+/// Agree to 8th bit prefixing if the other side asks for it.
+pub const QBIN_AGREE: u8 = b'Y';
+pub const QBIN_REFUSE: u8 = b'N';
+
+/// Shorter packet lengths the other side announces are ignored.
+pub const MIN_PACKET_LENGTH: usize = 10;
+pub const DEFAULT_MAXL: usize = 80;
+pub const MAX_SHORT_PACKET_LENGTH: usize = 94;
+/// Length we assume if the other side agrees on long packets but doesn't send MAXLX1/2.
+pub const DEFAULT_LONG_PACKET_LENGTH: usize = 500;
+pub const MAX_LONG_PACKET_LENGTH: usize = 95 * 95 - 1;
+pub const DEFAULT_WINDOW_SIZE: usize = 8;
+pub const MAX_WINDOW_SIZE: usize = 31;
+pub const DEFAULT_TIMEOUT: u8 = 10;
+pub const MAX_RETRIES: usize = 10;
@@ -0,0 +1,48 @@
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub enum TransmissionError {
+    InvalidPacketLength(usize),
+    InvalidSequenceNumber(u8),
+    HeaderChecksumMismatch(u8),
+    ChecksumMismatch(u8),
+    TruncatedPacket,
+    TooManyRetries,
+    RemoteError(String),
+}
+
+impl std::fmt::Display for TransmissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransmissionError::InvalidPacketLength(len) => {
+                write!(f, "invalid packet length {len}")
+            }
+            TransmissionError::InvalidSequenceNumber(seq) => {
+                write!(f, "invalid packet sequence number {seq}")
+            }
+            TransmissionError::HeaderChecksumMismatch(seq) => {
+                write!(f, "header checksum mismatch in packet #{seq}")
+            }
+            TransmissionError::ChecksumMismatch(seq) => {
+                write!(f, "block check mismatch in packet #{seq}")
+            }
+            TransmissionError::TruncatedPacket => write!(f, "truncated packet"),
+            TransmissionError::TooManyRetries => write!(f, "too many retries"),
+            TransmissionError::RemoteError(msg) => write!(f, "remote error: {msg}"),
+        }
+    }
+}
+
+impl Error for TransmissionError {
+    fn description(&self) -> &str {
+        "use std::display"
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+
+    fn cause(&self) -> Option<&dyn Error> {
+        self.source()
+    }
+}
@@ -0,0 +1,103 @@
+//
+// Kermit protocol specification https://www.kermitproject.org/kproto.pdf
+// Supports long packets, sliding windows and attribute packets.
+
+mod constants;
+mod err;
+mod packet;
+
+mod sk;
+use sk::Sk;
+
+mod rk;
+use rk::Rk;
+
+mod tests;
+
+use std::sync::{Arc, Mutex};
+
+use self::packet::KermitParameters;
+
+use super::{FileDescriptor, FileStorageHandler, Protocol, TransferState};
+use crate::{ui::connection::Connection, TerminalResult};
+
+pub struct Kermit {
+    params: KermitParameters,
+    rk: Option<Rk>,
+    sk: Option<Sk>,
+}
+
+impl Kermit {
+    pub fn new() -> Self {
+        Self {
+            params: KermitParameters::default(),
+            rk: None,
+            sk: None,
+        }
+    }
+}
+
+impl Default for Kermit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Protocol for Kermit {
+    fn update(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        storage_handler: &mut dyn FileStorageHandler,
+    ) -> TerminalResult<bool> {
+        if let Some(rk) = &mut self.rk {
+            rk.update(com, transfer_state, storage_handler)?;
+            if !rk.is_active() {
+                transfer_state.lock().unwrap().is_finished = true;
+                return Ok(false);
+            }
+        } else if let Some(sk) = &mut self.sk {
+            sk.update(com, transfer_state)?;
+            if !sk.is_active() {
+                transfer_state.lock().unwrap().is_finished = true;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn initiate_send(
+        &mut self,
+        com: &mut Connection,
+        files: Vec<FileDescriptor>,
+        transfer_state: &mut TransferState,
+    ) -> TerminalResult<()> {
+        transfer_state.protocol_name = "Kermit".to_string();
+        let mut sk = Sk::new(self.params);
+        sk.send(com, files)?;
+        self.sk = Some(sk);
+        Ok(())
+    }
+
+    fn initiate_recv(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &mut TransferState,
+    ) -> TerminalResult<()> {
+        transfer_state.protocol_name = "Kermit".to_string();
+        let mut rk = Rk::new(self.params);
+        rk.recv(com)?;
+        self.rk = Some(rk);
+        Ok(())
+    }
+
+    fn cancel(&mut self, com: &mut Connection) -> TerminalResult<()> {
+        if let Some(rk) = &mut self.rk {
+            rk.cancel(com)?;
+        }
+        if let Some(sk) = &mut self.sk {
+            sk.cancel(com)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,550 @@
+#![allow(clippy::wildcard_imports)]
+use std::{cmp::min, time::Duration};
+
+use super::{constants::*, err::TransmissionError};
+
+pub fn tochar(b: u8) -> u8 {
+    b.wrapping_add(32)
+}
+
+pub fn unchar(b: u8) -> u8 {
+    b.wrapping_sub(32)
+}
+
+pub fn ctl(b: u8) -> u8 {
+    b ^ 64
+}
+
+/// Single character checksum (block check type 1).
+pub fn get_checksum(data: &[u8]) -> u8 {
+    let s = data.iter().fold(0u32, |s, b| s + u32::from(*b));
+    tochar(((s + ((s & 0xC0) >> 6)) & 63) as u8)
+}
+
+/// CRC-CCITT as used by kermit (reflected polynomial 0x8408, initial value 0).
+pub fn get_crc16_kermit(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for b in data {
+        crc ^= u16::from(*b);
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0x8408;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    crc
+}
+
+pub fn get_block_check(data: &[u8], check_type: u8) -> Vec<u8> {
+    match check_type {
+        2 => {
+            let s = data.iter().fold(0u32, |s, b| s + u32::from(*b)) & 0xFFF;
+            vec![tochar(((s >> 6) & 63) as u8), tochar((s & 63) as u8)]
+        }
+        3 => {
+            let crc = get_crc16_kermit(data);
+            vec![
+                tochar(((crc >> 12) & 0x0F) as u8),
+                tochar(((crc >> 6) & 0x3F) as u8),
+                tochar((crc & 0x3F) as u8),
+            ]
+        }
+        _ => vec![get_checksum(data)],
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packet {
+    pub seq: u8,
+    pub kind: u8,
+    pub data: Vec<u8>,
+}
+
+impl Packet {
+    pub fn new(seq: u8, kind: u8, data: Vec<u8>) -> Self {
+        Self { seq, kind, data }
+    }
+
+    /// Encodes the packet without padding and end of line character.
+    /// Send-Init packets and their ACKs always use block check type 1.
+    pub fn encode(&self, check_type: u8) -> Vec<u8> {
+        let check_len = check_type as usize;
+        let mut res = Vec::with_capacity(self.data.len() + 10);
+        res.push(SOH);
+        let len = 2 + self.data.len() + check_len;
+        if len <= MAX_SHORT_PACKET_LENGTH {
+            res.push(tochar(len as u8));
+            res.push(tochar(self.seq));
+            res.push(self.kind);
+        } else {
+            let ext_len = self.data.len() + check_len;
+            res.push(tochar(0));
+            res.push(tochar(self.seq));
+            res.push(self.kind);
+            res.push(tochar((ext_len / 95) as u8));
+            res.push(tochar((ext_len % 95) as u8));
+            let hcheck = get_checksum(&res[1..]);
+            res.push(hcheck);
+        }
+        res.extend_from_slice(&self.data);
+        let check = get_block_check(&res[1..], check_type);
+        res.extend(check);
+        res
+    }
+
+    /// Tries to read the next packet from the buffer. Bytes outside of packets are skipped.
+    /// Returns `Ok(None)` if the packet isn't complete yet. Corrupted packets are removed
+    /// from the buffer.
+    pub fn read(buf: &mut Vec<u8>, check_type: u8) -> Result<Option<Packet>, TransmissionError> {
+        let Some(start) = buf.iter().position(|b| *b == SOH) else {
+            buf.clear();
+            return Ok(None);
+        };
+        buf.drain(..start);
+        if buf.len() < 4 {
+            return Ok(None);
+        }
+        let len = unchar(buf[1]) as usize;
+        let seq = unchar(buf[2]);
+        // sequence numbers are modulo 64
+        if seq >= 64 {
+            buf.drain(..1);
+            return Err(TransmissionError::InvalidSequenceNumber(seq));
+        }
+        let packet_type = buf[3];
+        let check_type = if packet_type == PACKET_SEND_INIT {
+            1
+        } else {
+            check_type
+        };
+        let check_len = check_type as usize;
+
+        let (data_start, total) = if len == 0 {
+            if buf.len() < 7 {
+                return wait_or_resync(buf);
+            }
+            if get_checksum(&buf[1..6]) != buf[6] {
+                buf.drain(..1);
+                return Err(TransmissionError::HeaderChecksumMismatch(seq));
+            }
+            let ext_len = unchar(buf[4]) as usize * 95 + unchar(buf[5]) as usize;
+            if ext_len < check_len {
+                buf.drain(..1);
+                return Err(TransmissionError::InvalidPacketLength(ext_len));
+            }
+            (7, 7 + ext_len)
+        } else {
+            if len < 2 + check_len || len > MAX_SHORT_PACKET_LENGTH {
+                buf.drain(..1);
+                return Err(TransmissionError::InvalidPacketLength(len));
+            }
+            (4, 2 + len)
+        };
+
+        if buf.len() < total {
+            return wait_or_resync(buf);
+        }
+        let data_end = total - check_len;
+        let check = get_block_check(&buf[1..data_end], check_type);
+        if check[..] != buf[data_end..total] {
+            buf.drain(..total);
+            return Err(TransmissionError::ChecksumMismatch(seq));
+        }
+        let data = buf[data_start..data_end].to_vec();
+        buf.drain(..total);
+        Ok(Some(Packet::new(seq, packet_type, data)))
+    }
+}
+
+/// Packets never contain a raw SOH - if there is one the current packet got truncated.
+fn wait_or_resync(buf: &mut Vec<u8>) -> Result<Option<Packet>, TransmissionError> {
+    if let Some(next) = buf.iter().skip(1).position(|b| *b == SOH) {
+        buf.drain(..=next);
+        return Err(TransmissionError::TruncatedPacket);
+    }
+    Ok(None)
+}
+
+/// Prefixing rules used for the data field of packets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quoting {
+    pub qctl: u8,
+    pub qbin: Option<u8>,
+    pub rept: Option<u8>,
+}
+
+impl Default for Quoting {
+    fn default() -> Self {
+        Self {
+            qctl: DEFAULT_QCTL,
+            qbin: None,
+            rept: None,
+        }
+    }
+}
+
+impl Quoting {
+    /// Encodes as much of `data` as fits into `max_len` bytes.
+    /// Returns the encoded bytes and the number of source bytes consumed.
+    pub fn encode(self, data: &[u8], max_len: usize) -> (Vec<u8>, usize) {
+        let mut res = Vec::with_capacity(min(max_len, data.len() * 2));
+        let mut enc = Vec::with_capacity(5);
+        let mut i = 0;
+        while i < data.len() {
+            let b = data[i];
+            let mut count = 1;
+            enc.clear();
+            if let Some(rept) = self.rept {
+                while i + count < data.len() && data[i + count] == b && count < 94 {
+                    count += 1;
+                }
+                if count > 2 {
+                    enc.push(rept);
+                    enc.push(tochar(count as u8));
+                } else {
+                    count = 1;
+                }
+            }
+            self.encode_byte(&mut enc, b);
+            if res.len() + enc.len() > max_len {
+                break;
+            }
+            res.extend_from_slice(&enc);
+            i += count;
+        }
+        (res, i)
+    }
+
+    fn encode_byte(self, res: &mut Vec<u8>, b: u8) {
+        let mut ch = b;
+        if let Some(qbin) = self.qbin {
+            if ch & 0x80 != 0 {
+                res.push(qbin);
+                ch &= 0x7F;
+            }
+        }
+        let a7 = ch & 0x7F;
+        if a7 < 32 || a7 == 127 {
+            res.push(self.qctl);
+            ch = ctl(ch);
+        } else if a7 == self.qctl || Some(a7) == self.qbin || Some(a7) == self.rept {
+            res.push(self.qctl);
+        }
+        res.push(ch);
+    }
+
+    pub fn decode(self, data: &[u8]) -> Vec<u8> {
+        let mut res = Vec::with_capacity(data.len());
+        let mut it = data.iter().copied();
+        while let Some(mut ch) = it.next() {
+            let mut count = 1;
+            if Some(ch) == self.rept {
+                let Some(n) = it.next() else { break };
+                count = unchar(n) as usize;
+                let Some(next) = it.next() else { break };
+                ch = next;
+            }
+            let mut high_bit = 0;
+            if self.qbin.is_some() && Some(ch) == self.qbin {
+                high_bit = 0x80;
+                let Some(next) = it.next() else { break };
+                ch = next;
+            }
+            if ch == self.qctl {
+                let Some(next) = it.next() else { break };
+                ch = next;
+                if (0x3F..=0x5F).contains(&(ch & 0x7F)) {
+                    ch = ctl(ch);
+                }
+            }
+            res.resize(res.len() + count, ch | high_bit);
+        }
+        res
+    }
+}
+
+/// Parameters exchanged in the Send-Init packet and its ACK.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KermitParameters {
+    pub maxl: usize,
+    pub timeout: u8,
+    pub npad: u8,
+    pub padc: u8,
+    pub eol: u8,
+    pub qctl: u8,
+    pub qbin: u8,
+    pub check_type: u8,
+    pub rept: u8,
+    pub capabilities: u8,
+    pub window_size: usize,
+    pub max_long_packet_length: usize,
+}
+
+impl Default for KermitParameters {
+    fn default() -> Self {
+        Self {
+            maxl: MAX_SHORT_PACKET_LENGTH,
+            timeout: DEFAULT_TIMEOUT,
+            npad: 0,
+            padc: 0,
+            eol: CR,
+            qctl: DEFAULT_QCTL,
+            qbin: QBIN_AGREE,
+            check_type: 3,
+            rept: DEFAULT_REPT,
+            capabilities: CAPAS_LONG_PACKETS | CAPAS_SLIDING_WINDOWS | CAPAS_ATTRIBUTES,
+            window_size: DEFAULT_WINDOW_SIZE,
+            max_long_packet_length: 4096,
+        }
+    }
+}
+
+fn is_prefix_char(ch: u8) -> bool {
+    (33..=62).contains(&ch) || (96..=126).contains(&ch)
+}
+
+impl KermitParameters {
+    pub fn encode(&self) -> Vec<u8> {
+        vec![
+            tochar(min(self.maxl, MAX_SHORT_PACKET_LENGTH) as u8),
+            tochar(self.timeout),
+            tochar(self.npad),
+            ctl(self.padc),
+            tochar(self.eol),
+            self.qctl,
+            self.qbin,
+            b'0' + self.check_type,
+            self.rept,
+            tochar(self.capabilities),
+            tochar(min(self.window_size, MAX_WINDOW_SIZE) as u8),
+            tochar((self.max_long_packet_length / 95) as u8),
+            tochar((self.max_long_packet_length % 95) as u8),
+        ]
+    }
+
+    /// Parses a Send-Init data field, missing fields get their protocol defaults.
+    pub fn decode(data: &[u8]) -> Self {
+        let field = |i: usize| data.get(i).copied().filter(|ch| *ch != b' ');
+        let mut res = Self {
+            maxl: field(0).map_or(DEFAULT_MAXL, |ch| unchar(ch) as usize),
+            timeout: field(1).map_or(DEFAULT_TIMEOUT, unchar),
+            npad: field(2).map_or(0, unchar),
+            padc: field(3).map_or(0, ctl),
+            eol: field(4).map_or(CR, unchar),
+            qctl: field(5).map_or(DEFAULT_QCTL, |ch| ch),
+            qbin: field(6).unwrap_or(QBIN_REFUSE),
+            check_type: field(7).map_or(1, |ch| ch.wrapping_sub(b'0')),
+            rept: field(8).unwrap_or(b' '),
+            capabilities: 0,
+            window_size: 1,
+            max_long_packet_length: DEFAULT_LONG_PACKET_LENGTH,
+        };
+        if !(1..=3).contains(&res.check_type) {
+            res.check_type = 1;
+        }
+        if res.maxl < MIN_PACKET_LENGTH {
+            res.maxl = DEFAULT_MAXL;
+        }
+
+        // CAPAS may span multiple bytes, the low bit marks a continuation
+        let mut i = 9;
+        if let Some(capas) = field(i) {
+            res.capabilities = unchar(capas) & !CAPAS_CONTINUATION;
+            while data
+                .get(i)
+                .is_some_and(|ch| unchar(*ch) & CAPAS_CONTINUATION != 0)
+            {
+                i += 1;
+            }
+        }
+        i += 1;
+        if let Some(window) = field(i) {
+            res.window_size = (unchar(window) as usize).clamp(1, MAX_WINDOW_SIZE);
+        }
+        if let (Some(x1), Some(x2)) = (field(i + 1), field(i + 2)) {
+            let len = unchar(x1) as usize * 95 + unchar(x2) as usize;
+            if len >= MIN_PACKET_LENGTH {
+                res.max_long_packet_length = min(len, MAX_LONG_PACKET_LENGTH);
+            }
+        }
+        res
+    }
+
+    /// Combines our own parameters with the ones the other side sent.
+    pub fn negotiate(&self, other: &KermitParameters) -> Session {
+        let qbin = if is_prefix_char(self.qbin)
+            && (other.qbin == QBIN_AGREE || other.qbin == self.qbin)
+        {
+            Some(self.qbin)
+        } else if is_prefix_char(other.qbin) && (self.qbin == QBIN_AGREE || self.qbin == other.qbin)
+        {
+            Some(other.qbin)
+        } else {
+            None
+        };
+        let rept = if is_prefix_char(self.rept) && self.rept == other.rept {
+            Some(self.rept)
+        } else {
+            None
+        };
+        let capabilities = self.capabilities & other.capabilities;
+        let check_type = if self.check_type == other.check_type {
+            self.check_type
+        } else {
+            1
+        };
+        let window_size = if capabilities & CAPAS_SLIDING_WINDOWS != 0 {
+            min(self.window_size, other.window_size).max(1)
+        } else {
+            1
+        };
+        let long_packets = capabilities & CAPAS_LONG_PACKETS != 0;
+        let max_packet_length = if long_packets {
+            min(other.max_long_packet_length, MAX_LONG_PACKET_LENGTH)
+        } else {
+            min(other.maxl, MAX_SHORT_PACKET_LENGTH)
+        };
+        let timeout = if other.timeout == 0 {
+            DEFAULT_TIMEOUT
+        } else {
+            other.timeout
+        };
+
+        Session {
+            send_quoting: Quoting {
+                qctl: self.qctl,
+                qbin,
+                rept,
+            },
+            recv_quoting: Quoting {
+                qctl: other.qctl,
+                qbin,
+                rept,
+            },
+            check_type,
+            window_size,
+            long_packets,
+            attributes: capabilities & CAPAS_ATTRIBUTES != 0,
+            max_packet_length,
+            timeout: Duration::from_secs(u64::from(timeout)),
+            npad: other.npad,
+            padc: other.padc,
+            eol: other.eol,
+        }
+    }
+}
+
+/// The parameters both sides agreed on.
+#[derive(Debug, Clone, Copy)]
+pub struct Session {
+    pub send_quoting: Quoting,
+    pub recv_quoting: Quoting,
+    pub check_type: u8,
+    pub window_size: usize,
+    pub long_packets: bool,
+    pub attributes: bool,
+    /// Maximum packet length the other side accepts.
+    /// For long packets that's the extended length (data + block check).
+    pub max_packet_length: usize,
+    pub timeout: Duration,
+    pub npad: u8,
+    pub padc: u8,
+    pub eol: u8,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            send_quoting: Quoting::default(),
+            recv_quoting: Quoting::default(),
+            check_type: 1,
+            window_size: 1,
+            long_packets: false,
+            attributes: false,
+            max_packet_length: DEFAULT_MAXL,
+            timeout: Duration::from_secs(u64::from(DEFAULT_TIMEOUT)),
+            npad: 0,
+            padc: 0,
+            eol: CR,
+        }
+    }
+}
+
+impl Session {
+    /// Maximum size of the encoded data field of packets we send.
+    pub fn max_data_length(&self) -> usize {
+        if self.long_packets {
+            self.max_packet_length
+                .saturating_sub(self.check_type as usize)
+        } else {
+            self.max_packet_length
+                .saturating_sub(2 + self.check_type as usize)
+        }
+    }
+
+    /// Encodes a packet including padding and end of line character.
+    pub fn encode_packet(&self, packet: &Packet) -> Vec<u8> {
+        let check_type = if packet.kind == PACKET_SEND_INIT {
+            1
+        } else {
+            self.check_type
+        };
+        let mut res = vec![self.padc; self.npad as usize];
+        res.extend(packet.encode(check_type));
+        res.push(self.eol);
+        res
+    }
+
+    pub fn get_check_and_size(&self) -> String {
+        let check = match self.check_type {
+            2 => "Checksum/12",
+            3 => "Crc16",
+            _ => "Checksum/6",
+        };
+        format!("{check}/{}", self.max_data_length())
+    }
+}
+
+/// Builds the data field of an attribute packet.
+pub fn encode_attributes(file_size: usize) -> Vec<u8> {
+    let mut res = Vec::new();
+    let size = file_size.to_string();
+    res.push(ATTR_FILE_LENGTH);
+    res.push(tochar(size.len() as u8));
+    res.extend_from_slice(size.as_bytes());
+
+    let size_k = file_size.div_ceil(1024).to_string();
+    res.push(ATTR_FILE_LENGTH_K);
+    res.push(tochar(size_k.len() as u8));
+    res.extend_from_slice(size_k.as_bytes());
+    res
+}
+
+/// Returns the file size of an attribute packet data field - if it's given.
+pub fn decode_file_size(data: &[u8]) -> Option<usize> {
+    let mut size = None;
+    let mut i = 0;
+    while i + 1 < data.len() {
+        let attr = data[i];
+        let len = unchar(data[i + 1]) as usize;
+        let end = min(i + 2 + len, data.len());
+        let value = std::str::from_utf8(&data[i + 2..end]).ok();
+        match attr {
+            ATTR_FILE_LENGTH => {
+                if let Some(v) = value.and_then(|v| v.parse::<usize>().ok()) {
+                    size = Some(v);
+                }
+            }
+            ATTR_FILE_LENGTH_K if size.is_none() => {
+                size = value
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .and_then(|v| v.checked_mul(1024));
+            }
+            _ => {}
+        }
+        i = end;
+    }
+    size
+}
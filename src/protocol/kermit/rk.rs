@@ -0,0 +1,323 @@
+#![allow(clippy::wildcard_imports)]
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use web_time::Instant;
+
+use super::{
+    constants::*,
+    err::TransmissionError,
+    packet::{decode_file_size, KermitParameters, Packet, Session},
+};
+use crate::{
+    protocol::{FileStorageHandler, TransferState},
+    ui::connection::Connection,
+    TerminalResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecvState {
+    Idle,
+    AwaitSendInit,
+    AwaitFileHeader,
+    AwaitFileData,
+}
+
+pub struct Rk {
+    state: RecvState,
+    params: KermitParameters,
+    session: Session,
+
+    /// sequence number of the next packet expected
+    seq: u8,
+    buf: Vec<u8>,
+    out_of_order: HashMap<u8, Packet>,
+
+    file_name: Option<String>,
+    file_size: usize,
+    file_open: bool,
+
+    pub errors: usize,
+    retries: usize,
+    last_activity: Instant,
+}
+
+impl Rk {
+    pub fn new(params: KermitParameters) -> Self {
+        Self {
+            state: RecvState::Idle,
+            params,
+            session: Session::default(),
+            seq: 0,
+            buf: Vec::new(),
+            out_of_order: HashMap::new(),
+            file_name: None,
+            file_size: 0,
+            file_open: false,
+            errors: 0,
+            retries: 0,
+            last_activity: Instant::now(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, RecvState::Idle)
+    }
+
+    pub fn recv(&mut self, com: &mut Connection) -> TerminalResult<()> {
+        self.state = RecvState::AwaitSendInit;
+        self.seq = 0;
+        self.session = Session::default();
+        // A NAK for packet 0 makes the sender (re)send its Send-Init packet.
+        self.send_packet(com, 0, PACKET_NAK, Vec::new())
+    }
+
+    pub fn cancel(&mut self, com: &mut Connection) -> TerminalResult<()> {
+        self.state = RecvState::Idle;
+        self.send_packet(com, self.seq, PACKET_ERROR, b"Transfer canceled".to_vec())
+    }
+
+    pub fn update(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        storage_handler: &mut dyn FileStorageHandler,
+    ) -> TerminalResult<()> {
+        if let RecvState::Idle = self.state {
+            return Ok(());
+        }
+        if let Ok(mut transfer_state) = transfer_state.lock() {
+            transfer_state.update_time();
+            transfer_state.current_state = match self.state {
+                RecvState::AwaitSendInit => "Negotiating…",
+                RecvState::AwaitFileHeader => "Await file header…",
+                RecvState::AwaitFileData => "Receive data…",
+                RecvState::Idle => "",
+            };
+            let transfer_info = &mut transfer_state.recieve_state;
+            if let Some(file) = storage_handler.current_file_name() {
+                transfer_info.file_name = file;
+                transfer_info.file_size = storage_handler.get_current_file_total_size();
+                transfer_info.bytes_transfered = storage_handler.current_file_length();
+            }
+            transfer_info.errors = self.errors;
+            transfer_info.check_size = self.session.get_check_and_size();
+            transfer_info.update_bps();
+        }
+
+        if com.is_data_available()? {
+            self.buf.extend(com.read_buffer());
+        }
+        loop {
+            match Packet::read(&mut self.buf, self.session.check_type) {
+                Ok(Some(packet)) => {
+                    self.last_activity = Instant::now();
+                    self.retries = 0;
+                    self.handle_packet(com, transfer_state, storage_handler, packet)?;
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    self.errors += 1;
+                    log::error!("{err}");
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        transfer_state.recieve_state.log_warning(format!("{err}"));
+                    }
+                    self.send_packet(com, self.seq, PACKET_NAK, Vec::new())?;
+                }
+            }
+            if let RecvState::Idle = self.state {
+                return Ok(());
+            }
+        }
+
+        if self.last_activity.elapsed() > self.session.timeout {
+            self.retries += 1;
+            self.errors += 1;
+            if self.retries > MAX_RETRIES {
+                if let Ok(mut transfer_state) = transfer_state.lock() {
+                    transfer_state
+                        .recieve_state
+                        .log_error(format!("{}", TransmissionError::TooManyRetries));
+                }
+                return self.cancel(com);
+            }
+            self.send_packet(com, self.seq, PACKET_NAK, Vec::new())?;
+            self.last_activity = Instant::now();
+        }
+        Ok(())
+    }
+
+    fn send_packet(
+        &mut self,
+        com: &mut Connection,
+        seq: u8,
+        packet_type: u8,
+        data: Vec<u8>,
+    ) -> TerminalResult<()> {
+        let packet = Packet::new(seq, packet_type, data);
+        com.send(self.session.encode_packet(&packet))
+    }
+
+    fn handle_packet(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        storage_handler: &mut dyn FileStorageHandler,
+        packet: Packet,
+    ) -> TerminalResult<()> {
+        match packet.kind {
+            PACKET_SEND_INIT => {
+                if !matches!(
+                    self.state,
+                    RecvState::AwaitSendInit | RecvState::AwaitFileHeader
+                ) {
+                    log::warn!("unexpected kermit send init packet");
+                    return Ok(());
+                }
+                let other = KermitParameters::decode(&packet.data);
+                // The ACK to the Send-Init packet needs to be sent with the defaults
+                self.session = Session::default();
+                self.send_packet(com, packet.seq, PACKET_ACK, self.params.encode())?;
+                self.session = self.params.negotiate(&other);
+                self.seq = (packet.seq + 1) % 64;
+                self.state = RecvState::AwaitFileHeader;
+                if let Ok(mut transfer_state) = transfer_state.lock() {
+                    transfer_state.recieve_state.log_info(format!(
+                        "Negotiated {} window size {}",
+                        self.session.get_check_and_size(),
+                        self.session.window_size
+                    ));
+                }
+                return Ok(());
+            }
+            PACKET_ERROR => {
+                let msg = String::from_utf8_lossy(&self.session.recv_quoting.decode(&packet.data))
+                    .to_string();
+                log::error!("{}", TransmissionError::RemoteError(msg.clone()));
+                if let Ok(mut transfer_state) = transfer_state.lock() {
+                    transfer_state
+                        .recieve_state
+                        .log_error(format!("{}", TransmissionError::RemoteError(msg)));
+                }
+                if self.file_open {
+                    storage_handler.close();
+                    self.file_open = false;
+                }
+                self.state = RecvState::Idle;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if let RecvState::AwaitSendInit = self.state {
+            // still waiting for the sender to start over
+            return Ok(());
+        }
+
+        let diff = ((packet.seq + 64 - self.seq) % 64) as usize;
+        if diff == 0 {
+            self.process_packet(com, transfer_state, storage_handler, &packet)?;
+            while let Some(next) = self.out_of_order.remove(&self.seq) {
+                self.process_packet(com, transfer_state, storage_handler, &next)?;
+            }
+        } else if diff < self.session.window_size {
+            // a packet got lost - keep this one and ask for the missing ones
+            if packet.kind == PACKET_DATA {
+                self.send_packet(com, packet.seq, PACKET_ACK, Vec::new())?;
+                for i in 0..diff {
+                    let seq = ((self.seq as usize + i) % 64) as u8;
+                    if !self.out_of_order.contains_key(&seq) {
+                        self.send_packet(com, seq, PACKET_NAK, Vec::new())?;
+                    }
+                }
+                self.out_of_order.insert(packet.seq, packet);
+            }
+        } else {
+            // already processed, the ACK got lost.
+            self.send_packet(com, packet.seq, PACKET_ACK, Vec::new())?;
+        }
+        Ok(())
+    }
+
+    fn process_packet(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        storage_handler: &mut dyn FileStorageHandler,
+        packet: &Packet,
+    ) -> TerminalResult<()> {
+        self.seq = (packet.seq + 1) % 64;
+        match packet.kind {
+            PACKET_FILE_HEADER => {
+                let name = self.session.recv_quoting.decode(&packet.data);
+                let file_name = String::from_utf8_lossy(&name).to_string();
+                self.file_name = Some(file_name);
+                self.file_size = 0;
+                self.file_open = false;
+                self.state = RecvState::AwaitFileData;
+            }
+            PACKET_ATTRIBUTES => {
+                if let Some(size) = decode_file_size(&packet.data) {
+                    self.file_size = size;
+                }
+                self.send_packet(com, packet.seq, PACKET_ACK, vec![ACCEPT_ATTRIBUTES])?;
+                return Ok(());
+            }
+            PACKET_DATA => {
+                self.open_file(transfer_state, storage_handler);
+                let data = self.session.recv_quoting.decode(&packet.data);
+                storage_handler.append(&data);
+            }
+            PACKET_EOF => {
+                self.open_file(transfer_state, storage_handler);
+                storage_handler.close();
+                self.file_open = false;
+                if let Ok(mut transfer_state) = transfer_state.lock() {
+                    let transfer_info = &mut transfer_state.recieve_state;
+                    if let Some(file_name) = self.file_name.take() {
+                        if packet.data.first() == Some(&DISCARD_FILE) {
+                            transfer_info
+                                .log_warning(format!("File {file_name} discarded by sender"));
+                        } else {
+                            transfer_info.files_finished.push(file_name);
+                        }
+                    }
+                }
+                self.state = RecvState::AwaitFileHeader;
+            }
+            PACKET_BREAK => {
+                self.send_packet(com, packet.seq, PACKET_ACK, Vec::new())?;
+                self.state = RecvState::Idle;
+                return Ok(());
+            }
+            packet_type => {
+                log::warn!("unexpected kermit packet type: {}", char::from(packet_type));
+            }
+        }
+        self.send_packet(com, packet.seq, PACKET_ACK, Vec::new())
+    }
+
+    fn open_file(
+        &mut self,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        storage_handler: &mut dyn FileStorageHandler,
+    ) {
+        if self.file_open {
+            return;
+        }
+        let file_name = self
+            .file_name
+            .clone()
+            .unwrap_or_else(|| "kermit.bin".to_string());
+        if let Ok(mut transfer_state) = transfer_state.lock() {
+            transfer_state.recieve_state.log_info(format!(
+                "Start file transfer: {file_name} ({} bytes)",
+                self.file_size
+            ));
+        }
+        storage_handler.open_file(&file_name, self.file_size);
+        self.file_open = true;
+    }
+}
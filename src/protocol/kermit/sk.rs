@@ -0,0 +1,365 @@
+#![allow(clippy::wildcard_imports)]
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use web_time::Instant;
+
+use super::{
+    constants::*,
+    err::TransmissionError,
+    packet::{encode_attributes, KermitParameters, Packet, Session},
+};
+use crate::{
+    protocol::{FileDescriptor, TransferState},
+    ui::connection::Connection,
+    TerminalResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SendState {
+    Idle,
+    SendInit,
+    FileHeader,
+    Attributes,
+    Data,
+    Eof,
+    Break,
+}
+
+/// A packet that is sent but not yet acknowledged.
+struct PendingPacket {
+    seq: u8,
+    packet_type: u8,
+    encoded: Vec<u8>,
+    acked: bool,
+}
+
+pub struct Sk {
+    state: SendState,
+    params: KermitParameters,
+    session: Session,
+
+    pub files: Vec<FileDescriptor>,
+    cur_file: usize,
+    data: Vec<u8>,
+    cur_file_pos: usize,
+    skip_file: bool,
+    skip_batch: bool,
+
+    seq: u8,
+    window: VecDeque<PendingPacket>,
+    buf: Vec<u8>,
+
+    pub errors: usize,
+    retries: usize,
+    last_activity: Instant,
+}
+
+impl Sk {
+    pub fn new(params: KermitParameters) -> Self {
+        Self {
+            state: SendState::Idle,
+            params,
+            session: Session::default(),
+            files: Vec::new(),
+            cur_file: 0,
+            data: Vec::new(),
+            cur_file_pos: 0,
+            skip_file: false,
+            skip_batch: false,
+            seq: 0,
+            window: VecDeque::new(),
+            buf: Vec::new(),
+            errors: 0,
+            retries: 0,
+            last_activity: Instant::now(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, SendState::Idle)
+    }
+
+    pub fn send(&mut self, com: &mut Connection, files: Vec<FileDescriptor>) -> TerminalResult<()> {
+        self.files = files;
+        self.cur_file = 0;
+        self.seq = 0;
+        self.session = Session::default();
+        self.state = SendState::SendInit;
+        self.send_packet(com, PACKET_SEND_INIT, self.params.encode())
+    }
+
+    pub fn cancel(&mut self, com: &mut Connection) -> TerminalResult<()> {
+        self.state = SendState::Idle;
+        self.window.clear();
+        self.send_packet(com, PACKET_ERROR, b"Transfer canceled".to_vec())
+    }
+
+    pub fn update(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+    ) -> TerminalResult<()> {
+        if let SendState::Idle = self.state {
+            return Ok(());
+        }
+        if let Ok(mut transfer_state) = transfer_state.lock() {
+            transfer_state.update_time();
+            transfer_state.current_state = match self.state {
+                SendState::SendInit => "Negotiating…",
+                SendState::FileHeader | SendState::Attributes => "Send file header…",
+                SendState::Data => "Send data…",
+                SendState::Eof | SendState::Break => "Finish file…",
+                SendState::Idle => "",
+            };
+            let transfer_info = &mut transfer_state.send_state;
+            if self.cur_file < self.files.len() {
+                let f = &self.files[self.cur_file];
+                transfer_info.file_name.clone_from(&f.file_name);
+                transfer_info.file_size = f.size;
+            }
+            transfer_info.bytes_transfered = self.cur_file_pos;
+            transfer_info.errors = self.errors;
+            transfer_info.check_size = self.session.get_check_and_size();
+            transfer_info.update_bps();
+        }
+
+        if com.is_data_available()? {
+            self.buf.extend(com.read_buffer());
+        }
+        loop {
+            match Packet::read(&mut self.buf, self.session.check_type) {
+                Ok(Some(packet)) => {
+                    self.last_activity = Instant::now();
+                    self.handle_packet(com, transfer_state, &packet)?;
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    self.errors += 1;
+                    log::error!("{err}");
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        transfer_state.send_state.log_warning(format!("{err}"));
+                    }
+                }
+            }
+            if let SendState::Idle = self.state {
+                return Ok(());
+            }
+        }
+
+        if let SendState::Data = self.state {
+            self.fill_window(com)?;
+        }
+
+        if let Some(pending) = self.window.iter().find(|p| !p.acked) {
+            if self.last_activity.elapsed() > self.session.timeout {
+                self.retries += 1;
+                self.errors += 1;
+                if self.retries > MAX_RETRIES {
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        transfer_state
+                            .send_state
+                            .log_error(format!("{}", TransmissionError::TooManyRetries));
+                    }
+                    return self.cancel(com);
+                }
+                com.send(pending.encoded.clone())?;
+                self.last_activity = Instant::now();
+            }
+        }
+        Ok(())
+    }
+
+    fn send_packet(
+        &mut self,
+        com: &mut Connection,
+        packet_type: u8,
+        data: Vec<u8>,
+    ) -> TerminalResult<()> {
+        let packet = Packet::new(self.seq, packet_type, data);
+        let encoded = self.session.encode_packet(&packet);
+        com.send(encoded.clone())?;
+        self.window.push_back(PendingPacket {
+            seq: self.seq,
+            packet_type,
+            encoded,
+            acked: false,
+        });
+        self.seq = (self.seq + 1) % 64;
+        self.last_activity = Instant::now();
+        Ok(())
+    }
+
+    fn fill_window(&mut self, com: &mut Connection) -> TerminalResult<()> {
+        let max_len = self.session.max_data_length();
+        while self.window.len() < self.session.window_size
+            && self.cur_file_pos < self.data.len()
+            && !self.skip_file
+        {
+            let (encoded, consumed) = self
+                .session
+                .send_quoting
+                .encode(&self.data[self.cur_file_pos..], max_len);
+            self.cur_file_pos += consumed;
+            self.send_packet(com, PACKET_DATA, encoded)?;
+        }
+        if self.window.is_empty() && (self.cur_file_pos >= self.data.len() || self.skip_file) {
+            let data = if self.skip_file {
+                vec![DISCARD_FILE]
+            } else {
+                Vec::new()
+            };
+            self.state = SendState::Eof;
+            self.send_packet(com, PACKET_EOF, data)?;
+        }
+        Ok(())
+    }
+
+    fn handle_packet(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        packet: &Packet,
+    ) -> TerminalResult<()> {
+        match packet.kind {
+            PACKET_ACK => self.acknowledge(com, transfer_state, packet)?,
+            PACKET_NAK => {
+                self.errors += 1;
+                if let Some(pending) = self.window.iter().find(|p| p.seq == packet.seq && !p.acked)
+                {
+                    com.send(pending.encoded.clone())?;
+                } else if packet.seq == self.seq {
+                    // NAK for the next packet implies an ACK for the previous one
+                    let seq = (packet.seq + 63) % 64;
+                    self.acknowledge(
+                        com,
+                        transfer_state,
+                        &Packet::new(seq, PACKET_ACK, Vec::new()),
+                    )?;
+                }
+            }
+            PACKET_ERROR => {
+                let msg = String::from_utf8_lossy(&self.session.recv_quoting.decode(&packet.data))
+                    .to_string();
+                log::error!("{}", TransmissionError::RemoteError(msg.clone()));
+                if let Ok(mut transfer_state) = transfer_state.lock() {
+                    transfer_state
+                        .send_state
+                        .log_error(format!("{}", TransmissionError::RemoteError(msg)));
+                }
+                self.state = SendState::Idle;
+            }
+            packet_type => {
+                log::warn!("unexpected kermit packet type: {}", char::from(packet_type));
+            }
+        }
+        Ok(())
+    }
+
+    fn acknowledge(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        packet: &Packet,
+    ) -> TerminalResult<()> {
+        let Some(pending) = self
+            .window
+            .iter_mut()
+            .find(|p| p.seq == packet.seq && !p.acked)
+        else {
+            // duplicate ACK
+            return Ok(());
+        };
+        pending.acked = true;
+        let packet_type = pending.packet_type;
+        self.retries = 0;
+        while self.window.front().is_some_and(|p| p.acked) {
+            self.window.pop_front();
+        }
+
+        match packet_type {
+            PACKET_SEND_INIT => {
+                let other = KermitParameters::decode(&packet.data);
+                self.session = self.params.negotiate(&other);
+                if let Ok(mut transfer_state) = transfer_state.lock() {
+                    transfer_state.send_state.log_info(format!(
+                        "Negotiated {} window size {}",
+                        self.session.get_check_and_size(),
+                        self.session.window_size
+                    ));
+                }
+                self.send_file_header(com, transfer_state)?;
+            }
+            PACKET_FILE_HEADER => {
+                if self.session.attributes {
+                    self.state = SendState::Attributes;
+                    let size = self.files[self.cur_file].size;
+                    self.send_packet(com, PACKET_ATTRIBUTES, encode_attributes(size))?;
+                } else {
+                    self.state = SendState::Data;
+                }
+            }
+            PACKET_ATTRIBUTES => {
+                if packet.data.first() == Some(&REFUSE_ATTRIBUTES) {
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        transfer_state.send_state.log_warning(format!(
+                            "File {} refused by receiver",
+                            self.files[self.cur_file].file_name
+                        ));
+                    }
+                    self.skip_file = true;
+                }
+                self.state = SendState::Data;
+            }
+            PACKET_DATA => match packet.data.first() {
+                Some(&INTERRUPT_FILE) => self.skip_file = true,
+                Some(&INTERRUPT_BATCH) => {
+                    self.skip_file = true;
+                    self.skip_batch = true;
+                }
+                _ => {}
+            },
+            PACKET_EOF => {
+                self.cur_file += 1;
+                if self.skip_batch {
+                    self.cur_file = self.files.len();
+                }
+                self.send_file_header(com, transfer_state)?;
+            }
+            PACKET_BREAK => {
+                self.state = SendState::Idle;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn send_file_header(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+    ) -> TerminalResult<()> {
+        self.cur_file_pos = 0;
+        self.skip_file = false;
+        if self.cur_file >= self.files.len() {
+            self.data = Vec::new();
+            self.state = SendState::Break;
+            return self.send_packet(com, PACKET_BREAK, Vec::new());
+        }
+        let fd = &self.files[self.cur_file];
+        self.data = fd.get_data();
+        if let Ok(mut transfer_state) = transfer_state.lock() {
+            transfer_state
+                .send_state
+                .log_info(format!("Send file {} ({} bytes)", fd.file_name, fd.size));
+        }
+        let (name, _) = self
+            .session
+            .send_quoting
+            .encode(fd.file_name.as_bytes(), self.session.max_data_length());
+        self.state = SendState::FileHeader;
+        self.send_packet(com, PACKET_FILE_HEADER, name)
+    }
+}
@@ -0,0 +1,277 @@
+#[cfg(test)]
+mod kermit_test {
+    use std::sync::{mpsc, Arc, Mutex};
+
+    use crate::{
+        protocol::{
+            kermit::{
+                constants::*,
+                err::TransmissionError,
+                packet::{
+                    decode_file_size, encode_attributes, get_crc16_kermit, tochar,
+                    KermitParameters, Packet, Quoting,
+                },
+                Kermit,
+            },
+            FileDescriptor, Protocol, TestStorageHandler, TransferState,
+        },
+        ui::connection::{Connection, SendData},
+    };
+
+    /// Connections for sender & receiver. Everything the sender writes goes through
+    /// `filter` before the receiver sees it.
+    fn run_protocols(
+        files: Vec<FileDescriptor>,
+        mut send: Kermit,
+        mut recv: Kermit,
+        mut filter: impl FnMut(Vec<u8>) -> Vec<u8>,
+    ) -> TestStorageHandler {
+        let (sender_tx, sender_out) = mpsc::channel();
+        let (recv_in_tx, recv_in) = mpsc::channel();
+        let (recv_tx, sender_in) = mpsc::channel();
+        let mut sender_com = Connection::new(sender_in, sender_tx);
+        let mut recv_com = Connection::new(recv_in, recv_tx);
+
+        let send_state = Arc::new(Mutex::new(TransferState::default()));
+        let recv_state = Arc::new(Mutex::new(TransferState::default()));
+        let mut send_storage = TestStorageHandler::new();
+        let mut recv_storage = TestStorageHandler::new();
+
+        send.initiate_send(&mut sender_com, files, &mut send_state.lock().unwrap())
+            .expect("error.");
+        recv.initiate_recv(&mut recv_com, &mut recv_state.lock().unwrap())
+            .expect("error.");
+
+        let mut send_running = true;
+        let mut recv_running = true;
+        for _ in 0..100_000 {
+            if send_running {
+                send_running = send
+                    .update(&mut sender_com, &send_state, &mut send_storage)
+                    .expect("error.");
+            }
+            while let Ok(SendData::Data(data)) = sender_out.try_recv() {
+                recv_in_tx.send(SendData::Data(filter(data))).unwrap();
+            }
+            if recv_running {
+                recv_running = recv
+                    .update(&mut recv_com, &recv_state, &mut recv_storage)
+                    .expect("error.");
+            }
+            if !send_running && !recv_running {
+                return recv_storage;
+            }
+        }
+        panic!("transfer didn't finish.");
+    }
+
+    fn test_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 13) as u8).collect()
+    }
+
+    #[test]
+    fn test_crc16_kermit() {
+        assert_eq!(0x2189, get_crc16_kermit(b"123456789"));
+    }
+
+    #[test]
+    fn test_packet_checksum() {
+        // "# Y" sums up to 156 -> (156 + 2) & 63 = 30
+        let packet = Packet::new(0, PACKET_ACK, Vec::new());
+        assert_eq!(vec![SOH, b'#', b' ', b'Y', b'>'], packet.encode(1));
+    }
+
+    #[test]
+    fn test_packet_roundtrip() {
+        for check_type in 1..=3 {
+            let packet = Packet::new(42, PACKET_DATA, b"Hello World".to_vec());
+            let mut buf = packet.encode(check_type);
+            buf.push(CR);
+            let read = Packet::read(&mut buf, check_type).unwrap().unwrap();
+            assert_eq!(packet, read);
+            assert_eq!(vec![CR], buf);
+        }
+    }
+
+    #[test]
+    fn test_long_packet_roundtrip() {
+        let data = vec![b'x'; 2000];
+        let packet = Packet::new(1, PACKET_DATA, data);
+        let mut buf = packet.encode(3);
+        assert_eq!(b' ', buf[1]);
+        let read = Packet::read(&mut buf, 3).unwrap().unwrap();
+        assert_eq!(packet, read);
+    }
+
+    #[test]
+    fn test_incomplete_packet() {
+        let packet = Packet::new(5, PACKET_DATA, b"abc".to_vec());
+        let encoded = packet.encode(3);
+        let mut buf = encoded[..5].to_vec();
+        assert!(Packet::read(&mut buf, 3).unwrap().is_none());
+        buf.extend_from_slice(&encoded[5..]);
+        assert_eq!(packet, Packet::read(&mut buf, 3).unwrap().unwrap());
+    }
+
+    #[test]
+    fn test_corrupt_packet() {
+        let packet = Packet::new(5, PACKET_DATA, b"abc".to_vec());
+        let mut buf = packet.encode(3);
+        buf[5] = b'x';
+        assert!(Packet::read(&mut buf, 3).is_err());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_sequence_number() {
+        for (seq_char, seq) in [(tochar(64), 64), (0xFF, 223), (b'\x10', 240)] {
+            let mut buf = Packet::new(5, PACKET_DATA, b"abc".to_vec()).encode(1);
+            buf[2] = seq_char;
+            assert!(matches!(
+                Packet::read(&mut buf, 1),
+                Err(TransmissionError::InvalidSequenceNumber(s)) if s == seq
+            ));
+        }
+    }
+
+    #[test]
+    fn test_quoting_roundtrip() {
+        let data: Vec<u8> = (0..=255).chain([0; 10]).chain([b'#'; 5]).collect();
+        for quoting in [
+            Quoting::default(),
+            Quoting {
+                qctl: b'#',
+                qbin: Some(b'&'),
+                rept: Some(b'~'),
+            },
+        ] {
+            let (encoded, consumed) = quoting.encode(&data, usize::MAX);
+            assert_eq!(data.len(), consumed);
+            assert!(encoded
+                .iter()
+                .all(|b| (b & 0x7F) >= 32 && (b & 0x7F) != 127));
+            assert_eq!(data, quoting.decode(&encoded));
+        }
+    }
+
+    #[test]
+    fn test_repeat_prefix() {
+        let quoting = Quoting {
+            qctl: b'#',
+            qbin: None,
+            rept: Some(b'~'),
+        };
+        let (encoded, _) = quoting.encode(b"aaaaab", usize::MAX);
+        assert_eq!(b"~%ab".to_vec(), encoded);
+    }
+
+    #[test]
+    fn test_encode_respects_max_length() {
+        let (encoded, consumed) = Quoting::default().encode(&[0, 1, 2, 3], 5);
+        assert_eq!(4, encoded.len());
+        assert_eq!(2, consumed);
+    }
+
+    #[test]
+    fn test_send_init_roundtrip() {
+        let params = KermitParameters::default();
+        assert_eq!(params, KermitParameters::decode(&params.encode()));
+    }
+
+    #[test]
+    fn test_negotiate_basic_kermit() {
+        // minimal kermit implementations only send the first fields
+        let other = KermitParameters::decode(b"~* @-#Y1");
+        let session = KermitParameters::default().negotiate(&other);
+        assert_eq!(1, session.check_type);
+        assert_eq!(1, session.window_size);
+        assert!(!session.long_packets);
+        assert!(!session.attributes);
+        assert_eq!(None, session.send_quoting.rept);
+        assert_eq!(94, session.max_packet_length);
+    }
+
+    #[test]
+    fn test_tiny_long_packet_length() {
+        // MAXLX1/2 announcing a 2 byte packet
+        let mut data = KermitParameters::default().encode();
+        let len = data.len();
+        data[len - 2] = b' ';
+        data[len - 1] = b'"';
+        let other = KermitParameters::decode(&data);
+        assert_eq!(DEFAULT_LONG_PACKET_LENGTH, other.max_long_packet_length);
+        let session = KermitParameters::default().negotiate(&other);
+        assert!(session.long_packets);
+        assert_eq!(
+            DEFAULT_LONG_PACKET_LENGTH - session.check_type as usize,
+            session.max_data_length()
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(Some(123_456), decode_file_size(&encode_attributes(123_456)));
+        assert_eq!(Some(2048), decode_file_size(b"!!2"));
+        // doesn't fit after the multiplication
+        let size = (usize::MAX / 1000).to_string();
+        let mut data = vec![b'!', tochar(size.len() as u8)];
+        data.extend_from_slice(size.as_bytes());
+        assert_eq!(None, decode_file_size(&data));
+    }
+
+    #[test]
+    fn test_kermit_simple() {
+        let data = vec![1u8, 2, 5, 10];
+        let files = vec![FileDescriptor::create_test(
+            "foo.bar".to_string(),
+            data.clone(),
+        )];
+        let storage_handler = run_protocols(files, Kermit::new(), Kermit::new(), |d| d);
+
+        assert_eq!(1, storage_handler.file.len());
+        assert_eq!(&data, storage_handler.file.get("foo.bar").unwrap());
+    }
+
+    #[test]
+    fn test_kermit_empty_file() {
+        let files = vec![FileDescriptor::create_test("empty".to_string(), Vec::new())];
+        let storage_handler = run_protocols(files, Kermit::new(), Kermit::new(), |d| d);
+        assert_eq!(
+            &Vec::<u8>::new(),
+            storage_handler.file.get("empty").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_kermit_batch() {
+        let data1 = test_data(100_000);
+        let data2 = test_data(1234);
+        let files = vec![
+            FileDescriptor::create_test("foo.bar".to_string(), data1.clone()),
+            FileDescriptor::create_test("bar.foo".to_string(), data2.clone()),
+        ];
+        let storage_handler = run_protocols(files, Kermit::new(), Kermit::new(), |d| d);
+
+        assert_eq!(2, storage_handler.file.len());
+        assert_eq!(&data1, storage_handler.file.get("foo.bar").unwrap());
+        assert_eq!(&data2, storage_handler.file.get("bar.foo").unwrap());
+    }
+
+    #[test]
+    fn test_kermit_recovers_from_corrupted_packets() {
+        let data = test_data(50_000);
+        let files = vec![FileDescriptor::create_test(
+            "foo.bar".to_string(),
+            data.clone(),
+        )];
+        let mut count = 0;
+        let storage_handler = run_protocols(files, Kermit::new(), Kermit::new(), |mut d| {
+            count += 1;
+            if count % 7 == 0 && d.len() > 20 {
+                d[10] ^= 0x01;
+            }
+            d
+        });
+        assert_eq!(&data, storage_handler.file.get("foo.bar").unwrap());
+    }
+}
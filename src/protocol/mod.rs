@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 use crate::ui::connection::Connection;
-use crate::TerminalResult;
+use crate::{TerminalResult, TextTransferSettings};
 use std::fs;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -16,6 +16,12 @@ pub use xymodem::*;
 pub mod zmodem;
 pub use zmodem::*;
 
+pub mod kermit;
+pub use kermit::*;
+
+pub mod text;
+pub use text::*;
+
 #[derive(Debug, Clone)]
 pub struct FileDescriptor {
     pub path_name: String,
@@ -63,6 +69,17 @@ impl FileDescriptor {
         }
     }
 
+    pub fn from_data(file_name: String, data: Vec<u8>) -> Self {
+        Self {
+            path_name: file_name.clone(),
+            file_name,
+            path: PathBuf::new(),
+            size: data.len(),
+            date: 0,
+            data: Some(data),
+        }
+    }
+
     pub fn get_data(&self) -> std::vec::Vec<u8> {
         if let Some(data) = &self.data {
             data.clone()
@@ -223,6 +240,9 @@ pub struct TransferState {
     pub send_state: TransferInformation,
     pub recieve_state: TransferInformation,
     pub request_cancel: bool,
+
+    /// Data received during the transfer that should be shown on the terminal.
+    pub terminal_output: Vec<u8>,
 }
 
 impl Default for TransferState {
@@ -236,6 +256,7 @@ impl Default for TransferState {
             send_state: TransferInformation::default(),
             recieve_state: TransferInformation::default(),
             request_cancel: false,
+            terminal_output: Vec::new(),
         }
     }
 }
@@ -280,11 +301,40 @@ pub enum TransferType {
     XModem1kG,
     YModem,
     YModemG,
+    Kermit,
     Text,
 }
 
 impl TransferType {
-    pub fn create(self) -> Box<dyn Protocol> {
+    pub const ALL: [TransferType; 9] = [
+        TransferType::ZModem,
+        TransferType::ZedZap,
+        TransferType::XModem,
+        TransferType::XModem1k,
+        TransferType::XModem1kG,
+        TransferType::YModem,
+        TransferType::YModemG,
+        TransferType::Kermit,
+        TransferType::Text,
+    ];
+
+    /// The name shown to the user, `from_name` parses it back.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            TransferType::ZModem => "Zmodem",
+            TransferType::ZedZap => "ZedZap",
+            TransferType::XModem => "Xmodem",
+            TransferType::XModem1k => "Xmodem 1k",
+            TransferType::XModem1kG => "Xmodem 1k-G",
+            TransferType::YModem => "Ymodem",
+            TransferType::YModemG => "Ymodem-G",
+            TransferType::Kermit => "Kermit",
+            TransferType::Text => "Text",
+        }
+    }
+
+    pub fn create(self, text_settings: &TextTransferSettings) -> Box<dyn Protocol> {
         match self {
             TransferType::ZModem => Box::new(Zmodem::new(1024)),
             TransferType::ZedZap => Box::new(Zmodem::new(8 * 1024)),
@@ -293,11 +343,29 @@ impl TransferType {
             TransferType::XModem1kG => Box::new(XYmodem::new(XYModemVariant::XModem1kG)),
             TransferType::YModem => Box::new(XYmodem::new(XYModemVariant::YModem)),
             TransferType::YModemG => Box::new(XYmodem::new(XYModemVariant::YModemG)),
-            TransferType::Text => panic!("Not implemented"),
+            TransferType::Kermit => Box::new(Kermit::new()),
+            TransferType::Text => Box::new(Text::new(text_settings.clone())),
+        }
+    }
+
+    /// Parses a name case insensitive, spaces and dashes are ignored.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = normalize_name(name);
+        match name.as_str() {
+            "zmodem8k" => Some(TransferType::ZedZap),
+            "ascii" => Some(TransferType::Text),
+            _ => TransferType::ALL
+                .into_iter()
+                .find(|protocol| normalize_name(protocol.name()) == name),
         }
     }
 }
 
+fn normalize_name(name: &str) -> String {
+    name.trim().to_ascii_lowercase().replace(['-', ' '], "")
+}
+
 pub fn str_from_null_terminated_utf8_unchecked(s: &[u8]) -> String {
     let mut res = String::new();
 
@@ -309,3 +377,21 @@ pub fn str_from_null_terminated_utf8_unchecked(s: &[u8]) -> String {
     }
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TransferType;
+
+    #[test]
+    fn test_transfer_type_names() {
+        for protocol in TransferType::ALL {
+            assert_eq!(Some(protocol), TransferType::from_name(protocol.name()));
+        }
+        assert_eq!(
+            Some(TransferType::ZedZap),
+            TransferType::from_name("zmodem 8k")
+        );
+        assert_eq!(Some(TransferType::Text), TransferType::from_name("ASCII"));
+        assert_eq!(None, TransferType::from_name("carrier pigeon"));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use web_time::Instant;
+
+use crate::{
+    protocol::{FileStorageHandler, TransferState},
+    ui::connection::Connection,
+    util::PatternRecognizer,
+    TerminalResult, TextTransferSettings,
+};
+
+/// Ctrl-Z - the classic end of text marker
+const SUB: u8 = 0x1A;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DownloadState {
+    Idle,
+    Start,
+    Receive,
+}
+
+pub struct TextDownload {
+    state: DownloadState,
+    settings: TextTransferSettings,
+
+    end_pattern: Option<PatternRecognizer>,
+    last_data: Instant,
+}
+
+impl TextDownload {
+    pub fn new(settings: TextTransferSettings) -> Self {
+        let end_pattern = if settings.end_pattern.is_empty() {
+            None
+        } else {
+            Some(PatternRecognizer::from(
+                settings.end_pattern.as_bytes(),
+                false,
+            ))
+        };
+        Self {
+            state: DownloadState::Idle,
+            settings,
+            end_pattern,
+            last_data: Instant::now(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, DownloadState::Idle)
+    }
+
+    pub fn recv(&mut self) {
+        self.state = DownloadState::Start;
+    }
+
+    pub fn cancel(&mut self) {
+        self.state = DownloadState::Idle;
+    }
+
+    pub fn update(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        storage_handler: &mut dyn FileStorageHandler,
+    ) -> TerminalResult<()> {
+        match self.state {
+            DownloadState::Idle => return Ok(()),
+            DownloadState::Start => {
+                storage_handler.open_file(&self.settings.capture_file_name, 0);
+                if let Ok(mut transfer_state) = transfer_state.lock() {
+                    transfer_state.recieve_state.log_info(format!(
+                        "Capture text into {}",
+                        self.settings.capture_file_name
+                    ));
+                }
+                self.last_data = Instant::now();
+                self.state = DownloadState::Receive;
+            }
+            DownloadState::Receive => {}
+        }
+
+        if com.is_data_available()? {
+            let data = com.read_buffer();
+            self.last_data = Instant::now();
+            let mut end = data.len();
+            // start of the data after the end marker
+            let mut trailing = data.len();
+            let mut reason = None;
+            for (i, ch) in data.iter().enumerate() {
+                if self.settings.end_on_ctrl_z && *ch == SUB {
+                    end = i;
+                    trailing = i + 1;
+                    reason = Some("Received Ctrl-Z.".to_string());
+                    break;
+                }
+                if let Some(pattern) = &mut self.end_pattern {
+                    if pattern.push_ch(*ch) {
+                        end = i + 1;
+                        trailing = end;
+                        reason = Some(format!("Received '{}'.", self.settings.end_pattern));
+                        break;
+                    }
+                }
+            }
+            storage_handler.append(&data[..end]);
+            if let Ok(mut transfer_state) = transfer_state.lock() {
+                transfer_state
+                    .terminal_output
+                    .extend_from_slice(&data[..end]);
+            }
+            if let Some(reason) = reason {
+                self.finish(transfer_state, storage_handler, reason, &data[trailing..]);
+            }
+        } else if self.settings.idle_timeout > 0
+            && self.last_data.elapsed() > Duration::from_secs(u64::from(self.settings.idle_timeout))
+        {
+            self.finish(
+                transfer_state,
+                storage_handler,
+                format!("No data for {} seconds.", self.settings.idle_timeout),
+                &[],
+            );
+        }
+
+        if let Ok(mut transfer_state) = transfer_state.lock() {
+            transfer_state.update_time();
+            transfer_state.current_state = "Capture text…";
+            let transfer_info = &mut transfer_state.recieve_state;
+            transfer_info
+                .file_name
+                .clone_from(&self.settings.capture_file_name);
+            if let DownloadState::Receive = self.state {
+                transfer_info.bytes_transfered = storage_handler.current_file_length();
+            }
+            transfer_info.update_bps();
+        }
+        Ok(())
+    }
+
+    fn finish(
+        &mut self,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        storage_handler: &mut dyn FileStorageHandler,
+        reason: String,
+        trailing: &[u8],
+    ) {
+        if let Ok(mut transfer_state) = transfer_state.lock() {
+            // the text after the end of the capture goes back to the terminal
+            transfer_state.terminal_output.extend_from_slice(trailing);
+            let transfer_info = &mut transfer_state.recieve_state;
+            transfer_info.bytes_transfered = storage_handler.current_file_length();
+            transfer_info.log_info(reason);
+            transfer_info
+                .files_finished
+                .push(self.settings.capture_file_name.clone());
+        }
+        storage_handler.close();
+        self.state = DownloadState::Idle;
+    }
+}
@@ -0,0 +1,92 @@
+//
+// Plain text transfers - the text is typed into the remote system line by line
+// or captured from the remote system until an end marker shows up.
+
+mod download;
+use download::TextDownload;
+
+mod upload;
+use upload::TextUpload;
+
+mod tests;
+
+use std::sync::{Arc, Mutex};
+
+use super::{FileDescriptor, FileStorageHandler, Protocol, TransferState};
+use crate::{ui::connection::Connection, TerminalResult, TextTransferSettings};
+
+pub struct Text {
+    settings: TextTransferSettings,
+    upload: Option<TextUpload>,
+    download: Option<TextDownload>,
+}
+
+impl Text {
+    pub fn new(settings: TextTransferSettings) -> Self {
+        Self {
+            settings,
+            upload: None,
+            download: None,
+        }
+    }
+}
+
+impl Protocol for Text {
+    fn update(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+        storage_handler: &mut dyn FileStorageHandler,
+    ) -> TerminalResult<bool> {
+        if let Some(upload) = &mut self.upload {
+            upload.update(com, transfer_state)?;
+            if !upload.is_active() {
+                transfer_state.lock().unwrap().is_finished = true;
+                return Ok(false);
+            }
+        }
+        if let Some(download) = &mut self.download {
+            download.update(com, transfer_state, storage_handler)?;
+            if !download.is_active() {
+                transfer_state.lock().unwrap().is_finished = true;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn initiate_send(
+        &mut self,
+        _com: &mut Connection,
+        files: Vec<FileDescriptor>,
+        transfer_state: &mut TransferState,
+    ) -> TerminalResult<()> {
+        transfer_state.protocol_name = "Text".to_string();
+        let mut upload = TextUpload::new(self.settings.clone());
+        upload.send(files);
+        self.upload = Some(upload);
+        Ok(())
+    }
+
+    fn initiate_recv(
+        &mut self,
+        _com: &mut Connection,
+        transfer_state: &mut TransferState,
+    ) -> TerminalResult<()> {
+        transfer_state.protocol_name = "Text".to_string();
+        let mut download = TextDownload::new(self.settings.clone());
+        download.recv();
+        self.download = Some(download);
+        Ok(())
+    }
+
+    fn cancel(&mut self, _com: &mut Connection) -> TerminalResult<()> {
+        if let Some(upload) = &mut self.upload {
+            upload.cancel();
+        }
+        if let Some(download) = &mut self.download {
+            download.cancel();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+#[cfg(test)]
+mod text_test {
+    #![allow(clippy::field_reassign_with_default)]
+    use std::{
+        sync::{mpsc, Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::{
+        protocol::{
+            text::{upload::split_lines, Text},
+            FileDescriptor, Protocol, TestStorageHandler, TransferState,
+        },
+        ui::connection::{Connection, SendData},
+        LineEnding, TextTransferSettings,
+    };
+
+    fn settings(line_ending: LineEnding) -> TextTransferSettings {
+        TextTransferSettings {
+            line_delay: 0,
+            char_delay: 0,
+            wait_for_echo: false,
+            wait_for_prompt: String::new(),
+            line_ending,
+            ..Default::default()
+        }
+    }
+
+    /// Runs a text upload, `remote` gets everything sent and returns the answer of the remote side.
+    fn run_upload(
+        settings: TextTransferSettings,
+        data: &[u8],
+        mut remote: impl FnMut(Vec<u8>) -> Vec<u8>,
+    ) -> (Vec<u8>, TransferState) {
+        let (sender_tx, sender_out) = mpsc::channel();
+        let (remote_tx, sender_in) = mpsc::channel();
+        let mut com = Connection::new(sender_in, sender_tx);
+        let state = Arc::new(Mutex::new(TransferState::default()));
+        let mut storage = TestStorageHandler::new();
+
+        let mut text = Text::new(settings);
+        text.initiate_send(
+            &mut com,
+            vec![FileDescriptor::create_test(
+                "foo.txt".to_string(),
+                data.to_vec(),
+            )],
+            &mut state.lock().unwrap(),
+        )
+        .expect("error.");
+
+        let mut result = Vec::new();
+        for _ in 0..1_000_000 {
+            let running = text.update(&mut com, &state, &mut storage).expect("error.");
+            while let Ok(SendData::Data(data)) = sender_out.try_recv() {
+                result.extend_from_slice(&data);
+                let answer = remote(data);
+                if !answer.is_empty() {
+                    remote_tx.send(SendData::Data(answer)).unwrap();
+                }
+            }
+            if !running {
+                let state = state.lock().unwrap().clone();
+                return (result, state);
+            }
+        }
+        panic!("transfer didn't finish.");
+    }
+
+    #[test]
+    fn test_split_lines() {
+        let lines = split_lines(b"a\r\nb\nc\rd", LineEnding::Cr);
+        assert_eq!(
+            vec![
+                b"a\r".to_vec(),
+                b"b\r".to_vec(),
+                b"c\r".to_vec(),
+                b"d".to_vec()
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn test_split_lines_empty_lines() {
+        let lines = split_lines(b"a\n\nb\n", LineEnding::CrLf);
+        assert_eq!(
+            vec![b"a\r\n".to_vec(), b"\r\n".to_vec(), b"b\r\n".to_vec()],
+            lines
+        );
+    }
+
+    #[test]
+    fn test_text_upload() {
+        let (result, state) =
+            run_upload(settings(LineEnding::Lf), b"foo\r\nbar\r\n", |_| Vec::new());
+        assert_eq!(b"foo\nbar\n".to_vec(), result);
+        assert_eq!(vec!["foo.txt".to_string()], state.send_state.files_finished);
+    }
+
+    #[test]
+    fn test_text_upload_char_delay() {
+        let mut settings = settings(LineEnding::Cr);
+        settings.char_delay = 1;
+        let mut packets = 0;
+        let (result, _) = run_upload(settings, b"abc\ndef", |_| {
+            packets += 1;
+            Vec::new()
+        });
+        assert_eq!(b"abc\rdef".to_vec(), result);
+        assert_eq!(7, packets);
+    }
+
+    #[test]
+    fn test_text_upload_wait_for_echo() {
+        let mut settings = settings(LineEnding::Cr);
+        settings.wait_for_echo = true;
+        let (result, state) = run_upload(settings, b"foo\nbar\n", |data| data);
+        assert_eq!(b"foo\rbar\r".to_vec(), result);
+        assert_eq!(0, state.send_state.warnings());
+        assert_eq!(b"foo\rbar\r".to_vec(), state.terminal_output);
+    }
+
+    #[test]
+    fn test_text_upload_wait_for_prompt() {
+        let mut settings = settings(LineEnding::Cr);
+        settings.wait_for_prompt = "> ".to_string();
+        let mut lines = 0;
+        let (result, state) = run_upload(settings, b"foo\nbar\nbaz\n", |data| {
+            if data.ends_with(b"\r") {
+                lines += 1;
+                format!("{lines}> ").into_bytes()
+            } else {
+                Vec::new()
+            }
+        });
+        assert_eq!(b"foo\rbar\rbaz\r".to_vec(), result);
+        assert_eq!(0, state.send_state.warnings());
+    }
+
+    /// Runs a text download, the remote sends `packets` one after another.
+    fn run_download(
+        settings: TextTransferSettings,
+        packets: &[&[u8]],
+    ) -> (TestStorageHandler, TransferState) {
+        let (sender_tx, _sender_out) = mpsc::channel();
+        let (remote_tx, recv_in) = mpsc::channel();
+        let mut com = Connection::new(recv_in, sender_tx);
+        let state = Arc::new(Mutex::new(TransferState::default()));
+        let mut storage = TestStorageHandler::new();
+
+        let mut text = Text::new(settings);
+        text.initiate_recv(&mut com, &mut state.lock().unwrap())
+            .expect("error.");
+
+        let mut packets = packets.iter();
+        for _ in 0..10_000 {
+            if let Some(data) = packets.next() {
+                remote_tx.send(SendData::Data(data.to_vec())).unwrap();
+            } else {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            let running = text.update(&mut com, &state, &mut storage).expect("error.");
+            if !running {
+                let state = state.lock().unwrap().clone();
+                return (storage, state);
+            }
+        }
+        panic!("transfer didn't finish.");
+    }
+
+    #[test]
+    fn test_text_download_ctrl_z() {
+        let (storage, state) = run_download(
+            TextTransferSettings::default(),
+            &[b"Hello\r\n", b"World\r\n\x1Atrailing"],
+        );
+        assert_eq!(b"Hello\r\nWorld\r\n".to_vec(), storage.file["capture.txt"]);
+        assert_eq!(
+            vec!["capture.txt".to_string()],
+            state.recieve_state.files_finished
+        );
+    }
+
+    #[test]
+    fn test_text_download_trailing_data() {
+        let (_, state) = run_download(
+            TextTransferSettings::default(),
+            &[b"Hello\r\n", b"World\x1A\r\nMain menu: "],
+        );
+        assert_eq!(
+            b"Hello\r\nWorld\r\nMain menu: ".to_vec(),
+            state.terminal_output
+        );
+
+        let mut settings = TextTransferSettings::default();
+        settings.end_pattern = "<<END>>".to_string();
+        let (storage, state) = run_download(settings, &[b"Msg\r\n<<END>>Prompt> "]);
+        assert_eq!(b"Msg\r\n<<END>>".to_vec(), storage.file["capture.txt"]);
+        assert_eq!(b"Msg\r\n<<END>>Prompt> ".to_vec(), state.terminal_output);
+    }
+
+    #[test]
+    fn test_text_download_end_pattern() {
+        let mut settings = TextTransferSettings::default();
+        settings.end_pattern = "<<END>>".to_string();
+        settings.capture_file_name = "msgs.txt".to_string();
+        let (storage, _) = run_download(settings, &[b"Msg 1\r\n<<EN", b"D>>more"]);
+        assert_eq!(b"Msg 1\r\n<<END>>".to_vec(), storage.file["msgs.txt"]);
+    }
+
+    #[test]
+    fn test_text_download_ctrl_z_disabled() {
+        let mut settings = TextTransferSettings::default();
+        settings.end_on_ctrl_z = false;
+        settings.end_pattern = "$".to_string();
+        let (storage, _) = run_download(settings, &[b"a\x1Ab$"]);
+        assert_eq!(b"a\x1Ab$".to_vec(), storage.file["capture.txt"]);
+    }
+
+    #[test]
+    fn test_text_download_idle_timeout() {
+        let mut settings = TextTransferSettings::default();
+        settings.idle_timeout = 1;
+        settings.end_on_ctrl_z = false;
+        let (storage, state) = run_download(settings, &[b"foo"]);
+        assert_eq!(b"foo".to_vec(), storage.file["capture.txt"]);
+        assert_eq!(3, state.recieve_state.bytes_transfered);
+    }
+}
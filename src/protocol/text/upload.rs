@@ -0,0 +1,252 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use web_time::Instant;
+
+use crate::{
+    protocol::{FileDescriptor, TransferState},
+    ui::connection::Connection,
+    util::PatternRecognizer,
+    LineEnding, TerminalResult, TextTransferSettings,
+};
+
+/// How long to wait for an echo or prompt before sending the next line anyways.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UploadState {
+    Idle,
+    SendLine,
+    WaitForEcho(Instant),
+    WaitForPrompt(Instant),
+}
+
+pub struct TextUpload {
+    state: UploadState,
+    settings: TextTransferSettings,
+
+    files: Vec<FileDescriptor>,
+    cur_file: usize,
+    lines: Vec<Vec<u8>>,
+    cur_line: usize,
+    cur_char: usize,
+    bytes_send: usize,
+    next_send: Instant,
+
+    echo_received: bool,
+    prompt: Option<PatternRecognizer>,
+    prompt_received: bool,
+}
+
+impl TextUpload {
+    pub fn new(settings: TextTransferSettings) -> Self {
+        let prompt = if settings.wait_for_prompt.is_empty() {
+            None
+        } else {
+            Some(PatternRecognizer::from(
+                settings.wait_for_prompt.as_bytes(),
+                true,
+            ))
+        };
+        Self {
+            state: UploadState::Idle,
+            settings,
+            files: Vec::new(),
+            cur_file: 0,
+            lines: Vec::new(),
+            cur_line: 0,
+            cur_char: 0,
+            bytes_send: 0,
+            next_send: Instant::now(),
+            echo_received: false,
+            prompt,
+            prompt_received: false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !matches!(self.state, UploadState::Idle)
+    }
+
+    pub fn send(&mut self, files: Vec<FileDescriptor>) {
+        self.files = files;
+        self.cur_file = 0;
+        self.start_file();
+    }
+
+    pub fn cancel(&mut self) {
+        self.state = UploadState::Idle;
+    }
+
+    fn start_file(&mut self) {
+        self.cur_line = 0;
+        self.cur_char = 0;
+        self.bytes_send = 0;
+        if let Some(fd) = self.files.get(self.cur_file) {
+            self.lines = split_lines(&fd.get_data(), self.settings.line_ending);
+            self.state = UploadState::SendLine;
+        } else {
+            self.lines.clear();
+            self.state = UploadState::Idle;
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+    ) -> TerminalResult<()> {
+        if let UploadState::Idle = self.state {
+            return Ok(());
+        }
+        if com.is_data_available()? {
+            let data = com.read_buffer();
+            for ch in &data {
+                if *ch == b'\r' || *ch == b'\n' {
+                    self.echo_received = true;
+                }
+                if let Some(prompt) = &mut self.prompt {
+                    if prompt.push_ch(*ch) {
+                        self.prompt_received = true;
+                    }
+                }
+            }
+            if let Ok(mut transfer_state) = transfer_state.lock() {
+                transfer_state.terminal_output.extend(data);
+            }
+        }
+
+        if let Ok(mut transfer_state) = transfer_state.lock() {
+            transfer_state.update_time();
+            transfer_state.current_state = match self.state {
+                UploadState::WaitForEcho(_) => "Wait for echo…",
+                UploadState::WaitForPrompt(_) => "Wait for prompt…",
+                _ => "Send text…",
+            };
+            let transfer_info = &mut transfer_state.send_state;
+            if let Some(fd) = self.files.get(self.cur_file) {
+                transfer_info.file_name.clone_from(&fd.file_name);
+                transfer_info.file_size = self.lines.iter().map(Vec::len).sum();
+            }
+            transfer_info.bytes_transfered = self.bytes_send;
+            transfer_info.check_size = format!("{}/{}", self.cur_line, self.lines.len());
+            transfer_info.update_bps();
+        }
+
+        let now = Instant::now();
+        match self.state {
+            UploadState::Idle => {}
+            UploadState::SendLine => {
+                if now < self.next_send {
+                    return Ok(());
+                }
+                if self.cur_line >= self.lines.len() {
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        let transfer_info = &mut transfer_state.send_state;
+                        transfer_info.log_info("File transferred.");
+                        if let Some(fd) = self.files.get(self.cur_file) {
+                            transfer_info.files_finished.push(fd.file_name.clone());
+                        }
+                    }
+                    self.cur_file += 1;
+                    self.start_file();
+                    return Ok(());
+                }
+                let line = &self.lines[self.cur_line];
+                let end = if self.settings.char_delay > 0 {
+                    self.cur_char + 1
+                } else {
+                    line.len()
+                };
+                com.send(line[self.cur_char..end].to_vec())?;
+                self.bytes_send += end - self.cur_char;
+                self.cur_char = end;
+                self.next_send = now + Duration::from_millis(u64::from(self.settings.char_delay));
+
+                if self.cur_char >= line.len() {
+                    self.cur_line += 1;
+                    self.cur_char = 0;
+                    self.next_send =
+                        now + Duration::from_millis(u64::from(self.settings.line_delay));
+                    self.echo_received = false;
+                    self.prompt_received = false;
+                    if let Some(prompt) = &mut self.prompt {
+                        prompt.reset();
+                    }
+                    self.wait_for_remote(now);
+                }
+            }
+            UploadState::WaitForEcho(start) => {
+                if self.echo_received {
+                    self.state = UploadState::SendLine;
+                    self.wait_for_prompt(now);
+                } else if now.duration_since(start) > WAIT_TIMEOUT {
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        transfer_state
+                            .send_state
+                            .log_warning(format!("No echo for line {}", self.cur_line));
+                    }
+                    self.state = UploadState::SendLine;
+                    self.wait_for_prompt(now);
+                }
+            }
+            UploadState::WaitForPrompt(start) => {
+                if self.prompt_received {
+                    self.state = UploadState::SendLine;
+                } else if now.duration_since(start) > WAIT_TIMEOUT {
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        transfer_state
+                            .send_state
+                            .log_warning(format!("No prompt after line {}", self.cur_line));
+                    }
+                    self.state = UploadState::SendLine;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn wait_for_remote(&mut self, now: Instant) {
+        if self.cur_line >= self.lines.len() {
+            return;
+        }
+        if self.settings.wait_for_echo {
+            self.state = UploadState::WaitForEcho(now);
+        } else {
+            self.wait_for_prompt(now);
+        }
+    }
+
+    fn wait_for_prompt(&mut self, now: Instant) {
+        if self.prompt.is_some() && !self.prompt_received {
+            self.state = UploadState::WaitForPrompt(now);
+        }
+    }
+}
+
+/// Splits the text into lines and translates the line endings.
+/// CR, LF and CR/LF are recognized as line break.
+pub fn split_lines(data: &[u8], line_ending: LineEnding) -> Vec<Vec<u8>> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'\r' | b'\n' => {
+                if data[i] == b'\r' && data.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                line.extend_from_slice(line_ending.as_bytes());
+                lines.push(std::mem::take(&mut line));
+            }
+            ch => line.push(ch),
+        }
+        i += 1;
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
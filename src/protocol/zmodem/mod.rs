@@ -17,7 +17,7 @@ mod rz;
 use rz::Rz;
 
 mod err;
-// mod tests;
+mod tests;
 
 use self::{err::TransmissionError, rz::read_zdle_byte};
 
@@ -26,6 +26,16 @@ pub enum RevcState {
     AwaitFileData,
     AwaitEOF,
     SendZRINIT,
+    /// The sender calculates the CRC of the data we already have
+    AwaitZCRC,
+}
+
+/// Incomplete file from a previous download that may be resumed
+struct PartialFile {
+    file_name: String,
+    file_size: usize,
+    length: usize,
+    crc: u32,
 }
 
 pub struct Rz {
@@ -44,6 +54,8 @@ pub struct Rz {
     can_break: bool,
     want_fcs_16: bool,
     escape_8th_bit: bool,
+
+    partial_file: Option<PartialFile>,
 }
 
 impl Rz {
@@ -63,6 +75,7 @@ impl Rz {
             no_streaming: false,
             want_fcs_16: true,
             escape_8th_bit: false,
+            partial_file: None,
         }
     }
 
@@ -243,16 +256,37 @@ impl Rz {
                                 file_size = file_size * 10 + (*b - b'0') as usize;
                             }
                             if let Ok(mut transfer_state) = transfer_state.lock() {
-                                let transfer_info = &mut transfer_state.recieve_state;
-
-                                transfer_info.log_info(format!(
+                                transfer_state.recieve_state.log_info(format!(
                                     "Start file transfer: {file_name} ({file_size} bytes)"
                                 ));
                             }
-                            storage_handler.open_file(&file_name, file_size);
 
+                            // a repeated ZFILE means the sender doesn't answer ZCRC
+                            let asked_for_crc = self.partial_file.take().is_some();
+                            if !asked_for_crc {
+                                if let Some((length, crc)) =
+                                    storage_handler.find_partial_file(&file_name, file_size)
+                                {
+                                    // only resume if the data we have matches the sender's file
+                                    Header::from_number(
+                                        self.get_header_type(),
+                                        ZFrameType::Crc,
+                                        length as u32,
+                                    )
+                                    .write(com, self.can_esc_control)?;
+                                    self.partial_file = Some(PartialFile {
+                                        file_name,
+                                        file_size,
+                                        length,
+                                        crc,
+                                    });
+                                    self.state = RevcState::AwaitZCRC;
+                                    return Ok(true);
+                                }
+                            }
+                            storage_handler.open_file(&file_name, file_size);
                             self.state = RevcState::AwaitZDATA;
-                            self.request_zpos(com, storage_handler.current_file_length() as u32)?;
+                            self.request_zpos(com, 0)?;
 
                             return Ok(true);
                         }
@@ -292,6 +326,36 @@ impl Rz {
                     self.state = RevcState::AwaitFileData;
                     return Ok(true);
                 }
+                ZFrameType::Crc => {
+                    let Some(partial) = self.partial_file.take() else {
+                        return Ok(false);
+                    };
+                    let resume_offset = if res.number() == partial.crc {
+                        storage_handler.open_partial_file(&partial.file_name, partial.file_size)
+                    } else {
+                        None
+                    };
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        let transfer_info = &mut transfer_state.recieve_state;
+                        if let Some(offset) = resume_offset {
+                            transfer_info.log_info(format!(
+                                "Resume partial file {} at {offset} bytes",
+                                partial.file_name
+                            ));
+                        } else {
+                            transfer_info.log_warning(format!(
+                                "Partial file {} doesn't match the first {} bytes, downloading it again",
+                                partial.file_name, partial.length
+                            ));
+                        }
+                    }
+                    if resume_offset.is_none() {
+                        storage_handler.open_file(&partial.file_name, partial.file_size);
+                    }
+                    self.state = RevcState::AwaitZDATA;
+                    self.request_zpos(com, storage_handler.current_file_length() as u32)?;
+                    return Ok(true);
+                }
                 ZFrameType::Eof => {
                     self.send_zrinit(com)?;
                     if let Ok(mut transfer_state) = transfer_state.lock() {
@@ -5,6 +5,8 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use icy_engine::get_crc32;
+
 use crate::{
     protocol::{
         zfile_flag, zmodem::err::TransmissionError, FileDescriptor, Header, HeaderType,
@@ -146,7 +148,7 @@ impl Sz {
         }
         match self.state {
             SendState::Await | SendState::AwaitZRPos => {
-                self.read_next_header(com)?;
+                self.read_next_header(com, transfer_state)?;
             }
             SendState::SendZRQInit => {
                 //                transfer_state.current_state = "Negotiating transfer";
@@ -177,6 +179,13 @@ impl Sz {
                 if self.cur_file < 0 {
                     return Ok(());
                 }
+                // the receiver may request a ZRPOS during streaming
+                if com.is_data_available()? {
+                    self.read_next_header(com, transfer_state)?;
+                    if !matches!(self.state, SendState::SendDataPackages) {
+                        return Ok(());
+                    }
+                }
                 let old_pos = self.cur_file_pos;
                 let end_pos = min(self.data.len(), self.cur_file_pos + self.package_len);
                 let nonstop = true; // self.package_len > 1024;
@@ -242,7 +251,11 @@ impl Sz {
         Ok(())
     }
 
-    fn read_next_header(&mut self, com: &mut Connection) -> TerminalResult<()> {
+    fn read_next_header(
+        &mut self,
+        com: &mut Connection,
+        transfer_state: &Arc<Mutex<TransferState>>,
+    ) -> TerminalResult<()> {
         let err = Header::read(com, &mut self.can_count);
         if self.can_count >= 5 {
             // transfer_info.write("Received cancel...".to_string());
@@ -315,19 +328,30 @@ impl Sz {
                 }
 
                 ZFrameType::RPos => {
-                    self.cur_file_pos = res.number() as usize;
-                    self.state = SendState::SendZDATA;
+                    let pos = min(res.number() as usize, self.data.len());
+                    if let Ok(mut transfer_state) = transfer_state.lock() {
+                        let transfer_info = &mut transfer_state.send_state;
+                        if matches!(self.state, SendState::AwaitZRPos) {
+                            if pos > 0 {
+                                transfer_info
+                                    .log_info(format!("Receiver resumes file at {pos} bytes"));
+                            }
+                        } else {
+                            transfer_info
+                                .log_warning(format!("Receiver requested data from {pos} bytes"));
+                        }
+                    }
 
-                    if let SendState::SendDataPackages = self.state {
+                    if !matches!(self.state, SendState::AwaitZRPos) {
+                        // error during transfer, use smaller packages.
+                        self.errors += 1;
                         if self.package_len > 512 {
-                            //reinit transfer.
                             self.package_len /= 2;
-                            self.state = SendState::SendZRQInit;
-                            //        com.write(b"rz\r")?;
-                            self.send_zrqinit(com)?;
-                            return Ok(());
                         }
                     }
+                    self.cur_file_pos = pos;
+                    self.transfered_file = false;
+                    self.state = SendState::SendZDATA;
                 }
 
                 ZFrameType::Fin => {
@@ -351,6 +375,19 @@ impl Sz {
                     Header::from_number(self.get_header_type(), ZFrameType::Ack, res.number())
                         .write(com, self.can_esc_control())?;
                 }
+                ZFrameType::Crc => {
+                    // the receiver checks the start of a partial file, 0 means the whole file
+                    let len = match res.number() as usize {
+                        0 => self.data.len(),
+                        len => min(len, self.data.len()),
+                    };
+                    Header::from_number(
+                        self.get_header_type(),
+                        ZFrameType::Crc,
+                        get_crc32(&self.data[..len]),
+                    )
+                    .write(com, self.can_esc_control())?;
+                }
                 ZFrameType::Abort | ZFrameType::FErr | ZFrameType::Can => {
                     Header::empty(self.get_header_type(), ZFrameType::Fin)
                         .write(com, self.can_esc_control())?;
@@ -10,12 +10,14 @@ use icy_engine_egui::FontExtension;
 
 use crate::{
     check_error,
-    features::{AutoFileTransfer, AutoLogin},
+    features::{
+        AutoFileTransfer, AutoLogin, AutoReconnect, HayesModem, Triggers, RECORDING_EXTENSION,
+    },
     ui::{
         dialogs::{self},
         BufferView, MainWindowState, ScreenMode,
     },
-    util::SoundThread,
+    util::{CharsetDecoder, SoundThread},
     AddressBook, Options,
 };
 
@@ -64,7 +66,20 @@ impl MainWindow {
             },
             connection: Some(Box::new(connection)),
             auto_login: AutoLogin::new(""),
+            auto_reconnect: AutoReconnect::default(),
             auto_file_transfer: AutoFileTransfer::default(),
+            triggers: Triggers::default(),
+            request_attention: false,
+            modem: HayesModem::default(),
+            host_key: None,
+            charset: CharsetDecoder::default(),
+            session_recorder: None,
+            session_player: None,
+            file_viewer: None,
+            view_file_dialog: None,
+            show_sauce: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            scripts: super::scripting::ScriptState::default(),
             screen_mode: ScreenMode::default(),
             current_file_transfer: None,
             buffer_parser: Box::<ansi::Parser>::default(),
@@ -118,6 +133,8 @@ impl MainWindow {
             if let Some(handle) = fts.join_handle.take() {
                 if let Ok(join) = handle.join() {
                     self.connection = Some(join);
+                    let r = self.connection().set_transfer_active(false);
+                    check_error!(self, r, false);
                     self.set_mode(MainWindowMode::ShowTerminal);
                 } else {
                     panic!("Error joining file transfer thread.");
@@ -135,6 +152,18 @@ impl MainWindow {
 fn parse_command_line(view: &mut MainWindow) {
     let args: Vec<String> = std::env::args().collect();
     if let Some(arg) = args.get(1) {
+        let path = std::path::Path::new(arg);
+        if path
+            .extension()
+            .is_some_and(|ext| ext == RECORDING_EXTENSION)
+        {
+            view.start_playback(path);
+            return;
+        }
+        if path.is_file() {
+            view.view_file(path);
+            return;
+        }
         view.dialing_directory_dialog.addresses.addresses[0].address = arg.clone();
         view.call_bbs(0);
     }
@@ -144,6 +173,10 @@ impl eframe::App for MainWindow {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         #[cfg(not(target_arch = "wasm32"))]
         self.update_title(frame);
+        if std::mem::take(&mut self.request_attention) {
+            #[cfg(not(target_arch = "wasm32"))]
+            frame.request_user_attention(egui::UserAttentionType::Informational);
+        }
 
         match self.get_mode() {
             MainWindowMode::ShowTerminal => {
@@ -178,8 +211,10 @@ impl eframe::App for MainWindow {
                 self.update_terminal_window(ctx, frame, false);
 
                 let mut join_thread = false;
+                let mut terminal_output = Vec::new();
                 if let Some(fts) = &mut self.current_file_transfer {
-                    let state = if let Ok(state) = fts.current_transfer.lock() {
+                    let state = if let Ok(mut state) = fts.current_transfer.lock() {
+                        terminal_output = std::mem::take(&mut state.terminal_output);
                         Some(state.clone())
                     } else {
                         log::error!("In file transfer but can't lock state.");
@@ -202,6 +237,9 @@ impl eframe::App for MainWindow {
                     log::error!("In file transfer but no current protocol.");
                     join_thread = true;
                 }
+                if !terminal_output.is_empty() {
+                    self.handle_received_data(terminal_output);
+                }
                 if join_thread {
                     self.get_connection_back();
                 }
@@ -234,6 +272,13 @@ impl eframe::App for MainWindow {
                 check_error!(self, res, false);
                 dialogs::show_iemsi::show_iemsi(self, ctx);
                 ctx.request_repaint_after(Duration::from_millis(150));
+            }
+            MainWindowMode::VerifyHostKey => {
+                let res = self.update_state();
+                self.update_terminal_window(ctx, frame, false);
+                check_error!(self, res, false);
+                dialogs::host_key_dialog::show_dialog(self, ctx);
+                ctx.request_repaint_after(Duration::from_millis(150));
             } // MainWindowMode::AskDeleteEntry => todo!(),
         }
     }
@@ -5,11 +5,14 @@ use std::sync::mpsc::{self};
 
 #[cfg(target_arch = "wasm32")]
 use wasm_thread as thread;
-use web_time::Instant;
+use web_time::{Duration, Instant};
 
 use crate::com::{Com, TermComResult};
+use crate::KeepAliveSettings;
 
 use super::connection::{Connection, OpenConnectionData, SendData};
+#[cfg(not(target_arch = "wasm32"))]
+use super::connection::{HostKeyDecision, HostKeyInfo};
 use super::MainWindow;
 
 const BITS_PER_BYTE: u32 = 8;
@@ -25,6 +28,12 @@ pub struct ConnectionThreadData {
     data_buffer: VecDeque<u8>,
     baud_rate: u32,
     last_send_time: Instant,
+
+    keepalive: KeepAliveSettings,
+    /// Keepalives are only sent while nothing else is sent
+    last_keepalive: Instant,
+    last_data_sent: Instant,
+    transfer_active: bool,
 }
 
 impl ConnectionThreadData {
@@ -36,20 +45,73 @@ impl ConnectionThreadData {
         self.thread_is_running &= self.tx.send(SendData::Disconnect).is_ok();
     }
 
+    /// The connection dropped without a hangup, the ui may reconnect.
+    fn connection_lost(&mut self, reason: String) {
+        log::warn!("{reason}");
+        if !self.data_buffer.is_empty() {
+            let data = self.data_buffer.drain(..).collect();
+            self.thread_is_running &= self.tx.send(SendData::Data(data)).is_ok();
+        }
+        self.is_connected = false;
+        self.com = Box::new(crate::com::NullConnection {});
+        self.baud_rate = 0;
+        self.thread_is_running &= self.tx.send(SendData::ConnectionLost(reason)).is_ok();
+    }
+
+    /// Sends the keepalives that are due, nothing is sent during file transfers.
+    fn keep_alive(&mut self) {
+        if self.transfer_active {
+            return;
+        }
+        let now = Instant::now();
+        let interval = Duration::from_secs(u64::from(self.keepalive.interval));
+        if self.keepalive.interval > 0 && now.duration_since(self.last_keepalive) >= interval {
+            self.last_keepalive = now;
+            if let Err(err) = self.com.send_keepalive() {
+                self.connection_lost(err.to_string());
+                return;
+            }
+        }
+
+        let anti_idle = Duration::from_secs(u64::from(self.keepalive.anti_idle_minutes) * 60);
+        if !self.keepalive.anti_idle_string.is_empty()
+            && self.keepalive.anti_idle_minutes > 0
+            && now.duration_since(self.last_data_sent) >= anti_idle
+        {
+            self.last_data_sent = now;
+            self.last_keepalive = now;
+            match self.keepalive.anti_idle_data() {
+                Ok(data) => {
+                    if let Err(err) = self.com.send(&data) {
+                        self.connection_lost(err.to_string());
+                    }
+                }
+                Err(err) => log::error!("Invalid anti-idle string: {err}"),
+            }
+        }
+    }
+
     fn read_data(&mut self) -> bool {
         if self.data_buffer.is_empty() {
-            if let Ok(Some(data)) = self.com.read_data() {
-                if self.baud_rate == 0 {
-                    if let Err(err) = self.tx.send(SendData::Data(data)) {
-                        log::error!("{err}");
-                        self.thread_is_running &= self.tx.send(SendData::Disconnect).is_ok();
-                    }
-                    // ctx.request_repaint();
-                } else {
-                    self.data_buffer.extend(data);
+            let data = match self.com.read_data() {
+                Ok(Some(data)) => data,
+                Ok(None) => return false,
+                Err(err) => {
+                    self.connection_lost(err.to_string());
+                    return false;
                 }
+            };
+            if self.baud_rate == 0 {
+                if let Err(err) = self.tx.send(SendData::Data(data)) {
+                    log::error!("{err}");
+                    self.thread_is_running &= self.tx.send(SendData::Disconnect).is_ok();
+                }
+                // ctx.request_repaint();
             } else {
-                return false;
+                self.data_buffer.extend(data);
+            }
+            if let Some(charset) = self.com.take_charset() {
+                self.thread_is_running &= self.tx.send(SendData::SetCharset(charset)).is_ok();
             }
         } else if self.baud_rate == 0 {
             if let Err(err) = self
@@ -82,15 +144,31 @@ impl ConnectionThreadData {
 
     fn try_connect(&mut self, connection_data: &OpenConnectionData) -> TermComResult<()> {
         self.com = match connection_data.protocol {
-            crate::addresses::Protocol::Telnet => {
+            crate::addresses::Protocol::Telnet | crate::addresses::Protocol::TelnetTls => {
                 Box::new(crate::com::ComTelnetImpl::connect(connection_data)?)
             }
             crate::addresses::Protocol::Raw => {
                 Box::new(crate::com::ComRawImpl::connect(connection_data)?)
             }
             #[cfg(not(target_arch = "wasm32"))]
+            crate::addresses::Protocol::Rlogin => {
+                Box::new(crate::com::rlogin::ComRloginImpl::connect(connection_data)?)
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            crate::addresses::Protocol::Command => Box::new(
+                crate::com::command::ComCommandImpl::connect(connection_data)?,
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            crate::addresses::Protocol::Local => {
+                Box::new(crate::com::local::ComLocalImpl::connect(connection_data)?)
+            }
+            #[cfg(not(target_arch = "wasm32"))]
             crate::addresses::Protocol::Ssh => {
-                Box::new(crate::com::ssh::SSHComImpl::connect(connection_data)?)
+                let (tx, rx) = (&self.tx, &self.rx);
+                Box::new(crate::com::ssh::SSHComImpl::connect(
+                    connection_data,
+                    &mut |info| ask_host_key(tx, rx, info),
+                )?)
             }
             crate::addresses::Protocol::WebSocket(_) => {
                 #[cfg(target_arch = "wasm32")] //TODO
@@ -100,8 +178,16 @@ impl ConnectionThreadData {
                     connection_data,
                 )?)
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            crate::addresses::Protocol::Serial => {
+                Box::new(crate::com::serial::ComSerialImpl::connect(connection_data)?)
+            }
             #[cfg(target_arch = "wasm32")]
-            crate::addresses::Protocol::Ssh => Box::new(crate::com::NullConnection {}),
+            crate::addresses::Protocol::Rlogin
+            | crate::addresses::Protocol::Command
+            | crate::addresses::Protocol::Local
+            | crate::addresses::Protocol::Ssh
+            | crate::addresses::Protocol::Serial => Box::new(crate::com::NullConnection {}),
         };
         Ok(())
     }
@@ -110,8 +196,11 @@ impl ConnectionThreadData {
         while let Ok(result) = self.rx.try_recv() {
             match result {
                 SendData::OpenConnection(connection_data) => {
+                    self.keepalive = connection_data.keepalive.clone();
                     match self.try_connect(&connection_data) {
                         Ok(()) => {
+                            self.last_keepalive = Instant::now();
+                            self.last_data_sent = Instant::now();
                             self.thread_is_running &= self.tx.send(SendData::Connected).is_ok();
                             self.is_connected = true;
                         }
@@ -125,16 +214,27 @@ impl ConnectionThreadData {
                     }
                 }
                 SendData::Data(buf) => {
+                    self.last_data_sent = Instant::now();
+                    self.last_keepalive = self.last_data_sent;
                     if let Err(err) = self.com.send(&buf) {
-                        log::error!("{err}");
-                        let _ = self.tx.send(SendData::Disconnect);
-                        self.disconnect();
+                        self.connection_lost(err.to_string());
                     }
                 }
 
+                SendData::TransferActive(active) => {
+                    self.transfer_active = active;
+                    // the transfer kept the connection busy
+                    self.last_data_sent = Instant::now();
+                    self.last_keepalive = self.last_data_sent;
+                }
                 SendData::SetBaudRate(baud) => {
                     self.baud_rate = baud;
                 }
+                SendData::SetWindowSize(window_size) => {
+                    if let Err(err) = self.com.set_window_size(window_size) {
+                        log::error!("Can't change window size: {err}");
+                    }
+                }
                 SendData::Disconnect => {
                     self.disconnect();
                 }
@@ -151,6 +251,10 @@ impl ConnectionThreadData {
             com: Box::new(crate::com::NullConnection {}),
             data_buffer: VecDeque::<u8>::new(),
             last_send_time: Instant::now(),
+            keepalive: KeepAliveSettings::default(),
+            last_keepalive: Instant::now(),
+            last_data_sent: Instant::now(),
+            transfer_active: false,
             thread_is_running: true,
             is_connected: false,
         }
@@ -160,16 +264,36 @@ impl ConnectionThreadData {
     pub fn poll(&mut self) {
         if self.is_connected {
             self.read_data();
+            self.keep_alive();
         }
         self.handle_receive();
     }
 }
 
+/// Asks the ui thread what to do with an unknown host key and waits for the answer.
+/// Changed keys are only shown, these connections are always aborted.
+#[cfg(not(target_arch = "wasm32"))]
+fn ask_host_key(
+    tx: &mpsc::Sender<SendData>,
+    rx: &mpsc::Receiver<SendData>,
+    info: HostKeyInfo,
+) -> HostKeyDecision {
+    let changed = info.changed;
+    if tx.send(SendData::VerifyHostKey(info)).is_err() || changed {
+        return HostKeyDecision::Abort;
+    }
+    loop {
+        match rx.recv() {
+            Ok(SendData::HostKeyDecision(decision)) => return decision,
+            Ok(SendData::Disconnect) | Err(_) => return HostKeyDecision::Abort,
+            Ok(_) => {}
+        }
+    }
+}
+
 impl MainWindow {
     #[cfg(not(target_arch = "wasm32"))]
     pub fn start_com_thread() -> Connection {
-        use web_time::Duration;
-
         let (tx, rx) = mpsc::channel::<SendData>();
         let (tx2, rx2) = mpsc::channel::<SendData>();
         std::thread::spawn(move || {
@@ -179,6 +303,7 @@ impl MainWindow {
                     if !data.read_data() {
                         std::thread::sleep(Duration::from_millis(25));
                     }
+                    data.keep_alive();
                 } else {
                     std::thread::sleep(Duration::from_millis(100));
                 }
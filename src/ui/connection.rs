@@ -2,7 +2,11 @@ use std::{collections::VecDeque, sync::mpsc};
 
 use web_time::{Duration, Instant};
 
-use crate::{Address, Terminal, TerminalResult};
+use crate::{
+    Address, Charset, CommandSettings, KeepAliveSettings, LocalSettings, ProxySettings,
+    RloginSettings, SerialSettings, SshSettings, TelnetSettings, Terminal, TerminalResult,
+    TlsSettings,
+};
 
 /// Connection is used for the ui and com thread to communicate.
 #[derive(Debug)]
@@ -13,6 +17,10 @@ pub struct Connection {
     pub tx: mpsc::Sender<SendData>,
     end_transfer: bool,
     buf: std::collections::VecDeque<u8>,
+    host_key_request: Option<HostKeyInfo>,
+    charset: Option<Charset>,
+    /// Reason why the remote side dropped the connection
+    connection_lost: Option<String>,
 }
 
 impl Connection {
@@ -24,6 +32,9 @@ impl Connection {
             rx,
             tx,
             buf: VecDeque::new(),
+            host_key_request: None,
+            charset: None,
+            connection_lost: None,
         }
     }
 
@@ -64,12 +75,27 @@ impl Connection {
                         self.is_connected = false;
                         break;
                     }
+                    SendData::ConnectionLost(reason) => {
+                        self.is_connected = false;
+                        self.end_transfer = true;
+                        self.connection_lost = Some(reason);
+                        break;
+                    }
                     SendData::EndTransfer => {
                         self.end_transfer = true;
                         break;
                     }
                     SendData::Connected => {
                         self.is_connected = true;
+                        self.connection_time = Instant::now();
+                        break;
+                    }
+                    SendData::VerifyHostKey(info) => {
+                        self.host_key_request = Some(info);
+                        break;
+                    }
+                    SendData::SetCharset(charset) => {
+                        self.charset = Some(charset);
                         break;
                     }
                     SendData::ConnectionError(err) => {
@@ -122,6 +148,36 @@ impl Connection {
         self.is_connected
     }
 
+    /// Returns the host key the com thread wants the user to verify.
+    pub fn take_host_key_request(&mut self) -> Option<HostKeyInfo> {
+        self.host_key_request.take()
+    }
+
+    /// Returns the reason if the remote side dropped the connection.
+    pub fn take_connection_lost(&mut self) -> Option<String> {
+        self.connection_lost.take()
+    }
+
+    /// Returns the charset the remote side agreed on.
+    pub fn take_charset(&mut self) -> Option<Charset> {
+        self.charset.take()
+    }
+
+    pub fn answer_host_key(&self, decision: HostKeyDecision) -> TerminalResult<()> {
+        self.tx.send(SendData::HostKeyDecision(decision))?;
+        Ok(())
+    }
+
+    pub fn set_window_size(&self, window_size: icy_engine::Size<u16>) -> TerminalResult<()> {
+        self.tx.send(SendData::SetWindowSize(window_size))?;
+        Ok(())
+    }
+
+    pub(crate) fn set_transfer_active(&self, active: bool) -> TerminalResult<()> {
+        self.tx.send(SendData::TransferActive(active))?;
+        Ok(())
+    }
+
     pub(crate) fn set_baud_rate(&self, baud_rate: u32) -> TerminalResult<()> {
         self.tx.send(SendData::SetBaudRate(baud_rate))?;
         Ok(())
@@ -132,13 +188,13 @@ impl Connection {
         call_adr: &Address,
         timeout: Duration,
         window_size: icy_engine::Size<u16>,
+        global_proxy: &ProxySettings,
     ) -> TerminalResult<()> {
-        self.tx
-            .send(SendData::OpenConnection(OpenConnectionData::from(
-                call_adr,
-                timeout,
-                window_size,
-            )))?;
+        let mut connection_data = OpenConnectionData::from(call_adr, timeout, window_size);
+        if call_adr.proxy.is_none() {
+            connection_data.proxy = global_proxy.clone();
+        }
+        self.tx.send(SendData::OpenConnection(connection_data))?;
         Ok(())
     }
 
@@ -169,6 +225,15 @@ pub struct OpenConnectionData {
     pub protocol: crate::Protocol,
     pub timeout: Duration,
     pub window_size: icy_engine::Size<u16>,
+    pub serial: SerialSettings,
+    pub ssh: SshSettings,
+    pub telnet: TelnetSettings,
+    pub rlogin: RloginSettings,
+    pub tls: TlsSettings,
+    pub command: CommandSettings,
+    pub local: LocalSettings,
+    pub keepalive: KeepAliveSettings,
+    pub proxy: ProxySettings,
 }
 
 impl OpenConnectionData {
@@ -181,20 +246,56 @@ impl OpenConnectionData {
             protocol: call_adr.protocol,
             timeout,
             window_size,
+            serial: call_adr.serial.clone(),
+            ssh: call_adr.ssh.clone(),
+            telnet: call_adr.telnet.clone(),
+            rlogin: call_adr.rlogin.clone(),
+            tls: call_adr.tls.clone(),
+            command: call_adr.command.clone(),
+            local: call_adr.local.clone(),
+            keepalive: call_adr.keepalive.clone(),
+            proxy: call_adr.proxy.clone().unwrap_or_default(),
         }
     }
 }
 
+/// A server key that isn't in the known hosts file
+#[derive(Debug, Clone)]
+pub struct HostKeyInfo {
+    pub host: String,
+    pub fingerprint: String,
+    /// The known hosts file contains a different key for the host.
+    /// The connection fails in that case, the user can't accept the key.
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostKeyDecision {
+    AcceptOnce,
+    AcceptAndSave,
+    Abort,
+}
+
 /// Data that is sent to the connection thread
 #[derive(Debug)]
 pub enum SendData {
     OpenConnection(OpenConnectionData),
     ConnectionError(String),
     Connected,
+    /// com thread waits for a `HostKeyDecision` from the ui thread
+    VerifyHostKey(HostKeyInfo),
+    HostKeyDecision(HostKeyDecision),
 
     Data(Vec<u8>),
     Disconnect,
+    /// The connection dropped without a `Disconnect` from the ui
+    ConnectionLost(String),
     EndTransfer,
     CancelTransfer,
+    /// Keepalives are paused while a file transfer runs
+    TransferActive(bool),
     SetBaudRate(u32),
+    SetWindowSize(icy_engine::Size<u16>),
+    /// The remote side switched to a different charset
+    SetCharset(Charset),
 }
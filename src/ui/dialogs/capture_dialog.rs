@@ -6,9 +6,9 @@ use egui::TextEdit;
 use egui::{Frame, Layout};
 use egui_file::FileDialog;
 use i18n_embed_fl::fl;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::{ui::MainWindowMode, Options};
+use crate::{features::RECORDING_EXTENSION, ui::MainWindowMode, Options};
 
 #[derive(Default)]
 pub struct DialogState {
@@ -17,6 +17,13 @@ pub struct DialogState {
 
     /// debug spew prevention
     pub show_capture_error: bool,
+
+    /// Records the session with timing, the main window starts & stops the recorder
+    pub record_session: bool,
+    recording_file_dialog: Option<FileDialog>,
+    playback_file_dialog: Option<FileDialog>,
+    /// Recording the user wants to play back
+    pub playback_request: Option<PathBuf>,
 }
 
 pub enum Message {
@@ -25,6 +32,12 @@ pub enum Message {
     OpenFolder,
     CloseDialog,
     ChangeCaptureFileName(String),
+    StartRecording,
+    StopRecording,
+    SelectRecordingFile,
+    ChangeRecordingFileName(String),
+    SelectPlaybackFile,
+    PlayRecording(PathBuf),
 }
 
 impl DialogState {
@@ -77,10 +90,52 @@ impl MainWindowState {
                     }
                 });
                 ui.add_space(8.);
+
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.label(RichText::new(fl!(
+                        crate::LANGUAGE_LOADER,
+                        "capture-dialog-recording-label"
+                    )));
+                    ui.horizontal(|ui| {
+                        let mut file = self.options.recording_filename.clone();
+                        let r = ui.add(TextEdit::singleline(&mut file).desired_width(370.));
+                        if r.changed() {
+                            result = Some(Message::ChangeRecordingFileName(file));
+                        }
+                        if ui.button("…").clicked() {
+                            result = Some(Message::SelectRecordingFile);
+                        }
+                    });
+                    ui.add_space(8.);
+                }
                 ui.separator();
                 ui.add_space(4.0);
 
                 ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if self.capture_dialog.record_session {
+                        if ui
+                            .button(fl!(crate::LANGUAGE_LOADER, "toolbar-stop-recording"))
+                            .clicked()
+                        {
+                            result = Some(Message::StopRecording);
+                            close_dialog = true;
+                        }
+                    } else if ui
+                        .add_enabled(
+                            !self.options.recording_filename.is_empty(),
+                            egui::Button::new(fl!(
+                                crate::LANGUAGE_LOADER,
+                                "capture-dialog-record-button"
+                            )),
+                        )
+                        .clicked()
+                    {
+                        result = Some(Message::StartRecording);
+                        close_dialog = true;
+                    }
+
                     if self.capture_dialog.capture_session {
                         if ui
                             .button(fl!(crate::LANGUAGE_LOADER, "toolbar-stop-capture"))
@@ -114,6 +169,14 @@ impl MainWindowState {
                         }
                     }
 
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui
+                        .button(fl!(crate::LANGUAGE_LOADER, "capture-dialog-play-button"))
+                        .clicked()
+                    {
+                        result = Some(Message::SelectPlaybackFile);
+                    }
+
                     if ui
                         .button(fl!(
                             crate::LANGUAGE_LOADER,
@@ -136,6 +199,28 @@ impl MainWindowState {
             }
         }
 
+        if let Some(dialog) = &mut self.capture_dialog.recording_file_dialog {
+            if dialog.show(ctx).selected() {
+                if let Some(path) = dialog.path() {
+                    let mut path = path.to_path_buf();
+                    if path.extension().is_none() {
+                        path.set_extension(RECORDING_EXTENSION);
+                    }
+                    if let Some(s) = path.to_str() {
+                        result = Some(Message::ChangeRecordingFileName(s.to_string()));
+                    }
+                }
+            }
+        }
+
+        if let Some(dialog) = &mut self.capture_dialog.playback_file_dialog {
+            if dialog.show(ctx).selected() {
+                if let Some(path) = dialog.path() {
+                    result = Some(Message::PlayRecording(path.to_path_buf()));
+                }
+            }
+        }
+
         if !open || close_dialog {
             result = Some(Message::CloseDialog);
         }
@@ -172,6 +257,38 @@ fn update_state(state: &mut MainWindowState, msg_opt: Option<Message>) {
             state.capture_dialog.show_capture_error = false;
             state.store_options();
         }
+        Some(Message::StartRecording) => {
+            state.capture_dialog.record_session = true;
+        }
+        Some(Message::StopRecording) => {
+            state.capture_dialog.record_session = false;
+        }
+        Some(Message::SelectRecordingFile) => {
+            let initial_path = if state.options.recording_filename.is_empty() {
+                None
+            } else {
+                Some(PathBuf::from(&state.options.recording_filename))
+            };
+            let mut dialog: FileDialog = FileDialog::save_file(initial_path);
+            dialog.open();
+            state.capture_dialog.recording_file_dialog = Some(dialog);
+        }
+        Some(Message::ChangeRecordingFileName(file)) => {
+            state.options.recording_filename = file;
+            state.store_options();
+        }
+        Some(Message::SelectPlaybackFile) => {
+            let initial_path = Path::new(&state.options.recording_filename)
+                .parent()
+                .map(std::path::Path::to_path_buf);
+            let mut dialog: FileDialog = FileDialog::open_file(initial_path);
+            dialog.open();
+            state.capture_dialog.playback_file_dialog = Some(dialog);
+        }
+        Some(Message::PlayRecording(path)) => {
+            state.capture_dialog.playback_request = Some(path);
+            state.mode = MainWindowMode::ShowTerminal;
+        }
         _ => {}
     }
 }
@@ -208,6 +325,30 @@ mod tests {
         assert!(!state.options_written);
     }
 
+    #[test]
+    fn test_start_stop_recording() {
+        let mut state: MainWindowState = MainWindowState::default();
+        update_state(&mut state, Some(super::Message::StartRecording));
+        assert!(state.capture_dialog.record_session);
+        assert!(!state.capture_dialog.capture_session);
+        update_state(&mut state, Some(super::Message::StopRecording));
+        assert!(!state.capture_dialog.record_session);
+        assert!(!state.options_written);
+    }
+
+    #[test]
+    fn test_change_recording_filename() {
+        let mut state: MainWindowState = MainWindowState::default();
+        update_state(
+            &mut state,
+            Some(super::Message::ChangeRecordingFileName(
+                "session.icyrec".to_string(),
+            )),
+        );
+        assert_eq!("session.icyrec", state.options.recording_filename);
+        assert!(state.options_written);
+    }
+
     #[test]
     fn test_change_filename() {
         let mut state: MainWindowState = MainWindowState::default();
@@ -9,7 +9,13 @@ use i18n_embed_fl::fl;
 use icy_engine::ansi::{BaudEmulation, MusicOption};
 
 use crate::{
-    addresses::{self, Address, Terminal},
+    addresses::{
+        self, Address, Charset, CommandSettings, FlowControl, KeepAliveSettings, Parity,
+        ProxySettings, ProxyType, ReconnectSettings, SerialSettings, SshAuthMethod, SshSettings,
+        StopBits, TelnetKeepAlive, TelnetSettings, Terminal, TlsSettings, Trigger, TriggerAction,
+    },
+    features::{check_trigger, parse_login_expression},
+    protocol::TransferType,
     ui::{MainWindow, MainWindowMode, ScreenMode, DEFAULT_MODES},
     util::Rng,
     AddressBook,
@@ -39,6 +45,8 @@ pub struct DialogState {
     pub addresses: AddressBook,
 
     pub cur_addr: usize,
+    /// Address dialed by the modem emulation, it's never stored in the phone book.
+    pub modem_address: Option<Address>,
     pub selected_bbs: Option<usize>,
     pub scroll_address_list_to_bottom: bool,
     pub dialing_directory_filter: DialingDirectoryFilter,
@@ -51,6 +59,14 @@ pub struct DialogState {
 }
 
 impl DialogState {
+    /// The address of the current connection.
+    #[must_use]
+    pub fn current_address(&self) -> Option<&Address> {
+        self.modem_address
+            .as_ref()
+            .or_else(|| self.addresses.addresses.get(self.cur_addr))
+    }
+
     pub fn get_address_mut(&mut self, uuid: Option<usize>) -> &mut Address {
         if uuid.is_none() {
             return &mut self.addresses.addresses[0];
@@ -394,6 +410,13 @@ impl DialogState {
             ui.label("✆");
             ui.label(adr.number_of_calls.to_string());
             ui.add_space(16.);
+
+            if adr.number_of_reconnects > 0 {
+                ui.label("🔄")
+                    .on_hover_text(fl!(crate::LANGUAGE_LOADER, "dialing_directory-reconnects"));
+                ui.label(adr.number_of_reconnects.to_string());
+                ui.add_space(16.);
+            }
             /*
             ui.label("⮉");
             ui.label(adr.uploaded_bytes.to_string());
@@ -526,6 +549,13 @@ impl DialogState {
                     });
                 ui.end_row();
 
+                if matches!(
+                    adr.protocol,
+                    addresses::Protocol::Telnet | addresses::Protocol::TelnetTls
+                ) {
+                    render_telnet_settings(ui, &mut adr.telnet);
+                }
+
                 if adr.terminal_type == Terminal::Ansi {
                     ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
                         ui.label(RichText::new(fl!(
@@ -621,6 +651,24 @@ impl DialogState {
                 });
                 ui.end_row();
 
+                if self.get_address_mut(self.selected_bbs).protocol == addresses::Protocol::Ssh {
+                    let show_passwords = self.show_passwords;
+                    render_ssh_settings(
+                        ui,
+                        &mut self.get_address_mut(self.selected_bbs).ssh,
+                        show_passwords,
+                    );
+                }
+                if matches!(
+                    self.get_address_mut(self.selected_bbs).protocol,
+                    addresses::Protocol::Telnet | addresses::Protocol::TelnetTls
+                ) {
+                    render_telnet_environment(
+                        ui,
+                        &mut self.get_address_mut(self.selected_bbs).telnet,
+                    );
+                }
+
                 // Autologin row
                 ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.label(RichText::new(fl!(
@@ -631,8 +679,23 @@ impl DialogState {
                 ui.add(
                     TextEdit::singleline(&mut self.get_address_mut(self.selected_bbs).auto_login)
                         .desired_width(f32::INFINITY),
-                );
+                )
+                .on_hover_text(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "dialing_directory-autologin-help"
+                ));
                 ui.end_row();
+                if let Err(err) =
+                    parse_login_expression(&self.get_address_mut(self.selected_bbs).auto_login)
+                {
+                    ui.label("");
+                    ui.label(RichText::new(err.to_string()).color(ui.visuals().error_fg_color));
+                    ui.end_row();
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                render_script_settings(ui, &mut self.get_address_mut(self.selected_bbs).scripts);
+                render_trigger_settings(ui, &mut self.get_address_mut(self.selected_bbs).triggers);
                 ui.label("");
 
                 ui.checkbox(
@@ -688,6 +751,7 @@ impl DialogState {
     }
 
     fn render_server_catogery(&mut self, ui: &mut egui::Ui) {
+        let show_passwords = self.show_passwords;
         let adr = self.get_address_mut(self.selected_bbs);
         egui::Grid::new("some_unique_id")
             .num_columns(2)
@@ -696,10 +760,15 @@ impl DialogState {
             .show(ui, |ui| {
                 // Addreess row
                 ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
-                    ui.label(RichText::new(fl!(
-                        crate::LANGUAGE_LOADER,
-                        "dialing_directory-address"
-                    )));
+                    ui.label(RichText::new(match adr.protocol {
+                        addresses::Protocol::Serial => {
+                            fl!(crate::LANGUAGE_LOADER, "dialing_directory-phone-number")
+                        }
+                        addresses::Protocol::Local => {
+                            fl!(crate::LANGUAGE_LOADER, "dialing_directory-local-program")
+                        }
+                        _ => fl!(crate::LANGUAGE_LOADER, "dialing_directory-address"),
+                    }));
                 });
                 ui.add(TextEdit::singleline(&mut adr.address));
                 ui.end_row();
@@ -722,6 +791,79 @@ impl DialogState {
                         }
                     });
                 ui.end_row();
+
+                if adr.protocol == addresses::Protocol::Serial {
+                    render_serial_settings(ui, &mut adr.serial);
+                }
+                if adr.protocol == addresses::Protocol::TelnetTls {
+                    render_tls_settings(ui, &mut adr.tls);
+                }
+                if adr.protocol == addresses::Protocol::Rlogin {
+                    ui.label("");
+                    ui.checkbox(
+                        &mut adr.rlogin.send_password,
+                        fl!(
+                            crate::LANGUAGE_LOADER,
+                            "dialing_directory-rlogin-send-password"
+                        ),
+                    )
+                    .on_hover_text(fl!(
+                        crate::LANGUAGE_LOADER,
+                        "dialing_directory-rlogin-send-password-hint"
+                    ));
+                    ui.end_row();
+                }
+                if adr.protocol == addresses::Protocol::Command {
+                    render_command_settings(ui, &mut adr.command);
+                }
+                if adr.protocol == addresses::Protocol::Local {
+                    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.label(RichText::new(fl!(
+                            crate::LANGUAGE_LOADER,
+                            "dialing_directory-local-working-directory"
+                        )));
+                    });
+                    ui.add(
+                        TextEdit::singleline(&mut adr.local.working_directory)
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.end_row();
+                }
+
+                // the command connects on its own
+                if !matches!(
+                    adr.protocol,
+                    addresses::Protocol::Serial
+                        | addresses::Protocol::Command
+                        | addresses::Protocol::Local
+                ) {
+                    let mut own_proxy = adr.proxy.is_some();
+                    ui.label("");
+                    if ui
+                        .checkbox(
+                            &mut own_proxy,
+                            fl!(crate::LANGUAGE_LOADER, "dialing_directory-proxy-override"),
+                        )
+                        .changed()
+                    {
+                        adr.proxy = own_proxy.then(ProxySettings::default);
+                    }
+                    ui.end_row();
+
+                    if let Some(proxy) = &mut adr.proxy {
+                        render_proxy_settings(ui, proxy, show_passwords);
+                    }
+                }
+
+                if adr.protocol != addresses::Protocol::Serial {
+                    render_reconnect_settings(ui, &mut adr.reconnect);
+                }
+                let uses_telnet = match adr.protocol {
+                    addresses::Protocol::Telnet | addresses::Protocol::TelnetTls => true,
+                    addresses::Protocol::Command => adr.command.use_telnet,
+                    _ => false,
+                };
+                render_keepalive_settings(ui, &mut adr.keepalive, uses_telnet);
             });
     }
 
@@ -835,6 +977,700 @@ impl egui::Widget for AddressRow {
     }
 }
 
+fn render_serial_settings(ui: &mut egui::Ui, serial: &mut SerialSettings) {
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-serial-device"
+        )));
+    });
+    ui.add(TextEdit::singleline(&mut serial.device));
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-serial-baud-rate"
+        )));
+    });
+    egui::ComboBox::from_id_source("serial_baud_rate")
+        .selected_text(RichText::new(serial.baud_rate.to_string()))
+        .width(PROTOCOL_COMBOBOX_WIDTH)
+        .show_ui(ui, |ui| {
+            for baud in &SerialSettings::BAUD_RATES {
+                let label = RichText::new(baud.to_string());
+                ui.selectable_value(&mut serial.baud_rate, *baud, label);
+            }
+        });
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-serial-format"
+        )));
+    });
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("serial_data_bits")
+            .selected_text(RichText::new(serial.data_bits.to_string()))
+            .width(40.)
+            .show_ui(ui, |ui| {
+                for bits in 5..=8 {
+                    let label = RichText::new(bits.to_string());
+                    ui.selectable_value(&mut serial.data_bits, bits, label);
+                }
+            });
+        egui::ComboBox::from_id_source("serial_parity")
+            .selected_text(RichText::new(format!("{:?}", serial.parity)))
+            .width(60.)
+            .show_ui(ui, |ui| {
+                for parity in &Parity::ALL {
+                    let label = RichText::new(format!("{parity:?}"));
+                    ui.selectable_value(&mut serial.parity, *parity, label);
+                }
+            });
+        egui::ComboBox::from_id_source("serial_stop_bits")
+            .selected_text(RichText::new(format!("{:?}", serial.stop_bits)))
+            .width(50.)
+            .show_ui(ui, |ui| {
+                for stop_bits in &StopBits::ALL {
+                    let label = RichText::new(format!("{stop_bits:?}"));
+                    ui.selectable_value(&mut serial.stop_bits, *stop_bits, label);
+                }
+            });
+    });
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-serial-flow-control"
+        )));
+    });
+    egui::ComboBox::from_id_source("serial_flow_control")
+        .selected_text(RichText::new(format!("{}", serial.flow_control)))
+        .width(PROTOCOL_COMBOBOX_WIDTH)
+        .show_ui(ui, |ui| {
+            for flow_control in &FlowControl::ALL {
+                let label = RichText::new(format!("{flow_control}"));
+                ui.selectable_value(&mut serial.flow_control, *flow_control, label);
+            }
+        });
+    ui.end_row();
+}
+
+fn render_tls_settings(ui: &mut egui::Ui, tls: &mut TlsSettings) {
+    ui.label("");
+    ui.checkbox(
+        &mut tls.verify_certificate,
+        fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-tls-verify-certificate"
+        ),
+    );
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-tls-fingerprint"
+        )));
+    });
+    ui.add(
+        TextEdit::singleline(&mut tls.fingerprint)
+            .hint_text("SHA256 AB:CD:…")
+            .desired_width(f32::INFINITY),
+    );
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-tls-server-name"
+        )));
+    });
+    ui.add(TextEdit::singleline(&mut tls.server_name));
+    ui.end_row();
+}
+
+fn render_reconnect_settings(ui: &mut egui::Ui, reconnect: &mut ReconnectSettings) {
+    ui.label("");
+    ui.checkbox(
+        &mut reconnect.enabled,
+        fl!(crate::LANGUAGE_LOADER, "dialing_directory-reconnect"),
+    );
+    ui.end_row();
+    if !reconnect.enabled {
+        return;
+    }
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-reconnect-retries"
+        )));
+    });
+    ui.add(egui::DragValue::new(&mut reconnect.retries).clamp_range(1..=100));
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-reconnect-delay"
+        )));
+    });
+    ui.add(
+        egui::DragValue::new(&mut reconnect.delay)
+            .clamp_range(1..=300)
+            .suffix(" s"),
+    )
+    .on_hover_text(fl!(
+        crate::LANGUAGE_LOADER,
+        "dialing_directory-reconnect-delay-hint"
+    ));
+    ui.end_row();
+}
+
+fn render_keepalive_settings(
+    ui: &mut egui::Ui,
+    keepalive: &mut KeepAliveSettings,
+    uses_telnet: bool,
+) {
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-keepalive-interval"
+        )));
+    });
+    ui.add(
+        egui::DragValue::new(&mut keepalive.interval)
+            .clamp_range(0..=3600)
+            .suffix(" s"),
+    )
+    .on_hover_text(fl!(
+        crate::LANGUAGE_LOADER,
+        "dialing_directory-keepalive-interval-hint"
+    ));
+    ui.end_row();
+
+    if uses_telnet && keepalive.interval > 0 {
+        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label(RichText::new(fl!(
+                crate::LANGUAGE_LOADER,
+                "dialing_directory-keepalive-telnet-command"
+            )));
+        });
+        let label = |command| match command {
+            TelnetKeepAlive::Nop => "NOP".to_string(),
+            TelnetKeepAlive::AreYouThere => fl!(
+                crate::LANGUAGE_LOADER,
+                "dialing_directory-keepalive-telnet-ayt"
+            ),
+        };
+        egui::ComboBox::from_id_source("combobox_keepalive")
+            .selected_text(label(keepalive.telnet_command))
+            .show_ui(ui, |ui| {
+                for command in [TelnetKeepAlive::Nop, TelnetKeepAlive::AreYouThere] {
+                    ui.selectable_value(&mut keepalive.telnet_command, command, label(command));
+                }
+            });
+        ui.end_row();
+    }
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-keepalive-anti-idle"
+        )));
+    });
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut keepalive.anti_idle_string)
+                .hint_text("\\r")
+                .desired_width(80.),
+        )
+        .on_hover_text(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-keepalive-anti-idle-hint"
+        ));
+        ui.add(
+            egui::DragValue::new(&mut keepalive.anti_idle_minutes)
+                .clamp_range(1..=120)
+                .suffix(" min"),
+        );
+    });
+    ui.end_row();
+    if let Err(err) = keepalive.anti_idle_data() {
+        ui.label("");
+        ui.label(RichText::new(err.to_string()).color(ui.visuals().error_fg_color));
+        ui.end_row();
+    }
+}
+
+fn render_command_settings(ui: &mut egui::Ui, command: &mut CommandSettings) {
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-command"
+        )));
+    });
+    ui.add(
+        TextEdit::singleline(&mut command.command)
+            .hint_text("ssh -W %h:%p jumphost")
+            .desired_width(f32::INFINITY),
+    )
+    .on_hover_text(fl!(
+        crate::LANGUAGE_LOADER,
+        "dialing_directory-command-hint"
+    ));
+    ui.end_row();
+
+    ui.label("");
+    ui.checkbox(
+        &mut command.use_telnet,
+        fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-command-use-telnet"
+        ),
+    );
+    ui.end_row();
+}
+
+/// Rows for a proxy grid, used for the global proxy settings as well.
+pub(crate) fn render_proxy_settings(
+    ui: &mut egui::Ui,
+    proxy: &mut ProxySettings,
+    show_passwords: bool,
+) {
+    let type_name = |proxy_type: ProxyType| {
+        if proxy_type == ProxyType::None {
+            fl!(crate::LANGUAGE_LOADER, "dialing_directory-proxy-none")
+        } else {
+            proxy_type.to_string()
+        }
+    };
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-proxy-type"
+        )));
+    });
+    egui::ComboBox::from_id_source("proxy_type")
+        .selected_text(RichText::new(type_name(proxy.proxy_type)))
+        .width(PROTOCOL_COMBOBOX_WIDTH)
+        .show_ui(ui, |ui| {
+            for proxy_type in &ProxyType::ALL {
+                let label = RichText::new(type_name(*proxy_type));
+                ui.selectable_value(&mut proxy.proxy_type, *proxy_type, label);
+            }
+        });
+    ui.end_row();
+
+    if proxy.proxy_type == ProxyType::None {
+        return;
+    }
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-proxy-address"
+        )));
+    });
+    ui.add(
+        TextEdit::singleline(&mut proxy.address)
+            .hint_text(format!("host:{}", proxy.proxy_type.default_port())),
+    );
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-proxy-user"
+        )));
+    });
+    ui.add(TextEdit::singleline(&mut proxy.user_name));
+    ui.end_row();
+
+    if proxy.proxy_type != ProxyType::Socks4a {
+        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label(RichText::new(fl!(
+                crate::LANGUAGE_LOADER,
+                "dialing_directory-proxy-password"
+            )));
+        });
+        ui.add(TextEdit::singleline(&mut proxy.password).password(!show_passwords));
+        ui.end_row();
+    }
+}
+
+fn render_ssh_settings(ui: &mut egui::Ui, ssh: &mut SshSettings, show_passwords: bool) {
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-ssh-key-file"
+        )));
+    });
+    ui.add(
+        TextEdit::singleline(&mut ssh.key_file)
+            .hint_text("~/.ssh/id_ed25519")
+            .desired_width(f32::INFINITY),
+    );
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-ssh-key-passphrase"
+        )));
+    });
+    ui.add(TextEdit::singleline(&mut ssh.key_passphrase).password(!show_passwords));
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-ssh-auth-order"
+        )));
+    });
+    ui.vertical(|ui| {
+        let len = ssh.auth_order.len();
+        let mut swap = None;
+        let mut remove = None;
+        for (i, method) in ssh.auth_order.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                    swap = Some(i - 1);
+                }
+                if ui
+                    .add_enabled(i + 1 < len, egui::Button::new("⬇"))
+                    .clicked()
+                {
+                    swap = Some(i);
+                }
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+                ui.label(format!("{}. {method}", i + 1));
+            });
+        }
+        if let Some(i) = swap {
+            ssh.auth_order.swap(i, i + 1);
+        }
+        if let Some(i) = remove {
+            ssh.auth_order.remove(i);
+        }
+
+        let missing: Vec<SshAuthMethod> = SshAuthMethod::ALL
+            .iter()
+            .filter(|method| !ssh.auth_order.contains(method))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            egui::ComboBox::from_id_source("ssh_add_auth_method")
+                .selected_text(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "dialing_directory-ssh-add-auth-method"
+                ))
+                .width(PROTOCOL_COMBOBOX_WIDTH)
+                .show_ui(ui, |ui| {
+                    for method in missing {
+                        if ui.selectable_label(false, method.to_string()).clicked() {
+                            ssh.auth_order.push(method);
+                        }
+                    }
+                });
+        }
+    });
+    ui.end_row();
+}
+
+fn render_telnet_settings(ui: &mut egui::Ui, telnet: &mut TelnetSettings) {
+    ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-telnet-terminal-types"
+        )));
+    });
+    ui.vertical(|ui| {
+        let len = telnet.terminal_types.len();
+        let mut swap = None;
+        let mut remove = None;
+        for (i, name) in telnet.terminal_types.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                    swap = Some(i - 1);
+                }
+                if ui
+                    .add_enabled(i + 1 < len, egui::Button::new("⬇"))
+                    .clicked()
+                {
+                    swap = Some(i);
+                }
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+                ui.add(TextEdit::singleline(name).desired_width(150.));
+            });
+        }
+        if let Some(i) = swap {
+            telnet.terminal_types.swap(i, i + 1);
+        }
+        if let Some(i) = remove {
+            telnet.terminal_types.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("➕").clicked() {
+                telnet.terminal_types.push(String::new());
+            }
+            if telnet.terminal_types.is_empty() {
+                ui.label(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "dialing_directory-telnet-terminal-types-default"
+                ));
+            }
+        });
+    });
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-telnet-charsets"
+        )));
+    });
+    ui.vertical(|ui| {
+        let len = telnet.charsets.len();
+        let mut swap = None;
+        let mut remove = None;
+        for (i, charset) in telnet.charsets.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                    swap = Some(i - 1);
+                }
+                if ui
+                    .add_enabled(i + 1 < len, egui::Button::new("⬇"))
+                    .clicked()
+                {
+                    swap = Some(i);
+                }
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+                ui.label(format!("{}. {charset}", i + 1));
+            });
+        }
+        if let Some(i) = swap {
+            telnet.charsets.swap(i, i + 1);
+        }
+        if let Some(i) = remove {
+            telnet.charsets.remove(i);
+        }
+
+        let missing: Vec<Charset> = Charset::ALL
+            .iter()
+            .filter(|charset| !telnet.charsets.contains(charset))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            egui::ComboBox::from_id_source("telnet_add_charset")
+                .selected_text(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "dialing_directory-telnet-add-charset"
+                ))
+                .width(PROTOCOL_COMBOBOX_WIDTH)
+                .show_ui(ui, |ui| {
+                    for charset in missing {
+                        if ui.selectable_label(false, charset.to_string()).clicked() {
+                            telnet.charsets.push(charset);
+                        }
+                    }
+                });
+        }
+    });
+    ui.end_row();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn render_script_settings(ui: &mut egui::Ui, scripts: &mut addresses::ScriptSettings) {
+    ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-login-script"
+        )));
+    });
+    ui.add(TextEdit::singleline(&mut scripts.login_script).desired_width(f32::INFINITY));
+    ui.end_row();
+
+    ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-script-macros"
+        )));
+    });
+    ui.vertical(|ui| {
+        let len = scripts.macros.len();
+        let mut swap = None;
+        let mut remove = None;
+        for (i, path) in scripts.macros.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                    swap = Some(i - 1);
+                }
+                if ui
+                    .add_enabled(i + 1 < len, egui::Button::new("⬇"))
+                    .clicked()
+                {
+                    swap = Some(i);
+                }
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+                ui.add(TextEdit::singleline(path).desired_width(f32::INFINITY));
+            });
+        }
+        if let Some(i) = swap {
+            scripts.macros.swap(i, i + 1);
+        }
+        if let Some(i) = remove {
+            scripts.macros.remove(i);
+        }
+        if ui.button("➕").clicked() {
+            scripts.macros.push(String::new());
+        }
+    });
+    ui.end_row();
+}
+
+fn trigger_action_label(action: &TriggerAction) -> String {
+    match action {
+        TriggerAction::Send(_) => fl!(crate::LANGUAGE_LOADER, "dialing_directory-trigger-send"),
+        TriggerAction::Beep => fl!(crate::LANGUAGE_LOADER, "dialing_directory-trigger-beep"),
+        TriggerAction::Download(_) => {
+            fl!(crate::LANGUAGE_LOADER, "dialing_directory-trigger-download")
+        }
+        TriggerAction::Upload(_) => fl!(crate::LANGUAGE_LOADER, "dialing_directory-trigger-upload"),
+        TriggerAction::StartCapture => {
+            fl!(crate::LANGUAGE_LOADER, "dialing_directory-trigger-capture")
+        }
+        TriggerAction::Highlight => {
+            fl!(
+                crate::LANGUAGE_LOADER,
+                "dialing_directory-trigger-highlight"
+            )
+        }
+        TriggerAction::Notify => fl!(crate::LANGUAGE_LOADER, "dialing_directory-trigger-notify"),
+    }
+}
+
+fn render_trigger_settings(ui: &mut egui::Ui, triggers: &mut Vec<Trigger>) {
+    ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-triggers"
+        )));
+    });
+    ui.vertical(|ui| {
+        let mut remove = None;
+        for (i, trigger) in triggers.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+                ui.add(
+                    TextEdit::singleline(&mut trigger.pattern)
+                        .hint_text(fl!(
+                            crate::LANGUAGE_LOADER,
+                            "dialing_directory-trigger-pattern"
+                        ))
+                        .desired_width(150.),
+                );
+                ui.checkbox(
+                    &mut trigger.is_regex,
+                    fl!(crate::LANGUAGE_LOADER, "dialing_directory-trigger-regex"),
+                );
+                egui::ComboBox::from_id_source(("trigger_action", i))
+                    .selected_text(trigger_action_label(&trigger.action))
+                    .show_ui(ui, |ui| {
+                        for action in [
+                            TriggerAction::Send(String::new()),
+                            TriggerAction::Beep,
+                            TriggerAction::Download(TransferType::default()),
+                            TriggerAction::Upload(TransferType::default()),
+                            TriggerAction::StartCapture,
+                            TriggerAction::Highlight,
+                            TriggerAction::Notify,
+                        ] {
+                            let selected = action.name() == trigger.action.name();
+                            let label = trigger_action_label(&action);
+                            if ui.selectable_label(selected, label).clicked() && !selected {
+                                trigger.action = action;
+                            }
+                        }
+                    });
+                match &mut trigger.action {
+                    TriggerAction::Send(text) => {
+                        ui.add(TextEdit::singleline(text).desired_width(f32::INFINITY));
+                    }
+                    TriggerAction::Download(protocol) | TriggerAction::Upload(protocol) => {
+                        egui::ComboBox::from_id_source(("trigger_protocol", i))
+                            .selected_text(protocol.name())
+                            .show_ui(ui, |ui| {
+                                for p in TransferType::ALL {
+                                    ui.selectable_value(protocol, p, p.name());
+                                }
+                            });
+                    }
+                    _ => {}
+                }
+            });
+            if let Err(err) = check_trigger(trigger) {
+                ui.label(RichText::new(err).color(ui.visuals().error_fg_color));
+            }
+        }
+        if let Some(i) = remove {
+            triggers.remove(i);
+        }
+        if ui.button("➕").clicked() {
+            triggers.push(Trigger::default());
+        }
+    });
+    ui.end_row();
+}
+
+fn render_telnet_environment(ui: &mut egui::Ui, telnet: &mut TelnetSettings) {
+    ui.with_layout(Layout::right_to_left(egui::Align::TOP), |ui| {
+        ui.label(RichText::new(fl!(
+            crate::LANGUAGE_LOADER,
+            "dialing_directory-telnet-environment"
+        )));
+    });
+    ui.vertical(|ui| {
+        let mut remove = None;
+        for (i, (name, value)) in telnet.environment.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+                ui.add(
+                    TextEdit::singleline(name)
+                        .hint_text("TERM")
+                        .desired_width(100.),
+                );
+                ui.label("=");
+                ui.add(TextEdit::singleline(value).desired_width(150.));
+            });
+        }
+        if let Some(i) = remove {
+            telnet.environment.remove(i);
+        }
+        if ui.button("➕").clicked() {
+            telnet.environment.push((String::new(), String::new()));
+        }
+    });
+    ui.end_row();
+}
+
 pub fn view_dialing_directory(window: &mut MainWindow, ctx: &egui::Context) {
     let mut open = true;
     let available_rect = ctx.available_rect();
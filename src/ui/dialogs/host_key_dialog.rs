@@ -0,0 +1,95 @@
+use eframe::egui::{self, RichText};
+use egui_modal::Modal;
+use i18n_embed_fl::fl;
+
+use crate::{
+    check_error,
+    ui::{connection::HostKeyDecision, MainWindow, MainWindowMode},
+};
+
+pub fn show_dialog(window: &mut MainWindow, ctx: &egui::Context) {
+    let Some(info) = window.host_key.clone() else {
+        window.set_mode(MainWindowMode::ShowTerminal);
+        return;
+    };
+    let mut decision = None;
+    if ctx.input(|i| i.key_down(egui::Key::Escape)) {
+        decision = Some(HostKeyDecision::Abort);
+    }
+
+    let modal = Modal::new(ctx, "host_key_modal");
+    modal.show(|ui| {
+        if info.changed {
+            modal.title(ui, fl!(crate::LANGUAGE_LOADER, "host-key-changed-title"));
+        } else {
+            modal.title(ui, fl!(crate::LANGUAGE_LOADER, "host-key-unknown-title"));
+        }
+        modal.frame(ui, |ui: &mut egui::Ui| {
+            if info.changed {
+                ui.label(
+                    RichText::new(fl!(
+                        crate::LANGUAGE_LOADER,
+                        "host-key-changed-warning",
+                        host = info.host.clone()
+                    ))
+                    .strong()
+                    .color(ui.visuals().error_fg_color),
+                );
+            } else {
+                ui.label(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "host-key-unknown-question",
+                    host = info.host.clone()
+                ));
+            }
+            ui.add_space(8.0);
+            ui.label(RichText::new(&info.fingerprint).monospace());
+        });
+        modal.buttons(ui, |ui| {
+            if info.changed {
+                if modal
+                    .button(ui, fl!(crate::LANGUAGE_LOADER, "host-key-close-button"))
+                    .clicked()
+                {
+                    decision = Some(HostKeyDecision::Abort);
+                }
+                return;
+            }
+            if modal
+                .button(
+                    ui,
+                    fl!(crate::LANGUAGE_LOADER, "host-key-accept-save-button"),
+                )
+                .clicked()
+            {
+                decision = Some(HostKeyDecision::AcceptAndSave);
+            }
+            if modal
+                .button(
+                    ui,
+                    fl!(crate::LANGUAGE_LOADER, "host-key-accept-once-button"),
+                )
+                .clicked()
+            {
+                decision = Some(HostKeyDecision::AcceptOnce);
+            }
+            if modal
+                .button(ui, fl!(crate::LANGUAGE_LOADER, "host-key-abort-button"))
+                .clicked()
+            {
+                decision = Some(HostKeyDecision::Abort);
+            }
+        });
+    });
+    modal.open();
+
+    if let Some(decision) = decision {
+        window.host_key = None;
+        // the com thread doesn't wait for an answer for changed keys
+        if !info.changed {
+            let r = window.connection().answer_host_key(decision);
+            check_error!(window, r, false);
+        }
+        window.set_mode(MainWindowMode::ShowTerminal);
+    }
+}
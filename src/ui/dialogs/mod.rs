@@ -1,6 +1,7 @@
 pub mod capture_dialog;
 pub mod dialing_directory_dialog;
 pub mod export_dialog;
+pub mod host_key_dialog;
 pub mod protocol_selector;
 pub mod settings_dialog;
 pub mod show_iemsi;
@@ -8,48 +8,22 @@ use crate::ui::{MainWindow, MainWindowMode};
 
 use lazy_static::lazy_static;
 lazy_static! {
-    static ref PROTOCOL_TABLE: [(TransferType, String, String); 8] = [
-        (
-            TransferType::ZModem,
-            "Zmodem".to_string(),
-            fl!(crate::LANGUAGE_LOADER, "protocol-zmodem-description")
-        ),
-        (
-            TransferType::ZedZap,
-            "ZedZap".to_string(),
-            fl!(crate::LANGUAGE_LOADER, "protocol-zmodem8k-description"),
-        ),
-        (
-            TransferType::XModem,
-            "Xmodem".to_string(),
-            fl!(crate::LANGUAGE_LOADER, "protocol-xmodem-description")
-        ),
-        (
-            TransferType::XModem1k,
-            "Xmodem 1k".to_string(),
-            fl!(crate::LANGUAGE_LOADER, "protocol-xmodem1k-description")
-        ),
-        (
-            TransferType::XModem1kG,
-            "Xmodem 1k-G".to_string(),
-            fl!(crate::LANGUAGE_LOADER, "protocol-xmodem1kG-description")
-        ),
-        (
-            TransferType::YModem,
-            "Ymodem".to_string(),
-            fl!(crate::LANGUAGE_LOADER, "protocol-ymodem-description")
-        ),
-        (
-            TransferType::YModemG,
-            "Ymodem-G".to_string(),
-            fl!(crate::LANGUAGE_LOADER, "protocol-ymodemg-description")
-        ),
-        (
-            TransferType::Text,
-            "Text".to_string(),
-            fl!(crate::LANGUAGE_LOADER, "protocol-text-description")
-        )
-    ];
+    static ref PROTOCOL_TABLE: [(TransferType, String); 9] =
+        TransferType::ALL.map(|protocol| (protocol, description(protocol)));
+}
+
+fn description(protocol: TransferType) -> String {
+    match protocol {
+        TransferType::ZModem => fl!(crate::LANGUAGE_LOADER, "protocol-zmodem-description"),
+        TransferType::ZedZap => fl!(crate::LANGUAGE_LOADER, "protocol-zmodem8k-description"),
+        TransferType::XModem => fl!(crate::LANGUAGE_LOADER, "protocol-xmodem-description"),
+        TransferType::XModem1k => fl!(crate::LANGUAGE_LOADER, "protocol-xmodem1k-description"),
+        TransferType::XModem1kG => fl!(crate::LANGUAGE_LOADER, "protocol-xmodem1kG-description"),
+        TransferType::YModem => fl!(crate::LANGUAGE_LOADER, "protocol-ymodem-description"),
+        TransferType::YModemG => fl!(crate::LANGUAGE_LOADER, "protocol-ymodemg-description"),
+        TransferType::Kermit => fl!(crate::LANGUAGE_LOADER, "protocol-kermit-description"),
+        TransferType::Text => fl!(crate::LANGUAGE_LOADER, "protocol-text-description"),
+    }
 }
 
 pub fn view_selector(
@@ -80,13 +54,10 @@ pub fn view_selector(
                 .min_col_width(130.)
                 .min_row_height(24.)
                 .show(ui, |ui| {
-                    for (protocol, title, descr) in &*PROTOCOL_TABLE {
-                        if download && matches!(*protocol, TransferType::Text) {
-                            continue;
-                        }
+                    for (protocol, descr) in &*PROTOCOL_TABLE {
                         ui.with_layout(ui.layout().with_cross_justify(true), |ui| {
                             if ui
-                                .selectable_label(false, RichText::new(title).strong())
+                                .selectable_label(false, RichText::new(protocol.name()).strong())
                                 .clicked()
                             {
                                 window.initiate_file_transfer(*protocol, download);
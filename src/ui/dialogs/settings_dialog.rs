@@ -4,7 +4,7 @@ use i18n_embed_fl::fl;
 
 use crate::{
     ui::{MainWindowMode, MainWindowState},
-    KeyBindings, Scaling,
+    KeyBindings, LineEnding, ProxySettings, Scaling, TextTransferSettings,
 };
 use lazy_static::lazy_static;
 lazy_static! {
@@ -35,13 +35,16 @@ pub(crate) enum Message {
     ChangeOpenglScaling(Scaling),
     UpdateKeybinds(KeyBindings),
     ChangeConsoleBeep(bool),
+    ChangeResumeDownloads(bool),
+    UpdateTextTransfer(TextTransferSettings),
+    UpdateProxy(ProxySettings),
 }
 
 type ShowSettingsCallback = fn(&MainWindowState, ui: &mut egui::Ui) -> Option<Message>;
 type ResetMessage = Option<Message>;
 
 lazy_static! {
-    static ref SETTING_CATEGORIES: [(String, ShowSettingsCallback, ResetMessage); 4] = [
+    static ref SETTING_CATEGORIES: [(String, ShowSettingsCallback, ResetMessage); 6] = [
         (
             fl!(crate::LANGUAGE_LOADER, "settings-monitor-category"),
             show_monitor_settings,
@@ -57,6 +60,16 @@ lazy_static! {
             show_terminal_settings,
             None
         ),
+        (
+            fl!(crate::LANGUAGE_LOADER, "settings-text-transfer-category"),
+            show_text_transfer_settings,
+            Some(Message::UpdateTextTransfer(TextTransferSettings::default()))
+        ),
+        (
+            fl!(crate::LANGUAGE_LOADER, "settings-proxy-category"),
+            show_proxy_settings,
+            Some(Message::UpdateProxy(ProxySettings::default()))
+        ),
         (
             fl!(crate::LANGUAGE_LOADER, "settings-keybinds-category"),
             crate::show_keybinds_settings,
@@ -221,6 +234,24 @@ fn show_terminal_settings(state: &MainWindowState, ui: &mut egui::Ui) -> Option<
         result = Some(Message::ChangeConsoleBeep(beep));
     }
 
+    let mut resume = state.options.resume_downloads;
+    if ui
+        .checkbox(
+            &mut resume,
+            fl!(
+                crate::LANGUAGE_LOADER,
+                "settings-terminal-resume-downloads-checkbox"
+            ),
+        )
+        .on_hover_text(fl!(
+            crate::LANGUAGE_LOADER,
+            "settings-terminal-resume-downloads-tooltip"
+        ))
+        .changed()
+    {
+        result = Some(Message::ChangeResumeDownloads(resume));
+    }
+
     ui.add_space(16.0);
     if ui
         .button(fl!(
@@ -236,6 +267,139 @@ fn show_terminal_settings(state: &MainWindowState, ui: &mut egui::Ui) -> Option<
     result
 }
 
+fn show_text_transfer_settings(state: &MainWindowState, ui: &mut egui::Ui) -> Option<Message> {
+    let mut settings = state.options.text_transfer.clone();
+
+    egui::Grid::new("text_transfer_grid")
+        .num_columns(2)
+        .spacing([4.0, 8.0])
+        .min_row_height(24.)
+        .show(ui, |ui| {
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(RichText::new(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "settings-text-transfer-line-delay"
+                )));
+            });
+            ui.add(egui::DragValue::new(&mut settings.line_delay).clamp_range(0..=10_000));
+            ui.end_row();
+
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(RichText::new(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "settings-text-transfer-char-delay"
+                )));
+            });
+            ui.add(egui::DragValue::new(&mut settings.char_delay).clamp_range(0..=1_000));
+            ui.end_row();
+
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(RichText::new(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "settings-text-transfer-line-ending"
+                )));
+            });
+            egui::ComboBox::from_id_source("text_transfer_line_ending")
+                .width(150.)
+                .selected_text(format!("{:?}", settings.line_ending))
+                .show_ui(ui, |ui| {
+                    for t in &LineEnding::ALL {
+                        ui.selectable_value(&mut settings.line_ending, *t, format!("{t:?}"));
+                    }
+                });
+            ui.end_row();
+
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(RichText::new(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "settings-text-transfer-wait-for-prompt"
+                )));
+            });
+            ui.add(TextEdit::singleline(&mut settings.wait_for_prompt));
+            ui.end_row();
+        });
+
+    ui.checkbox(
+        &mut settings.wait_for_echo,
+        fl!(
+            crate::LANGUAGE_LOADER,
+            "settings-text-transfer-wait-for-echo-checkbox"
+        ),
+    );
+
+    ui.add_space(8.0);
+    ui.separator();
+    ui.add_space(8.0);
+
+    egui::Grid::new("text_capture_grid")
+        .num_columns(2)
+        .spacing([4.0, 8.0])
+        .min_row_height(24.)
+        .show(ui, |ui| {
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(RichText::new(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "settings-text-transfer-capture-file-name"
+                )));
+            });
+            ui.add(TextEdit::singleline(&mut settings.capture_file_name));
+            ui.end_row();
+
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(RichText::new(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "settings-text-transfer-idle-timeout"
+                )));
+            });
+            ui.add(egui::DragValue::new(&mut settings.idle_timeout).clamp_range(0..=3600));
+            ui.end_row();
+
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(RichText::new(fl!(
+                    crate::LANGUAGE_LOADER,
+                    "settings-text-transfer-end-pattern"
+                )));
+            });
+            ui.add(TextEdit::singleline(&mut settings.end_pattern));
+            ui.end_row();
+        });
+
+    ui.checkbox(
+        &mut settings.end_on_ctrl_z,
+        fl!(
+            crate::LANGUAGE_LOADER,
+            "settings-text-transfer-end-on-ctrl-z-checkbox"
+        ),
+    );
+    ui.add_space(8.0);
+
+    if settings == state.options.text_transfer {
+        None
+    } else {
+        Some(Message::UpdateTextTransfer(settings))
+    }
+}
+
+fn show_proxy_settings(state: &MainWindowState, ui: &mut egui::Ui) -> Option<Message> {
+    let mut proxy = state.options.proxy.clone();
+
+    egui::Grid::new("proxy_grid")
+        .num_columns(2)
+        .spacing([4.0, 8.0])
+        .min_row_height(24.)
+        .show(ui, |ui| {
+            crate::ui::dialogs::dialing_directory_dialog::render_proxy_settings(
+                ui, &mut proxy, false,
+            );
+        });
+
+    if proxy == state.options.proxy {
+        None
+    } else {
+        Some(Message::UpdateProxy(proxy))
+    }
+}
+
 fn show_monitor_settings(state: &MainWindowState, ui: &mut egui::Ui) -> Option<Message> {
     let mut result = None;
 
@@ -372,6 +536,18 @@ fn update_state(state: &mut MainWindowState, message_opt: Option<Message>) {
             state.options.console_beep = beep;
             state.store_options();
         }
+        Some(Message::ChangeResumeDownloads(resume)) => {
+            state.options.resume_downloads = resume;
+            state.store_options();
+        }
+        Some(Message::UpdateTextTransfer(settings)) => {
+            state.options.text_transfer = settings;
+            state.store_options();
+        }
+        Some(Message::UpdateProxy(proxy)) => {
+            state.options.proxy = proxy;
+            state.store_options();
+        }
         _ => {}
     }
 }
@@ -386,7 +562,8 @@ mod tests {
             dialogs::settings_dialog::{update_state, SETTING_CATEGORIES},
             MainWindowState,
         },
-        IEMSISettings, KeyBindings, Options, Scaling,
+        IEMSISettings, KeyBindings, LineEnding, Options, ProxySettings, ProxyType, Scaling,
+        TextTransferSettings,
     };
 
     #[test]
@@ -453,6 +630,20 @@ mod tests {
         assert!(state.options_written);
     }
 
+    #[test]
+    fn test_change_resume_downloads() {
+        let mut state: MainWindowState = MainWindowState::default();
+        update_state(
+            &mut state,
+            Some(super::Message::ChangeResumeDownloads(true)),
+        );
+        assert_ne!(
+            Options::default().resume_downloads,
+            state.options.resume_downloads
+        );
+        assert!(state.options_written);
+    }
+
     #[test]
     fn test_set_keybindings() {
         let mut state: MainWindowState = MainWindowState::default();
@@ -499,4 +690,29 @@ mod tests {
         assert_eq!(Scaling::Linear, state.options.scaling);
         assert!(state.options_written);
     }
+
+    #[test]
+    fn test_set_text_transfer_settings() {
+        let mut state: MainWindowState = MainWindowState::default();
+        let mut settings = TextTransferSettings::default();
+        settings.line_delay = 500;
+        settings.line_ending = LineEnding::CrLf;
+        update_state(
+            &mut state,
+            Some(super::Message::UpdateTextTransfer(settings)),
+        );
+        assert_ne!(TextTransferSettings::default(), state.options.text_transfer);
+        assert!(state.options_written);
+    }
+
+    #[test]
+    fn test_set_proxy_settings() {
+        let mut state: MainWindowState = MainWindowState::default();
+        let mut proxy = ProxySettings::default();
+        proxy.proxy_type = ProxyType::Socks5;
+        proxy.address = "localhost:1080".to_string();
+        update_state(&mut state, Some(super::Message::UpdateProxy(proxy.clone())));
+        assert_eq!(proxy, state.options.proxy);
+        assert!(state.options_written);
+    }
 }
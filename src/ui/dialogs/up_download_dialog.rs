@@ -113,17 +113,30 @@ impl FileTransferDialog {
                     ui.label(RichText::new(fl!(crate::LANGUAGE_LOADER, "transfer-file")));
                     ui.label(RichText::new(file_name));
                 });
-                ui.add(
-                    ProgressBar::new(
-                        transfer_info.bytes_transfered as f32 / transfer_info.file_size as f32,
-                    )
-                    .text(RichText::new(format!(
-                        "{}% {}/{}",
-                        (transfer_info.bytes_transfered * 100) / max(1, transfer_info.file_size),
-                        bb.bytes(transfer_info.bytes_transfered as u64),
-                        bb.bytes(transfer_info.file_size as u64)
-                    ))),
-                );
+                if transfer_info.file_size == 0 {
+                    // size is unknown - happens on text capture
+                    ui.add(
+                        ProgressBar::new(0.0)
+                            .animate(true)
+                            .text(RichText::new(format!(
+                                "{}",
+                                bb.bytes(transfer_info.bytes_transfered as u64)
+                            ))),
+                    );
+                } else {
+                    ui.add(
+                        ProgressBar::new(
+                            transfer_info.bytes_transfered as f32 / transfer_info.file_size as f32,
+                        )
+                        .text(RichText::new(format!(
+                            "{}% {}/{}",
+                            (transfer_info.bytes_transfered * 100)
+                                / max(1, transfer_info.file_size),
+                            bb.bytes(transfer_info.bytes_transfered as u64),
+                            bb.bytes(transfer_info.file_size as u64)
+                        ))),
+                    );
+                }
                 ui.horizontal(|ui| {
                     ui.label(RichText::new(fl!(crate::LANGUAGE_LOADER, "transfer-rate")));
                     let bps = bb.bytes(transfer_info.get_bps()).to_string();
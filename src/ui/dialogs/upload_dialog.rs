@@ -36,14 +36,21 @@ impl MainWindow {
                     ) {
                         match std::fs::read(path) {
                             Ok(bytes) => {
-                                let r = self.connection.as_mut().unwrap().send(bytes);
-                                check_error!(self, r, true);
+                                let file_name = path
+                                    .file_name()
+                                    .map(|f| f.to_string_lossy().to_string())
+                                    .unwrap_or_default();
+                                let data = self.convert_text_upload(bytes);
+                                self.start_file_transfer(
+                                    self.upload_dialog.protocol_type,
+                                    false,
+                                    Some(vec![FileDescriptor::from_data(file_name, data)]),
+                                );
                             }
                             r => {
                                 check_error!(self, r, true);
                             }
                         }
-                        self.set_mode(MainWindowMode::ShowTerminal);
                         return;
                     }
 
@@ -59,4 +66,23 @@ impl MainWindow {
             }
         }
     }
+
+    /// Text files are usually stored in UTF-8 - translate them to the charset of the current terminal.
+    /// Files that aren't valid UTF-8 are assumed to be in the right charset already.
+    /// Characters the charset can't encode are sent as '?'.
+    fn convert_text_upload(&self, bytes: Vec<u8>) -> Vec<u8> {
+        match String::from_utf8(bytes) {
+            Ok(text) => text
+                .chars()
+                .map(|ch| {
+                    let converted = self.buffer_parser.convert_from_unicode(ch, 0);
+                    u8::try_from(converted).unwrap_or_else(|_| {
+                        log::warn!("text upload: can't encode '{ch}' in the terminal charset");
+                        b'?'
+                    })
+                })
+                .collect(),
+            Err(err) => err.into_bytes(),
+        }
+    }
 }
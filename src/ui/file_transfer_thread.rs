@@ -1,4 +1,5 @@
 use crate::protocol::{FileDescriptor, TransferState};
+use crate::TextTransferSettings;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 
@@ -17,13 +18,15 @@ impl FileTransferThread {
         protocol_type: crate::protocol::TransferType,
         download: bool,
         files_opt: Option<Vec<FileDescriptor>>,
+        text_settings: TextTransferSettings,
+        resume_downloads: bool,
     ) -> Self {
         let current_transfer = Arc::new(Mutex::new(TransferState::default()));
 
         let current_transfer2 = current_transfer.clone();
 
         let join_handle = thread::spawn(move || {
-            let mut protocol = protocol_type.create();
+            let mut protocol = protocol_type.create(&text_settings);
 
             if let Err(err) = if download {
                 protocol.initiate_recv(&mut connection, &mut current_transfer2.lock().unwrap())
@@ -39,6 +42,7 @@ impl FileTransferThread {
             }
 
             if let Ok(mut storage_handler) = crate::protocol::DiskStorageHandler::new() {
+                storage_handler.resume_partial_files = resume_downloads;
                 let mut is_running = true;
                 while is_running {
                     if let Err(err) = connection.update_state() {
@@ -0,0 +1,246 @@
+use std::path::Path;
+
+use eframe::egui::{self, RichText};
+use egui_file::FileDialog;
+use i18n_embed_fl::fl;
+use icy_engine::ansi::BaudEmulation;
+use web_time::Instant;
+
+use crate::{
+    addresses::Terminal,
+    features::FileViewer,
+    ui::{ScreenMode, DEFAULT_MODES},
+    Address,
+};
+
+use super::MainWindow;
+
+impl MainWindow {
+    pub fn init_view_file_dialog(&mut self) {
+        let mut dialog = FileDialog::open_file(None);
+        dialog.open();
+        self.view_file_dialog = Some(dialog);
+    }
+
+    pub(crate) fn show_view_file_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.view_file_dialog else {
+            return;
+        };
+        if dialog.show(ctx).selected() {
+            if let Some(path) = dialog.path() {
+                let path = path.to_path_buf();
+                self.view_file_dialog = None;
+                self.view_file(&path);
+            }
+        }
+    }
+
+    /// Shows a local file offline, a running connection is closed.
+    pub fn view_file(&mut self, path: &Path) {
+        let viewer = match FileViewer::load(path) {
+            Ok(viewer) => viewer,
+            Err(err) => {
+                self.show_error(&err.to_string());
+                return;
+            }
+        };
+        self.prepare_offline_view();
+        self.file_viewer = Some(viewer);
+        self.restart_file_viewer();
+        self.show_terminal();
+    }
+
+    pub fn close_file_viewer(&mut self) {
+        self.file_viewer = None;
+        self.show_sauce = false;
+    }
+
+    /// Shows the file from the beginning with the current viewer settings.
+    fn restart_file_viewer(&mut self) {
+        let Some(viewer) = &mut self.file_viewer else {
+            return;
+        };
+        viewer.restart();
+        let mut address = Address::new(String::new());
+        address.terminal_type = viewer.terminal;
+        address.baud_emulation = viewer.baud_emulation;
+        let screen_mode = viewer.screen_mode;
+
+        self.sound_thread.clear();
+        self.set_screen_mode(screen_mode);
+        self.reset_screen(address.get_terminal_parser(&address));
+        self.buffer_view
+            .lock()
+            .buf
+            .terminal_state
+            .set_baud_rate(address.baud_emulation);
+    }
+
+    /// Feeds the part of the file that is due through the parser.
+    pub(crate) fn update_file_viewer(&mut self) {
+        let Some(viewer) = &mut self.file_viewer else {
+            return;
+        };
+        let data = viewer.update(Instant::now()).to_vec();
+        if data.is_empty() {
+            return;
+        }
+        for ch in data {
+            if let Err(err) = self.print_char(ch) {
+                log::error!("{err}");
+            }
+        }
+        self.buffer_view.lock().buf.update_hyperlinks();
+    }
+
+    pub(crate) fn show_file_viewer_bar(&mut self, ctx: &egui::Context, frame: egui::Frame) {
+        let Some(viewer) = &mut self.file_viewer else {
+            return;
+        };
+        let mut restart = false;
+        let mut export = false;
+        let mut close = false;
+
+        egui::TopBottomPanel::bottom("file_viewer_bar")
+            .frame(frame)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(name) = viewer.path.file_name() {
+                        ui.label(RichText::new(name.to_string_lossy()).strong());
+                    }
+
+                    egui::ComboBox::from_id_source("viewer_terminal_combobox")
+                        .selected_text(viewer.terminal.to_string())
+                        .show_ui(ui, |ui| {
+                            for t in Terminal::ALL {
+                                restart |= ui
+                                    .selectable_value(&mut viewer.terminal, t, t.to_string())
+                                    .changed();
+                            }
+                        });
+
+                    egui::ComboBox::from_id_source("viewer_screen_mode_combobox")
+                        .selected_text(viewer.screen_mode.to_string())
+                        .show_ui(ui, |ui| {
+                            for mode in DEFAULT_MODES {
+                                if matches!(mode, ScreenMode::Default) {
+                                    ui.separator();
+                                    continue;
+                                }
+                                restart |= ui
+                                    .selectable_value(
+                                        &mut viewer.screen_mode,
+                                        mode,
+                                        mode.to_string(),
+                                    )
+                                    .changed();
+                            }
+                        });
+
+                    egui::ComboBox::from_id_source("viewer_baud_combobox")
+                        .selected_text(viewer.baud_emulation.to_string())
+                        .show_ui(ui, |ui| {
+                            for b in BaudEmulation::OPTIONS {
+                                restart |= ui
+                                    .selectable_value(&mut viewer.baud_emulation, b, b.to_string())
+                                    .changed();
+                            }
+                        });
+
+                    if ui
+                        .button("⟲")
+                        .on_hover_text(fl!(crate::LANGUAGE_LOADER, "viewer-restart"))
+                        .clicked()
+                    {
+                        restart = true;
+                    }
+                    if viewer.sauce.is_some() {
+                        ui.toggle_value(&mut self.show_sauce, "SAUCE");
+                    }
+                    if ui
+                        .button("💾")
+                        .on_hover_text(fl!(crate::LANGUAGE_LOADER, "terminal-menu-export"))
+                        .clicked()
+                    {
+                        export = true;
+                    }
+                    if ui
+                        .button("✖")
+                        .on_hover_text(fl!(crate::LANGUAGE_LOADER, "viewer-close"))
+                        .clicked()
+                    {
+                        close = true;
+                    }
+                });
+            });
+
+        if self.show_sauce {
+            if let Some(sauce) = &viewer.sauce {
+                egui::Window::new("SAUCE")
+                    .open(&mut self.show_sauce)
+                    .collapsible(false)
+                    .resizable(false)
+                    .show(ctx, |ui| {
+                        egui::Grid::new("sauce_grid").num_columns(2).show(ui, |ui| {
+                            let rows = [
+                                (
+                                    fl!(crate::LANGUAGE_LOADER, "viewer-sauce-title"),
+                                    &sauce.title,
+                                ),
+                                (
+                                    fl!(crate::LANGUAGE_LOADER, "viewer-sauce-author"),
+                                    &sauce.author,
+                                ),
+                                (
+                                    fl!(crate::LANGUAGE_LOADER, "viewer-sauce-group"),
+                                    &sauce.group,
+                                ),
+                                (
+                                    fl!(crate::LANGUAGE_LOADER, "viewer-sauce-date"),
+                                    &sauce.date,
+                                ),
+                                (
+                                    fl!(crate::LANGUAGE_LOADER, "viewer-sauce-font"),
+                                    &sauce.font,
+                                ),
+                            ];
+                            for (label, value) in rows {
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        ui.label(label);
+                                    },
+                                );
+                                ui.label(value.as_str());
+                                ui.end_row();
+                            }
+                            if let Some(width) = sauce.width() {
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        ui.label(fl!(crate::LANGUAGE_LOADER, "viewer-sauce-width"));
+                                    },
+                                );
+                                ui.label(width.to_string());
+                                ui.end_row();
+                            }
+                        });
+                        if !sauce.comments.is_empty() {
+                            ui.separator();
+                            for comment in &sauce.comments {
+                                ui.label(comment.as_str());
+                            }
+                        }
+                    });
+            }
+        }
+
+        if close {
+            self.close_file_viewer();
+        } else if export {
+            self.init_export_dialog();
+        } else if restart {
+            self.restart_file_viewer();
+        }
+    }
+}
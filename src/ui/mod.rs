@@ -4,17 +4,20 @@ use chrono::Utc;
 use egui::Vec2;
 use egui_bind::BindTarget;
 use i18n_embed_fl::fl;
-use icy_engine::{BufferParser, Position};
+use icy_engine::{BufferParser, Position, Selection};
 use icy_engine_egui::BufferView;
 use std::sync::Arc;
 use std::time::Instant;
 
 use eframe::egui::Key;
 
-use crate::features::{AutoFileTransfer, AutoLogin};
-use crate::util::SoundThread;
-use crate::Options;
+use crate::features::{
+    AutoFileTransfer, AutoLogin, AutoReconnect, Direction, FileViewer, HayesModem, ModemAction,
+    SessionPlayer, SessionRecorder, TriggerMatch, Triggers,
+};
+use crate::util::{CharsetDecoder, SoundThread};
 use crate::{protocol::FileDescriptor, TerminalResult};
+use crate::{Address, Charset, Options, TriggerAction};
 
 pub mod app;
 pub mod connection;
@@ -31,13 +34,19 @@ pub mod dialogs;
 
 pub mod com_thread;
 pub mod file_transfer_thread;
+pub mod file_viewer;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod scripting;
+pub mod session_playback;
 
 #[macro_export]
 macro_rules! check_error {
     ($main_window: expr, $res: expr, $terminate_connection: expr) => {{
         if let Err(err) = $res {
             log::error!("{err}");
-            $main_window.output_string(format!("\n\r{err}\n\r").as_str());
+            $main_window
+                .println(format!("\n\r{err}\n\r").as_str())
+                .unwrap_or_default();
 
             if $terminate_connection {
                 $main_window
@@ -65,6 +74,7 @@ pub enum MainWindowMode {
     ShowExportDialog,
     ShowUploadDialog,
     ShowIEMSI,
+    VerifyHostKey,
 }
 
 #[derive(Default)]
@@ -106,11 +116,34 @@ pub struct MainWindow {
 
     screen_mode: ScreenMode,
     auto_login: AutoLogin,
+    /// Redials after the remote side dropped the connection
+    auto_reconnect: AutoReconnect,
     is_fullscreen_mode: bool,
     drag_start: Option<Vec2>,
     last_pos: Position,
 
     auto_file_transfer: AutoFileTransfer,
+    /// Pattern → action rules of the address
+    triggers: Triggers,
+    /// A notify trigger fired, the window asks for attention on the next frame
+    request_attention: bool,
+    /// Handles the keyboard input while there is no connection
+    modem: HayesModem,
+    /// Host key the user needs to verify before the connection continues
+    host_key: Option<connection::HostKeyInfo>,
+    /// Charset the remote side switched to
+    charset: CharsetDecoder,
+    /// Records the session with timing
+    session_recorder: Option<SessionRecorder>,
+    /// Plays a recorded session instead of a connection
+    session_player: Option<SessionPlayer>,
+    /// Shows a local file instead of a connection
+    file_viewer: Option<FileViewer>,
+    view_file_dialog: Option<egui_file::FileDialog>,
+    show_sauce: bool,
+    /// Running script, console & login script of the address
+    #[cfg(not(target_arch = "wasm32"))]
+    scripts: scripting::ScriptState,
 
     // protocols
     pub current_file_transfer: Option<FileTransferThread>,
@@ -158,35 +191,112 @@ impl MainWindow {
         Ok(())
     }
 
+    fn show_error(&mut self, err: &str) {
+        log::error!("{err}");
+        self.println(&format!("\n\r{err}\n\r")).unwrap_or_default();
+    }
+
+    /// Clears the screen for a new parser.
+    fn reset_screen(&mut self, parser: Box<dyn BufferParser>) {
+        self.buffer_parser = parser;
+        let mut buffer_view = self.buffer_view.lock();
+        buffer_view.buf.stop_sixel_threads();
+        buffer_view.buf.layers[0].clear();
+        buffer_view.clear();
+        buffer_view.redraw_font();
+        buffer_view.redraw_palette();
+        buffer_view.redraw_view();
+    }
+
     pub fn output_char(&mut self, ch: char) {
-        let translated_char = self.buffer_parser.convert_from_unicode(ch, 0);
-        if self.connection().is_connected() {
-            let r = self.connection().send(vec![translated_char as u8]);
-            check_error!(self, r, false);
-        } else if let Err(err) = self.print_char(translated_char as u8) {
-            log::error!("{err}");
+        if let Some(data) = self.charset.encode(ch) {
+            self.send_input(data);
+            return;
         }
+        let translated_char = self.buffer_parser.convert_from_unicode(ch, 0);
+        self.send_input(vec![translated_char as u8]);
     }
 
     pub fn output_string(&mut self, str: &str) {
-        if self.connection().is_connected() {
-            let mut v = Vec::new();
-            for ch in str.chars() {
-                let translated_char = self.buffer_parser.convert_from_unicode(ch, 0);
-                v.push(translated_char as u8);
+        let mut v = Vec::new();
+        for ch in str.chars() {
+            if let Some(data) = self.charset.encode(ch) {
+                v.extend(data);
+                continue;
             }
-            let r = self.connection().send(v);
+            let translated_char = self.buffer_parser.convert_from_unicode(ch, 0);
+            v.push(translated_char as u8);
+        }
+        self.send_input(v);
+    }
+
+    /// Sends user input to the connection - or to the offline modem if there is no connection
+    /// or the modem is in command mode.
+    pub fn send_input(&mut self, data: Vec<u8>) {
+        // playbacks and file views don't take input
+        if self.session_player.is_some() || self.file_viewer.is_some() {
+            return;
+        }
+        if self.connection().is_connected() && !self.modem.is_command_mode() {
+            self.modem.data_sent(&data);
+            self.record_session(Direction::Outbound, &data);
+            let r = self.connection().send(data);
             check_error!(self, r, false);
-        } else {
-            for ch in str.chars() {
-                let translated_char = self.buffer_parser.convert_from_unicode(ch, 0);
-                if let Err(err) = self.print_char(translated_char as u8) {
-                    log::error!("{err}");
-                }
+            return;
+        }
+        for ch in data {
+            if let Some(action) = self
+                .modem
+                .input(ch, &self.dialing_directory_dialog.addresses)
+            {
+                self.print_modem_output();
+                self.run_modem_action(action);
+            }
+        }
+        self.print_modem_output();
+    }
+
+    fn print_modem_output(&mut self) {
+        for ch in self.modem.take_output() {
+            if let Err(err) = self.print_char(ch) {
+                log::error!("{err}");
+            }
+        }
+    }
+
+    fn run_modem_action(&mut self, action: ModemAction) {
+        match action {
+            ModemAction::Dial(i) => self.call_bbs(i),
+            ModemAction::DialAddress(address) => {
+                self.auto_reconnect.reset();
+                self.dial_modem_address(address, false);
+            }
+            ModemAction::Hangup => {
+                self.auto_reconnect.reset();
+                check_error!(self, self.connection().disconnect(), false);
+                self.sound_thread.clear();
             }
         }
     }
 
+    /// Prints received data, characters of a negotiated charset are mapped to the buffer charset.
+    fn print_received(&mut self, c: u8) -> Result<(), Box<dyn std::error::Error>> {
+        if self.charset.charset() == Charset::Cp437 {
+            return self.print_char(c);
+        }
+        let mut chars = Vec::new();
+        self.charset.decode(c, &mut chars);
+        for ch in chars {
+            let ch = if ch.is_ascii() {
+                ch
+            } else {
+                self.buffer_parser.convert_from_unicode(ch, 0)
+            };
+            self.print_char(ch as u8)?;
+        }
+        Ok(())
+    }
+
     pub fn print_char(&mut self, c: u8) -> Result<(), Box<dyn std::error::Error>> {
         let result = self
             .buffer_view
@@ -197,7 +307,13 @@ impl MainWindow {
         match result {
             icy_engine::CallbackAction::None => {}
             icy_engine::CallbackAction::SendString(result) => {
-                if self.connection().is_connected() {
+                // file transfers take the connection
+                if self
+                    .connection
+                    .as_ref()
+                    .is_some_and(|con| con.is_connected())
+                {
+                    self.record_session(Direction::Outbound, result.as_bytes());
                     let r = self.connection().send(result.as_bytes().to_vec());
                     check_error!(self, r, false);
                 }
@@ -213,10 +329,14 @@ impl MainWindow {
                 }
             }
             icy_engine::CallbackAction::ChangeBaudEmulation(baud_emulation) => {
-                let r = self
-                    .connection()
-                    .set_baud_rate(baud_emulation.get_baud_rate());
-                check_error!(self, r, false);
+                if let Some(viewer) = &mut self.file_viewer {
+                    viewer.baud_emulation = baud_emulation;
+                } else {
+                    let r = self
+                        .connection()
+                        .set_baud_rate(baud_emulation.get_baud_rate());
+                    check_error!(self, r, false);
+                }
             }
             icy_engine::CallbackAction::ResizeTerminal(_, _) => {
                 self.buffer_view.lock().redraw_view();
@@ -249,12 +369,15 @@ impl MainWindow {
         let r = crate::protocol::DiskStorageHandler::new();
         check_error!(self, r, false);
         if let Some(mut con) = self.connection.take() {
+            check_error!(self, con.set_transfer_active(true), false);
             con.start_transfer();
             self.current_file_transfer = Some(FileTransferThread::new(
                 con,
                 protocol_type,
                 download,
                 files_opt,
+                self.state.options.text_transfer.clone(),
+                self.state.options.resume_downloads,
             ));
         }
     }
@@ -277,9 +400,50 @@ impl MainWindow {
         }
     }
 
+    /// Returns true if the trigger started a download, the connection is taken then.
+    fn run_trigger(&mut self, trigger: TriggerMatch) -> bool {
+        match trigger.action {
+            TriggerAction::Send(_) => self.send_input(trigger.data),
+            TriggerAction::Beep => {
+                let r = self.sound_thread.beep();
+                check_error!(self, r, false);
+            }
+            TriggerAction::Download(protocol_type) => {
+                self.initiate_file_transfer(protocol_type, true);
+                return true;
+            }
+            TriggerAction::Upload(protocol_type) => {
+                self.initiate_file_transfer(protocol_type, false);
+            }
+            TriggerAction::StartCapture => self.state.capture_dialog.capture_session = true,
+            TriggerAction::Highlight => {
+                // the matched text ends at the caret
+                let mut buffer_view = self.buffer_view.lock();
+                let pos = buffer_view.caret.get_position();
+                let start = (pos.x - trigger.len as i32).max(0);
+                let mut selection = Selection::new(start as f32, pos.y as f32);
+                selection.set_lead(pos.x as f32, pos.y as f32);
+                buffer_view.set_selection(selection);
+            }
+            TriggerAction::Notify => {
+                let r = self.sound_thread.beep();
+                check_error!(self, r, false);
+                self.request_attention = true;
+            }
+        }
+        false
+    }
+
     pub fn set_screen_mode(&mut self, mode: ScreenMode) {
         self.screen_mode = mode;
         mode.set_mode(self);
+        // connection is taken during file transfers
+        if let Some(con) = &self.connection {
+            if con.is_connected() {
+                let r = con.set_window_size(mode.get_window_size());
+                check_error!(self, r, false);
+            }
+        }
     }
 
     pub fn show_terminal(&mut self) {
@@ -312,55 +476,87 @@ impl MainWindow {
     }
 
     pub fn call_bbs(&mut self, i: usize) {
-        self.set_mode(MainWindowMode::ShowTerminal);
-        let cloned_addr = self.dialing_directory_dialog.addresses.addresses[i].clone();
+        self.auto_reconnect.reset();
+        self.dial(i, false);
+    }
 
-        {
-            let address = &mut self.dialing_directory_dialog.addresses.addresses[i];
-            let mut adr = address.address.clone();
-            if !adr.contains(':') {
-                adr.push_str(":23");
-            }
+    /// Reconnects keep the screen, they're counted separately in the statistics.
+    fn dial(&mut self, i: usize, is_reconnect: bool) {
+        let address = &mut self.dialing_directory_dialog.addresses.addresses[i];
+        if is_reconnect {
+            address.number_of_reconnects += 1;
+        } else {
             address.number_of_calls += 1;
-            address.last_call = Some(Utc::now());
+        }
+        address.last_call = Some(Utc::now());
+        let cloned_addr = address.clone();
+        self.dialing_directory_dialog.cur_addr = i;
+        self.dialing_directory_dialog.modem_address = None;
+        self.connect_to(&cloned_addr, is_reconnect);
+        let r = self.dialing_directory_dialog.addresses.store_phone_book();
+        check_error!(self, r, false);
+    }
 
-            self.auto_login = AutoLogin::new(&cloned_addr.auto_login);
-            self.auto_file_transfer.reset();
+    /// Dials an address typed into the modem emulation without touching the phone book.
+    fn dial_modem_address(&mut self, address: String, is_reconnect: bool) {
+        let mut adr = Address::new(String::new());
+        adr.address = address;
+        self.dialing_directory_dialog.modem_address = Some(adr.clone());
+        self.connect_to(&adr, is_reconnect);
+    }
+
+    fn connect_to(&mut self, addr: &Address, is_reconnect: bool) {
+        self.set_mode(MainWindowMode::ShowTerminal);
+        self.stop_playback();
+        self.close_file_viewer();
+        self.auto_login = AutoLogin::new(&addr.auto_login);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.schedule_login_script();
+        self.auto_file_transfer.reset();
+        self.triggers = Triggers::new(&addr.triggers);
+        self.charset = CharsetDecoder::default();
+        if !is_reconnect {
             self.buffer_view.lock().buf.layers[0].clear();
-            self.buffer_view.lock().buf.stop_sixel_threads();
-            self.dialing_directory_dialog.cur_addr = i;
-            self.buffer_parser = address.get_terminal_parser(&cloned_addr);
-            self.buffer_view
-                .lock()
-                .buf
-                .terminal_state
-                .set_baud_rate(address.baud_emulation);
+        }
+        self.buffer_view.lock().buf.stop_sixel_threads();
+        self.buffer_parser = addr.get_terminal_parser(addr);
+        self.buffer_view
+            .lock()
+            .buf
+            .terminal_state
+            .set_baud_rate(addr.baud_emulation);
 
-            self.buffer_view.lock().redraw_font();
-            self.buffer_view.lock().redraw_palette();
-            self.buffer_view.lock().redraw_view();
+        self.buffer_view.lock().redraw_font();
+        self.buffer_view.lock().redraw_palette();
+        self.buffer_view.lock().redraw_view();
+        if !is_reconnect {
             self.buffer_view.lock().clear();
         }
-        self.set_screen_mode(cloned_addr.screen_mode);
-        let r = self.dialing_directory_dialog.addresses.store_phone_book();
-        check_error!(self, r, false);
+        self.set_screen_mode(addr.screen_mode);
 
+        let address =
+            if addr.protocol == crate::addresses::Protocol::Serial && addr.address.is_empty() {
+                addr.serial.device.clone()
+            } else {
+                addr.address.clone()
+            };
         self.println(&fl!(
             crate::LANGUAGE_LOADER,
             "connect-to",
-            address = cloned_addr.address.clone()
+            address = address
         ))
         .unwrap_or_default();
 
         let timeout = self.get_options().connect_timeout;
+        let proxy = self.get_options().proxy.clone();
         let window_size = self.screen_mode.get_window_size();
         let r = self
             .connection()
-            .connect(&cloned_addr, timeout, window_size);
+            .connect(addr, timeout, window_size, &proxy);
         check_error!(self, r, false);
         let r = self
             .connection()
-            .set_baud_rate(cloned_addr.baud_emulation.get_baud_rate());
+            .set_baud_rate(addr.baud_emulation.get_baud_rate());
         check_error!(self, r, false);
     }
 
@@ -368,10 +564,54 @@ impl MainWindow {
         #[cfg(target_arch = "wasm32")]
         self.poll_thread.poll();
 
+        self.update_session_recording();
+        self.update_playback();
+        self.update_file_viewer();
+
         let r = self.connection().update_state();
+        let connect_failed = r.is_err();
+        if let Err(err) = &r {
+            self.modem.connection_failed(&err.to_string());
+        }
         check_error!(self, r, false);
+        if connect_failed && self.auto_reconnect.is_active() {
+            self.schedule_reconnect(web_time::Duration::ZERO);
+        }
+        if let Some(reason) = self.connection().take_connection_lost() {
+            self.println(&format!("\n\r{reason}\n\r"))
+                .unwrap_or_default();
+            let connected_for =
+                web_time::Instant::now().duration_since(self.connection().get_connection_time());
+            self.schedule_reconnect(connected_for);
+        }
+        self.update_reconnect();
         let r = self.sound_thread.update_state();
         check_error!(self, r, false);
+
+        if let Some(charset) = self.connection().take_charset() {
+            log::info!("switching to charset {charset}");
+            self.charset.set_charset(charset);
+        }
+
+        if let Some(info) = self.connection().take_host_key_request() {
+            self.host_key = Some(info);
+            self.set_mode(MainWindowMode::VerifyHostKey);
+        }
+
+        let is_connected = self.connection().is_connected();
+        self.modem.set_carrier(is_connected);
+        self.modem.poll();
+        self.print_modem_output();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.update_script();
+            // a script started a file transfer
+            if self.connection.is_none() {
+                return Ok(());
+            }
+        }
+
         if self.connection().is_disconnected() {
             return Ok(());
         }
@@ -382,80 +622,154 @@ impl MainWindow {
         };
 
         if let Some(data) = data_opt {
-            self.state
-                .capture_dialog
-                .append_data(&self.state.options, &data);
-            let has_data = !data.is_empty();
-
-            for ch in data {
-                if self.get_options().iemsi.autologin && self.connection().is_connected() {
-                    if let Some(adr) = self
-                        .dialing_directory_dialog
-                        .addresses
-                        .addresses
-                        .get(self.dialing_directory_dialog.cur_addr)
-                    {
-                        if let Some(con) = &mut self.connection {
-                            if let Err(err) =
-                                self.auto_login.try_login(con, adr, ch, &self.state.options)
-                            {
-                                log::error!("{err}");
-                            }
+            if self.handle_received_data(data) {
+                return Ok(());
+            }
+        }
+
+        if self.get_options().iemsi.autologin {
+            if let Some(adr) = self.dialing_directory_dialog.current_address() {
+                if let Some(con) = &mut self.connection {
+                    if con.is_connected() {
+                        if let Err(err) = self.auto_login.run_autologin(con, adr) {
+                            log::error!("{err}");
                         }
                     }
                 }
-                /*
-                match ch {
-                    b'\\' => print!("\\\\"),
-                    b'\n' => println!("\\n"),
-                    b'\r' => print!("\\r"),
-                    b'\"' => print!("\\\""),
-                    _ => {
-                        if ch < b' ' || ch == b'\x7F' {
-                            print!("\\x{ch:02X}");
-                        } else if ch > b'\x7F' {
-                            print!("\\u{{{ch:02X}}}");
-                        } else {
-                            print!("{}", char::from_u32(ch as u32).unwrap());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Passes received data to the terminal and everything that watches it.
+    /// Returns true if the data started a file transfer, the connection is taken then.
+    pub(crate) fn handle_received_data(&mut self, data: Vec<u8>) -> bool {
+        self.state
+            .capture_dialog
+            .append_data(&self.state.options, &data);
+        self.record_session(Direction::Inbound, &data);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.script_data_received(&data);
+        let has_data = !data.is_empty();
+        // actions are skipped while a file transfer has the connection
+        let has_connection = self.connection.is_some();
+
+        for ch in data {
+            if self.get_options().iemsi.autologin
+                && self
+                    .connection
+                    .as_ref()
+                    .is_some_and(|con| con.is_connected())
+            {
+                if let Some(adr) = self.dialing_directory_dialog.current_address() {
+                    if let Some(con) = &mut self.connection {
+                        if let Err(err) =
+                            self.auto_login.try_login(con, adr, ch, &self.state.options)
+                        {
+                            log::error!("{err}");
                         }
                     }
-                }*/
+                }
+            }
+            /*
+            match ch {
+                b'\\' => print!("\\\\"),
+                b'\n' => println!("\\n"),
+                b'\r' => print!("\\r"),
+                b'\"' => print!("\\\""),
+                _ => {
+                    if ch < b' ' || ch == b'\x7F' {
+                        print!("\\x{ch:02X}");
+                    } else if ch > b'\x7F' {
+                        print!("\\u{{{ch:02X}}}");
+                    } else {
+                        print!("{}", char::from_u32(ch as u32).unwrap());
+                    }
+                }
+            }*/
 
-                if let Err(err) = self.print_char(ch) {
-                    log::error!("{err}");
+            if let Err(err) = self.print_received(ch) {
+                log::error!("{err}");
+            }
+
+            if let Some(trigger) = self.triggers.push_ch(ch) {
+                if has_connection && self.run_trigger(trigger) {
+                    return true;
                 }
+            }
 
-                if let Some((protocol_type, download)) = self.auto_file_transfer.try_transfer(ch) {
+            if let Some((protocol_type, download)) = self.auto_file_transfer.try_transfer(ch) {
+                if has_connection {
                     self.initiate_file_transfer(protocol_type, download);
-                    return Ok(());
+                    return true;
                 }
             }
-            if has_data {
-                self.buffer_view.lock().buf.update_hyperlinks();
-            }
         }
+        if has_data {
+            self.buffer_view.lock().buf.update_hyperlinks();
+        }
+        false
+    }
 
-        if self.get_options().iemsi.autologin {
-            if let Some(adr) = self
+    fn schedule_reconnect(&mut self, connected_for: web_time::Duration) {
+        let Some(adr) = self.dialing_directory_dialog.current_address() else {
+            return;
+        };
+        let settings = adr.reconnect.clone();
+        if !self
+            .auto_reconnect
+            .connection_lost(&settings, connected_for)
+            && settings.enabled
+        {
+            self.println(&format!(
+                "{}\n\r",
+                fl!(crate::LANGUAGE_LOADER, "reconnect-giving-up")
+            ))
+            .unwrap_or_default();
+        }
+    }
+
+    /// Shows the countdown and redials once it's over.
+    fn update_reconnect(&mut self) {
+        if !self.auto_reconnect.is_pending() {
+            return;
+        }
+        let now = web_time::Instant::now();
+        if let Some(seconds) = self.auto_reconnect.countdown(now) {
+            let retries = self
                 .dialing_directory_dialog
-                .addresses
-                .addresses
-                .get(self.dialing_directory_dialog.cur_addr)
+                .current_address()
+                .unwrap()
+                .reconnect
+                .retries;
+            let text = fl!(
+                crate::LANGUAGE_LOADER,
+                "reconnect-countdown",
+                seconds = seconds,
+                attempt = self.auto_reconnect.attempt(),
+                retries = retries
+            );
+            // overwrites the last countdown
+            self.println(&format!("\r{text}  ")).unwrap_or_default();
+        }
+        if self.auto_reconnect.should_reconnect(now) {
+            self.println("\n\r").unwrap_or_default();
+            if let Some(address) = self
+                .dialing_directory_dialog
+                .modem_address
+                .as_ref()
+                .map(|adr| adr.address.clone())
             {
-                if let Some(con) = &mut self.connection {
-                    if con.is_connected() {
-                        if let Err(err) = self.auto_login.run_autologin(con, adr) {
-                            log::error!("{err}");
-                        }
-                    }
-                }
+                self.dial_modem_address(address, true);
+            } else {
+                self.dial(self.dialing_directory_dialog.cur_addr, true);
             }
         }
-
-        Ok(())
     }
 
     pub fn hangup(&mut self) {
+        self.auto_reconnect.reset();
         check_error!(self, self.connection().disconnect(), false);
         self.sound_thread.clear();
         self.set_mode(MainWindowMode::ShowDialingDirectory);
@@ -467,17 +781,13 @@ impl MainWindow {
         }
         let user_name = self
             .dialing_directory_dialog
-            .addresses
-            .addresses
-            .get(self.dialing_directory_dialog.cur_addr)
+            .current_address()
             .unwrap()
             .user_name
             .clone();
         let password = self
             .dialing_directory_dialog
-            .addresses
-            .addresses
-            .get(self.dialing_directory_dialog.cur_addr)
+            .current_address()
             .unwrap()
             .password
             .clone();
@@ -489,9 +799,11 @@ impl MainWindow {
             }
         }
         self.output_string(&user_name);
+        self.record_session(Direction::Outbound, &cr);
         let r = self.connection().send(cr.clone());
         check_error!(self, r, false);
         self.output_string(&password);
+        self.record_session(Direction::Outbound, &cr);
         let r = self.connection().send(cr);
         check_error!(self, r, false);
     }
@@ -509,8 +821,7 @@ impl MainWindow {
                 let sec = d.as_secs();
                 let minutes = sec / 60;
                 let hours = minutes / 60;
-                let cur = &self.dialing_directory_dialog.addresses.addresses
-                    [self.dialing_directory_dialog.cur_addr];
+                let cur = self.dialing_directory_dialog.current_address().unwrap();
                 let t = format!("{:02}:{:02}:{:02}", hours, minutes % 60, sec % 60);
                 let s = if cur.system_name.is_empty() {
                     cur.address.clone()
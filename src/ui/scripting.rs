@@ -0,0 +1,399 @@
+use std::path::Path;
+
+use eframe::{
+    egui::{self, RichText, TextEdit},
+    epaint::{FontFamily, FontId},
+};
+use egui_file::FileDialog;
+use i18n_embed_fl::fl;
+use icy_engine::{AttributedChar, TextAttribute};
+
+use crate::{
+    features::{ScreenSnapshot, ScriptEvent, ScriptRequest, ScriptRunner},
+    protocol::FileDescriptor,
+    util::CharsetDecoder,
+    Charset,
+};
+
+use super::MainWindow;
+
+/// Lines the console keeps
+const MAX_CONSOLE_LINES: usize = 1000;
+
+#[derive(Default)]
+pub struct ScriptState {
+    runner: Option<ScriptRunner>,
+    /// The login script of the address starts once the connection is up.
+    login_pending: bool,
+    file_dialog: Option<FileDialog>,
+
+    console_open: bool,
+    /// Output lines, true for errors
+    console: Vec<(String, bool)>,
+    /// One line script typed into the console
+    command: String,
+    command_has_focus: bool,
+    /// Decodes the received data for the script, it has its own state for incomplete sequences.
+    decoder: CharsetDecoder,
+}
+
+impl ScriptState {
+    /// Keyboard input goes to the console instead of the terminal.
+    pub fn has_input_focus(&self) -> bool {
+        self.console_open && self.command_has_focus
+    }
+
+    fn print(&mut self, text: String, is_error: bool) {
+        self.console.push((text, is_error));
+        if self.console.len() > MAX_CONSOLE_LINES {
+            self.console.drain(..self.console.len() - MAX_CONSOLE_LINES);
+        }
+    }
+}
+
+impl MainWindow {
+    pub fn init_run_script_dialog(&mut self) {
+        let mut dialog = FileDialog::open_file(None);
+        dialog.open();
+        self.scripts.file_dialog = Some(dialog);
+    }
+
+    pub fn run_script_file(&mut self, path: &Path) {
+        match std::fs::read_to_string(path) {
+            Ok(source) => {
+                let name = path.file_name().map_or_else(
+                    || path.to_string_lossy().to_string(),
+                    |name| name.to_string_lossy().to_string(),
+                );
+                self.run_script(name, source);
+            }
+            Err(err) => {
+                self.scripts
+                    .print(format!("{}: {err}", path.display()), true);
+                self.scripts.console_open = true;
+            }
+        }
+    }
+
+    /// Starts the script, a running script gets stopped.
+    pub fn run_script(&mut self, name: String, source: String) {
+        self.stop_script();
+        let connected = self
+            .connection
+            .as_ref()
+            .is_some_and(|con| con.is_connected());
+        self.scripts.print(
+            fl!(
+                crate::LANGUAGE_LOADER,
+                "script-started",
+                name = name.clone()
+            ),
+            false,
+        );
+        self.scripts.runner = Some(ScriptRunner::start(name, source, connected));
+    }
+
+    pub fn stop_script(&mut self) {
+        if let Some(runner) = self.scripts.runner.take() {
+            runner.stop();
+            self.scripts.print(
+                fl!(
+                    crate::LANGUAGE_LOADER,
+                    "script-stopped",
+                    name = runner.name.clone()
+                ),
+                false,
+            );
+        }
+    }
+
+    /// Stops the running script, the login script of the address runs after connecting.
+    pub(crate) fn schedule_login_script(&mut self) {
+        self.stop_script();
+        self.scripts.login_pending = true;
+    }
+
+    /// Passes the received data on to the running script.
+    /// The script gets the received data as text, non ASCII characters are decoded
+    /// with the negotiated charset or the buffer parser like `print_received` does.
+    pub(crate) fn script_data_received(&mut self, data: &[u8]) {
+        let Some(runner) = &self.scripts.runner else {
+            return;
+        };
+        let charset = self.charset.charset();
+        let decoder = &mut self.scripts.decoder;
+        if decoder.charset() != charset {
+            decoder.set_charset(charset);
+        }
+        let mut chars = Vec::new();
+        for b in data {
+            decoder.decode(*b, &mut chars);
+        }
+        let text = chars
+            .into_iter()
+            .map(|ch| {
+                if ch.is_ascii() || charset != Charset::Cp437 {
+                    ch
+                } else {
+                    self.buffer_parser
+                        .convert_to_unicode(AttributedChar::new(ch, TextAttribute::default()))
+                }
+            })
+            .collect();
+        runner.send_event(ScriptEvent::Data(text));
+    }
+
+    /// Handles the requests of the running script, a transfer the script starts takes the connection.
+    pub(crate) fn update_script(&mut self) {
+        let is_connected = self.connection().is_connected();
+        if self.scripts.login_pending && is_connected {
+            self.scripts.login_pending = false;
+            let login_script = self
+                .dialing_directory_dialog
+                .current_address()
+                .map(|adr| adr.scripts.login_script.clone())
+                .unwrap_or_default();
+            if !login_script.is_empty() {
+                self.run_script_file(Path::new(&login_script));
+            }
+        }
+
+        let Some(runner) = &mut self.scripts.runner else {
+            return;
+        };
+        runner.set_connected(is_connected);
+        // the transfer is over once the main window updates again
+        if runner.waiting_for_transfer {
+            runner.waiting_for_transfer = false;
+            runner.send_event(ScriptEvent::TransferFinished);
+        }
+
+        while let Some(request) = self
+            .scripts
+            .runner
+            .as_ref()
+            .and_then(ScriptRunner::try_request)
+        {
+            if !self.handle_script_request(request) {
+                break;
+            }
+        }
+    }
+
+    /// Returns false if no further requests can be handled in this update.
+    fn handle_script_request(&mut self, request: ScriptRequest) -> bool {
+        match request {
+            ScriptRequest::Send(text) => {
+                if self.connection().is_connected() {
+                    self.output_string(&text);
+                }
+            }
+            ScriptRequest::ReadScreen => {
+                let screen = self.screen_snapshot();
+                if let Some(runner) = &self.scripts.runner {
+                    runner.send_event(ScriptEvent::Screen(screen));
+                }
+            }
+            ScriptRequest::Transfer {
+                protocol,
+                download,
+                files,
+            } => {
+                if let Some(runner) = &mut self.scripts.runner {
+                    runner.waiting_for_transfer = true;
+                }
+                if self.connection().is_disconnected() {
+                    return false;
+                }
+                if download {
+                    self.start_file_transfer(protocol, true, None);
+                } else {
+                    match FileDescriptor::from_paths(&files) {
+                        Ok(files) => self.start_file_transfer(protocol, false, Some(files)),
+                        Err(err) => self.scripts.print(err.to_string(), true),
+                    }
+                }
+                return false;
+            }
+            ScriptRequest::Hangup => self.hangup(),
+            ScriptRequest::Print(text) => self.scripts.print(text, false),
+            ScriptRequest::Finished(result) => {
+                let name = self
+                    .scripts
+                    .runner
+                    .take()
+                    .map(|runner| runner.name.clone())
+                    .unwrap_or_default();
+                if let Some(err) = result {
+                    self.scripts.print(format!("{name}: {err}"), true);
+                    self.scripts.console_open = true;
+                } else {
+                    self.scripts.print(
+                        fl!(crate::LANGUAGE_LOADER, "script-finished", name = name),
+                        false,
+                    );
+                }
+                return false;
+            }
+        }
+        true
+    }
+
+    fn screen_snapshot(&self) -> ScreenSnapshot {
+        let buffer_view = self.buffer_view.lock();
+        let buf = &buffer_view.buf;
+        let first_line = buf.get_first_visible_line();
+        let lines = (first_line..first_line + buf.get_height())
+            .map(|y| {
+                (0..buf.get_width())
+                    .map(|x| self.buffer_parser.convert_to_unicode(buf.get_char_xy(x, y)))
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        let caret = buffer_view.caret.get_position();
+        ScreenSnapshot {
+            width: buf.get_width() as i64,
+            lines,
+            cursor_x: caret.x as i64,
+            cursor_y: caret.y as i64 - first_line as i64,
+        }
+    }
+
+    pub(crate) fn show_script_menu(&mut self, ui: &mut egui::Ui, img_size: f32) {
+        let macros = self
+            .dialing_directory_dialog
+            .current_address()
+            .map(|adr| adr.scripts.macros.clone())
+            .unwrap_or_default();
+
+        ui.menu_button(
+            RichText::new("📜").font(FontId::new(img_size, FontFamily::Monospace)),
+            |ui| {
+                for path in &macros {
+                    let path = Path::new(path);
+                    let name = path.file_stem().unwrap_or(path.as_os_str());
+                    if ui.button(name.to_string_lossy()).clicked() {
+                        self.run_script_file(path);
+                        ui.close_menu();
+                    }
+                }
+                if !macros.is_empty() {
+                    ui.separator();
+                }
+                if ui
+                    .button(fl!(crate::LANGUAGE_LOADER, "menu-item-run-script"))
+                    .clicked()
+                {
+                    self.init_run_script_dialog();
+                    ui.close_menu();
+                }
+                if self.scripts.runner.is_some()
+                    && ui
+                        .button(fl!(crate::LANGUAGE_LOADER, "menu-item-stop-script"))
+                        .clicked()
+                {
+                    self.stop_script();
+                    ui.close_menu();
+                }
+                if ui
+                    .button(fl!(crate::LANGUAGE_LOADER, "menu-item-script-console"))
+                    .clicked()
+                {
+                    self.scripts.console_open = true;
+                    ui.close_menu();
+                }
+            },
+        )
+        .response
+        .on_hover_ui(|ui| {
+            ui.label(RichText::new(fl!(crate::LANGUAGE_LOADER, "terminal-scripts")).small());
+        });
+    }
+
+    pub(crate) fn show_script_windows(&mut self, ctx: &egui::Context) {
+        if let Some(dialog) = &mut self.scripts.file_dialog {
+            if dialog.show(ctx).selected() {
+                if let Some(path) = dialog.path() {
+                    let path = path.to_path_buf();
+                    self.scripts.file_dialog = None;
+                    self.run_script_file(&path);
+                }
+            }
+        }
+
+        if !self.scripts.console_open {
+            self.scripts.command_has_focus = false;
+            return;
+        }
+        let mut open = true;
+        let mut run = false;
+        let mut stop = false;
+        let is_running = self.scripts.runner.is_some();
+        let scripts = &mut self.scripts;
+        egui::Window::new(fl!(crate::LANGUAGE_LOADER, "script-console-title"))
+            .open(&mut open)
+            .default_size([500.0, 300.0])
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(250.0)
+                    .stick_to_bottom(true)
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| {
+                        for (line, is_error) in &scripts.console {
+                            let text = RichText::new(line).monospace();
+                            if *is_error {
+                                ui.label(text.color(ui.visuals().error_fg_color));
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        TextEdit::singleline(&mut scripts.command)
+                            .hint_text(fl!(crate::LANGUAGE_LOADER, "script-console-hint"))
+                            .desired_width(300.0),
+                    );
+                    scripts.command_has_focus = response.has_focus();
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        run = true;
+                        response.request_focus();
+                    }
+                    if ui
+                        .button(fl!(crate::LANGUAGE_LOADER, "script-console-run"))
+                        .clicked()
+                    {
+                        run = true;
+                    }
+                    if ui
+                        .add_enabled(
+                            is_running,
+                            egui::Button::new(fl!(crate::LANGUAGE_LOADER, "script-console-stop")),
+                        )
+                        .clicked()
+                    {
+                        stop = true;
+                    }
+                    if ui
+                        .button(fl!(crate::LANGUAGE_LOADER, "script-console-clear"))
+                        .clicked()
+                    {
+                        scripts.console.clear();
+                    }
+                });
+            });
+        self.scripts.console_open = open;
+
+        if stop {
+            self.stop_script();
+        }
+        if run && !self.scripts.command.trim().is_empty() {
+            let command = std::mem::take(&mut self.scripts.command);
+            self.scripts.print(format!("> {command}"), false);
+            self.run_script("console".to_string(), command);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::path::Path;
+
+use eframe::egui::{self, RichText};
+use i18n_embed_fl::fl;
+use web_time::{Duration, Instant};
+
+use crate::{
+    check_error,
+    features::{Direction, PlaybackSpeed, Recording, SessionPlayer, SessionRecorder},
+    util::CharsetDecoder,
+};
+
+use super::MainWindow;
+
+impl MainWindow {
+    /// Starts and stops the recorder and playbacks as requested by the capture dialog.
+    pub(crate) fn update_session_recording(&mut self) {
+        if let Some(path) = self.state.capture_dialog.playback_request.take() {
+            self.start_playback(&path);
+        }
+
+        let record = self.state.capture_dialog.record_session;
+        if record == self.session_recorder.is_some() {
+            return;
+        }
+        if !record {
+            self.session_recorder = None;
+            return;
+        }
+        let Some(address) = self.dialing_directory_dialog.current_address() else {
+            return;
+        };
+        match SessionRecorder::start(Path::new(&self.state.options.recording_filename), address) {
+            Ok(recorder) => self.session_recorder = Some(recorder),
+            Err(err) => {
+                self.state.capture_dialog.record_session = false;
+                self.show_error(&err.to_string());
+            }
+        }
+    }
+
+    pub(crate) fn record_session(&mut self, direction: Direction, data: &[u8]) {
+        if let Some(recorder) = &mut self.session_recorder {
+            if let Err(err) = recorder.record(direction, data) {
+                log::error!("Stopped recording the session: {err}");
+                self.session_recorder = None;
+                self.state.capture_dialog.record_session = false;
+            }
+        }
+    }
+
+    /// Plays a recorded session, a running connection is closed.
+    pub fn start_playback(&mut self, path: &Path) {
+        let recording = match Recording::load(path) {
+            Ok(recording) => recording,
+            Err(err) => {
+                self.show_error(&err.to_string());
+                return;
+            }
+        };
+        self.prepare_offline_view();
+        self.set_screen_mode(recording.address.screen_mode);
+        self.session_player = Some(SessionPlayer::new(recording));
+        self.reset_playback_screen();
+        self.show_terminal();
+    }
+
+    /// Closes the connection and ends playbacks and file views before showing something offline.
+    pub(crate) fn prepare_offline_view(&mut self) {
+        self.auto_reconnect.reset();
+        if self.connection().is_connected() {
+            check_error!(self, self.connection().disconnect(), false);
+        }
+        // offline views shouldn't end up in the recording
+        self.state.capture_dialog.record_session = false;
+        self.session_recorder = None;
+        self.session_player = None;
+        self.file_viewer = None;
+        self.sound_thread.clear();
+        self.charset = CharsetDecoder::default();
+    }
+
+    pub fn stop_playback(&mut self) {
+        self.session_player = None;
+    }
+
+    /// Clears the screen and parser state before the playback starts over.
+    fn reset_playback_screen(&mut self) {
+        let Some(player) = &self.session_player else {
+            return;
+        };
+        let address = &player.recording.address;
+        let parser = address.get_terminal_parser(address);
+        self.reset_screen(parser);
+    }
+
+    /// Feeds the recorded data that is due through the parser.
+    pub(crate) fn update_playback(&mut self) {
+        let Some(player) = &mut self.session_player else {
+            return;
+        };
+        let data = player.update(Instant::now());
+        if data.is_empty() {
+            return;
+        }
+        for ch in data {
+            if let Err(err) = self.print_char(ch) {
+                log::error!("{err}");
+            }
+        }
+        self.buffer_view.lock().buf.update_hyperlinks();
+    }
+
+    fn seek_playback(&mut self, position: Duration) {
+        let Some(player) = &mut self.session_player else {
+            return;
+        };
+        if player.seek(position) {
+            self.sound_thread.clear();
+            self.reset_playback_screen();
+        }
+        self.update_playback();
+    }
+
+    pub(crate) fn show_playback_bar(&mut self, ctx: &egui::Context, frame: egui::Frame) {
+        let Some(player) = &mut self.session_player else {
+            return;
+        };
+        let duration = player.recording.duration();
+        let mut position = player.position().as_secs_f32();
+        let mut speed = player.speed;
+        let mut seek = None;
+        let mut stop = false;
+
+        egui::TopBottomPanel::bottom("playback_bar")
+            .frame(frame)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let (icon, hint) = if player.is_paused() {
+                        ("▶", fl!(crate::LANGUAGE_LOADER, "playback-resume"))
+                    } else {
+                        ("⏸", fl!(crate::LANGUAGE_LOADER, "playback-pause"))
+                    };
+                    if ui.button(icon).on_hover_text(hint).clicked() {
+                        let paused = player.is_paused();
+                        player.set_paused(!paused);
+                    }
+                    if ui
+                        .button("⏹")
+                        .on_hover_text(fl!(crate::LANGUAGE_LOADER, "playback-stop"))
+                        .clicked()
+                    {
+                        stop = true;
+                    }
+
+                    ui.spacing_mut().slider_width = 400.0;
+                    let r = ui.add(
+                        egui::Slider::new(&mut position, 0.0..=duration.as_secs_f32())
+                            .show_value(false),
+                    );
+                    if r.changed() {
+                        seek = Some(Duration::from_secs_f32(position));
+                    }
+                    ui.label(RichText::new(format!(
+                        "{} / {}",
+                        format_time(player.position()),
+                        format_time(duration)
+                    )));
+
+                    let recording = &player.recording;
+                    let mut info = recording.address.system_name.clone();
+                    if let Some(started) = recording.started {
+                        info.push_str(&started.format(" %Y-%m-%d %H:%M").to_string());
+                    }
+                    ui.label(RichText::new(info.trim()).small());
+
+                    egui::ComboBox::from_id_source("playback_speed_combobox")
+                        .selected_text(speed.to_string())
+                        .show_ui(ui, |ui| {
+                            for s in PlaybackSpeed::ALL {
+                                ui.selectable_value(&mut speed, s, s.to_string());
+                            }
+                        });
+                });
+            });
+
+        player.speed = speed;
+        if stop {
+            self.stop_playback();
+        } else if let Some(position) = seek {
+            self.seek_playback(position);
+        }
+    }
+}
+
+fn format_time(time: Duration) -> String {
+    let sec = time.as_secs();
+    format!("{:02}:{:02}", sec / 60, sec % 60)
+}
@@ -9,8 +9,6 @@ use egui::Button;
 use i18n_embed_fl::fl;
 use icy_engine::{Position, Selection};
 
-use crate::check_error;
-
 use super::{dialogs, MainWindow, MainWindowMode};
 
 fn encode_mouse_button(button: i32) -> char {
@@ -115,6 +113,9 @@ impl MainWindow {
                             self.show_dialing_directory();
                         }
 
+                        #[cfg(not(target_arch = "wasm32"))]
+                        self.show_script_menu(ui, img_size);
+
                         if self.auto_login.iemsi.isi.is_some() {
                             if self.get_mode() == MainWindowMode::ShowIEMSI {
                                 let r: egui::Response = ui.add(egui::Button::new(RichText::new(
@@ -162,6 +163,17 @@ impl MainWindow {
                             }
                         }
 
+                        if self.state.capture_dialog.record_session {
+                            let r: egui::Response = ui.add(egui::Button::new(RichText::new(fl!(
+                                crate::LANGUAGE_LOADER,
+                                "toolbar-stop-recording"
+                            ))));
+
+                            if r.clicked() {
+                                self.state.capture_dialog.record_session = false;
+                            }
+                        }
+
                         let size = ui.available_size_before_wrap();
                         ui.add_space(size.x - 70.0);
 
@@ -207,6 +219,14 @@ impl MainWindow {
                                     self.set_mode(MainWindowMode::ShowCaptureDialog);
                                     ui.close_menu();
                                 }
+                                #[cfg(not(target_arch = "wasm32"))]
+                                if ui
+                                    .button(fl!(crate::LANGUAGE_LOADER, "menu-item-view-file"))
+                                    .clicked()
+                                {
+                                    self.init_view_file_dialog();
+                                    ui.close_menu();
+                                }
 
                                 if ui
                                     .button(fl!(crate::LANGUAGE_LOADER, "menu-item-settings"))
@@ -221,6 +241,14 @@ impl MainWindow {
                 });
         }
 
+        if !self.is_fullscreen_mode {
+            self.show_playback_bar(ctx, button_frame);
+            self.show_file_viewer_bar(ctx, button_frame);
+        }
+        self.show_view_file_dialog(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.show_script_windows(ctx);
+
         let frame_no_margins = egui::containers::Frame::none()
             .outer_margin(egui::style::Margin::same(0.0))
             .inner_margin(egui::style::Margin::same(0.0));
@@ -280,9 +308,14 @@ impl MainWindow {
         settings.selection_fg = self.screen_mode.get_selection_fg();
         settings.selection_bg = self.screen_mode.get_selection_bg();
 
+        // the find dialog and the script console take the keyboard
+        #[cfg(not(target_arch = "wasm32"))]
+        let keyboard_taken = self.show_find_dialog || self.scripts.has_input_focus();
+        #[cfg(target_arch = "wasm32")]
+        let keyboard_taken = self.show_find_dialog;
+
         let opt = icy_engine_egui::TerminalOptions {
-            focus_lock: matches!(self.get_mode(), MainWindowMode::ShowTerminal)
-                && !self.show_find_dialog,
+            focus_lock: matches!(self.get_mode(), MainWindowMode::ShowTerminal) && !keyboard_taken,
             filter: self.get_options().scaling.get_filter(),
             settings,
             stick_to_bottom: true,
@@ -297,7 +330,7 @@ impl MainWindow {
 
         if matches!(self.get_mode(), MainWindowMode::ShowTerminal)
             && ui.is_enabled()
-            && !self.show_find_dialog
+            && !keyboard_taken
         {
             let events: Vec<egui::Event> = ui.input(|i| i.events.clone());
             for e in events {
@@ -474,16 +507,7 @@ impl MainWindow {
                         }
                         for (k, m) in key_map {
                             if *k == key_code {
-                                if self.connection.as_ref().unwrap().is_connected() {
-                                    let res = self.connection.as_mut().unwrap().send(m.to_vec());
-                                    check_error!(self, res, true);
-                                } else {
-                                    for c in *m {
-                                        if let Err(err) = self.print_char(*c) {
-                                            log::error!("Error printing char: {}", err);
-                                        }
-                                    }
-                                }
+                                self.send_input(m.to_vec());
                                 response.mark_changed();
                                 ui.input_mut(|i| i.consume_key(modifiers, key));
                                 break;
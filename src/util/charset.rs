@@ -0,0 +1,130 @@
+use crate::addresses::Charset;
+
+/// Decodes incoming data of a negotiated charset into unicode characters.
+#[derive(Debug, Default)]
+pub struct CharsetDecoder {
+    charset: Charset,
+    /// Incomplete UTF-8 sequence
+    buffer: Vec<u8>,
+}
+
+impl CharsetDecoder {
+    pub fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    pub fn set_charset(&mut self, charset: Charset) {
+        self.charset = charset;
+        self.buffer.clear();
+    }
+
+    /// Appends the decoded characters of the byte to `output`, incomplete sequences are buffered.
+    /// CP437 isn't decoded - the bytes are passed through as is.
+    pub fn decode(&mut self, byte: u8, output: &mut Vec<char>) {
+        match self.charset {
+            Charset::Cp437 | Charset::Latin1 => output.push(char::from(byte)),
+            Charset::Utf8 => self.decode_utf8(byte, output),
+        }
+    }
+
+    fn decode_utf8(&mut self, byte: u8, output: &mut Vec<char>) {
+        if !self.buffer.is_empty() && byte & 0xC0 != 0x80 {
+            // sequence was cut - drop it and start over with this byte
+            self.buffer.clear();
+            output.push(char::REPLACEMENT_CHARACTER);
+        }
+
+        if self.buffer.is_empty() {
+            match utf8_sequence_len(byte) {
+                1 => output.push(char::from(byte)),
+                0 => output.push(char::REPLACEMENT_CHARACTER),
+                _ => self.buffer.push(byte),
+            }
+            return;
+        }
+
+        self.buffer.push(byte);
+        if self.buffer.len() < utf8_sequence_len(self.buffer[0]) {
+            return;
+        }
+        let ch = std::str::from_utf8(&self.buffer)
+            .ok()
+            .and_then(|s| s.chars().next())
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        self.buffer.clear();
+        output.push(ch);
+    }
+
+    /// Encodes user input in the negotiated charset.
+    /// Returns `None` for CP437 - that's handled by the buffer parser.
+    pub fn encode(&self, ch: char) -> Option<Vec<u8>> {
+        match self.charset {
+            Charset::Cp437 => None,
+            Charset::Utf8 => Some(ch.to_string().into_bytes()),
+            Charset::Latin1 => Some(vec![u8::try_from(ch as u32).unwrap_or(b'?')]),
+        }
+    }
+}
+
+fn utf8_sequence_len(first_byte: u8) -> usize {
+    match first_byte {
+        0x00..=0x7F => 1,
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CharsetDecoder;
+    use crate::addresses::Charset;
+
+    fn decode(decoder: &mut CharsetDecoder, data: &[u8]) -> String {
+        let mut result = Vec::new();
+        for b in data {
+            decoder.decode(*b, &mut result);
+        }
+        result.into_iter().collect()
+    }
+
+    #[test]
+    fn test_decode_utf8() {
+        let mut decoder = CharsetDecoder::default();
+        decoder.set_charset(Charset::Utf8);
+        assert_eq!("a░▒▓€😀", decode(&mut decoder, "a░▒▓€😀".as_bytes()));
+
+        // sequences split between reads
+        let data = "║".as_bytes();
+        assert_eq!("", decode(&mut decoder, &data[..1]));
+        assert_eq!("║", decode(&mut decoder, &data[1..]));
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8() {
+        let mut decoder = CharsetDecoder::default();
+        decoder.set_charset(Charset::Utf8);
+        assert_eq!("\u{FFFD}a", decode(&mut decoder, &[0xB0, b'a']));
+        assert_eq!("\u{FFFD}a", decode(&mut decoder, &[0xE2, 0x96, b'a']));
+        assert_eq!("\u{FFFD}b", decode(&mut decoder, &[0xE2, b'b']));
+        assert_eq!("\u{FFFD}ä", decode(&mut decoder, &[0xE2, 0xC3, 0xA4]));
+    }
+
+    #[test]
+    fn test_decode_latin1() {
+        let mut decoder = CharsetDecoder::default();
+        decoder.set_charset(Charset::Latin1);
+        assert_eq!("äöü", decode(&mut decoder, &[0xE4, 0xF6, 0xFC]));
+        assert_eq!(Some(vec![0xE4]), decoder.encode('ä'));
+        assert_eq!(Some(vec![b'?']), decoder.encode('€'));
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut decoder = CharsetDecoder::default();
+        assert_eq!(None, decoder.encode('ä'));
+        decoder.set_charset(Charset::Utf8);
+        assert_eq!(Some("ä".as_bytes().to_vec()), decoder.encode('ä'));
+    }
+}
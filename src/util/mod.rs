@@ -6,3 +6,9 @@ pub use music::*;
 
 pub mod pattern_recognizer;
 pub use pattern_recognizer::*;
+
+pub mod charset;
+pub use charset::*;
+
+pub mod sauce;
+pub use sauce::*;
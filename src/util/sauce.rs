@@ -0,0 +1,152 @@
+/// Size of the SAUCE record at the end of the file
+const RECORD_SIZE: usize = 128;
+/// Size of a comment line, the comment block starts with "COMNT"
+const COMMENT_SIZE: usize = 64;
+
+/// Data type of character based files (ANSI, ASCII, PETSCII, …)
+pub const DATA_TYPE_CHARACTER: u8 = 1;
+pub const DATA_TYPE_BINARY_TEXT: u8 = 5;
+
+/// The SAUCE record (<https://www.acid.org/info/sauce/sauce.htm>) art files carry at the end.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SauceRecord {
+    pub title: String,
+    pub author: String,
+    pub group: String,
+    /// CCYYMMDD
+    pub date: String,
+    pub data_type: u8,
+    pub file_type: u8,
+    pub tinfo1: u16,
+    pub font: String,
+    pub comments: Vec<String>,
+}
+
+impl SauceRecord {
+    /// Splits the SAUCE record off, the returned content doesn't contain the record,
+    /// the comment block or the EOF marker.
+    pub fn extract(data: &[u8]) -> (&[u8], Option<SauceRecord>) {
+        if data.len() < RECORD_SIZE {
+            return (data, None);
+        }
+        let start = data.len() - RECORD_SIZE;
+        let record = &data[start..];
+        if !record.starts_with(b"SAUCE") {
+            return (data, None);
+        }
+
+        let mut result = SauceRecord {
+            title: decode(&record[7..42]),
+            author: decode(&record[42..62]),
+            group: decode(&record[62..82]),
+            date: decode(&record[82..90]),
+            data_type: record[94],
+            file_type: record[95],
+            tinfo1: u16::from_le_bytes([record[96], record[97]]),
+            font: decode(&record[106..128]),
+            comments: Vec::new(),
+        };
+
+        let mut content_end = start;
+        let comment_lines = record[104] as usize;
+        let comment_block = 5 + comment_lines * COMMENT_SIZE;
+        if comment_lines > 0 && start >= comment_block {
+            let block = &data[start - comment_block..start];
+            if block.starts_with(b"COMNT") {
+                result.comments = block[5..].chunks(COMMENT_SIZE).map(decode).collect();
+                content_end -= comment_block;
+            }
+        }
+        let mut content = &data[..content_end];
+        if content.last() == Some(&0x1A) {
+            content = &content[..content.len() - 1];
+        }
+        (content, Some(result))
+    }
+
+    /// Width in characters for character based files, if the record has one.
+    #[must_use]
+    pub fn width(&self) -> Option<u16> {
+        match self.data_type {
+            DATA_TYPE_CHARACTER if self.tinfo1 > 0 => Some(self.tinfo1),
+            // the file type is the half width
+            DATA_TYPE_BINARY_TEXT if self.file_type > 0 => Some(u16::from(self.file_type) * 2),
+            _ => None,
+        }
+    }
+}
+
+/// SAUCE strings are padded with spaces or zeros.
+fn decode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|b| **b != 0)
+        .map(|b| char::from(*b))
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sauce_file(content: &[u8], comments: &[&str]) -> Vec<u8> {
+        let mut data = content.to_vec();
+        data.push(0x1A);
+        if !comments.is_empty() {
+            data.extend_from_slice(b"COMNT");
+            for comment in comments {
+                data.extend_from_slice(format!("{comment:64}").as_bytes());
+            }
+        }
+        let mut record = b"SAUCE00".to_vec();
+        record.extend_from_slice(
+            format!("{:35}{:20}{:20}20230401", "Title", "Author", "Group").as_bytes(),
+        );
+        record.extend_from_slice(&u32::try_from(content.len()).unwrap().to_le_bytes());
+        record.extend_from_slice(&[DATA_TYPE_CHARACTER, 1]);
+        record.extend_from_slice(&160u16.to_le_bytes());
+        record.extend_from_slice(&25u16.to_le_bytes());
+        record.extend_from_slice(&[0, 0, 0, 0]);
+        record.push(u8::try_from(comments.len()).unwrap());
+        record.push(1);
+        let mut font = b"IBM VGA".to_vec();
+        font.resize(22, 0);
+        record.extend_from_slice(&font);
+        assert_eq!(RECORD_SIZE, record.len());
+        data.extend_from_slice(&record);
+        data
+    }
+
+    #[test]
+    fn test_extract_sauce() {
+        let data = sauce_file(b"\x1B[1mHello", &["first", "second"]);
+        let (content, sauce) = SauceRecord::extract(&data);
+        assert_eq!(b"\x1B[1mHello", content);
+        let sauce = sauce.unwrap();
+        assert_eq!("Title", sauce.title);
+        assert_eq!("Author", sauce.author);
+        assert_eq!("Group", sauce.group);
+        assert_eq!("20230401", sauce.date);
+        assert_eq!("IBM VGA", sauce.font);
+        assert_eq!(vec!["first", "second"], sauce.comments);
+        assert_eq!(Some(160), sauce.width());
+    }
+
+    #[test]
+    fn test_without_comments() {
+        let data = sauce_file(b"Hello", &[]);
+        let (content, sauce) = SauceRecord::extract(&data);
+        assert_eq!(b"Hello", content);
+        assert!(sauce.unwrap().comments.is_empty());
+    }
+
+    #[test]
+    fn test_no_sauce() {
+        let data = b"Hello\x1A".repeat(30);
+        let (content, sauce) = SauceRecord::extract(&data);
+        assert_eq!(&data[..], content);
+        assert!(sauce.is_none());
+    }
+}